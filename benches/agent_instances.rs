@@ -0,0 +1,79 @@
+//! CPU side of a WebGL2 agent frame at 10 000 agents: choosing which agents
+//! to draw with `render_subset`, then packing their instance buffer with
+//! `instance_data`, for the default render limit and for every agent. The
+//! GPU draw itself needs a browser. Run with
+//! `cargo bench --target x86_64-unknown-linux-gnu --bench agent_instances`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use battleo::agent::Agent;
+    use battleo::genes::Genes;
+    use battleo::render_layout::{
+        atlas_index, instance_data, render_subset, DEFAULT_MAX_RENDER_AGENTS,
+    };
+    use criterion::{black_box, Criterion};
+
+    const AGENTS: usize = 10_000;
+
+    /// Stands in for `WebGlRenderer::agent_vertex`, which only builds for
+    /// wasm: the same attributes, without the colour grading.
+    fn vertex(agent: &Agent) -> [f32; 12] {
+        let hue = agent.genes.is_predator as f32;
+        [
+            agent.x as f32,
+            agent.y as f32,
+            hue,
+            0.5,
+            1.0 - hue,
+            agent.genes.size as f32,
+            agent.energy as f32,
+            0.0,
+            atlas_index(&agent.state, agent.genes.is_predator > 0.5) as f32,
+            agent.mutated_genes as f32,
+            -(agent.age as f32),
+            agent.satiation as f32,
+        ]
+    }
+
+    pub fn agent_instances(c: &mut Criterion) {
+        let agents: Vec<Agent> = (0..AGENTS)
+            .map(|i| {
+                let (x, y) = ((i % 1000) as f64, (i / 1000 * 80) as f64);
+                Agent::new(x, y, Genes::new(), 1)
+            })
+            .collect();
+        let subset: Vec<Agent> =
+            render_subset(&agents, DEFAULT_MAX_RENDER_AGENTS, 0.0, 1000.0, 800.0)
+                .into_iter()
+                .map(|i| agents[i].clone())
+                .collect();
+
+        let mut group = c.benchmark_group("agent_instances_10000");
+        group.bench_function("render_subset", |b| {
+            b.iter(|| {
+                black_box(render_subset(
+                    &agents,
+                    DEFAULT_MAX_RENDER_AGENTS,
+                    0.0,
+                    1000.0,
+                    800.0,
+                ))
+            })
+        });
+        group.bench_function("instance_data_limit", |b| {
+            b.iter(|| black_box(instance_data(&subset, vertex)))
+        });
+        group.bench_function("instance_data_all", |b| {
+            b.iter(|| black_box(instance_data(&agents, vertex)))
+        });
+        group.finish();
+    }
+
+    criterion::criterion_group!(benches, agent_instances);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,41 @@
+//! Cost of reading every agent once per frame, by copying them with
+//! `get_agents` vs borrowing them with `with_agents`. Borrowing should be
+//! several times cheaper. Run with
+//! `cargo bench --target x86_64-unknown-linux-gnu --bench agent_view`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use battleo::agent::Agent;
+    use battleo::simulation_core::{SimulationConfig, UnifiedSimulation};
+    use criterion::{black_box, Criterion};
+
+    fn total_energy(agents: &[Agent]) -> f64 {
+        agents.iter().map(|agent| agent.energy).sum()
+    }
+
+    pub fn read_agents(c: &mut Criterion) {
+        let simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 2000,
+            max_agents: 2000,
+            use_ecs: false,
+            ..Default::default()
+        });
+
+        let mut group = c.benchmark_group("agents_per_frame");
+        group.bench_function("get_agents", |b| {
+            b.iter(|| black_box(total_energy(&simulation.get_agents())))
+        });
+        group.bench_function("with_agents", |b| {
+            b.iter(|| black_box(simulation.with_agents(total_energy)))
+        });
+        group.finish();
+    }
+
+    criterion::criterion_group!(benches, read_agents);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
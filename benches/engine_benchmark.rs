@@ -0,0 +1,35 @@
+//! Legacy vs ECS engine throughput over `BENCHMARK_STEPS` steps, using
+//! `HeadlessSimulationV2::run_benchmark`. Run with
+//! `cargo bench --target x86_64-unknown-linux-gnu --bench engine_benchmark`.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    use battleo::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+    let run = |use_ecs: bool| {
+        let config = HeadlessSimulationConfig {
+            use_ecs,
+            ..Default::default()
+        };
+        HeadlessSimulationV2::new(config).run_benchmark()
+    };
+    let legacy = run(false);
+    let ecs = run(true);
+
+    for result in [&legacy, &ecs] {
+        println!(
+            "{:<6} {:>10.1} steps/s {:>12.0} ns/step  peak agents {:>5}  \
+             peak resources {:>5}  score {:.1}",
+            result.engine_type,
+            result.steps_per_second,
+            result.ns_per_step,
+            result.peak_agents,
+            result.peak_resources,
+            result.final_score
+        );
+    }
+    println!("ECS speedup over Legacy: {:.2}x", ecs.compare_to(&legacy));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
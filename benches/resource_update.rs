@@ -0,0 +1,41 @@
+//! `ResourceUpdateSystem` with and without the rayon path, at resource counts
+//! either side of typical play. Run with
+//! `cargo bench --target x86_64-unknown-linux-gnu --bench resource_update`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use battleo::ecs::{spawn_random_resource, ParallelResourceUpdate};
+    use battleo::ecs_systems::{ResourceUpdateSystem, System};
+    use criterion::Criterion;
+    use hecs::World;
+
+    pub fn resource_update(c: &mut Criterion) {
+        let mut group = c.benchmark_group("resource_update");
+        for count in [500, 1500] {
+            for parallel in [false, true] {
+                let mut world = World::new();
+                for _ in 0..count {
+                    spawn_random_resource(&mut world, 1000.0, 800.0);
+                }
+                world.spawn((ParallelResourceUpdate {
+                    enabled: parallel,
+                    frames: 0,
+                },));
+
+                let path = if parallel { "parallel" } else { "sequential" };
+                group.bench_function(format!("{}_{}", path, count), |b| {
+                    b.iter(|| ResourceUpdateSystem.run(&mut world))
+                });
+            }
+        }
+        group.finish();
+    }
+
+    criterion::criterion_group!(benches, resource_update);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
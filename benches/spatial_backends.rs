@@ -0,0 +1,74 @@
+//! Grid vs hash vs quad tree neighbour lookups: 2000 agents on a 1000x800
+//! world, one query per agent, then whole legacy engine steps at 1000 agents.
+//! Run with
+//! `cargo bench --target x86_64-unknown-linux-gnu --bench spatial_backends`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use battleo::simulation_core::{SimulationConfig, UnifiedSimulation};
+    use battleo::spatial::SpatialBackend;
+    use criterion::{black_box, BenchmarkId, Criterion};
+    use rand::prelude::*;
+
+    const AGENTS: usize = 2000;
+    const WIDTH: f64 = 1000.0;
+    const HEIGHT: f64 = 800.0;
+    const CELL_SIZE: f64 = 50.0;
+    const BACKENDS: [(&str, SpatialBackend); 3] = [
+        ("grid", SpatialBackend::Grid),
+        ("hash", SpatialBackend::Hash),
+        ("quadtree", SpatialBackend::QuadTree),
+    ];
+
+    pub fn query_radius(c: &mut Criterion) {
+        let mut rng = StdRng::seed_from_u64(42);
+        let points: Vec<(f64, f64)> = (0..AGENTS)
+            .map(|_| (rng.gen_range(0.0..WIDTH), rng.gen_range(0.0..HEIGHT)))
+            .collect();
+
+        let mut group = c.benchmark_group("query_radius");
+        for sense_range in [20.0, 50.0, 100.0, 200.0] {
+            for (name, backend) in BACKENDS {
+                let mut index = backend.build(WIDTH, HEIGHT, CELL_SIZE);
+                for (id, &(x, y)) in points.iter().enumerate() {
+                    index.insert(x, y, id);
+                }
+                let id = BenchmarkId::new(name, sense_range);
+                group.bench_with_input(id, &sense_range, |b, &r| {
+                    b.iter(|| {
+                        points
+                            .iter()
+                            .map(|&(x, y)| index.query_radius(x, y, black_box(r)).len())
+                            .sum::<usize>()
+                    })
+                });
+            }
+        }
+        group.finish();
+    }
+
+    pub fn legacy_step(c: &mut Criterion) {
+        let mut group = c.benchmark_group("legacy_step_1000_agents");
+        group.sample_size(20);
+        for (name, backend) in BACKENDS {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 1000,
+                max_agents: 1000,
+                use_ecs: false,
+                spatial_backend: backend,
+                seed: Some(42),
+                ..Default::default()
+            });
+            group.bench_function(name, |b| b.iter(|| simulation.update()));
+        }
+        group.finish();
+    }
+
+    criterion::criterion_group!(benches, query_radius, legacy_step);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,40 @@
+//! Cost of a frame that reads the statistics once vs twice, as when both
+//! `WebSimulation` and the JS frontend ask for them. With the cache the two
+//! should match. Run with
+//! `cargo bench --target x86_64-unknown-linux-gnu --bench stats_cache`.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use battleo::simulation_core::{SimulationConfig, UnifiedSimulation};
+    use criterion::{black_box, Criterion};
+
+    pub fn get_stats(c: &mut Criterion) {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 2000,
+            max_agents: 2000,
+            use_ecs: false,
+            ..Default::default()
+        });
+
+        let mut group = c.benchmark_group("get_stats_per_frame");
+        for calls in [1, 2] {
+            group.bench_function(format!("{}_calls", calls), |b| {
+                b.iter(|| {
+                    simulation.invalidate_stats();
+                    for _ in 0..calls {
+                        black_box(simulation.get_stats());
+                    }
+                })
+            });
+        }
+        group.finish();
+    }
+
+    criterion::criterion_group!(benches, get_stats);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(native::benches);
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
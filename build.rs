@@ -0,0 +1,5 @@
+fn main() {
+    // Emits the linker flags a Node.js addon needs
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
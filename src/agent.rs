@@ -1,7 +1,422 @@
-use crate::genes::Genes;
-use crate::resource::Resource;
+use crate::boundary::BoundaryBehavior;
+use crate::genes::{
+    genetic_distance, mutated_gene_mask, Genes, MutationDistribution, GENE_COUNT, GENE_NAMES,
+};
+use crate::resource::{
+    near_poisoning, poison_damage, remember_poisoning, Resource, POISON_LEARNING_INTELLIGENCE,
+};
+use crate::stats::{mean, percentile};
+use crate::toxin::toxin_avoidance;
 use rand::prelude::*;
+use rand_distr::Normal;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Agents with a `migration` gene above this set off migrating.
+pub const MIGRATION_THRESHOLD: f64 = 0.7;
+/// Seconds of age between migrations for agents with a strong migration gene.
+pub const MIGRATION_INTERVAL: f64 = 50.0;
+/// Seconds each migration lasts.
+pub const MIGRATION_DURATION: f64 = 5.0;
+/// Migrants travel at this multiple of their speed.
+pub const MIGRATION_SPEED_MULTIPLIER: f64 = 3.0;
+
+/// Seconds of age between an agent's looks at its neighbours' genes.
+pub const SOCIAL_LEARNING_INTERVAL: f64 = 30.0;
+/// How much richer than the learner a neighbour must be to be worth copying.
+const SOCIAL_LEARNING_ENERGY_RATIO: f64 = 1.3;
+/// Share of the neighbour's gene value blended into the learner's.
+const SOCIAL_LEARNING_RATE: f64 = 0.1;
+
+/// Agents with `cooperation` above this share food with hungry kin.
+const COOPERATION_THRESHOLD: f64 = 0.6;
+/// Kin below this energy are fed by cooperators, who never share themselves
+/// below it either.
+pub const HUNGRY_ENERGY: f64 = 20.0;
+/// Energy given, and the donor's cost, per unit of `cooperation`.
+const SHARE_GIFT: f64 = 10.0;
+const SHARE_COST: f64 = 5.0;
+/// How close a hungry relative must be to be fed.
+pub const SHARE_RADIUS: f64 = 30.0;
+/// Genetic distance within which two untagged agents count as kin.
+const KIN_DISTANCE: f64 = 0.2;
+
+/// Predators fuller than this stop hunting and graze instead.
+pub const SATIATED: f64 = 0.8;
+/// Satiation gained per kill.
+pub const SATIATION_PER_KILL: f64 = 0.5;
+/// Satiation lost per second per unit of `metabolism`.
+const SATIATION_DECAY: f64 = 0.1;
+
+/// Weight of a prey agent's energy against a resource's when choosing a
+/// target: prey has to be run down and can fight back.
+pub const HUNTING_BONUS: f64 = 0.25;
+/// Weight of a resource's energy when choosing a target.
+pub const FORAGING_BONUS: f64 = 1.0;
+
+/// A fleeing agent runs at its speed times this, plus `FLEE_STAMINA_SPEED`
+/// per unit of stamina.
+const FLEE_SPEED: f64 = 2.5;
+const FLEE_STAMINA_SPEED: f64 = 0.5;
+/// Energy a fleeing agent burns per second per unit of stamina.
+const FLEE_COST: f64 = 0.1;
+
+/// Stamina burnt per second of chasing or fleeing.
+const STAMINA_DRAIN: f64 = 0.5;
+/// Share of its stamina gene an agent recovers per second of rest.
+const STAMINA_RECOVERY: f64 = 0.1;
+/// Below this share of its stamina gene an agent is exhausted and runs at
+/// half speed.
+const EXHAUSTION_THRESHOLD: f64 = 0.2;
+
+/// Highest generation `generation_distribution` counts separately; later
+/// generations share its slot.
+pub const MAX_TRACKED_GENERATION: u32 = 100;
+
+/// Age at which every agent dies of old age.
+pub const MAX_AGE: f64 = 200.0;
+/// Buckets in `age_distribution`, each spanning a tenth of `MAX_AGE`.
+pub const AGE_BUCKETS: usize = 10;
+
+// Bathtub mortality: a newborn hazard decaying over the first few seconds, a
+// small constant background, and an exponential rise once senescence sets in.
+const INFANT_MORTALITY: f64 = 0.05;
+const INFANT_MORTALITY_DECAY: f64 = 1.5;
+const BACKGROUND_MORTALITY: f64 = 0.0002;
+const SENESCENT_MORTALITY: f64 = 0.002;
+const SENESCENCE_ONSET: f64 = 150.0;
+const SENESCENCE_SCALE: f64 = 10.0;
+/// Seconds senescence is delayed per unit of stamina above 1.0.
+const STAMINA_LONGEVITY: f64 = 20.0;
+
+/// Energy burnt per second before metabolism and efficiency scale it:
+/// linear in size and speed, or with `allometric` Kleiber's mass^0.75 for
+/// size and quadratic drag for speed.
+pub fn metabolic_cost(size: f64, speed: f64, allometric: bool) -> f64 {
+    if allometric {
+        size.powf(0.75) * 0.05 + speed.powi(2) * 0.01
+    } else {
+        size * 0.05 + speed * 0.02
+    }
+}
+
+/// Target score of prey holding `energy` at `distance` for a hunter whose
+/// `is_predator` gene is `predation`. Stealth and intelligence make the
+/// chase surer.
+pub fn prey_score(
+    energy: f64,
+    distance: f64,
+    predation: f64,
+    stealth: f64,
+    intelligence: f64,
+) -> f64 {
+    energy / (distance + 1.0)
+        * predation
+        * HUNTING_BONUS
+        * (1.0 + stealth)
+        * (1.0 + intelligence)
+}
+
+/// Target score of a resource holding `energy` at `distance` for an agent
+/// whose `is_predator` gene is `predation`. Comparable with `prey_score`,
+/// so the predator gene trades hunting against foraging smoothly.
+pub fn resource_score(energy: f64, distance: f64, predation: f64) -> f64 {
+    energy / (distance + 1.0) * (1.0 - predation) * FORAGING_BONUS
+}
+
+/// Unit vector running from `(x, y)` away from every threat at `threats`,
+/// each pushing with the inverse of its distance so the closest weigh most.
+/// A surrounded agent heads for the widest gap. `None` without threats or if
+/// their pushes cancel out.
+pub fn escape_heading(
+    x: f64,
+    y: f64,
+    threats: impl IntoIterator<Item = (f64, f64)>,
+) -> Option<(f64, f64)> {
+    let (mut escape_x, mut escape_y) = (0.0, 0.0);
+    for (tx, ty) in threats {
+        let distance = (x - tx).hypot(y - ty);
+        if distance > 0.0 {
+            escape_x += (x - tx) / distance.powi(2);
+            escape_y += (y - ty) / distance.powi(2);
+        }
+    }
+    let length = escape_x.hypot(escape_y);
+    (length > 0.0).then(|| (escape_x / length, escape_y / length))
+}
+
+/// Speed of an agent fleeing at gene `speed`; stamina makes for a faster
+/// sprint.
+pub fn flee_speed(speed: f64, stamina: f64) -> f64 {
+    speed * (FLEE_SPEED + stamina * FLEE_STAMINA_SPEED)
+}
+
+/// Energy burnt per second of fleeing, more for high-stamina runners.
+pub fn flee_cost(stamina: f64) -> f64 {
+    stamina * FLEE_COST
+}
+
+/// Stamina left after `delta_time` seconds of chasing or fleeing
+/// (`exerting`), or of rest, which restores it up to the `stamina` gene.
+pub fn next_stamina(current: f64, stamina: f64, exerting: bool, delta_time: f64) -> f64 {
+    if exerting {
+        (current - delta_time * STAMINA_DRAIN).max(0.0)
+    } else {
+        (current + stamina * STAMINA_RECOVERY * delta_time).min(stamina)
+    }
+}
+
+/// Share of its full running speed an agent with `current` stamina left
+/// out of its `stamina` gene can manage.
+pub fn stamina_pace(current: f64, stamina: f64) -> f64 {
+    if current < EXHAUSTION_THRESHOLD * stamina {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+/// Energy a donor with this `cooperation` gene and `energy` gives hungry kin,
+/// and what giving costs it; `None` for non-cooperators and for donors the
+/// cost would leave hungry themselves.
+pub fn share_terms(cooperation: f64, energy: f64) -> Option<(f64, f64)> {
+    let cost = cooperation * SHARE_COST;
+    (cooperation > COOPERATION_THRESHOLD && energy - cost >= HUNGRY_ENERGY)
+        .then_some((cooperation * SHARE_GIFT, cost))
+}
+
+/// Same tagged species (0 is untagged), or genetically close enough to be
+/// family.
+pub fn are_kin(species: u32, other_species: u32, genes: &Genes, other_genes: &Genes) -> bool {
+    (species != 0 && species == other_species)
+        || genetic_distance(genes, other_genes) < KIN_DISTANCE
+}
+
+/// Satiation left after digesting for `delta_time` seconds at this
+/// `metabolism`.
+pub fn digest(satiation: f64, metabolism: f64, delta_time: f64) -> f64 {
+    (satiation - metabolism * SATIATION_DECAY * delta_time).max(0.0)
+}
+
+/// Smallest patch, in energy, worth harvesting for an agent of this
+/// `metabolism` and `size` (its R*); leaner agents can live off scraps the
+/// rest pass over.
+pub fn r_star(metabolism: f64, size: f64) -> f64 {
+    metabolism * size * 3.0
+}
+
+/// Whether an agent with this `migration` gene, which last set off
+/// migrating at `last_migration_time`, is due to set off again at `age`.
+pub fn is_due_to_migrate(migration: f64, age: f64, last_migration_time: f64) -> bool {
+    migration > MIGRATION_THRESHOLD && age - last_migration_time >= MIGRATION_INTERVAL
+}
+
+/// The compass direction the agent with `id` always migrates in.
+pub fn migration_heading(id: u64) -> (f64, f64) {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    match hasher.finish() % 4 {
+        0 => (0.0, -1.0),
+        1 => (1.0, 0.0),
+        2 => (0.0, 1.0),
+        _ => (-1.0, 0.0),
+    }
+}
+
+/// Gaussian error, in world units, in where an agent with `genes` perceives
+/// something `distance` away. It grows towards the edge of the sense range
+/// and shrinks with intelligence.
+pub fn sensory_noise(genes: &Genes, distance: f64, rng: &mut impl Rng) -> f64 {
+    let std = perception_std(genes.sense_range, genes.intelligence, distance);
+    Normal::new(0.0, std).map_or(0.0, |normal| normal.sample(rng))
+}
+
+/// Standard deviation of `sensory_noise` for an agent with this
+/// `sense_range` and `intelligence`.
+pub fn perception_std(sense_range: f64, intelligence: f64, distance: f64) -> f64 {
+    distance / sense_range * (1.0 / intelligence)
+}
+
+/// Where something at (x, y), `distance` away, seems to be to an agent with
+/// this `sense_range` and `intelligence`; see `sensory_noise`.
+pub fn perceived_position(
+    x: f64,
+    y: f64,
+    distance: f64,
+    sense_range: f64,
+    intelligence: f64,
+    rng: &mut impl Rng,
+) -> (f64, f64) {
+    let std = perception_std(sense_range, intelligence, distance);
+    match Normal::new(0.0, std) {
+        Ok(normal) => (x + normal.sample(rng), y + normal.sample(rng)),
+        Err(_) => (x, y),
+    }
+}
+
+/// Chance per second of dying of natural causes at `age`. Stamina delays the
+/// rise in old age.
+pub fn mortality_rate(age: f64, stamina: f64) -> f64 {
+    let infant = INFANT_MORTALITY * (-age / INFANT_MORTALITY_DECAY).exp();
+    let onset = SENESCENCE_ONSET + (stamina - 1.0) * STAMINA_LONGEVITY;
+    let senescent = SENESCENT_MORTALITY * ((age - onset) / SENESCENCE_SCALE).exp();
+    infant + BACKGROUND_MORTALITY + senescent
+}
+
+/// Index into `age_distribution` for an agent of `age`.
+pub fn age_bucket(age: f64) -> usize {
+    ((age / MAX_AGE * AGE_BUCKETS as f64).max(0.0) as usize).min(AGE_BUCKETS - 1)
+}
+
+/// Number of agents in each tenth of the maximum lifespan.
+pub fn age_distribution(agents: &[Agent]) -> [usize; AGE_BUCKETS] {
+    let mut buckets = [0; AGE_BUCKETS];
+    for agent in agents {
+        buckets[age_bucket(agent.age)] += 1;
+    }
+    buckets
+}
+
+/// Highest trophic level an agent can reach; kills beyond it don't raise it.
+pub const MAX_TROPHIC_LEVEL: u8 = 5;
+/// Agents at or above this trophic level count as apex predators.
+pub const APEX_TROPHIC_LEVEL: u8 = 3;
+
+/// (mean trophic level, apex predator count, Shannon entropy in nats of the
+/// trophic level distribution). All zero when there are no agents.
+pub fn trophic_summary(agents: &[Agent]) -> (f64, usize, f64) {
+    trophic_level_summary(agents.iter().map(|agent| agent.trophic_level))
+}
+
+/// `trophic_summary` of the agents at these trophic `levels`.
+pub fn trophic_level_summary(levels: impl IntoIterator<Item = u8>) -> (f64, usize, f64) {
+    let mut counts = [0usize; MAX_TROPHIC_LEVEL as usize + 1];
+    for level in levels {
+        counts[level.min(MAX_TROPHIC_LEVEL) as usize] += 1;
+    }
+    let total = counts.iter().sum::<usize>() as f64;
+    if total == 0.0 {
+        return (0.0, 0, 0.0);
+    }
+
+    let average = counts
+        .iter()
+        .enumerate()
+        .map(|(level, &count)| level as f64 * count as f64)
+        .sum::<f64>()
+        / total;
+    let apex = counts[APEX_TROPHIC_LEVEL as usize..].iter().sum();
+    let diversity = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let share = count as f64 / total;
+            -share * share.ln()
+        })
+        .sum();
+    (average, apex, diversity)
+}
+
+/// Number of agents at each generation, up to the highest one alive (capped at
+/// `MAX_TRACKED_GENERATION`). Empty when there are no agents.
+pub fn generation_distribution(agents: &[Agent]) -> Vec<usize> {
+    let mut counts = Vec::new();
+    for agent in agents {
+        let generation = agent.generation.min(MAX_TRACKED_GENERATION) as usize;
+        if counts.len() <= generation {
+            counts.resize(generation + 1, 0);
+        }
+        counts[generation] += 1;
+    }
+    counts
+}
+
+/// Agents per equal-width slice of `gene_name`'s `gene_bounds_for` range,
+/// lowest first. `None` if `gene_name` isn't in `GENE_NAMES`.
+pub fn gene_histogram(agents: &[Agent], gene_name: &str, buckets: usize) -> Option<Vec<u32>> {
+    let index = GENE_NAMES.iter().position(|&name| name == gene_name)?;
+    let mut counts = vec![0; buckets];
+    if buckets == 0 {
+        return Some(counts);
+    }
+    for agent in agents {
+        let value = agent.genes.normalized()[index];
+        let bucket = ((value * buckets as f64) as usize).min(buckets - 1);
+        counts[bucket] += 1;
+    }
+    Some(counts)
+}
+
+/// Five-number summary of one gene across the population, plus its mean.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GeneBoxplot {
+    pub p5: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub mean: f64,
+}
+
+/// Percentiles of `gene_name` over `agents`, in the gene's own units. All
+/// zero without agents; `None` if `gene_name` isn't in `GENE_NAMES`.
+pub fn gene_boxplot(agents: &[Agent], gene_name: &str) -> Option<GeneBoxplot> {
+    let index = GENE_NAMES.iter().position(|&name| name == gene_name)?;
+    let mut values: Vec<f64> = agents.iter().map(|agent| agent.genes.gene(index)).collect();
+    values.sort_by(f64::total_cmp);
+    Some(GeneBoxplot {
+        p5: percentile(&values, 5.0),
+        p25: percentile(&values, 25.0),
+        median: percentile(&values, 50.0),
+        p75: percentile(&values, 75.0),
+        p95: percentile(&values, 95.0),
+        mean: mean(&values),
+    })
+}
+
+/// What the inspector panel shows about one agent.
+#[derive(Clone, Debug, Serialize)]
+pub struct AgentInfo {
+    pub id: u64,
+    pub x: f64,
+    pub y: f64,
+    pub energy: f64,
+    pub generation: u32,
+    /// `AgentState` variant name, e.g. `"Seeking"`.
+    pub state: String,
+    pub genes: GenesSummary,
+}
+
+/// The genes most telling of how an agent behaves.
+#[derive(Clone, Debug, Serialize)]
+pub struct GenesSummary {
+    pub speed: f64,
+    pub size: f64,
+    pub aggression: f64,
+    pub is_predator: f64,
+    pub intelligence: f64,
+}
+
+impl AgentInfo {
+    pub fn from_agent(agent: &Agent) -> Self {
+        Self {
+            id: agent.id(),
+            x: agent.x,
+            y: agent.y,
+            energy: agent.energy,
+            generation: agent.generation,
+            state: format!("{:?}", agent.state),
+            genes: GenesSummary {
+                speed: agent.genes.speed,
+                size: agent.genes.size,
+                aggression: agent.genes.aggression,
+                is_predator: agent.genes.is_predator,
+                intelligence: agent.genes.intelligence,
+            },
+        }
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Agent {
@@ -24,31 +439,143 @@ pub struct Agent {
     pub is_dying: bool,  // Whether the agent is in death animation
     pub spawn_fade: f64, // Fade in timer for new agents (0.0 = invisible, 1.0 = fully visible)
     pub spawn_position: Option<(f64, f64)>, // Position where agent was spawned
+    #[serde(default)]
+    pub phenotype: PhenotypeModifiers, // Environmental plasticity, not inherited
+    #[serde(default)]
+    pub migrating_direction: Option<(f64, f64)>, // Unit compass heading while migrating
+    #[serde(default)]
+    pub last_migration_time: f64, // Age at which the last migration started
+    #[serde(default)]
+    pub r_star: f64, // Smallest resource energy worth harvesting (R* competition)
+    #[serde(default)]
+    pub current_stamina: f64, // Drains while hunting, recovers otherwise
+    #[serde(default)]
+    pub mutated_genes: u32, // `mutated_gene_mask` against the parents; 0 for founders
+    #[serde(default)]
+    pub species_id: u32, // Set by `assign_species_ids`; 0 until first tagged
+    #[serde(default = "default_trophic_level")]
+    pub trophic_level: u8, // 1 after eating a resource, prey's level + 1 after a kill
+    #[serde(default)]
+    pub satiation: f64, // 0.0-1.0, raised by kills and digested over time
+    #[serde(default)]
+    pub home_habitat_id: Option<usize>, // Last habitat the agent was inside
+    #[serde(default)]
+    pub migration_waypoint: Option<(f64, f64)>, // Next habitat on a migrant's route
+    #[serde(default)]
+    pub perception_error: f64, // How far the last target or threat was misperceived
+    #[serde(default)]
+    pub poisoned_spots: Option<Box<Vec<(f64, f64)>>>, // Where it was poisoned; boxed as few need it
+}
+
+pub(crate) fn default_trophic_level() -> u8 {
+    1
+}
+
+/// Per-frame scaling of gene expression by the local environment.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PhenotypeModifiers {
+    pub speed_scale: f64,
+    pub metabolism_scale: f64,
+    pub reproduction_scale: f64,
+}
+
+impl Default for PhenotypeModifiers {
+    fn default() -> Self {
+        Self {
+            speed_scale: 1.0,
+            metabolism_scale: 1.0,
+            reproduction_scale: 1.0,
+        }
+    }
+}
+
+impl PhenotypeModifiers {
+    /// Resource abundance at which agents no longer show a stress response.
+    const ABUNDANT_RESOURCES: f64 = 5.0;
+    /// Neighbours within this distance add to crowding.
+    pub const CROWDING_RADIUS: f64 = 100.0;
+    /// Resources within this distance add to abundance.
+    pub const FORAGING_RADIUS: f64 = 200.0;
+
+    /// The modifiers of an agent at (x, y) among `neighbours` and
+    /// `resources`, given as (x, y, energy), each counting for less the
+    /// further away it is.
+    pub fn around(
+        x: f64,
+        y: f64,
+        neighbours: impl IntoIterator<Item = (f64, f64)>,
+        resources: impl IntoIterator<Item = (f64, f64, f64)>,
+    ) -> Self {
+        let population_density: f64 = neighbours
+            .into_iter()
+            .map(|(nx, ny)| (nx - x).hypot(ny - y))
+            .filter(|&distance| distance < Self::CROWDING_RADIUS)
+            .map(|distance| 1.0 / (distance + 1.0))
+            .sum();
+        let resource_abundance: f64 = resources
+            .into_iter()
+            .map(|(rx, ry, energy)| (energy, (rx - x).hypot(ry - y)))
+            .filter(|&(_, distance)| distance < Self::FORAGING_RADIUS)
+            .map(|(energy, distance)| energy / (distance + 1.0))
+            .sum();
+        Self::from_environment(population_density, resource_abundance)
+    }
+
+    /// Crowding suppresses reproduction and scarcity raises metabolism.
+    /// Speed is unaffected until the world has a temperature field.
+    pub fn from_environment(population_density: f64, resource_abundance: f64) -> Self {
+        let scarcity = 1.0 - (resource_abundance / Self::ABUNDANT_RESOURCES).min(1.0);
+
+        Self {
+            speed_scale: 1.0,
+            metabolism_scale: 1.0 + scarcity * 0.5,
+            reproduction_scale: 1.0 / (1.0 + population_density),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum AgentState {
     Seeking,
     Hunting,
+    /// Hunting the quarry at (target_x, target_y) alongside every other
+    /// predator with the same `pack_id`. Only the ECS engine forms packs
+    /// (see `ecs_systems::AgentBehaviorSystem`); `Agent` itself chases a
+    /// pack's quarry as it would any other.
+    PackHunting {
+        target_x: f64,
+        target_y: f64,
+        pack_id: u64,
+    },
     Feeding,
     Reproducing,
     Fighting,
     Fleeing,
+    Migrating,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum DeathReason {
     Starvation,
     OldAge,
     KilledByPredator,
     Combat,
     NaturalCauses,
+    Poisoned,
+    /// Crossed a `BoundaryBehavior::Lethal` edge.
+    FellOffEdge,
 }
 
 impl Agent {
     pub fn new(x: f64, y: f64, genes: Genes, generation: u32) -> Self {
-        let mut rng = thread_rng();
+        Self::random(x, y, genes, generation, &mut thread_rng())
+    }
+
+    /// An agent at (`x`, `y`) heading a direction drawn from `rng`.
+    pub fn random(x: f64, y: f64, genes: Genes, generation: u32, rng: &mut impl Rng) -> Self {
         let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
+        let r_star = Self::r_star_for(&genes);
+        let current_stamina = genes.stamina;
 
         Self {
             x,
@@ -70,9 +597,27 @@ impl Agent {
             is_dying: false,
             spawn_fade: 0.0, // Start invisible and fade in
             spawn_position: Some((x, y)),
+            phenotype: PhenotypeModifiers::default(),
+            migrating_direction: None,
+            last_migration_time: 0.0,
+            r_star,
+            current_stamina,
+            mutated_genes: 0,
+            species_id: 0,
+            trophic_level: 1,
+            satiation: 0.0,
+            home_habitat_id: None,
+            migration_waypoint: None,
+            perception_error: 0.0,
+            poisoned_spots: None,
         }
     }
 
+    /// Minimum resource energy an agent with these genes can profitably harvest.
+    pub fn r_star_for(genes: &Genes) -> f64 {
+        r_star(genes.metabolism, genes.size)
+    }
+
     pub fn update(
         &mut self,
         delta_time: f64,
@@ -80,6 +625,7 @@ impl Agent {
         agents: &[Agent],
         canvas_width: f64,
         canvas_height: f64,
+        boundary: BoundaryBehavior,
     ) -> Option<usize> {
         self.age += delta_time;
 
@@ -102,8 +648,10 @@ impl Agent {
         }
 
         // Much higher energy consumption - agents should die quickly without food
-        let base_energy_cost = (self.genes.size * 0.05 + self.genes.speed * 0.02) * delta_time;
-        let metabolism_factor = self.genes.metabolism;
+        // Standalone agents have no config, so they keep the linear model
+        let base_energy_cost =
+            metabolic_cost(self.genes.size, self.genes.speed, false) * delta_time;
+        let metabolism_factor = self.genes.metabolism * self.phenotype.metabolism_scale;
         let environmental_factor = 1.0 + (self.x / canvas_width + self.y / canvas_height) * 0.001;
         let total_energy_cost = base_energy_cost * metabolism_factor * environmental_factor;
         self.energy -= total_energy_cost / self.genes.energy_efficiency;
@@ -116,36 +664,56 @@ impl Agent {
             return None;
         }
 
-        if self.age > 200.0 {
-            self.is_dying = true;
-            self.death_reason = Some(DeathReason::OldAge);
-            self.death_fade = 0.0;
+        if self.check_mortality(delta_time, &mut thread_rng()) {
             return None;
         }
 
         // Complex behavioral decision making
         self.update_behavior_state(resources, agents);
 
+        if self.should_migrate() {
+            self.start_migration();
+        }
+
+        self.update_stamina(delta_time);
+        self.satiation = digest(self.satiation, self.genes.metabolism, delta_time);
+
         let mut consumed_resource = None;
 
         // Update behavior based on current state
         match self.state {
             AgentState::Seeking => self.seek_targets(resources, agents),
-            AgentState::Hunting => self.hunt_target(delta_time),
+            AgentState::Hunting | AgentState::PackHunting { .. } => self.hunt_target(delta_time),
             AgentState::Feeding => consumed_resource = self.feed_on_resource(resources),
             AgentState::Reproducing => self.reproduce(),
             AgentState::Fighting => self.fight_agent(agents),
-            AgentState::Fleeing => self.flee_from_danger(delta_time),
+            AgentState::Fleeing => self.flee_from_danger(delta_time, agents),
+            AgentState::Migrating => self.migrate(),
         }
 
         // Move agent with complex physics
-        self.move_agent(delta_time, canvas_width, canvas_height);
+        if !self.move_agent(
+            delta_time,
+            canvas_width,
+            canvas_height,
+            boundary,
+            &mut thread_rng(),
+        ) {
+            self.is_dying = true;
+            self.death_reason = Some(DeathReason::FellOffEdge);
+            self.death_fade = 0.0;
+            return consumed_resource;
+        }
 
         // Check for reproduction with more complex conditions
-        if self.can_reproduce() {
+        if self.state != AgentState::Migrating && self.can_reproduce() {
             self.state = AgentState::Reproducing;
         }
 
+        if self.age % SOCIAL_LEARNING_INTERVAL < delta_time {
+            self.learn_from_neighbors(agents, &mut thread_rng());
+        }
+
         // Reduced learning calculations - only run occasionally
         if self.age % 1.0 < delta_time {
             // Only run every 1 second instead of every 10 seconds (10x faster)
@@ -155,18 +723,36 @@ impl Agent {
         consumed_resource
     }
 
+    /// Rolls for a death of natural causes over `delta_time` (see
+    /// `mortality_rate`) and retires agents past `MAX_AGE`, marking them
+    /// dying. Returns whether the agent died.
+    pub fn check_mortality(&mut self, delta_time: f64, rng: &mut impl Rng) -> bool {
+        let reason = if rng.gen::<f64>() < mortality_rate(self.age, self.genes.stamina) * delta_time
+        {
+            DeathReason::NaturalCauses
+        } else if self.age > MAX_AGE {
+            DeathReason::OldAge
+        } else {
+            return false;
+        };
+        self.is_dying = true;
+        self.death_reason = Some(reason);
+        self.death_fade = 0.0;
+        true
+    }
+
     fn update_behavior_state(&mut self, resources: &[Resource], agents: &[Agent]) {
         // Complex decision making based on environment
         let mut threat_level = 0.0;
         let mut resource_abundance = 0.0;
-        let mut _population_density = 0.0;
+        let mut population_density = 0.0;
 
         // Calculate environmental factors
         for agent in agents {
             if agent.id() != self.id() {
                 let distance = self.distance_to(agent.x, agent.y);
                 if distance < 100.0 {
-                    _population_density += 1.0 / (distance + 1.0);
+                    population_density += 1.0 / (distance + 1.0);
                     if agent.genes.size > self.genes.size * 1.2 {
                         threat_level += 1.0 / (distance + 1.0);
                     }
@@ -181,6 +767,9 @@ impl Agent {
             }
         }
 
+        self.phenotype =
+            PhenotypeModifiers::from_environment(population_density, resource_abundance);
+
         // Complex behavioral adaptation - REMOVED SPEED MULTIPLICATION
         // This was causing exponential speed growth
         let _stress_factor = threat_level * 0.5 + (1.0 - resource_abundance / 1000.0) * 0.3;
@@ -206,27 +795,112 @@ impl Agent {
         }
     }
 
+    pub fn is_cooperator(&self) -> bool {
+        self.genes.cooperation > COOPERATION_THRESHOLD
+    }
+
+    /// Same tagged species, or genetically close enough to be family.
+    pub fn is_kin(&self, other: &Agent) -> bool {
+        are_kin(self.species_id, other.species_id, &self.genes, &other.genes)
+    }
+
+    /// Eats a poisonous resource at (x, y) with `energy_delta`, losing
+    /// `poison_damage`. Agents above `POISON_LEARNING_INTELLIGENCE` remember
+    /// the spot; see `avoids_poison_at`.
+    pub fn eat_poison(&mut self, x: f64, y: f64, energy_delta: f64) {
+        self.energy -= poison_damage(energy_delta, self.genes.defense);
+        if self.energy <= 0.0 {
+            self.death_reason = Some(DeathReason::Poisoned);
+        }
+        if self.genes.intelligence > POISON_LEARNING_INTELLIGENCE {
+            let spots = self.poisoned_spots.get_or_insert_with(Default::default);
+            remember_poisoning(spots, x, y);
+        }
+    }
+
+    /// Whether the agent won't eat at (x, y) for having been poisoned near it.
+    pub fn avoids_poison_at(&self, x: f64, y: f64) -> bool {
+        near_poisoning(self.poisoned_spots.as_deref().map_or(&[], |spots| spots), x, y)
+    }
+
+    /// Called after feeding: a cooperator gives `cooperation * SHARE_GIFT`
+    /// energy to the hungriest kin within `SHARE_RADIUS` that is below
+    /// `HUNGRY_ENERGY`, paying `cooperation * SHARE_COST`. Returns the energy
+    /// given, 0 if nobody was fed.
+    pub fn share_energy(&mut self, neighbors: &mut [Agent]) -> f64 {
+        let Some((gift, cost)) = share_terms(self.genes.cooperation, self.energy) else {
+            return 0.0;
+        };
+
+        let recipient = neighbors
+            .iter_mut()
+            .filter(|agent| agent.energy < HUNGRY_ENERGY && !agent.is_dying)
+            .filter(|agent| self.distance_to(agent.x, agent.y) <= SHARE_RADIUS)
+            .filter(|agent| self.is_kin(agent))
+            .min_by(|a, b| a.energy.total_cmp(&b.energy));
+        let Some(recipient) = recipient else {
+            return 0.0;
+        };
+
+        recipient.energy = (recipient.energy + gift).min(recipient.max_energy);
+        self.energy -= cost;
+        gift
+    }
+
+    /// Smart, placid agents pick up habits from their neighbours.
+    pub fn is_social_learner(&self) -> bool {
+        self.genes.intelligence > 0.7 && self.genes.aggression < 0.5
+    }
+
+    /// Nudges one random gene towards the richest neighbour within
+    /// `sense_range` that has at least `SOCIAL_LEARNING_ENERGY_RATIO` times
+    /// this agent's energy and is no older a generation. Returns whether
+    /// anything was learned.
+    pub fn learn_from_neighbors(&mut self, agents: &[Agent], rng: &mut impl Rng) -> bool {
+        if !self.is_social_learner() {
+            return false;
+        }
+
+        let id = self.id();
+        let teacher = agents
+            .iter()
+            .filter(|agent| {
+                agent.id() != id
+                    && agent.energy > self.energy * SOCIAL_LEARNING_ENERGY_RATIO
+                    && agent.generation >= self.generation
+                    && self.distance_to(agent.x, agent.y) <= self.genes.sense_range
+            })
+            .max_by(|a, b| a.energy.total_cmp(&b.energy));
+        let Some(teacher) = teacher else {
+            return false;
+        };
+
+        let gene = rng.gen_range(0..GENE_COUNT);
+        let target = teacher.genes.gene(gene);
+        let value = self.genes.gene_mut(gene);
+        *value = (1.0 - SOCIAL_LEARNING_RATE) * *value + SOCIAL_LEARNING_RATE * target;
+        true
+    }
+
     fn seek_targets(&mut self, resources: &[Resource], agents: &[Agent]) {
         let mut best_target = None;
         let mut best_score = f64::NEG_INFINITY;
 
-        // PREDATOR BEHAVIOR: Hunt prey first if predator
-        if self.is_predator() {
+        // Hunt prey while still hungry, weighed against resources by how
+        // predatory the agent is
+        let predation = self.predation();
+        if !self.is_satiated() {
             for agent in agents {
                 if agent.id() != self.id() && agent.is_prey() {
                     let distance = self.distance_to(agent.x, agent.y);
                     if distance <= self.genes.sense_range * self.genes.territory_size / 100.0 {
-                        // Calculate hunting score based on predator genes
-                        let energy_score = agent.energy / 100.0;
-                        let distance_penalty = distance / self.genes.sense_range;
-                        let stealth_bonus = self.genes.stealth;
-                        let intelligence_bonus = self.genes.intelligence;
-
-                        let score = energy_score
-                            * (1.0 - distance_penalty)
-                            * (1.0 + stealth_bonus)
-                            * (1.0 + intelligence_bonus);
-
+                        let score = prey_score(
+                            agent.energy,
+                            distance,
+                            predation,
+                            self.genes.stealth,
+                            self.genes.intelligence,
+                        );
                         if score > best_score {
                             best_score = score;
                             best_target = Some((agent.x, agent.y, true, "prey"));
@@ -242,32 +916,50 @@ impl Agent {
                 if agent.id() != self.id() && agent.is_predator() {
                     let distance = self.distance_to(agent.x, agent.y);
                     if distance <= self.genes.sense_range {
-                        // Flee from predators
+                        // Flee from predators; the escape direction is picked each frame
                         self.state = AgentState::Fleeing;
-                        let flee_x = self.x - (agent.x - self.x) * 2.0;
-                        let flee_y = self.y - (agent.y - self.y) * 2.0;
-                        self.target_x = Some(flee_x);
-                        self.target_y = Some(flee_y);
+                        self.target_x = None;
+                        self.target_y = None;
                         return;
                     }
                 }
             }
         }
 
-        // Look for resources (both predators and prey eat resources)
+        // Look for resources (both predators and prey eat resources), seen
+        // through sensory noise
+        let mut resource_error = 0.0;
         for resource in resources {
-            if resource.is_available() {
+            if resource.is_available_to(self.r_star) {
                 let distance = resource.distance_to(self.x, self.y);
                 if distance <= self.genes.sense_range {
-                    let score = resource.energy / (distance + 1.0);
+                    let score = resource_score(resource.energy, distance, predation)
+                        * toxin_avoidance(resource.toxin, self.genes.defense);
                     if score > best_score {
                         best_score = score;
-                        best_target = Some((resource.x, resource.y, false, "resource"));
+                        let noise_x = sensory_noise(&self.genes, distance, &mut thread_rng());
+                        let noise_y = sensory_noise(&self.genes, distance, &mut thread_rng());
+                        resource_error = noise_x.hypot(noise_y);
+                        best_target = Some((
+                            resource.x + noise_x,
+                            resource.y + noise_y,
+                            false,
+                            "resource",
+                        ));
                     }
                 }
             }
         }
 
+        // Migrants out of room at home head for the next habitat on their route
+        if let Some((waypoint_x, waypoint_y)) = self.migration_waypoint {
+            let score = self.genes.migration;
+            if score > best_score {
+                best_score = score;
+                best_target = Some((waypoint_x, waypoint_y, false, "waypoint"));
+            }
+        }
+
         // Look for other agents (predator vs predator fights)
         if self.is_predator() {
             for agent in agents {
@@ -294,10 +986,8 @@ impl Agent {
                         } else if size_ratio > 1.2 && attack_ratio < 0.8 {
                             // Flee from stronger predator
                             self.state = AgentState::Fleeing;
-                            let flee_x = self.x - (agent.x - self.x) * 1.5;
-                            let flee_y = self.y - (agent.y - self.y) * 1.5;
-                            self.target_x = Some(flee_x);
-                            self.target_y = Some(flee_y);
+                            self.target_x = None;
+                            self.target_y = None;
                             return;
                         }
                     }
@@ -305,7 +995,10 @@ impl Agent {
             }
         }
 
-        if let Some((tx, ty, is_agent, _target_type)) = best_target {
+        if let Some((tx, ty, is_agent, target_type)) = best_target {
+            if target_type == "resource" {
+                self.perception_error = resource_error;
+            }
             self.target_x = Some(tx);
             self.target_y = Some(ty);
             self.state = if is_agent {
@@ -330,13 +1023,7 @@ impl Agent {
                 self.state = AgentState::Feeding;
             } else {
                 // Move towards target with predator-specific speed
-                let base_speed = self.genes.speed;
-                let hunting_speed = if self.is_predator() {
-                    base_speed * self.genes.hunting_speed * 2.0
-                } else {
-                    base_speed * 2.0
-                };
-
+                let hunting_speed = self.hunting_speed();
                 self.dx = (dx / distance) * hunting_speed;
                 self.dy = (dy / distance) * hunting_speed;
             }
@@ -345,12 +1032,39 @@ impl Agent {
         }
     }
 
+    fn hunting_speed(&self) -> f64 {
+        let base_speed = self.effective_speed();
+        let hunting_speed = if self.is_predator() {
+            base_speed * self.genes.hunting_speed * 2.0
+        } else {
+            base_speed * 2.0
+        };
+        if self.is_exhausted() {
+            hunting_speed * 0.5
+        } else {
+            hunting_speed
+        }
+    }
+
+    /// Strength of an attack by this agent on `opponent`.
+    fn combat_power(&self, opponent: &Agent) -> f64 {
+        let attack = self.genes.attack_power * self.genes.size * self.energy * 0.01;
+        let opponent_defense = opponent.genes.defense * opponent.genes.size;
+        let effective_power = attack / (opponent_defense + 1.0);
+
+        // Add intelligence and stamina factors
+        let intelligence_bonus = self.genes.intelligence * 0.5;
+        let stamina_bonus = self.genes.stamina * 0.3;
+        effective_power * (1.0 + intelligence_bonus + stamina_bonus)
+    }
+
     fn feed_on_resource(&mut self, resources: &[Resource]) -> Option<usize> {
         if let (Some(_tx), Some(_ty)) = (self.target_x, self.target_y) {
             for (i, resource) in resources.iter().enumerate() {
-                if resource.distance_to(self.x, self.y) < 5.0 {
+                if resource.energy > self.r_star && resource.distance_to(self.x, self.y) < 5.0 {
                     // Consume the resource and gain energy - much more energy from resources
                     self.energy += 50.0 * self.genes.energy_efficiency; // Increased from 20.0
+                    self.trophic_level = 1;
                     if self.energy > self.max_energy {
                         self.energy = self.max_energy;
                     }
@@ -382,26 +1096,8 @@ impl Agent {
             for agent in agents {
                 if agent.distance_to(self.x, self.y) < 5.0 {
                     // Enhanced combat mechanics using predator genes
-                    let my_attack = self.genes.attack_power * self.genes.size * self.energy * 0.01;
-                    let my_defense = self.genes.defense * self.genes.size;
-                    let their_attack =
-                        agent.genes.attack_power * agent.genes.size * agent.energy * 0.01;
-                    let their_defense = agent.genes.defense * agent.genes.size;
-
-                    // Calculate combat outcome
-                    let my_effective_power = my_attack / (their_defense + 1.0);
-                    let their_effective_power = their_attack / (my_defense + 1.0);
-
-                    // Add intelligence and stamina factors
-                    let my_intelligence_bonus = self.genes.intelligence * 0.5;
-                    let my_stamina_bonus = self.genes.stamina * 0.3;
-                    let their_intelligence_bonus = agent.genes.intelligence * 0.5;
-                    let their_stamina_bonus = agent.genes.stamina * 0.3;
-
-                    let my_total_power =
-                        my_effective_power * (1.0 + my_intelligence_bonus + my_stamina_bonus);
-                    let their_total_power = their_effective_power
-                        * (1.0 + their_intelligence_bonus + their_stamina_bonus);
+                    let my_total_power = self.combat_power(agent);
+                    let their_total_power = agent.combat_power(self);
 
                     if my_total_power > their_total_power {
                         // Win the fight - predators get more energy from prey
@@ -413,6 +1109,8 @@ impl Agent {
 
                         self.energy += energy_gain;
                         self.kills += 1;
+                        self.trophic_level = (agent.trophic_level + 1).min(MAX_TROPHIC_LEVEL);
+                        self.satiation = (self.satiation + SATIATION_PER_KILL).min(1.0);
 
                         // Predators get bonus energy from successful hunts
                         if self.is_predator() {
@@ -455,20 +1153,96 @@ impl Agent {
         self.target_y = None;
     }
 
-    fn flee_from_danger(&mut self, _delta_time: f64) {
-        if let (Some(tx), Some(ty)) = (self.target_x, self.target_y) {
-            let dx = tx - self.x;
-            let dy = ty - self.y;
-            let distance = (dx * dx + dy * dy).sqrt();
+    /// Runs from every predator within `sense_range` along `escape_heading`,
+    /// or goes back to seeking once none are left. `agents` is expected to be
+    /// the nearby agents from the spatial grid.
+    pub fn flee_from_danger(&mut self, delta_time: f64, agents: &[Agent]) {
+        let threats: Vec<(f64, f64)> = agents
+            .iter()
+            .filter(|agent| {
+                agent.id() != self.id()
+                    && agent.is_predator()
+                    && self.distance_to(agent.x, agent.y) <= self.genes.sense_range
+            })
+            .map(|agent| (agent.x, agent.y))
+            .collect();
+
+        if threats.is_empty() {
+            self.state = AgentState::Seeking;
+            self.target_x = None;
+            self.target_y = None;
+            return;
+        }
 
-            if distance > self.genes.sense_range {
+        self.state = AgentState::Fleeing;
+        if let Some((escape_x, escape_y)) = escape_heading(self.x, self.y, threats) {
+            let speed = flee_speed(self.effective_speed(), self.genes.stamina)
+                * stamina_pace(self.current_stamina, self.genes.stamina);
+            self.dx = escape_x * speed;
+            self.dy = escape_y * speed;
+        }
+        self.energy -= flee_cost(self.genes.stamina) * delta_time;
+    }
+
+    /// Chasing and fleeing drain stamina; resting restores it up to the
+    /// gene's capacity.
+    pub fn update_stamina(&mut self, delta_time: f64) {
+        let exerting = matches!(
+            self.state,
+            AgentState::Hunting | AgentState::PackHunting { .. } | AgentState::Fleeing
+        );
+        self.current_stamina = next_stamina(
+            self.current_stamina,
+            self.genes.stamina,
+            exerting,
+            delta_time,
+        );
+    }
+
+    /// Too full to hunt; grazes until digestion brings it back to `SATIATED`.
+    pub fn is_satiated(&self) -> bool {
+        self.satiation > SATIATED
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        stamina_pace(self.current_stamina, self.genes.stamina) < 1.0
+    }
+
+    /// Sets off migrating when due, and keeps migrants on their heading
+    /// until `MIGRATION_DURATION` is up.
+    pub fn update_migration(&mut self) {
+        if self.should_migrate() {
+            self.start_migration();
+        }
+        if self.state == AgentState::Migrating {
+            self.migrate();
+        }
+    }
+
+    fn should_migrate(&self) -> bool {
+        self.state != AgentState::Migrating
+            && is_due_to_migrate(self.genes.migration, self.age, self.last_migration_time)
+    }
+
+    fn start_migration(&mut self) {
+        // Each agent always heads the same compass direction
+        self.migrating_direction = Some(migration_heading(self.id()));
+        self.last_migration_time = self.age;
+        self.target_x = None;
+        self.target_y = None;
+        self.state = AgentState::Migrating;
+    }
+
+    fn migrate(&mut self) {
+        match self.migrating_direction {
+            Some((dir_x, dir_y)) if self.age - self.last_migration_time < MIGRATION_DURATION => {
+                let speed = self.effective_speed() * MIGRATION_SPEED_MULTIPLIER;
+                self.dx = dir_x * speed;
+                self.dy = dir_y * speed;
+            }
+            _ => {
+                self.migrating_direction = None;
                 self.state = AgentState::Seeking;
-                self.target_x = None;
-                self.target_y = None;
-            } else {
-                let speed = self.genes.speed * 3.0; // Faster when fleeing
-                self.dx = (dx / distance) * speed;
-                self.dy = (dy / distance) * speed;
             }
         }
     }
@@ -480,30 +1254,31 @@ impl Agent {
         self.state = AgentState::Seeking;
     }
 
-    fn move_agent(&mut self, delta_time: f64, canvas_width: f64, canvas_height: f64) {
+    /// Returns `false` if the agent crossed a lethal boundary.
+    pub fn move_agent(
+        &mut self,
+        delta_time: f64,
+        canvas_width: f64,
+        canvas_height: f64,
+        boundary: BoundaryBehavior,
+        rng: &mut impl Rng,
+    ) -> bool {
         // Apply movement
         self.x += self.dx * delta_time;
         self.y += self.dy * delta_time;
 
-        // Boundary wrapping - these will be set by the simulation
-        // For now, use reasonable defaults
-        let max_x = canvas_width;
-        let max_y = canvas_height;
-        if self.x < 0.0 {
-            self.x = max_x;
-        }
-        if self.x > max_x {
-            self.x = 0.0;
-        }
-        if self.y < 0.0 {
-            self.y = max_y;
-        }
-        if self.y > max_y {
-            self.y = 0.0;
+        if !boundary.apply(
+            &mut self.x,
+            &mut self.y,
+            &mut self.dx,
+            &mut self.dy,
+            canvas_width,
+            canvas_height,
+        ) {
+            return false;
         }
 
         // Add some randomness to movement
-        let mut rng = thread_rng();
         if rng.gen::<f64>() < 0.01 {
             let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
             self.dx += angle.cos() * 0.1;
@@ -516,17 +1291,26 @@ impl Agent {
             self.dx /= length;
             self.dy /= length;
         }
+        true
     }
 
     fn random_movement(&mut self) {
         let mut rng = thread_rng();
         let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
-        self.dx = angle.cos() * self.genes.speed;
-        self.dy = angle.sin() * self.genes.speed;
+        self.dx = angle.cos() * self.effective_speed();
+        self.dy = angle.sin() * self.effective_speed();
+    }
+
+    /// Gene speed as expressed in the current environment.
+    fn effective_speed(&self) -> f64 {
+        self.genes.speed * self.phenotype.speed_scale
     }
 
     pub fn can_reproduce(&self) -> bool {
-        self.energy > 10.0 && self.age > 2.0 && self.age - self.last_reproduction > 1.0
+        // Crowding lengthens the cooldown between litters
+        self.energy > 10.0
+            && self.age > 2.0
+            && self.age - self.last_reproduction > 1.0 / self.phenotype.reproduction_scale
         // Much faster reproduction - reproduce when they have energy
     }
 
@@ -537,27 +1321,55 @@ impl Agent {
     }
 
     pub fn id(&self) -> u64 {
-        // Simple ID based on position and generation
-        ((self.x * 1000.0) as u64) ^ ((self.y * 1000.0) as u64) ^ (self.generation as u64)
+        // Simple ID based on birthplace and generation, so it survives moving
+        let (x, y) = self.spawn_position.unwrap_or((self.x, self.y));
+        ((x * 1000.0) as u64) ^ ((y * 1000.0) as u64) ^ (self.generation as u64)
     }
 
     pub fn is_alive(&self) -> bool {
-        self.energy > 0.0 && self.age < 200.0
+        self.energy > 0.0 && self.age < MAX_AGE
     }
 
+    /// Omnivores hunt while well fed and fall back to foraging when hungry.
     pub fn is_predator(&self) -> bool {
-        self.genes.is_predator > 0.5
+        self.genes.is_predator > 0.5 || (self.genes.omnivore > 0.7 && self.energy > 60.0)
+    }
+
+    /// How strongly the agent prefers prey to resources, from 0 (pure
+    /// forager) to 1 (pure hunter): its `is_predator` gene, raised to its
+    /// `omnivore` gene while a well-fed omnivore.
+    pub fn predation(&self) -> f64 {
+        if self.genes.omnivore > 0.7 && self.energy > 60.0 {
+            self.genes.is_predator.max(self.genes.omnivore)
+        } else {
+            self.genes.is_predator
+        }
+    }
+
+    pub fn is_omnivore(&self) -> bool {
+        self.genes.omnivore > 0.7 && self.genes.is_predator <= 0.5
     }
 
     pub fn is_prey(&self) -> bool {
         !self.is_predator()
     }
 
-    pub fn create_offspring(&self, other: &Agent) -> Self {
-        let new_genes = self
-            .genes
-            .inherit_from(&other.genes, self.genes.mutation_rate);
-        let mut rng = thread_rng();
+    pub fn create_offspring(
+        &self,
+        other: &Agent,
+        mutation_distribution: MutationDistribution,
+        population_avg_attack: f64,
+        population_avg_defense: f64,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let new_genes = self.genes.inherit_from(
+            &other.genes,
+            self.genes.mutation_rate,
+            mutation_distribution,
+            population_avg_attack,
+            population_avg_defense,
+            rng,
+        );
 
         // Position offspring near parent
         let offset_x = rng.gen_range(-10.0..10.0);
@@ -565,7 +1377,9 @@ impl Agent {
         let spawn_x = self.x + offset_x;
         let spawn_y = self.y + offset_y;
 
-        let mut offspring = Self::new(spawn_x, spawn_y, new_genes, self.generation + 1);
+        let mutated_genes = mutated_gene_mask(&new_genes, &self.genes, &other.genes);
+        let mut offspring = Self::random(spawn_x, spawn_y, new_genes, self.generation + 1, rng);
+        offspring.mutated_genes = mutated_genes;
 
         // Set spawn position for proper fade-in
         offspring.spawn_position = Some((spawn_x, spawn_y));
@@ -573,4 +1387,549 @@ impl Agent {
 
         offspring
     }
+
+    /// Packs the agent into a fixed 64-byte record for streaming or replays.
+    ///
+    /// Layout (little endian): x, y as f32 (0..8); dx, dy as i8 unit direction
+    /// (8, 9); energy as u8 over 0-100 (10); age in centiseconds as u16 (11..13);
+    /// generation u16 (13..15); state in bits 0-2 and predator flag in bit 3 (15);
+    /// kills u16 (16..18); speed, size, aggression, intelligence as u8 (18..22);
+    /// trophic level u8 (22). The remaining bytes are reserved and zero.
+    pub fn to_compact_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[0..4].copy_from_slice(&(self.x as f32).to_le_bytes());
+        bytes[4..8].copy_from_slice(&(self.y as f32).to_le_bytes());
+
+        let length = (self.dx * self.dx + self.dy * self.dy).sqrt();
+        let (dir_x, dir_y) = if length > 0.0 {
+            (self.dx / length, self.dy / length)
+        } else {
+            (0.0, 0.0)
+        };
+        bytes[8] = (dir_x * 127.0).round() as i8 as u8;
+        bytes[9] = (dir_y * 127.0).round() as i8 as u8;
+
+        bytes[10] = quantize(self.energy, 0.0, 100.0);
+        let age = (self.age * 100.0).round().clamp(0.0, u16::MAX as f64) as u16;
+        bytes[11..13].copy_from_slice(&age.to_le_bytes());
+        let generation = self.generation.min(u16::MAX as u32) as u16;
+        bytes[13..15].copy_from_slice(&generation.to_le_bytes());
+
+        let predator_bit = if self.genes.is_predator > 0.5 { 1 << 3 } else { 0 };
+        bytes[15] = state_to_bits(&self.state) | predator_bit;
+        let kills = self.kills.min(u16::MAX as u32) as u16;
+        bytes[16..18].copy_from_slice(&kills.to_le_bytes());
+
+        bytes[18] = quantize(self.genes.speed, 0.1, 3.0);
+        bytes[19] = quantize(self.genes.size, 0.3, 2.5);
+        bytes[20] = quantize(self.genes.aggression, 0.0, 1.0);
+        bytes[21] = quantize(self.genes.intelligence, 0.1, 3.0);
+        bytes[22] = self.trophic_level;
+        bytes
+    }
+
+    /// Rebuilds an approximate agent from `to_compact_bytes` output. Genes not
+    /// stored in the record are freshly randomized.
+    pub fn from_compact_bytes(bytes: &[u8; 64]) -> Self {
+        let read_f32 = |i: usize| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) as f64;
+        let read_u16 = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+
+        let mut genes = Genes::new();
+        genes.speed = dequantize(bytes[18], 0.1, 3.0);
+        genes.size = dequantize(bytes[19], 0.3, 2.5);
+        genes.aggression = dequantize(bytes[20], 0.0, 1.0);
+        genes.intelligence = dequantize(bytes[21], 0.1, 3.0);
+        genes.is_predator = if bytes[15] & (1 << 3) != 0 { 1.0 } else { 0.0 };
+
+        let mut agent = Self::new(read_f32(0), read_f32(4), genes, read_u16(13) as u32);
+        agent.dx = bytes[8] as i8 as f64 / 127.0;
+        agent.dy = bytes[9] as i8 as f64 / 127.0;
+        agent.energy = dequantize(bytes[10], 0.0, 100.0);
+        agent.age = read_u16(11) as f64 / 100.0;
+        agent.state = state_from_bits(bytes[15] & 0b111);
+        agent.kills = read_u16(16) as u32;
+        // Records written before the trophic level was stored hold 0 here
+        agent.trophic_level = bytes[22].clamp(1, MAX_TROPHIC_LEVEL);
+        agent.spawn_fade = 1.0;
+        agent
+    }
+}
+
+fn quantize(value: f64, min: f64, max: f64) -> u8 {
+    (((value - min) / (max - min)).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn dequantize(value: u8, min: f64, max: f64) -> f64 {
+    min + value as f64 / 255.0 * (max - min)
+}
+
+fn state_to_bits(state: &AgentState) -> u8 {
+    match state {
+        AgentState::Seeking => 0,
+        AgentState::Hunting => 1,
+        AgentState::Feeding => 2,
+        AgentState::Reproducing => 3,
+        AgentState::Fighting => 4,
+        AgentState::Fleeing => 5,
+        AgentState::Migrating => 6,
+        AgentState::PackHunting { .. } => 7,
+    }
+}
+
+fn state_from_bits(bits: u8) -> AgentState {
+    match bits {
+        1 => AgentState::Hunting,
+        2 => AgentState::Feeding,
+        3 => AgentState::Reproducing,
+        4 => AgentState::Fighting,
+        5 => AgentState::Fleeing,
+        6 => AgentState::Migrating,
+        // The pack's target isn't stored, so pack hunters come back solo
+        7 => AgentState::Hunting,
+        _ => AgentState::Seeking,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent_at(x: f64, y: f64, is_predator: f64) -> Agent {
+        let genes = Genes {
+            is_predator,
+            sense_range: 100.0,
+            ..Genes::new()
+        };
+        Agent::new(x, y, genes, 0)
+    }
+
+    #[test]
+    fn test_social_learning_raises_average_fitness() {
+        // A crowd where energy tracks genetic fitness, so the richest
+        // neighbour is also the fittest one. Positions, and so ids, stay
+        // distinct whatever order the agents are in.
+        let crowd: Vec<Agent> = (0..100)
+            .map(|i| {
+                let genes = Genes {
+                    intelligence: 1.0,
+                    aggression: 0.3,
+                    sense_range: 500.0,
+                    ..Genes::new()
+                };
+                Agent::new((i % 10) as f64 * 10.0, (i / 10) as f64 * 10.0, genes, 1)
+            })
+            .collect();
+        let average_fitness = |agents: &[Agent]| {
+            agents.iter().map(|a| a.genes.get_fitness_score()).sum::<f64>() / agents.len() as f64
+        };
+        let run = |learning: bool| {
+            let mut rng = thread_rng();
+            let mut agents = crowd.clone();
+            for _ in 0..200 {
+                // Energy by fitness rank: the best agent has ten times the worst's
+                agents.sort_by(|a, b| {
+                    a.genes.get_fitness_score().total_cmp(&b.genes.get_fitness_score())
+                });
+                for (rank, agent) in agents.iter_mut().enumerate() {
+                    agent.energy = 10.0 + rank as f64;
+                }
+                let snapshot = agents.clone();
+                if learning {
+                    for agent in &mut agents {
+                        agent.learn_from_neighbors(&snapshot, &mut rng);
+                    }
+                }
+            }
+            average_fitness(&agents)
+        };
+
+        let initial = average_fitness(&crowd);
+        let best = crowd.iter().map(|a| a.genes.get_fitness_score()).fold(0.0, f64::max);
+        let without_learning = run(false);
+        let with_learning = run(true);
+
+        assert!((without_learning - initial).abs() < 1e-9);
+        // Learners close at least 30% of the gap to the best agent
+        assert!(
+            with_learning > initial + 0.3 * (best - initial),
+            "{} -> {} (best {})",
+            initial,
+            with_learning,
+            best
+        );
+    }
+
+    #[test]
+    fn test_crowding_suppresses_reproduction() {
+        let mut crowded = agent_at(100.0, 100.0, 0.0);
+        let neighbours: Vec<Agent> = (0..20)
+            .map(|i| {
+                let x = 101.0 + (i % 5) as f64 * 3.0;
+                let y = 100.0 + (i / 5) as f64 * 3.0;
+                agent_at(x, y, 0.0)
+            })
+            .collect();
+        crowded.update_behavior_state(&[], &neighbours);
+
+        let mut isolated = agent_at(500.0, 500.0, 0.0);
+        isolated.update_behavior_state(&[], &[]);
+
+        assert!(crowded.phenotype.reproduction_scale < 0.5);
+        assert_eq!(isolated.phenotype.reproduction_scale, 1.0);
+        assert!(crowded.phenotype.reproduction_scale < isolated.phenotype.reproduction_scale);
+    }
+
+    #[test]
+    fn test_omnivore_switches_role_with_energy() {
+        let mut omnivore = agent_at(0.0, 0.0, 0.0);
+        omnivore.genes.omnivore = 0.9;
+
+        omnivore.energy = 80.0;
+        assert!(omnivore.is_predator());
+
+        omnivore.energy = 20.0;
+        assert!(omnivore.is_prey());
+
+        let mut prey = agent_at(0.0, 0.0, 0.0);
+        prey.genes.omnivore = 0.1;
+        prey.energy = 80.0;
+        assert!(prey.is_prey());
+    }
+
+    #[test]
+    fn test_surrounded_agent_flees_through_gap() {
+        let mut prey = agent_at(200.0, 200.0, 0.0);
+        prey.state = AgentState::Fleeing;
+
+        // Predators to the east, north and south; the west is open
+        let predators = vec![
+            agent_at(230.0, 200.0, 1.0),
+            agent_at(200.0, 170.0, 1.0),
+            agent_at(200.0, 230.0, 1.0),
+        ];
+        let energy_before = prey.energy;
+
+        prey.flee_from_danger(1.0 / 60.0, &predators);
+
+        assert_eq!(prey.state, AgentState::Fleeing);
+        assert!(prey.dx < 0.0);
+        assert!(prey.dy.abs() < prey.dx.abs() * 0.01);
+        assert!(prey.energy < energy_before);
+    }
+
+    #[test]
+    fn test_compact_bytes_round_trip() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let mut agent = agent_at(
+                rng.gen_range(0.0..1000.0),
+                rng.gen_range(0.0..800.0),
+                rng.gen_range(0.0..1.0),
+            );
+            agent.energy = rng.gen_range(0.0..100.0);
+            agent.age = rng.gen_range(0.0..200.0);
+            agent.generation = rng.gen_range(0..1000);
+            agent.kills = rng.gen_range(0..50);
+            agent.state = state_from_bits(rng.gen_range(0..7));
+            agent.trophic_level = rng.gen_range(1..=MAX_TROPHIC_LEVEL);
+
+            let restored = Agent::from_compact_bytes(&agent.to_compact_bytes());
+
+            assert!((restored.energy - agent.energy).abs() <= 0.5);
+            assert!((restored.x - agent.x).abs() < 1e-3);
+            assert!((restored.y - agent.y).abs() < 1e-3);
+            assert!((restored.age - agent.age).abs() <= 0.01);
+            assert_eq!(restored.generation, agent.generation);
+            assert_eq!(restored.kills, agent.kills);
+            assert_eq!(restored.trophic_level, agent.trophic_level);
+            assert_eq!(restored.state, agent.state);
+            assert_eq!(restored.genes.is_predator > 0.5, agent.genes.is_predator > 0.5);
+            assert!((restored.genes.speed - agent.genes.speed).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_high_metabolism_agents_ignore_small_patches() {
+        let mut patch = Resource::new(10.0, 0.0);
+        patch.energy = 10.0;
+        patch.spawn_fade = 1.0;
+        patch.is_spawning = false;
+
+        let forager = |metabolism: f64, size: f64| {
+            let mut agent = agent_at(0.0, 0.0, 0.0);
+            agent.genes.omnivore = 0.0;
+            agent.genes.metabolism = metabolism;
+            agent.genes.size = size;
+            agent.r_star = Agent::r_star_for(&agent.genes);
+            agent
+        };
+
+        let mut lean = forager(0.8, 0.9);
+        lean.seek_targets(std::slice::from_ref(&patch), &[]);
+        assert!(lean.target_x.is_some_and(|x| (x - patch.x).abs() < 5.0));
+
+        let mut hungry = forager(2.5, 2.0);
+        assert!(hungry.r_star > patch.energy);
+        hungry.seek_targets(std::slice::from_ref(&patch), &[]);
+        assert_eq!(hungry.target_x, None);
+        assert_eq!(hungry.state, AgentState::Seeking);
+    }
+
+    #[test]
+    fn test_stamina_drains_during_hunt() {
+        let mut hunter = agent_at(0.0, 0.0, 1.0);
+        // Past infant mortality, but not yet due to breed again
+        hunter.age = 10.0;
+        hunter.last_reproduction = hunter.age + 5.0;
+        hunter.genes.stamina = 1.0;
+        hunter.current_stamina = 1.0;
+        hunter.state = AgentState::Hunting;
+        hunter.target_x = Some(900.0);
+        hunter.target_y = Some(700.0);
+
+        for _ in 0..10 {
+            hunter.update(0.1, &[], &[], 1000.0, 800.0, BoundaryBehavior::Wrap);
+        }
+        assert_eq!(hunter.state, AgentState::Hunting);
+        assert!(hunter.current_stamina < 1.0);
+
+        for _ in 0..10 {
+            hunter.update(0.1, &[], &[], 1000.0, 800.0, BoundaryBehavior::Wrap);
+        }
+        assert!(hunter.is_exhausted());
+
+        let exhausted_stamina = hunter.current_stamina;
+        hunter.state = AgentState::Seeking;
+        hunter.update(0.1, &[], &[], 1000.0, 800.0, BoundaryBehavior::Wrap);
+        assert!(hunter.current_stamina > exhausted_stamina);
+    }
+
+    #[test]
+    fn test_apex_predators_emerge_from_kills_up_the_chain() {
+        // The prey already ate a herbivore, so whoever kills it is apex
+        let mut prey = agent_at(400.0, 400.0, 0.0);
+        prey.trophic_level = 2;
+        let mut predators: Vec<Agent> = [(403.0, 400.0), (600.0, 400.0)]
+            .into_iter()
+            .map(|(x, y)| {
+                let mut predator = agent_at(x, y, 1.0);
+                predator.genes.attack_power = 3.0;
+                predator.genes.migration = 0.0;
+                // Past infant mortality, but not yet due to breed again
+                predator.age = 30.0;
+                predator.last_reproduction = 1000.0;
+                predator.state = AgentState::Fighting;
+                predator.target_x = Some(prey.x);
+                predator.target_y = Some(prey.y);
+                predator
+            })
+            .collect();
+        assert_eq!(trophic_summary(&predators).1, 0);
+
+        let snapshot = [prey.clone()];
+        for predator in &mut predators {
+            predator.update(0.05, &[], &snapshot, 1000.0, 800.0, BoundaryBehavior::Wrap);
+        }
+
+        let apex: Vec<&Agent> = predators
+            .iter()
+            .filter(|predator| predator.trophic_level >= APEX_TROPHIC_LEVEL)
+            .collect();
+        assert!(!apex.is_empty(), "no predator killed the prey");
+        assert!(apex.iter().all(|predator| predator.kills > 0));
+        let mut population = predators.clone();
+        population.push(prey);
+        let (average, apex_count, diversity) = trophic_summary(&population);
+        assert_eq!(apex_count, apex.len());
+        assert!(average > 1.0);
+        assert!(diversity > 0.0);
+    }
+
+    #[test]
+    fn test_feeding_resets_trophic_level() {
+        let mut grazer = agent_at(100.0, 100.0, 0.0);
+        grazer.trophic_level = 4;
+        grazer.target_x = Some(100.0);
+        grazer.target_y = Some(100.0);
+        let mut resource = Resource::new(101.0, 100.0);
+        resource.energy = 100.0;
+
+        assert_eq!(grazer.feed_on_resource(&[resource]), Some(0));
+        assert_eq!(grazer.trophic_level, 1);
+    }
+
+    #[test]
+    fn test_lethal_boundary_kills_agent() {
+        let mut wanderer = agent_at(1005.0, 400.0, 0.0);
+        wanderer.update(0.1, &[], &[], 1000.0, 800.0, BoundaryBehavior::Lethal);
+        assert!(wanderer.is_dying);
+        assert_eq!(wanderer.death_reason, Some(DeathReason::FellOffEdge));
+
+        let mut survivor = agent_at(1005.0, 400.0, 0.0);
+        survivor.age = 10.0; // Past infant mortality
+        survivor.update(0.1, &[], &[], 1000.0, 800.0, BoundaryBehavior::Bounce);
+        assert!(!survivor.is_dying);
+        assert!(survivor.x <= 1000.0);
+    }
+
+    #[test]
+    fn test_deaths_follow_bathtub_curve() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let delta_time = 0.1;
+        let mut deaths = [0; AGE_BUCKETS];
+        for _ in 0..5000 {
+            let mut age: f64 = 0.0;
+            while age < MAX_AGE && rng.gen::<f64>() >= mortality_rate(age, 1.0) * delta_time {
+                age += delta_time;
+            }
+            deaths[age_bucket(age)] += 1;
+        }
+
+        // Ages at death are U-shaped: many infants, many elders, few in between
+        let (first, last) = (deaths[0], deaths[AGE_BUCKETS - 1]);
+        for &middle in &deaths[1..AGE_BUCKETS - 3] {
+            assert!(first > middle && last > middle, "{:?}", deaths);
+        }
+    }
+
+    #[test]
+    fn test_stamina_delays_senescence() {
+        assert!(mortality_rate(2.0, 1.0) > mortality_rate(50.0, 1.0));
+        assert!(mortality_rate(180.0, 1.0) > mortality_rate(50.0, 1.0));
+        assert!(mortality_rate(180.0, 1.5) < mortality_rate(180.0, 1.0));
+    }
+
+    #[test]
+    fn test_agent_fits_memory_budget() {
+        // `estimated_heap_bytes` multiplies this by the population
+        assert!(std::mem::size_of::<Agent>() < 512, "{}", std::mem::size_of::<Agent>());
+    }
+
+    #[test]
+    fn test_gene_boxplot_is_ordered() {
+        let agents: Vec<Agent> = (0..200).map(|_| Agent::new(0.0, 0.0, Genes::new(), 0)).collect();
+        let boxplot = gene_boxplot(&agents, "speed").unwrap();
+
+        assert!(boxplot.p25 <= boxplot.median && boxplot.median <= boxplot.p75);
+        assert!(boxplot.p5 <= boxplot.p25 && boxplot.p75 <= boxplot.p95);
+        assert!(boxplot.p5 < boxplot.p95, "{:?}", boxplot);
+        assert!(boxplot.p5 <= boxplot.mean && boxplot.mean <= boxplot.p95);
+        assert_eq!(gene_boxplot(&[], "speed"), Some(GeneBoxplot::default()));
+        assert!(gene_boxplot(&agents, "wingspan").is_none());
+    }
+
+    #[test]
+    fn test_dim_agents_misjudge_where_food_is() {
+        let mut patch = Resource::new(90.0, 0.0);
+        patch.energy = 30.0;
+        patch.spawn_fade = 1.0;
+        patch.is_spawning = false;
+
+        // Walks straight to where a patch near the edge of its senses seemed
+        // to be, and reports whether it could feed there
+        let reaches_food = |intelligence: f64| {
+            let mut grazer = agent_at(0.0, 0.0, 0.0);
+            grazer.genes.intelligence = intelligence;
+            grazer.r_star = 0.0;
+            grazer.seek_targets(std::slice::from_ref(&patch), &[]);
+            grazer.x = grazer.target_x.unwrap();
+            grazer.y = grazer.target_y.unwrap();
+            let error = grazer.distance_to(patch.x, patch.y);
+            assert!((grazer.perception_error - error).abs() < 1e-9);
+            grazer.feed_on_resource(std::slice::from_ref(&patch)).is_some()
+        };
+        let successes = |intelligence| (0..200).filter(|_| reaches_food(intelligence)).count();
+
+        assert!(successes(0.1) < 100);
+        assert!(successes(2.0) > 190);
+    }
+
+    #[test]
+    fn test_satiated_predator_grazes_instead_of_hunting() {
+        let mut prey = agent_at(20.0, 0.0, 0.0);
+        prey.genes.omnivore = 0.0;
+        let mut patch = Resource::new(-40.0, 0.0);
+        patch.energy = 30.0;
+        patch.spawn_fade = 1.0;
+        patch.is_spawning = false;
+        let predator = || {
+            let mut agent = agent_at(0.0, 0.0, 1.0);
+            agent.genes.territory_size = 100.0;
+            agent.r_star = 0.0;
+            agent
+        };
+
+        let mut hungry = predator();
+        hungry.seek_targets(&[], std::slice::from_ref(&prey));
+        assert_eq!(hungry.target_x, Some(prey.x));
+
+        let mut full = predator();
+        full.satiation = 0.9;
+        full.seek_targets(&[], std::slice::from_ref(&prey));
+        assert_eq!(full.target_x, None);
+        assert_eq!(full.state, AgentState::Seeking);
+
+        full.seek_targets(std::slice::from_ref(&patch), std::slice::from_ref(&prey));
+        assert!(full.target_x.is_some_and(|x| (x - patch.x).abs() < 5.0));
+        assert_eq!(full.state, AgentState::Hunting);
+    }
+
+    #[test]
+    fn test_predator_gene_trades_hunting_against_foraging() {
+        let mut prey = agent_at(20.0, 0.0, 0.0);
+        prey.genes.omnivore = 0.0;
+        prey.energy = 100.0;
+        let mut patch = Resource::new(-20.0, 0.0);
+        patch.energy = 40.0;
+        patch.spawn_fade = 1.0;
+        patch.is_spawning = false;
+        let seeker = |is_predator| {
+            let mut agent = agent_at(0.0, 0.0, is_predator);
+            agent.genes.omnivore = 0.0;
+            agent.genes.territory_size = 100.0;
+            agent.genes.stealth = 0.0;
+            agent.genes.intelligence = 1.0;
+            agent.r_star = 0.0;
+            agent
+        };
+        let target = |is_predator, resources: &[Resource]| {
+            let mut agent = seeker(is_predator);
+            agent.seek_targets(resources, std::slice::from_ref(&prey));
+            agent.target_x
+        };
+        let both = std::slice::from_ref(&patch);
+
+        // Keen hunters pick the prey and foragers the food, the switch
+        // depending on what's on offer rather than on a fixed 0.5
+        assert_eq!(target(0.8, both), Some(prey.x));
+        assert_eq!(target(0.6, both), Some(prey.x));
+        assert!(target(0.4, both).is_some_and(|x| (x - patch.x).abs() < 5.0));
+        assert!(target(0.2, both).is_some_and(|x| (x - patch.x).abs() < 5.0));
+        // With nothing to graze the forager hunts opportunistically
+        assert_eq!(target(0.2, &[]), Some(prey.x));
+    }
+
+    #[test]
+    fn test_clever_agents_remember_where_they_were_poisoned() {
+        let poisoned = |intelligence: f64, defense: f64, energy: f64| {
+            let mut agent = agent_at(0.0, 0.0, 0.0);
+            agent.genes.intelligence = intelligence;
+            agent.genes.defense = defense;
+            agent.energy = energy;
+            agent.eat_poison(3.0, 4.0, crate::resource::POISON_ENERGY_DELTA);
+            agent
+        };
+
+        let clever = poisoned(2.0, 0.5, 80.0);
+        assert_eq!(clever.energy, 65.0);
+        assert_eq!(clever.death_reason, None);
+        assert!(clever.avoids_poison_at(5.0, 5.0));
+        assert!(!clever.avoids_poison_at(50.0, 50.0));
+
+        let dim = poisoned(1.0, 0.0, 20.0);
+        assert_eq!(dim.death_reason, Some(DeathReason::Poisoned));
+        assert!(!dim.avoids_poison_at(3.0, 4.0));
+        // Thick-skinned agents shrug it off
+        assert_eq!(poisoned(1.0, 1.2, 50.0).energy, 50.0);
+    }
 }
@@ -0,0 +1,62 @@
+use crate::genes::Genes;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+/// Where the agents of one `AgentArchetype` start.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum PositionDistribution {
+    /// Anywhere in the world with equal probability.
+    #[default]
+    Uniform,
+    /// Normally distributed around (cx, cy), clamped to the world.
+    Gaussian { cx: f64, cy: f64, std: f64 },
+    /// Every agent on the same point.
+    Fixed { x: f64, y: f64 },
+}
+
+impl PositionDistribution {
+    pub fn sample(&self, width: f64, height: f64, rng: &mut impl Rng) -> (f64, f64) {
+        match *self {
+            PositionDistribution::Uniform => {
+                (rng.gen_range(0.0..width), rng.gen_range(0.0..height))
+            }
+            PositionDistribution::Gaussian { cx, cy, std } => {
+                let (x, y) = match Normal::new(0.0, std) {
+                    Ok(normal) => (cx + normal.sample(rng), cy + normal.sample(rng)),
+                    Err(_) => (cx, cy),
+                };
+                (x.clamp(0.0, width), y.clamp(0.0, height))
+            }
+            PositionDistribution::Fixed { x, y } => (x, y),
+        }
+    }
+}
+
+/// `count` founders sharing exactly `genes` and `initial_energy`, such as
+/// 200 slow herbivores or 20 fast predators.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgentArchetype {
+    pub genes: Genes,
+    pub initial_energy: f64,
+    #[serde(default)]
+    pub position_distribution: PositionDistribution,
+    pub count: usize,
+}
+
+/// Calls `spawn(x, y, genes, energy)` once per agent of every archetype, in
+/// order, with positions drawn from each archetype's distribution.
+pub fn spawn_archetypes(
+    archetypes: &[AgentArchetype],
+    width: f64,
+    height: f64,
+    rng: &mut impl Rng,
+    mut spawn: impl FnMut(f64, f64, Genes, f64),
+) {
+    for archetype in archetypes {
+        for _ in 0..archetype.count {
+            let (x, y) = archetype.position_distribution.sample(width, height, rng);
+            spawn(x, y, archetype.genes.clone(), archetype.initial_energy);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Rectangular region whose resources are denser or sparser, and richer or
+/// poorer, than elsewhere.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BiomeZone {
+    /// (x, y, width, height) with (x, y) the top-left corner.
+    pub rect: (f64, f64, f64, f64),
+    /// Relative chance that a resource spawn attempt inside the zone succeeds.
+    pub resource_spawn_multiplier: f64,
+    /// Factor on the `max_energy`, `growth_rate` and `regeneration_rate` of
+    /// resources grown inside.
+    pub energy_multiplier: f64,
+    pub name: String,
+}
+
+impl BiomeZone {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let (left, top, width, height) = self.rect;
+        x >= left && x < left + width && y >= top && y < top + height
+    }
+}
+
+/// Biome zones laid over the world. Where zones overlap the first one wins;
+/// outside every zone both multipliers are 1.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct BiomeMap {
+    pub zones: Vec<BiomeZone>,
+}
+
+impl BiomeMap {
+    pub fn new(zones: Vec<BiomeZone>) -> Self {
+        Self { zones }
+    }
+
+    pub fn zone_at(&self, x: f64, y: f64) -> Option<&BiomeZone> {
+        self.zones.iter().find(|zone| zone.contains(x, y))
+    }
+
+    pub fn spawn_multiplier_at(&self, x: f64, y: f64) -> f64 {
+        self.zone_at(x, y)
+            .map_or(1.0, |zone| zone.resource_spawn_multiplier)
+    }
+
+    pub fn energy_multiplier_at(&self, x: f64, y: f64) -> f64 {
+        self.zone_at(x, y)
+            .map_or(1.0, |zone| zone.energy_multiplier)
+    }
+
+    /// Whether a resource spawn attempt at (x, y) goes ahead. Attempts are
+    /// kept with probability proportional to the local spawn multiplier, the
+    /// richest zone (or the open ground, if richer) always succeeding, so
+    /// density across the world follows the multipliers.
+    pub fn accepts_spawn(&self, x: f64, y: f64, rng: &mut impl Rng) -> bool {
+        let richest = self
+            .zones
+            .iter()
+            .map(|zone| zone.resource_spawn_multiplier)
+            .fold(1.0, f64::max);
+        let chance = (self.spawn_multiplier_at(x, y) / richest).clamp(0.0, 1.0);
+        rng.gen_bool(chance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawns_are_rejected_in_proportion_to_the_multiplier() {
+        let map = BiomeMap::new(vec![
+            BiomeZone {
+                rect: (0.0, 0.0, 100.0, 100.0),
+                resource_spawn_multiplier: 4.0,
+                energy_multiplier: 2.0,
+                name: "forest".to_string(),
+            },
+            BiomeZone {
+                rect: (50.0, 50.0, 100.0, 100.0),
+                resource_spawn_multiplier: 1.0,
+                energy_multiplier: 0.5,
+                name: "desert".to_string(),
+            },
+        ]);
+        assert_eq!(map.zone_at(75.0, 75.0).unwrap().name, "forest");
+        assert_eq!(map.energy_multiplier_at(120.0, 120.0), 0.5);
+        assert_eq!(map.energy_multiplier_at(500.0, 500.0), 1.0);
+
+        let mut rng = rand::thread_rng();
+        let accepted = |x: f64, y: f64, rng: &mut rand::rngs::ThreadRng| {
+            (0..4000).filter(|_| map.accepts_spawn(x, y, rng)).count()
+        };
+        assert_eq!(accepted(10.0, 10.0, &mut rng), 4000);
+        let desert = accepted(120.0, 120.0, &mut rng);
+        assert!((800..1200).contains(&desert), "{}", desert);
+    }
+}
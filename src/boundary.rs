@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// What happens to an agent that moves past the edge of the world.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundaryBehavior {
+    /// Reappear on the opposite edge.
+    #[default]
+    Wrap,
+    /// Reflect off the edge, keeping the angle of incidence.
+    Bounce,
+    /// Die on contact with the edge.
+    Lethal,
+}
+
+impl BoundaryBehavior {
+    /// Keeps `(x, y)` inside a `width` x `height` world, reflecting the
+    /// velocity when bouncing. Returns `false` if the position is out of bounds
+    /// under `Lethal`, in which case nothing is modified.
+    pub fn apply(
+        self,
+        x: &mut f64,
+        y: &mut f64,
+        dx: &mut f64,
+        dy: &mut f64,
+        width: f64,
+        height: f64,
+    ) -> bool {
+        match self {
+            BoundaryBehavior::Wrap => {
+                if *x < 0.0 {
+                    *x = width;
+                } else if *x > width {
+                    *x = 0.0;
+                }
+                if *y < 0.0 {
+                    *y = height;
+                } else if *y > height {
+                    *y = 0.0;
+                }
+                true
+            }
+            BoundaryBehavior::Bounce => {
+                if *x < 0.0 || *x > width {
+                    *x = x.clamp(0.0, width);
+                    *dx = -*dx;
+                }
+                if *y < 0.0 || *y > height {
+                    *y = y.clamp(0.0, height);
+                    *dy = -*dy;
+                }
+                true
+            }
+            BoundaryBehavior::Lethal => (0.0..=width).contains(x) && (0.0..=height).contains(y),
+        }
+    }
+}
+
+impl FromStr for BoundaryBehavior {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "wrap" => Ok(BoundaryBehavior::Wrap),
+            "bounce" => Ok(BoundaryBehavior::Bounce),
+            "lethal" => Ok(BoundaryBehavior::Lethal),
+            _ => Err(format!("Unknown boundary behavior: {}", mode)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_moves_to_opposite_edge() {
+        let (mut x, mut y, mut dx, mut dy) = (-1.0, 50.0, -1.0, 0.0);
+        assert!(BoundaryBehavior::Wrap.apply(&mut x, &mut y, &mut dx, &mut dy, 100.0, 80.0));
+        assert_eq!((x, y), (100.0, 50.0));
+        assert_eq!((dx, dy), (-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_bounce_reflects_angle_of_incidence() {
+        let (mut x, mut y, mut dx, mut dy) = (103.0, 40.0, 0.6, 0.8);
+        assert!(BoundaryBehavior::Bounce.apply(&mut x, &mut y, &mut dx, &mut dy, 100.0, 80.0));
+        assert_eq!((x, y), (100.0, 40.0));
+        assert_eq!((dx, dy), (-0.6, 0.8));
+
+        let (mut x, mut y, mut dx, mut dy) = (-2.0, -3.0, -0.6, -0.8);
+        assert!(BoundaryBehavior::Bounce.apply(&mut x, &mut y, &mut dx, &mut dy, 100.0, 80.0));
+        assert_eq!((x, y), (0.0, 0.0));
+        assert_eq!((dx, dy), (0.6, 0.8));
+    }
+
+    #[test]
+    fn test_lethal_rejects_out_of_bounds() {
+        let (mut x, mut y, mut dx, mut dy) = (50.0, 81.0, 0.0, 1.0);
+        assert!(!BoundaryBehavior::Lethal.apply(&mut x, &mut y, &mut dx, &mut dy, 100.0, 80.0));
+        let (mut x, mut y) = (50.0, 79.0);
+        assert!(BoundaryBehavior::Lethal.apply(&mut x, &mut y, &mut dx, &mut dy, 100.0, 80.0));
+    }
+
+    #[test]
+    fn test_parse_modes() {
+        assert_eq!("bounce".parse(), Ok(BoundaryBehavior::Bounce));
+        assert_eq!("lethal".parse(), Ok(BoundaryBehavior::Lethal));
+        assert_eq!("wrap".parse(), Ok(BoundaryBehavior::Wrap));
+        assert!("sticky".parse::<BoundaryBehavior>().is_err());
+    }
+}
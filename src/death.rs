@@ -0,0 +1,91 @@
+use crate::agent::DeathReason;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Default for `SimulationConfig::max_death_history`.
+pub const DEFAULT_MAX_DEATH_HISTORY: usize = 5000;
+
+/// Where, when and why one agent died.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct DeathRecord {
+    pub step: usize,
+    pub x: f64,
+    pub y: f64,
+    pub age: f64,
+    pub generation: u32,
+    pub reason: DeathReason,
+    pub is_predator: bool,
+}
+
+/// Ring buffer of the most recent deaths, for post-run analysis.
+#[derive(Clone, Debug, Default)]
+pub struct DeathHistory {
+    records: VecDeque<DeathRecord>,
+}
+
+impl DeathHistory {
+    /// Appends `record`, dropping the oldest entries so at most `capacity`
+    /// remain.
+    pub fn push(&mut self, record: DeathRecord, capacity: usize) {
+        self.records.push_back(record);
+        while self.records.len() > capacity {
+            self.records.pop_front();
+        }
+    }
+
+    /// Oldest first.
+    pub fn records(&self) -> &VecDeque<DeathRecord> {
+        &self.records
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+/// How many of `records` had each reason.
+pub fn count_by_reason<'a>(
+    records: impl IntoIterator<Item = &'a DeathRecord>,
+) -> HashMap<DeathReason, usize> {
+    let mut counts = HashMap::new();
+    for record in records {
+        *counts.entry(record.reason.clone()).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(step: usize, reason: DeathReason) -> DeathRecord {
+        DeathRecord {
+            step,
+            x: 0.0,
+            y: 0.0,
+            age: 1.0,
+            generation: 0,
+            reason,
+            is_predator: false,
+        }
+    }
+
+    #[test]
+    fn test_history_keeps_most_recent_deaths() {
+        let mut history = DeathHistory::default();
+        for step in 0..10 {
+            let reason = if step < 7 {
+                DeathReason::Starvation
+            } else {
+                DeathReason::OldAge
+            };
+            history.push(record(step, reason), 5);
+        }
+
+        assert_eq!(history.records().len(), 5);
+        assert_eq!(history.records()[0].step, 5);
+        let counts = count_by_reason(history.records());
+        assert_eq!(counts[&DeathReason::Starvation], 2);
+        assert_eq!(counts[&DeathReason::OldAge], 3);
+    }
+}
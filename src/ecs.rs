@@ -1,7 +1,28 @@
-use hecs::World;
+use crate::agent::{
+    age_bucket, default_trophic_level, r_star, trophic_level_summary, PhenotypeModifiers,
+};
+use crate::archetype::{spawn_archetypes, AgentArchetype};
+use crate::biome::BiomeMap;
+use crate::boundary::BoundaryBehavior;
+use crate::ecs_systems::{Scheduler, System};
+use crate::genes::{genetic_distance, sample_genes, InitialGeneDistribution};
+use crate::habitat::Habitat;
+use crate::interaction::InteractionLog;
+use crate::resource::{
+    near_poisoning, remember_poisoning, ResourceType, DECOMPOSITION_RETAINED, POISON_CHANCE,
+    POISON_ENERGY_DELTA,
+};
+use crate::rng::{simulation_rng, SimulationRng};
+use crate::simulation_core::SimulationStats;
+use crate::species::{assign_genome_species_ids, genome_species_summary, SpeciesSummary};
+use crate::stats::sample_variance;
+use crate::teleporter::{teleport, Teleporter};
+use hecs::{Entity, EntityBuilder, World};
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 // ============================================================================
 // COMPONENTS
@@ -50,6 +71,29 @@ pub struct Genes {
     pub metabolism: f64,
     pub intelligence: f64,
     pub stamina: f64,
+    pub omnivore: f64,
+    pub migration: f64,
+    #[serde(default)]
+    pub cooperation: f64,
+    pub mutation_strength: f64,
+}
+
+impl Genes {
+    /// Omnivores hunt while well fed and fall back to foraging when hungry,
+    /// as `agent::Agent::is_predator` has it.
+    pub fn is_predator_at(&self, energy: f64) -> bool {
+        self.is_predator > 0.5 || (self.omnivore > 0.7 && energy > 60.0)
+    }
+
+    /// Preference for prey over resources at `energy`; see
+    /// `agent::Agent::predation`.
+    pub fn predation_at(&self, energy: f64) -> f64 {
+        if self.omnivore > 0.7 && energy > 60.0 {
+            self.is_predator.max(self.omnivore)
+        } else {
+            self.is_predator
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -57,19 +101,104 @@ pub struct AgentState {
     pub state: AgentStateEnum,
     pub target_x: Option<f64>,
     pub target_y: Option<f64>,
+    /// `StableId` of the prey at `target_x`/`target_y`, if chasing one.
+    #[serde(default)]
+    pub target_id: Option<u64>,
     pub last_reproduction: f64,
     pub kills: u32,
     pub generation: u32,
+    /// Of `kills`, those made while pack hunting.
+    #[serde(default)]
+    pub pack_kills: u32,
+    /// Compass heading while `Migrating`; see `agent::migration_heading`.
+    #[serde(default)]
+    pub migrating_direction: Option<(f64, f64)>,
+    /// `Age` at which the agent last set off migrating.
+    #[serde(default)]
+    pub last_migration_time: f64,
+    /// How its surroundings scaled its genes' expression this tick.
+    #[serde(default)]
+    pub phenotype: PhenotypeModifiers,
+    /// Stamina left out of its `Genes::stamina`; see `agent::next_stamina`.
+    #[serde(default)]
+    pub current_stamina: f64,
+    /// 0.0-1.0, raised by kills and digested over time; see `agent::SATIATED`.
+    #[serde(default)]
+    pub satiation: f64,
+    /// How far its last target or threat was misperceived; see
+    /// `agent::perceived_position`.
+    #[serde(default)]
+    pub perception_error: f64,
+    /// 1 for a primary consumer, its last kill's level + 1 after a kill.
+    #[serde(default = "default_trophic_level")]
+    pub trophic_level: u8,
+}
+
+/// Where an agent last fed, so it can head back there when no food is in
+/// sight.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Memory {
+    pub last_food_x: Option<f64>,
+    pub last_food_y: Option<f64>,
+    /// The agent's `Age` when it last fed.
+    pub last_food_time: f64,
+    /// Times it has come back to a remembered spot and found food there.
+    pub visits: u32,
+    /// Where it was poisoned, if clever enough to recall; see
+    /// `resource::POISON_LEARNING_INTELLIGENCE`.
+    #[serde(default)]
+    pub poisoned_spots: Vec<(f64, f64)>,
+}
+
+impl Memory {
+    pub fn remember(&mut self, x: f64, y: f64, time: f64) {
+        self.last_food_x = Some(x);
+        self.last_food_y = Some(y);
+        self.last_food_time = time;
+    }
+
+    pub fn forget(&mut self) {
+        self.last_food_x = None;
+        self.last_food_y = None;
+    }
+
+    /// The remembered spot, if the agent fed there within `span` seconds of
+    /// `now` and it lies within `reach` of (x, y).
+    pub fn recall(&self, x: f64, y: f64, now: f64, span: f64, reach: f64) -> Option<(f64, f64)> {
+        let (fx, fy) = (self.last_food_x?, self.last_food_y?);
+        (now - self.last_food_time <= span && (fx - x).hypot(fy - y) <= reach).then_some((fx, fy))
+    }
+
+    pub fn remember_poisoning(&mut self, x: f64, y: f64) {
+        remember_poisoning(&mut self.poisoned_spots, x, y);
+    }
+
+    /// Whether the agent shuns resources at (x, y) for having been poisoned
+    /// near it.
+    pub fn avoids_poison_at(&self, x: f64, y: f64) -> bool {
+        near_poisoning(&self.poisoned_spots, x, y)
+    }
+
+    /// Whether the agent is on its way back to the remembered spot.
+    pub fn is_guiding(&self, state: &AgentState) -> bool {
+        self.last_food_x.is_some()
+            && state.state == AgentStateEnum::Seeking
+            && state.target_x == self.last_food_x
+            && state.target_y == self.last_food_y
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum AgentStateEnum {
     Seeking,
     Hunting,
+    /// Hunting `AgentState::target_x/y` with the pack led by `pack_id`.
+    PackHunting { pack_id: u64 },
     Feeding,
     Reproducing,
     Fighting,
     Fleeing,
+    Migrating,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -86,6 +215,9 @@ pub enum DeathReason {
     KilledByPredator,
     Combat,
     NaturalCauses,
+    Poisoned,
+    /// Crossed a `BoundaryBehavior::Lethal` edge.
+    FellOffEdge,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -94,6 +226,20 @@ pub struct SpawnAnimation {
     pub spawn_position: Option<(f64, f64)>,
 }
 
+/// Owned copies of every component an agent entity carries.
+pub type AgentComponents = (
+    Position,
+    Velocity,
+    Energy,
+    Age,
+    AgentState,
+    Genes,
+    Size,
+    DeathAnimation,
+    SpawnAnimation,
+    SpeciesId,
+);
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Resource {
     pub energy: f64,
@@ -107,6 +253,13 @@ pub struct Resource {
     pub spawn_fade: f64,
     pub is_depleting: bool,
     pub deplete_fade: f64,
+    #[serde(default)]
+    pub is_carcass: bool,
+    #[serde(default)]
+    pub resource_type: ResourceType,
+    /// Energy an eater gains or, if negative, loses outright.
+    #[serde(default)]
+    pub energy_delta: f64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -117,34 +270,261 @@ pub struct Size {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AgentTag;
 
+/// Next value handed out by `StableId::next`, shared by every world so that
+/// IDs stay unique across resets and imports.
+static SIMULATION_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies an agent or resource for its whole life. Unlike `hecs::Entity`
+/// it is kept by `EcsWorld::to_json` round trips and never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StableId {
+    pub value: u64,
+}
+
+impl StableId {
+    pub fn next() -> Self {
+        Self {
+            value: SIMULATION_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ResourceTag;
 
+/// Lineage an agent belongs to. Founders and random newcomers share
+/// `SpeciesId::FOUNDERS`; offspring keep their parent's unless
+/// `EcsWorld::spawn_offspring` finds they have drifted too far from it.
+/// `EcsWorld::assign_species_ids` periodically re-tags everyone by
+/// clustering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SpeciesId(pub u32);
+
+impl SpeciesId {
+    pub const FOUNDERS: SpeciesId = SpeciesId(0);
+}
+
 // ============================================================================
 // WORLD MANAGEMENT
 // ============================================================================
 
+/// Singleton component tracking time since the last periodic resource spawn.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceSpawnTimer {
+    pub elapsed: f64,
+}
+
+/// Singleton caps on agents and resources, mirroring `EcsWorld::max_agents`
+/// and `max_resources` so systems see changes made mid-run.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PopulationLimits {
+    pub max_agents: usize,
+    pub max_resources: usize,
+}
+
+/// Singleton holding the world's random stream; see `world_rng`. Left out
+/// of `to_json`, so imported worlds draw from the OS instead.
+pub struct WorldRng(pub SimulationRng);
+
+/// Singleton collecting the interactions systems record, present only while
+/// the engine logs them; see `EcsWorld::take_interactions`. Entries carry
+/// `step` 0 until the engine stamps them.
+#[derive(Default)]
+pub struct InteractionEvents(pub Vec<InteractionLog>);
+
+/// One agent `DeathSystem` removed.
+#[derive(Clone, Debug)]
+pub struct AgentDeath {
+    pub x: f64,
+    pub y: f64,
+    pub age: f64,
+    pub generation: u32,
+    pub reason: DeathReason,
+    pub is_predator: bool,
+}
+
+/// Singleton collecting the agents `DeathSystem` removed, oldest first; see
+/// `EcsWorld::take_deaths`.
+#[derive(Default)]
+pub struct DeathEvents(pub Vec<AgentDeath>);
+
+/// Singleton collecting `(x, y, energy)` for every resource agents ate from,
+/// the resource's position and the energy it lost; see
+/// `EcsWorld::take_consumption`.
+#[derive(Default)]
+pub struct ResourceConsumption(pub Vec<(f64, f64, f64)>);
+
+/// Singleton multiplier on the resource spawn rate, set by the engine each tick.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ResourceSpawnScale(pub f64);
+
+/// Singleton `SimulationConfig::warmth`, set by the engine each tick.
+/// Resources grow slower and agents burn more energy as it falls.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Warmth(pub f64);
+
+/// Singleton switching `AgentBehaviorSystem` to the allometric
+/// `metabolic_cost`, set by the engine each tick.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct AllometricScaling(pub bool);
+
+/// Singleton letting `ResourceUpdateSystem` update resources on the rayon
+/// pool, set by the engine each tick, with a count of the frames that did.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct ParallelResourceUpdate {
+    pub enabled: bool,
+    pub frames: u64,
+}
+
+/// Singleton counting agents killed by poisonous resources, kept by
+/// `DeathSystem`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct PoisonDeaths(pub u32);
+
+/// Singleton totting up the energy cooperators have given hungry kin, kept
+/// by `CombatSystem`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct EnergyShared(pub f64);
+
+/// Why `EcsWorld::from_json` rejected a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EcsImportError {
+    /// A required field is absent or has the wrong JSON type.
+    MissingField(&'static str),
+    /// The id is zero or otherwise not a valid `hecs::Entity`.
+    InvalidEntityId(u64),
+    UnknownComponent(String),
+    InvalidComponent { name: String, message: String },
+}
+
+impl std::fmt::Display for EcsImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EcsImportError::MissingField(field) => {
+                write!(f, "missing or invalid field `{}`", field)
+            }
+            EcsImportError::InvalidEntityId(id) => write!(f, "{} is not a valid entity id", id),
+            EcsImportError::UnknownComponent(name) => write!(f, "unknown component `{}`", name),
+            EcsImportError::InvalidComponent { name, message } => {
+                write!(f, "invalid `{}` component: {}", name, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EcsImportError {}
+
+/// Generates the per-entity (de)serializers used by `EcsWorld::to_json` and
+/// `from_json`. Components are keyed by their type name.
+macro_rules! json_components {
+    ($($component:ident),* $(,)?) => {
+        fn components_to_json(entity: hecs::EntityRef<'_>) -> serde_json::Map<String, Value> {
+            let mut components = serde_json::Map::new();
+            $(
+                if let Some(component) = entity.get::<&$component>() {
+                    // Components have only string keys, so this cannot fail
+                    let value = serde_json::to_value(&*component)
+                        .expect("component serializes to JSON");
+                    components.insert(stringify!($component).to_string(), value);
+                }
+            )*
+            components
+        }
+
+        fn add_component_from_json(
+            builder: &mut EntityBuilder,
+            name: &str,
+            value: &Value,
+        ) -> Result<(), EcsImportError> {
+            let invalid = |error: serde_json::Error| EcsImportError::InvalidComponent {
+                name: name.to_string(),
+                message: error.to_string(),
+            };
+            match name {
+                $(
+                    stringify!($component) => {
+                        builder.add($component::deserialize(value).map_err(invalid)?);
+                    }
+                )*
+                _ => return Err(EcsImportError::UnknownComponent(name.to_string())),
+            }
+            Ok(())
+        }
+    };
+}
+
+json_components!(
+    Position,
+    Velocity,
+    Energy,
+    Age,
+    Genes,
+    AgentState,
+    Memory,
+    DeathAnimation,
+    SpawnAnimation,
+    Resource,
+    Size,
+    AgentTag,
+    ResourceTag,
+    StableId,
+    SpeciesId,
+    ResourceSpawnTimer,
+    PoisonDeaths,
+    EnergyShared,
+    ResourceSpawnScale,
+    PopulationLimits,
+    Warmth,
+    ParallelResourceUpdate,
+    AllometricScaling,
+    Habitat,
+    BoundaryBehavior,
+    BiomeMap,
+);
+
 pub struct EcsWorld {
     pub world: World,
     pub canvas_width: f64,
     pub canvas_height: f64,
     pub max_agents: usize,
     pub max_resources: usize,
-    pub resource_spawn_timer: f64,
+    boundary_behavior: BoundaryBehavior,
+    /// Where `spawn_initial_population` draws founders' genes from.
+    initial_genes: InitialGeneDistribution,
+    /// Founders `spawn_initial_population` uses instead, when non-empty.
+    archetypes: Vec<AgentArchetype>,
+    /// Also kept as a singleton entity for `ResourceSpawnSystem`.
+    biome_map: Option<BiomeMap>,
+    scheduler: Scheduler,
+    /// Entity of every `StableId`; entries for despawned entities linger
+    /// until the next `update` and are checked on lookup.
+    stable_ids: HashMap<u64, Entity>,
+    /// Next `SpeciesId` handed out by `spawn_offspring`.
+    next_species_id: u32,
 }
 
 impl EcsWorld {
     pub fn new(canvas_width: f64, canvas_height: f64) -> Self {
-        let world = World::new();
-
-        let mut ecs_world = Self {
-            world,
+        Self::with_initial_genes(
             canvas_width,
             canvas_height,
-            max_agents: 10000,
-            max_resources: 1500,
-            resource_spawn_timer: 0.0,
-        };
+            InitialGeneDistribution::Uniform,
+            simulation_rng(None),
+        )
+    }
+
+    /// Like `new`, with the initial population's genes drawn from
+    /// `initial_genes`, as are those of every later `reset`, and every draw
+    /// the world makes taken from `rng`.
+    pub fn with_initial_genes(
+        canvas_width: f64,
+        canvas_height: f64,
+        initial_genes: InitialGeneDistribution,
+        rng: SimulationRng,
+    ) -> Self {
+        let mut ecs_world = Self::empty(canvas_width, canvas_height, 10000, 1500);
+        ecs_world.initial_genes = initial_genes;
+        ecs_world.set_rng(rng);
 
         // Spawn initial population
         ecs_world.spawn_initial_population();
@@ -152,264 +532,186 @@ impl EcsWorld {
         ecs_world
     }
 
-    pub fn update(&mut self) {
-        let delta_time = 1.0 / 60.0;
-
-        // Update resources
-        self.update_resources(delta_time);
-
-        // Update agents
-        self.update_agents(delta_time);
-
-        // Handle death
-        self.handle_death();
-
-        // Handle reproduction
-        self.handle_reproduction();
-
-        // Spawn new resources
-        self.resource_spawn_timer += delta_time;
-        if self.resource_spawn_timer > 0.5 && self.get_resource_count() < self.max_resources {
-            self.spawn_resource();
-            self.resource_spawn_timer = 0.0;
-        }
-    }
-
-    fn update_resources(&mut self, delta_time: f64) {
-        // Sequential processing for now
-        for (_, resource) in self.world.query_mut::<&mut Resource>() {
-            resource.update(delta_time);
+    fn empty(
+        canvas_width: f64,
+        canvas_height: f64,
+        max_agents: usize,
+        max_resources: usize,
+    ) -> Self {
+        Self {
+            world: World::new(),
+            canvas_width,
+            canvas_height,
+            max_agents,
+            max_resources,
+            boundary_behavior: BoundaryBehavior::default(),
+            initial_genes: InitialGeneDistribution::Uniform,
+            archetypes: Vec::new(),
+            biome_map: None,
+            scheduler: Scheduler::with_default_systems(
+                canvas_width,
+                canvas_height,
+                max_agents,
+                max_resources,
+            ),
+            stable_ids: HashMap::new(),
+            next_species_id: 1,
         }
     }
 
-    fn update_agents(&mut self, delta_time: f64) {
-        // Get all resources for agent decision making
-        let resources: Vec<_> = self
+    /// Every entity with its components, for external tooling:
+    /// `{ "width", "height", "max_agents", "max_resources", "entities": [...] }`
+    /// where each entity is `{ "id": u64, "components": { "Position": {...}, ... } }`.
+    pub fn to_json(&self) -> Value {
+        let entities: Vec<Value> = self
             .world
-            .query::<(&Position, &Resource)>()
             .iter()
-            .map(|(_, (pos, res))| (pos.x, pos.y, res.clone()))
+            .map(|entity| {
+                json!({
+                    "id": entity.entity().to_bits().get(),
+                    "components": components_to_json(entity),
+                })
+            })
             .collect();
 
-        let resources = Arc::new(resources);
-        let canvas_width = self.canvas_width;
-        let canvas_height = self.canvas_height;
-
-        // Sequential processing for now
-        for (_, (pos, vel, energy, age, state, genes)) in self.world.query_mut::<(
-            &mut Position,
-            &mut Velocity,
-            &mut Energy,
-            &mut Age,
-            &mut AgentState,
-            &Genes,
-        )>() {
-            // Inline the update logic to avoid borrowing issues
-            age.value += delta_time;
-
-            // Energy consumption
-            let base_energy_cost = (genes.size * 0.05 + genes.speed * 0.02) * delta_time;
-            let metabolism_factor = genes.metabolism;
-            let environmental_factor = 1.0 + (pos.x / canvas_width + pos.y / canvas_height) * 0.001;
-            let total_energy_cost = base_energy_cost * metabolism_factor * environmental_factor;
-            energy.current -= total_energy_cost / genes.energy_efficiency;
-
-            // Check for death
-            if energy.current <= 0.0 || age.value > 200.0 {
-                continue;
-            }
-
-            // Update behavior - inline to avoid borrowing issues
-            // Simple seeking behavior
-            let mut best_target = None;
-            let mut best_score = f64::NEG_INFINITY;
-
-            for (rx, ry, resource) in resources.iter() {
-                if resource.is_available() {
-                    let distance = ((pos.x - rx).powi(2) + (pos.y - ry).powi(2)).sqrt();
-                    if distance <= genes.sense_range {
-                        let score = resource.energy / (distance + 1.0);
-                        if score > best_score {
-                            best_score = score;
-                            best_target = Some((*rx, *ry));
-                        }
-                    }
-                }
-            }
-
-            if let Some((tx, ty)) = best_target {
-                state.target_x = Some(tx);
-                state.target_y = Some(ty);
-                state.state = AgentStateEnum::Hunting;
-            } else {
-                // Random movement
-                let mut rng = thread_rng();
-                let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
-                vel.dx = angle.cos() * genes.speed;
-                vel.dy = angle.sin() * genes.speed;
-            }
-
-            // Move agent - inline to avoid borrowing issues
-            // Apply movement
-            pos.x += vel.dx * delta_time;
-            pos.y += vel.dy * delta_time;
-
-            // Boundary wrapping
-            if pos.x < 0.0 {
-                pos.x = canvas_width;
-            }
-            if pos.x > canvas_width {
-                pos.x = 0.0;
-            }
-            if pos.y < 0.0 {
-                pos.y = canvas_height;
-            }
-            if pos.y > canvas_height {
-                pos.y = 0.0;
-            }
-
-            // Normalize direction vector
-            let length = (vel.dx * vel.dx + vel.dy * vel.dy).sqrt();
-            if length > 0.0 {
-                vel.dx /= length;
-                vel.dy /= length;
-            }
-        }
+        json!({
+            "width": self.canvas_width,
+            "height": self.canvas_height,
+            "max_agents": self.max_agents,
+            "max_resources": self.max_resources,
+            "entities": entities,
+        })
     }
 
-    fn update_agent(
-        &self,
-        pos: &mut Position,
-        vel: &mut Velocity,
-        energy: &mut Energy,
-        age: &mut Age,
-        state: &mut AgentState,
-        genes: &Genes,
-        resources: &Arc<Vec<(f64, f64, Resource)>>,
-        delta_time: f64,
-        canvas_width: f64,
-        canvas_height: f64,
-    ) {
-        age.value += delta_time;
-
-        // Energy consumption
-        let base_energy_cost = (genes.size * 0.05 + genes.speed * 0.02) * delta_time;
-        let metabolism_factor = genes.metabolism;
-        let environmental_factor = 1.0 + (pos.x / canvas_width + pos.y / canvas_height) * 0.001;
-        let total_energy_cost = base_energy_cost * metabolism_factor * environmental_factor;
-        energy.current -= total_energy_cost / genes.energy_efficiency;
-
-        // Check for death
-        if energy.current <= 0.0 || age.value > 200.0 {
-            // Mark for death - this will be handled by the death system
-            return;
-        }
-
-        // Update behavior
-        self.update_behavior(pos, vel, state, genes, resources);
-
-        // Move agent
-        self.move_agent(pos, vel, delta_time, canvas_width, canvas_height);
-    }
-
-    fn update_behavior(
-        &self,
-        pos: &Position,
-        vel: &mut Velocity,
-        state: &mut AgentState,
-        genes: &Genes,
-        resources: &Arc<Vec<(f64, f64, Resource)>>,
-    ) {
-        // Simple seeking behavior
-        let mut best_target = None;
-        let mut best_score = f64::NEG_INFINITY;
-
-        for (rx, ry, resource) in resources.iter() {
-            if resource.is_available() {
-                let distance = ((pos.x - rx).powi(2) + (pos.y - ry).powi(2)).sqrt();
-                if distance <= genes.sense_range {
-                    let score = resource.energy / (distance + 1.0);
-                    if score > best_score {
-                        best_score = score;
-                        best_target = Some((*rx, *ry));
-                    }
-                }
+    /// Rebuilds a world from `to_json` output. Entities keep their ids.
+    pub fn from_json(v: &Value) -> Result<EcsWorld, EcsImportError> {
+        let number = |field: &'static str| {
+            v.get(field)
+                .and_then(Value::as_f64)
+                .ok_or(EcsImportError::MissingField(field))
+        };
+        let count = |field: &'static str| {
+            v.get(field)
+                .and_then(Value::as_u64)
+                .map(|count| count as usize)
+                .ok_or(EcsImportError::MissingField(field))
+        };
+        let mut ecs_world = Self::empty(
+            number("width")?,
+            number("height")?,
+            count("max_agents")?,
+            count("max_resources")?,
+        );
+
+        let entities = v
+            .get("entities")
+            .and_then(Value::as_array)
+            .ok_or(EcsImportError::MissingField("entities"))?;
+        for entity in entities {
+            let id = entity
+                .get("id")
+                .and_then(Value::as_u64)
+                .ok_or(EcsImportError::MissingField("id"))?;
+            let handle = Entity::from_bits(id).ok_or(EcsImportError::InvalidEntityId(id))?;
+            let components = entity
+                .get("components")
+                .and_then(Value::as_object)
+                .ok_or(EcsImportError::MissingField("components"))?;
+
+            let mut builder = EntityBuilder::new();
+            for (name, value) in components {
+                add_component_from_json(&mut builder, name, value)?;
             }
+            ecs_world.world.spawn_at(handle, builder.build());
         }
 
-        if let Some((tx, ty)) = best_target {
-            state.target_x = Some(tx);
-            state.target_y = Some(ty);
-            state.state = AgentStateEnum::Hunting;
-        } else {
-            // Random movement
-            let mut rng = thread_rng();
-            let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
-            vel.dx = angle.cos() * genes.speed;
-            vel.dy = angle.sin() * genes.speed;
+        if let Some((_, boundary)) = ecs_world.world.query::<&BoundaryBehavior>().iter().next() {
+            ecs_world.boundary_behavior = *boundary;
         }
+        ecs_world.biome_map = ecs_world
+            .world
+            .query::<&BiomeMap>()
+            .iter()
+            .next()
+            .map(|(_, biome_map)| biome_map.clone());
+        ecs_world.rebuild_stable_id_index();
+        // Keep IDs handed out from now on clear of the imported ones
+        if let Some(&highest) = ecs_world.stable_ids.keys().max() {
+            SIMULATION_ID_COUNTER.fetch_max(highest + 1, Ordering::Relaxed);
+        }
+        ecs_world.reserve_species_ids();
+        Ok(ecs_world)
     }
 
-    fn move_agent(
-        &self,
-        pos: &mut Position,
-        vel: &mut Velocity,
-        delta_time: f64,
-        canvas_width: f64,
-        canvas_height: f64,
-    ) {
-        // Apply movement
-        pos.x += vel.dx * delta_time;
-        pos.y += vel.dy * delta_time;
-
-        // Boundary wrapping
-        if pos.x < 0.0 {
-            pos.x = canvas_width;
-        }
-        if pos.x > canvas_width {
-            pos.x = 0.0;
-        }
-        if pos.y < 0.0 {
-            pos.y = canvas_height;
-        }
-        if pos.y > canvas_height {
-            pos.y = 0.0;
-        }
+    pub fn update(&mut self) {
+        self.scheduler.run(&mut self.world);
+        self.rebuild_stable_id_index();
+    }
 
-        // Normalize direction vector
-        let length = (vel.dx * vel.dx + vel.dy * vel.dy).sqrt();
-        if length > 0.0 {
-            vel.dx /= length;
-            vel.dy /= length;
-        }
+    /// Re-indexes every `StableId` in the world. Needed after spawning
+    /// straight into `world` rather than through `EcsWorld`'s own methods.
+    pub fn rebuild_stable_id_index(&mut self) {
+        self.stable_ids = self
+            .world
+            .query::<&StableId>()
+            .iter()
+            .map(|(entity, id)| (id.value, entity))
+            .collect();
     }
 
-    fn handle_death(&mut self) {
-        let mut to_remove = Vec::new();
+    /// The living agent with `StableId` `id`, if there is one.
+    pub fn get_agent_by_stable_id(&self, id: u64) -> Option<Entity> {
+        let entity = *self.stable_ids.get(&id)?;
+        let matches = self.world.get::<&StableId>(entity).is_ok_and(|s| s.value == id);
+        (matches && self.world.get::<&AgentTag>(entity).is_ok()).then_some(entity)
+    }
 
-        for (entity, (energy, age)) in self.world.query::<(&Energy, &Age)>().iter() {
-            if energy.current <= 0.0 || age.value > 200.0 {
-                to_remove.push(entity);
-            }
-        }
+    pub fn get_agent(&self, entity: Entity) -> Option<AgentComponents> {
+        let mut query = self
+            .world
+            .query_one::<(
+                &Position,
+                &Velocity,
+                &Energy,
+                &Age,
+                &AgentState,
+                &Genes,
+                &Size,
+                &DeathAnimation,
+                &SpawnAnimation,
+                Option<&SpeciesId>,
+            )>(entity)
+            .ok()?;
+        let (pos, vel, energy, age, state, genes, size, death, spawn, species) = query.get()?;
+        Some((
+            pos.clone(),
+            vel.clone(),
+            energy.clone(),
+            age.clone(),
+            state.clone(),
+            genes.clone(),
+            size.clone(),
+            death.clone(),
+            spawn.clone(),
+            species.copied().unwrap_or(SpeciesId::FOUNDERS),
+        ))
+    }
 
-        // Remove dead entities
-        for entity in to_remove {
-            self.world.despawn(entity).ok();
+    fn index_stable_id(&mut self, entity: Entity) {
+        if let Ok(id) = self.world.get::<&StableId>(entity) {
+            self.stable_ids.insert(id.value, entity);
         }
     }
 
-    fn handle_reproduction(&mut self) {
-        // Simplified reproduction - just spawn new agents occasionally
-        let mut rng = thread_rng();
-        let agent_count = self.get_agent_count();
-
-        if agent_count < self.max_agents && rng.gen::<f64>() < 0.1 {
-            let x = rng.gen_range(0.0..self.canvas_width);
-            let y = rng.gen_range(0.0..self.canvas_height);
-            let genes = self.generate_random_genes();
-            self.spawn_agent(x, y, genes, 0);
-        }
+    /// Adds a system to the update schedule so that it runs after every system
+    /// named in `after`. A system that can't be ordered is rejected and the
+    /// schedule left unchanged.
+    pub fn register_system(
+        &mut self,
+        system: Box<dyn System>,
+        after: &[&str],
+    ) -> Result<(), String> {
+        self.scheduler.try_register_system(system, after)
     }
 
     fn can_reproduce(&self, energy: &Energy, age: &Age, state: &AgentState) -> bool {
@@ -420,8 +722,8 @@ impl EcsWorld {
         ((pos1.x - pos2.x).powi(2) + (pos1.y - pos2.y).powi(2)).sqrt()
     }
 
-    fn inherit_genes(&self, genes1: &Genes, genes2: &Genes) -> Genes {
-        let mut rng = thread_rng();
+    fn inherit_genes(&mut self, genes1: &Genes, genes2: &Genes) -> Genes {
+        let mut rng = world_rng(&mut self.world);
         let blend_factor = rng.gen_range(0.3..0.7);
 
         Genes {
@@ -453,86 +755,56 @@ impl EcsWorld {
             intelligence: genes1.intelligence * blend_factor
                 + genes2.intelligence * (1.0 - blend_factor),
             stamina: genes1.stamina * blend_factor + genes2.stamina * (1.0 - blend_factor),
+            omnivore: genes1.omnivore * blend_factor + genes2.omnivore * (1.0 - blend_factor),
+            migration: genes1.migration * blend_factor + genes2.migration * (1.0 - blend_factor),
+            cooperation: genes1.cooperation * blend_factor
+                + genes2.cooperation * (1.0 - blend_factor),
+            mutation_strength: genes1.mutation_strength * blend_factor
+                + genes2.mutation_strength * (1.0 - blend_factor),
         }
     }
 
     fn spawn_agent(&mut self, x: f64, y: f64, genes: Genes, generation: u32) {
-        let mut rng = thread_rng();
-        let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
-        let size_value = genes.size * 3.0;
-
-        self.world.spawn((
-            Position { x, y },
-            Velocity {
-                dx: angle.cos() * genes.speed,
-                dy: angle.sin() * genes.speed,
-            },
-            Energy {
-                current: 80.0,
-                max: 100.0,
-            },
-            Age { value: 0.0 },
-            genes,
-            AgentState {
-                state: AgentStateEnum::Seeking,
-                target_x: None,
-                target_y: None,
-                last_reproduction: 0.0,
-                kills: 0,
-                generation,
-            },
-            DeathAnimation {
-                fade: 0.0,
-                reason: DeathReason::NaturalCauses,
-                is_dying: false,
-            },
-            SpawnAnimation {
-                fade: 0.0,
-                spawn_position: Some((x, y)),
-            },
-            Size { value: size_value },
-            AgentTag,
-        ));
+        let entity = spawn_agent(&mut self.world, x, y, genes, generation);
+        self.index_stable_id(entity);
     }
 
     fn spawn_resource(&mut self) {
-        let mut rng = thread_rng();
-        let x = rng.gen_range(0.0..self.canvas_width);
-        let y = rng.gen_range(0.0..self.canvas_height);
-
-        let initial_energy = rng.gen_range(15.0..40.0);
-        let max_energy = rng.gen_range(30.0..60.0);
-
-        self.world.spawn((
-            Position { x, y },
-            Resource {
-                energy: 0.0,
-                max_energy,
-                size: 3.0,
-                growth_rate: rng.gen_range(0.1..0.5),
-                regeneration_rate: rng.gen_range(0.02..0.1),
-                age: 0.0,
-                target_energy: initial_energy,
-                is_spawning: true,
-                spawn_fade: 0.0,
-                is_depleting: false,
-                deplete_fade: 0.0,
-            },
-            Size { value: 3.0 },
-            ResourceTag,
-        ));
+        if let Some(entity) =
+            spawn_random_resource(&mut self.world, self.canvas_width, self.canvas_height)
+        {
+            self.index_stable_id(entity);
+        }
     }
 
     fn spawn_initial_population(&mut self) {
-        let mut rng = thread_rng();
+        let mut rng = world_rng(&mut self.world);
 
         // Spawn initial agents
-        let initial_agents = 100; // 10% of max
-        for _ in 0..initial_agents {
-            let x = rng.gen_range(0.0..self.canvas_width);
-            let y = rng.gen_range(0.0..self.canvas_height);
-            let genes = self.generate_random_genes();
-            self.spawn_agent(x, y, genes, 0);
+        if self.archetypes.is_empty() {
+            let initial_agents = 100; // 10% of max
+            for _ in 0..initial_agents {
+                let x = rng.gen_range(0.0..self.canvas_width);
+                let y = rng.gen_range(0.0..self.canvas_height);
+                let genes = to_ecs_genes(&sample_genes(&self.initial_genes, &mut rng));
+                self.spawn_agent(x, y, genes, 0);
+            }
+        } else {
+            let mut founders = Vec::new();
+            spawn_archetypes(
+                &self.archetypes,
+                self.canvas_width,
+                self.canvas_height,
+                &mut rng,
+                |x, y, genes, energy| founders.push((x, y, to_ecs_genes(&genes), energy)),
+            );
+            for (x, y, genes, energy) in founders {
+                let entity = spawn_agent(&mut self.world, x, y, genes, 0);
+                if let Ok(mut current) = self.world.get::<&mut Energy>(entity) {
+                    current.current = energy;
+                }
+                self.index_stable_id(entity);
+            }
         }
 
         // Spawn initial resources
@@ -542,37 +814,21 @@ impl EcsWorld {
         }
     }
 
-    fn generate_random_genes(&self) -> Genes {
-        let mut rng = thread_rng();
-
-        Genes {
-            speed: rng.gen_range(0.8..1.5),
-            sense_range: rng.gen_range(30.0..80.0),
-            size: rng.gen_range(0.9..1.3),
-            energy_efficiency: rng.gen_range(0.8..1.2),
-            reproduction_threshold: rng.gen_range(60.0..120.0),
-            mutation_rate: rng.gen_range(0.02..0.08),
-            aggression: rng.gen_range(0.2..0.8),
-            color_hue: rng.gen_range(0.0..360.0),
-            is_predator: rng.gen_range(0.0..0.3),
-            hunting_speed: rng.gen_range(1.0..2.0),
-            attack_power: rng.gen_range(0.5..1.5),
-            defense: rng.gen_range(0.5..1.5),
-            stealth: rng.gen_range(0.0..1.0),
-            pack_mentality: rng.gen_range(0.0..1.0),
-            territory_size: rng.gen_range(50.0..150.0),
-            metabolism: rng.gen_range(0.8..1.4),
-            intelligence: rng.gen_range(0.5..1.5),
-            stamina: rng.gen_range(0.5..1.5),
-        }
+    fn generate_random_genes(&mut self) -> Genes {
+        random_genes(&mut world_rng(&mut self.world))
     }
 
     pub fn get_agent_count(&self) -> usize {
-        self.world.query::<&AgentTag>().iter().count()
+        agent_count(&self.world)
     }
 
     pub fn get_resource_count(&self) -> usize {
-        self.world.query::<&ResourceTag>().iter().count()
+        resource_count(&self.world)
+    }
+
+    /// Every entity in the world, singletons included.
+    pub fn entity_count(&self) -> usize {
+        self.world.len() as usize
     }
 
     pub fn add_agent(&mut self, x: f64, y: f64) {
@@ -584,34 +840,391 @@ impl EcsWorld {
 
     pub fn add_resource(&mut self, x: f64, y: f64) {
         if self.get_resource_count() < self.max_resources {
-            let _entity = self.world.spawn((
+            let multiplier = biome_energy_multiplier(&self.world, x, y);
+            let entity = self.world.spawn((
                 Position { x, y },
                 Resource {
                     energy: 0.0,
-                    max_energy: 60.0,
+                    max_energy: 60.0 * multiplier,
                     size: 3.0,
-                    growth_rate: 0.3,
-                    regeneration_rate: 0.05,
+                    growth_rate: 0.3 * multiplier,
+                    regeneration_rate: 0.05 * multiplier,
                     age: 0.0,
                     target_energy: 30.0,
                     is_spawning: true,
                     spawn_fade: 0.0,
                     is_depleting: false,
                     deplete_fade: 0.0,
+                    is_carcass: false,
+                    resource_type: ResourceType::Food,
+                    energy_delta: 0.0,
                 },
                 Size { value: 3.0 },
                 ResourceTag,
+                StableId::next(),
             ));
+            self.index_stable_id(entity);
+        }
+    }
+
+    pub fn add_agent_with_genes(&mut self, x: f64, y: f64, genes: Genes) {
+        if self.get_agent_count() < self.max_agents {
+            self.spawn_agent(x, y, genes, 0);
+        }
+    }
+
+    /// A child of `parent1` and `parent2` with their genes blended, through
+    /// `spawn_offspring` from `parent1`.
+    pub fn reproduce(
+        &mut self,
+        parent1: Entity,
+        parent2: Entity,
+        speciation_threshold: f64,
+    ) -> Option<Entity> {
+        let genes1 = self.world.get::<&Genes>(parent1).ok().map(|g| (*g).clone())?;
+        let genes2 = self.world.get::<&Genes>(parent2).ok().map(|g| (*g).clone())?;
+        let genes = self.inherit_genes(&genes1, &genes2);
+        self.spawn_offspring(parent1, genes, speciation_threshold)
+    }
+
+    /// Spawns an agent carrying `genes` where `parent` stands, one generation
+    /// on. It stays in the parent's species unless the `genetic_distance`
+    /// between their genes is over `speciation_threshold`, in which case it
+    /// founds a new one. `None` if `parent` isn't an agent or the world is
+    /// full.
+    pub fn spawn_offspring(
+        &mut self,
+        parent: Entity,
+        genes: Genes,
+        speciation_threshold: f64,
+    ) -> Option<Entity> {
+        if self.get_agent_count() >= self.max_agents {
+            return None;
+        }
+        let (x, y, generation, parent_species, distance) = {
+            let mut query = self
+                .world
+                .query_one::<(&Position, &AgentState, &Genes, Option<&SpeciesId>, &AgentTag)>(
+                    parent,
+                )
+                .ok()?;
+            let (pos, state, parent_genes, species, _) = query.get()?;
+            let distance = genetic_distance(&from_ecs_genes(parent_genes), &from_ecs_genes(&genes));
+            let species = species.copied().unwrap_or(SpeciesId::FOUNDERS);
+            (pos.x, pos.y, state.generation + 1, species, distance)
+        };
+
+        let species = if distance > speciation_threshold {
+            self.next_species_id += 1;
+            SpeciesId(self.next_species_id - 1)
+        } else {
+            parent_species
+        };
+        let entity = spawn_agent(&mut self.world, x, y, genes, generation);
+        self.world.insert_one(entity, species).ok();
+        self.index_stable_id(entity);
+        Some(entity)
+    }
+
+    /// Keeps species IDs handed out from now on clear of those already in the
+    /// world, for after agents are brought in with their own.
+    pub fn reserve_species_ids(&mut self) {
+        if let Some(highest) = self
+            .world
+            .query::<&SpeciesId>()
+            .iter()
+            .map(|(_, s)| s.0)
+            .max()
+        {
+            self.next_species_id = self.next_species_id.max(highest + 1);
+        }
+    }
+
+    /// Re-tags every agent's `SpeciesId` by clustering at genetic distance
+    /// `speciation_threshold`, as `species::assign_species_ids` does for
+    /// `Agent`s. Each cluster keeps the ID most of its members already carry.
+    pub fn assign_species_ids(&mut self, speciation_threshold: f64) {
+        let (entities, tagged): (Vec<_>, Vec<_>) = self
+            .world
+            .query::<(&Position, &Genes, Option<&SpeciesId>, &AgentTag)>()
+            .iter()
+            .map(|(entity, (pos, genes, species, _))| {
+                let species = species.copied().unwrap_or(SpeciesId::FOUNDERS);
+                (entity, ((pos.x, pos.y, from_ecs_genes(genes)), species.0))
+            })
+            .unzip();
+        let (genomes, current): (Vec<_>, Vec<_>) = tagged.into_iter().unzip();
+        let ids = assign_genome_species_ids(
+            &genomes,
+            &current,
+            speciation_threshold,
+            &mut self.next_species_id,
+        );
+        for (entity, id) in entities.into_iter().zip(ids) {
+            self.world.insert_one(entity, SpeciesId(id)).ok();
+        }
+    }
+
+    /// (species ID, living agents) for every species present, by ID.
+    pub fn get_species_distribution(&self) -> Vec<(u32, usize)> {
+        let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+        for (_, (species, _)) in self.world.query::<(&SpeciesId, &AgentTag)>().iter() {
+            *counts.entry(species.0).or_default() += 1;
+        }
+        counts.into_iter().collect()
+    }
+
+    /// Removes a uniformly random subset of agents so that only
+    /// `survival_fraction` of them remain.
+    pub fn cull_agents(&mut self, survival_fraction: f64) {
+        let mut agents: Vec<_> = self
+            .world
+            .query::<(&AgentTag, &Position, &Energy, &Genes)>()
+            .iter()
+            .map(|(entity, (_, pos, energy, genes))| {
+                (entity, pos.x, pos.y, energy.current * genes.size)
+            })
+            .collect();
+        let survivors = (agents.len() as f64 * survival_fraction.clamp(0.0, 1.0)).round() as usize;
+
+        agents.shuffle(&mut world_rng(&mut self.world));
+        for (entity, x, y, remains) in agents.into_iter().skip(survivors) {
+            self.world.despawn(entity).ok();
+            self.add_death_resource(x, y, remains);
+        }
+    }
+
+    /// Leaves a deposit worth `DECOMPOSITION_RETAINED` of `remains` at (x, y).
+    pub fn add_death_resource(&mut self, x: f64, y: f64, remains: f64) {
+        if remains > 0.0 && self.get_resource_count() < self.max_resources {
+            let entity = spawn_carcass_resource(&mut self.world, x, y, remains);
+            self.index_stable_id(entity);
         }
     }
 
+    pub fn poison_deaths(&self) -> u32 {
+        self.world
+            .query::<&PoisonDeaths>()
+            .iter()
+            .next()
+            .map_or(0, |(_, deaths)| deaths.0)
+    }
+
+    pub fn get_death_resource_count(&self) -> usize {
+        self.world
+            .query::<&Resource>()
+            .iter()
+            .filter(|(_, resource)| resource.is_carcass)
+            .count()
+    }
+
     pub fn reset(&mut self) {
-        self.world = World::new();
-        self.resource_spawn_timer = 0.0;
+        self.clear();
         self.spawn_initial_population();
     }
 
-    pub fn get_agents(&self) -> Vec<(Position, Velocity, Energy, Age, AgentState, Genes, Size)> {
+    /// Where every later `reset` draws founders' genes from.
+    pub fn set_initial_genes(&mut self, initial_genes: InitialGeneDistribution) {
+        self.initial_genes = initial_genes;
+    }
+
+    /// Founders for every later `reset`; empty goes back to random agents.
+    pub fn set_archetypes(&mut self, archetypes: Vec<AgentArchetype>) {
+        self.archetypes = archetypes;
+    }
+
+    pub fn add_habitat(&mut self, habitat: Habitat) {
+        self.world.spawn((habitat,));
+    }
+
+    pub fn get_habitats(&self) -> Vec<Habitat> {
+        self.world
+            .query::<&Habitat>()
+            .iter()
+            .map(|(_, habitat)| habitat.clone())
+            .collect()
+    }
+
+    /// Moves every agent inside a teleporter entrance to beside its exit.
+    pub fn teleport_agents(&mut self, teleporters: &mut [Teleporter]) {
+        let mut rng = world_rng(&mut self.world);
+        for (_, (pos, _)) in self.world.query_mut::<(&mut Position, &AgentTag)>() {
+            teleport(teleporters, &mut pos.x, &mut pos.y, &mut rng);
+        }
+    }
+
+    /// Takes effect for resources grown from now on, including those of the
+    /// next `reset`.
+    pub fn set_biome_map(&mut self, biome_map: Option<BiomeMap>) {
+        let existing: Vec<_> = self
+            .world
+            .query::<&BiomeMap>()
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in existing {
+            self.world.despawn(entity).ok();
+        }
+        if let Some(biome_map) = &biome_map {
+            self.world.spawn((biome_map.clone(),));
+        }
+        self.biome_map = biome_map;
+    }
+
+    /// Stored as a singleton entity so that systems can read it each tick.
+    pub fn set_boundary_behavior(&mut self, boundary: BoundaryBehavior) {
+        self.boundary_behavior = boundary;
+        let existing: Vec<_> = self
+            .world
+            .query::<&BoundaryBehavior>()
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in existing {
+            self.world.despawn(entity).ok();
+        }
+        self.world.spawn((boundary,));
+    }
+
+    /// Restarts every draw the world makes from `rng`.
+    pub fn set_rng(&mut self, rng: SimulationRng) {
+        match self.world.query_mut::<&mut WorldRng>().into_iter().next() {
+            Some((_, existing)) => existing.0 = rng,
+            None => {
+                self.world.spawn((WorldRng(rng),));
+            }
+        }
+    }
+
+    /// Changes the agent and resource caps, including for the systems
+    /// already scheduled.
+    pub fn set_limits(&mut self, max_agents: usize, max_resources: usize) {
+        self.max_agents = max_agents;
+        self.max_resources = max_resources;
+        let limits = PopulationLimits {
+            max_agents,
+            max_resources,
+        };
+        match self.world.query_mut::<&mut PopulationLimits>().into_iter().next() {
+            Some((_, existing)) => *existing = limits,
+            None => {
+                self.world.spawn((limits,));
+            }
+        }
+    }
+
+    pub fn set_resource_spawn_scale(&mut self, scale: f64) {
+        match self.world.query_mut::<&mut ResourceSpawnScale>().into_iter().next() {
+            Some((_, existing)) => existing.0 = scale,
+            None => {
+                self.world.spawn((ResourceSpawnScale(scale),));
+            }
+        }
+    }
+
+    /// Starts or stops collecting `InteractionEvents`.
+    pub fn set_interaction_logging(&mut self, enabled: bool) {
+        let existing = self.world.query_mut::<&InteractionEvents>().into_iter().next();
+        match (existing.map(|(entity, _)| entity), enabled) {
+            (None, true) => {
+                self.world.spawn((InteractionEvents::default(),));
+            }
+            (Some(entity), false) => {
+                self.world.despawn(entity).ok();
+            }
+            _ => {}
+        }
+    }
+
+    /// Interactions recorded since the last call, oldest first.
+    pub fn take_interactions(&mut self) -> Vec<InteractionLog> {
+        match self.world.query_mut::<&mut InteractionEvents>().into_iter().next() {
+            Some((_, events)) => std::mem::take(&mut events.0),
+            None => Vec::new(),
+        }
+    }
+
+    /// Agents that died since the last call, oldest first. The `DeathEvents`
+    /// singleton goes with them, so it never shows up in `to_json` as an
+    /// entity without components.
+    pub fn take_deaths(&mut self) -> Vec<AgentDeath> {
+        let Some((entity, _)) = self.world.query_mut::<&DeathEvents>().into_iter().next() else {
+            return Vec::new();
+        };
+        let deaths = self.world.remove_one::<DeathEvents>(entity);
+        self.world.despawn(entity).ok();
+        deaths.map_or_else(|_| Vec::new(), |deaths| deaths.0)
+    }
+
+    /// Resource energy eaten since the last call, as `(x, y, energy)`.
+    /// Like `take_deaths`, the singleton goes with what it collected.
+    pub fn take_consumption(&mut self) -> Vec<(f64, f64, f64)> {
+        let existing = self
+            .world
+            .query_mut::<&ResourceConsumption>()
+            .into_iter()
+            .next();
+        let Some((entity, _)) = existing else {
+            return Vec::new();
+        };
+        let consumption = self.world.remove_one::<ResourceConsumption>(entity);
+        self.world.despawn(entity).ok();
+        consumption.map_or_else(|_| Vec::new(), |consumption| consumption.0)
+    }
+
+    pub fn set_warmth(&mut self, warmth: f64) {
+        match self.world.query_mut::<&mut Warmth>().into_iter().next() {
+            Some((_, existing)) => existing.0 = warmth,
+            None => {
+                self.world.spawn((Warmth(warmth),));
+            }
+        }
+    }
+
+    pub fn set_parallel_resource_update(&mut self, enabled: bool) {
+        match self.world.query_mut::<&mut ParallelResourceUpdate>().into_iter().next() {
+            Some((_, existing)) => existing.enabled = enabled,
+            None => {
+                self.world.spawn((ParallelResourceUpdate { enabled, frames: 0 },));
+            }
+        }
+    }
+
+    pub fn set_allometric_scaling(&mut self, enabled: bool) {
+        match self.world.query_mut::<&mut AllometricScaling>().into_iter().next() {
+            Some((_, existing)) => existing.0 = enabled,
+            None => {
+                self.world.spawn((AllometricScaling(enabled),));
+            }
+        }
+    }
+
+    /// Frames whose resources were updated in parallel since the last `clear`.
+    pub fn parallel_resource_update_frames(&self) -> u64 {
+        self.world
+            .query::<&ParallelResourceUpdate>()
+            .iter()
+            .next()
+            .map_or(0, |(_, update)| update.frames)
+    }
+
+    /// Removes every entity without spawning a fresh population.
+    pub fn clear(&mut self) {
+        let rng = world_rng(&mut self.world);
+        self.world = World::new();
+        self.stable_ids.clear();
+        self.world.spawn((WorldRng(rng),));
+        self.world.spawn((self.boundary_behavior,));
+        self.world.spawn((PopulationLimits {
+            max_agents: self.max_agents,
+            max_resources: self.max_resources,
+        },));
+        if let Some(biome_map) = &self.biome_map {
+            self.world.spawn((biome_map.clone(),));
+        }
+    }
+
+    pub fn get_agents(&self) -> Vec<AgentComponents> {
         self.world
             .query::<(
                 &Position,
@@ -621,22 +1234,133 @@ impl EcsWorld {
                 &AgentState,
                 &Genes,
                 &Size,
+                &DeathAnimation,
+                &SpawnAnimation,
+                Option<&SpeciesId>,
             )>()
             .iter()
-            .map(|(_, (pos, vel, energy, age, state, genes, size))| {
-                (
-                    pos.clone(),
-                    vel.clone(),
-                    energy.clone(),
-                    age.clone(),
-                    state.clone(),
-                    genes.clone(),
-                    size.clone(),
-                )
-            })
+            .map(
+                |(_, (pos, vel, energy, age, state, genes, size, death, spawn, species))| {
+                    (
+                        pos.clone(),
+                        vel.clone(),
+                        energy.clone(),
+                        age.clone(),
+                        state.clone(),
+                        genes.clone(),
+                        size.clone(),
+                        death.clone(),
+                        spawn.clone(),
+                        species.copied().unwrap_or(SpeciesId::FOUNDERS),
+                    )
+                },
+            )
             .collect()
     }
 
+    /// Population statistics gathered in one pass over the agents, without
+    /// converting them to `Agent`s. The species fields are left at 0 for the
+    /// caller to fill from `species_summary`, which costs far more.
+    pub fn compute_stats(&self) -> SimulationStats {
+        let mut stats = SimulationStats {
+            resource_count: self.get_resource_count(),
+            total_energy_shared: self
+                .world
+                .query::<&EnergyShared>()
+                .iter()
+                .next()
+                .map_or(0.0, |(_, shared)| shared.0),
+            ..SimulationStats::default()
+        };
+        let (mut r_stars, mut trophic_levels) = (Vec::new(), Vec::new());
+        let (mut age, mut speed, mut size, mut aggression) = (0.0, 0.0, 0.0, 0.0);
+        let (mut sense_range, mut efficiency, mut fitness) = (0.0, 0.0, 0.0);
+        let (mut stamina, mut mutation_strength, mut generation) = (0.0, 0.0, 0.0);
+        let (mut guided, mut suppression) = (0, 0.0);
+        let (mut predators, mut satiation, mut perception_error) = (0, 0.0, 0.0);
+
+        for (_, (energy, agent_age, state, genes, memory, _)) in self
+            .world
+            .query::<(
+                &Energy,
+                &Age,
+                &AgentState,
+                &Genes,
+                Option<&Memory>,
+                &AgentTag,
+            )>()
+            .iter()
+        {
+            if memory.is_some_and(|memory| memory.is_guiding(state)) {
+                guided += 1;
+            }
+            stats.agent_count += 1;
+            stats.total_energy += energy.current;
+            stats.max_generation = stats.max_generation.max(state.generation);
+            generation += state.generation as f64;
+            stats.total_kills += state.kills;
+            stats.total_pack_kills += state.pack_kills;
+            stats.age_distribution[age_bucket(agent_age.value)] += 1;
+            age += agent_age.value;
+            speed += genes.speed;
+            size += genes.size;
+            aggression += genes.aggression;
+            sense_range += genes.sense_range;
+            efficiency += genes.energy_efficiency;
+            fitness += energy.current / energy.max;
+            stamina += state.current_stamina;
+            mutation_strength += genes.mutation_strength;
+            suppression += 1.0 - state.phenotype.reproduction_scale;
+            perception_error += state.perception_error;
+            if genes.is_predator_at(energy.current) {
+                predators += 1;
+                satiation += state.satiation;
+            }
+            r_stars.push(r_star(genes.metabolism, genes.size));
+            trophic_levels.push(state.trophic_level);
+        }
+
+        if stats.agent_count == 0 {
+            return stats;
+        }
+        let count = stats.agent_count as f64;
+        stats.average_age = age / count;
+        stats.average_speed = speed / count;
+        stats.average_size = size / count;
+        stats.average_aggression = aggression / count;
+        stats.average_sense_range = sense_range / count;
+        stats.average_energy_efficiency = efficiency / count;
+        stats.average_fitness = fitness / count;
+        stats.average_stamina = stamina / count;
+        stats.average_mutation_strength = mutation_strength / count;
+        stats.average_generation = generation / count;
+        stats.average_memory_utilization = guided as f64 / count;
+        stats.average_reproduction_suppression = suppression / count;
+        stats.average_perception_error = perception_error / count;
+        if predators > 0 {
+            stats.average_predator_satiation = satiation / predators as f64;
+        }
+        stats.resource_competition_index = sample_variance(&r_stars);
+        (
+            stats.average_trophic_level,
+            stats.apex_predator_count,
+            stats.trophic_diversity,
+        ) = trophic_level_summary(trophic_levels);
+        stats
+    }
+
+    /// The agents split into species at genetic distance
+    /// `speciation_threshold`.
+    pub fn species_summary(&self, speciation_threshold: f64) -> SpeciesSummary {
+        let genomes: Vec<_> = self
+            .world
+            .query::<(&Position, &Genes, &AgentTag)>()
+            .iter()
+            .map(|(_, (pos, genes, _))| (pos.x, pos.y, from_ecs_genes(genes)))
+            .collect();
+        genome_species_summary(&genomes, speciation_threshold)
+    }
+
     pub fn get_resources(&self) -> Vec<(Position, Resource, Size)> {
         self.world
             .query::<(&Position, &Resource, &Size)>()
@@ -646,10 +1370,262 @@ impl EcsWorld {
     }
 }
 
+// ============================================================================
+// SPAWNING HELPERS
+// ============================================================================
+
+pub fn spawn_agent(world: &mut World, x: f64, y: f64, genes: Genes, generation: u32) -> Entity {
+    let mut rng = world_rng(world);
+    let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
+    let size_value = genes.size * 3.0;
+    let current_stamina = genes.stamina;
+
+    world.spawn((
+        Position { x, y },
+        Velocity {
+            dx: angle.cos() * genes.speed,
+            dy: angle.sin() * genes.speed,
+        },
+        Energy {
+            current: 80.0,
+            max: 100.0,
+        },
+        Age { value: 0.0 },
+        genes,
+        AgentState {
+            state: AgentStateEnum::Seeking,
+            target_x: None,
+            target_y: None,
+            target_id: None,
+            last_reproduction: 0.0,
+            kills: 0,
+            generation,
+            pack_kills: 0,
+            migrating_direction: None,
+            last_migration_time: 0.0,
+            phenotype: PhenotypeModifiers::default(),
+            current_stamina,
+            satiation: 0.0,
+            perception_error: 0.0,
+            trophic_level: 1,
+        },
+        Memory::default(),
+        DeathAnimation {
+            fade: 0.0,
+            reason: DeathReason::NaturalCauses,
+            is_dying: false,
+        },
+        SpawnAnimation {
+            fade: 0.0,
+            spawn_position: Some((x, y)),
+        },
+        Size { value: size_value },
+        AgentTag,
+        StableId::next(),
+        SpeciesId::FOUNDERS,
+    ))
+}
+
+/// A stream split off the world's `WorldRng`, so draws stay repeatable for
+/// a seeded world; one seeded from the OS for worlds without it.
+pub fn world_rng(world: &mut World) -> SimulationRng {
+    match world.query_mut::<&mut WorldRng>().into_iter().next() {
+        Some((_, rng)) => SimulationRng::seed_from_u64(rng.0.gen()),
+        None => simulation_rng(None),
+    }
+}
+
+/// `energy_multiplier` of the world's `BiomeMap` singleton at (x, y); 1
+/// without one.
+fn biome_energy_multiplier(world: &World, x: f64, y: f64) -> f64 {
+    world
+        .query::<&BiomeMap>()
+        .iter()
+        .next()
+        .map_or(1.0, |(_, biome_map)| biome_map.energy_multiplier_at(x, y))
+}
+
+/// A freshly grown resource at a random spot. `None` when the world's
+/// `BiomeMap` rejects the spot; see `BiomeMap::accepts_spawn`.
+pub fn spawn_random_resource(
+    world: &mut World,
+    canvas_width: f64,
+    canvas_height: f64,
+) -> Option<Entity> {
+    let mut rng = world_rng(world);
+    let x = rng.gen_range(0.0..canvas_width);
+    let y = rng.gen_range(0.0..canvas_height);
+
+    let accepted = world
+        .query::<&BiomeMap>()
+        .iter()
+        .next()
+        .is_none_or(|(_, biome_map)| biome_map.accepts_spawn(x, y, &mut rng));
+    if !accepted {
+        return None;
+    }
+    let multiplier = biome_energy_multiplier(world, x, y);
+
+    let initial_energy = rng.gen_range(15.0..40.0);
+    let max_energy = rng.gen_range(30.0..60.0) * multiplier;
+    let (resource_type, energy_delta) = if rng.gen_bool(POISON_CHANCE) {
+        (ResourceType::Poison, POISON_ENERGY_DELTA)
+    } else {
+        (ResourceType::Food, 0.0)
+    };
+
+    Some(world.spawn((
+        Position { x, y },
+        Resource {
+            energy: 0.0,
+            max_energy,
+            size: 3.0,
+            growth_rate: rng.gen_range(0.1..0.5) * multiplier,
+            regeneration_rate: rng.gen_range(0.02..0.1) * multiplier,
+            age: 0.0,
+            target_energy: initial_energy,
+            is_spawning: true,
+            spawn_fade: 0.0,
+            is_depleting: false,
+            deplete_fade: 0.0,
+            is_carcass: false,
+            resource_type,
+            energy_delta,
+        },
+        Size { value: 3.0 },
+        ResourceTag,
+        StableId::next(),
+    )))
+}
+
+/// Leaves a nutrient deposit where an agent died; see `resource::Resource::carcass`.
+pub fn spawn_carcass_resource(world: &mut World, x: f64, y: f64, remains: f64) -> Entity {
+    let mut rng = world_rng(world);
+    let energy = remains * DECOMPOSITION_RETAINED;
+
+    world.spawn((
+        Position { x, y },
+        Resource {
+            energy,
+            max_energy: energy,
+            size: 3.0,
+            growth_rate: rng.gen_range(0.1..0.5),
+            regeneration_rate: rng.gen_range(0.02..0.1),
+            age: 0.0,
+            target_energy: energy,
+            is_spawning: true,
+            spawn_fade: 0.0,
+            is_depleting: false,
+            deplete_fade: 0.0,
+            is_carcass: true,
+            resource_type: ResourceType::Food,
+            energy_delta: 0.0,
+        },
+        Size { value: 3.0 },
+        ResourceTag,
+        StableId::next(),
+    ))
+}
+
+/// Converts the shared `genes::Genes` into the ECS component.
+pub fn to_ecs_genes(genes: &crate::genes::Genes) -> Genes {
+    Genes {
+        speed: genes.speed,
+        sense_range: genes.sense_range,
+        size: genes.size,
+        energy_efficiency: genes.energy_efficiency,
+        reproduction_threshold: genes.reproduction_threshold,
+        mutation_rate: genes.mutation_rate,
+        aggression: genes.aggression,
+        color_hue: genes.color_hue,
+        is_predator: genes.is_predator,
+        hunting_speed: genes.hunting_speed,
+        attack_power: genes.attack_power,
+        defense: genes.defense,
+        stealth: genes.stealth,
+        pack_mentality: genes.pack_mentality,
+        territory_size: genes.territory_size,
+        metabolism: genes.metabolism,
+        intelligence: genes.intelligence,
+        stamina: genes.stamina,
+        omnivore: genes.omnivore,
+        migration: genes.migration,
+        cooperation: genes.cooperation,
+        mutation_strength: genes.mutation_strength,
+    }
+}
+
+/// Converts the ECS component back into the shared `genes::Genes`.
+pub fn from_ecs_genes(genes: &Genes) -> crate::genes::Genes {
+    crate::genes::Genes {
+        speed: genes.speed,
+        sense_range: genes.sense_range,
+        size: genes.size,
+        energy_efficiency: genes.energy_efficiency,
+        reproduction_threshold: genes.reproduction_threshold,
+        mutation_rate: genes.mutation_rate,
+        aggression: genes.aggression,
+        color_hue: genes.color_hue,
+        is_predator: genes.is_predator,
+        hunting_speed: genes.hunting_speed,
+        attack_power: genes.attack_power,
+        defense: genes.defense,
+        stealth: genes.stealth,
+        pack_mentality: genes.pack_mentality,
+        territory_size: genes.territory_size,
+        metabolism: genes.metabolism,
+        intelligence: genes.intelligence,
+        stamina: genes.stamina,
+        omnivore: genes.omnivore,
+        migration: genes.migration,
+        cooperation: genes.cooperation,
+        mutation_strength: genes.mutation_strength,
+    }
+}
+
+pub fn random_genes(rng: &mut impl Rng) -> Genes {
+    Genes {
+        speed: rng.gen_range(0.8..1.5),
+        sense_range: rng.gen_range(30.0..80.0),
+        size: rng.gen_range(0.9..1.3),
+        energy_efficiency: rng.gen_range(0.8..1.2),
+        reproduction_threshold: rng.gen_range(60.0..120.0),
+        mutation_rate: rng.gen_range(0.02..0.08),
+        aggression: rng.gen_range(0.2..0.8),
+        color_hue: rng.gen_range(0.0..360.0),
+        is_predator: rng.gen_range(0.0..0.3),
+        hunting_speed: rng.gen_range(1.0..2.0),
+        attack_power: rng.gen_range(0.5..1.5),
+        defense: rng.gen_range(0.5..1.5),
+        stealth: rng.gen_range(0.0..1.0),
+        pack_mentality: rng.gen_range(0.0..1.0),
+        territory_size: rng.gen_range(50.0..150.0),
+        metabolism: rng.gen_range(0.8..1.4),
+        intelligence: rng.gen_range(0.5..1.5),
+        stamina: rng.gen_range(0.5..1.5),
+        omnivore: rng.gen_range(0.0..1.0),
+        migration: rng.gen_range(0.0..1.0),
+        cooperation: rng.gen_range(0.0..1.0),
+        mutation_strength: rng.gen_range(0.03..0.08),
+    }
+}
+
+pub fn agent_count(world: &World) -> usize {
+    world.query::<&AgentTag>().iter().count()
+}
+
+pub fn resource_count(world: &World) -> usize {
+    world.query::<&ResourceTag>().iter().count()
+}
+
+
 // Extension trait for Resource to add the update method
 impl Resource {
-    pub fn update(&mut self, delta_time: f64) {
+    /// Advances the resource by `delta_time`, growing and regenerating at
+    /// `growth_scale` times its usual rates.
+    pub fn update(&mut self, delta_time: f64, growth_scale: f64) {
         self.age += delta_time;
+        let growth_rate = self.growth_rate * growth_scale;
 
         // Handle spawning fade-in
         if self.is_spawning {
@@ -668,12 +1644,12 @@ impl Resource {
             }
         }
 
-        // Smooth energy growth towards target
-        if !self.is_spawning && !self.is_depleting {
+        // Smooth energy growth towards target, starting while still fading in
+        if !self.is_depleting {
             let energy_diff = self.target_energy - self.energy;
             if energy_diff.abs() > 0.1 {
                 let growth_direction = if energy_diff > 0.0 { 1.0 } else { -1.0 };
-                let growth_amount = self.growth_rate * delta_time * growth_direction;
+                let growth_amount = growth_rate * delta_time * growth_direction;
 
                 if energy_diff.abs() < growth_amount.abs() {
                     self.energy = self.target_energy;
@@ -684,7 +1660,7 @@ impl Resource {
 
             // Natural growth towards max energy
             if self.energy < self.max_energy {
-                self.energy += self.growth_rate * delta_time * 0.05;
+                self.energy += growth_rate * delta_time * 0.05;
                 if self.energy > self.max_energy {
                     self.energy = self.max_energy;
                 }
@@ -704,11 +1680,195 @@ impl Resource {
 
         // Regeneration when depleted
         if self.energy < 10.0 && !self.is_depleting {
-            self.energy += self.regeneration_rate * delta_time * 0.2;
+            self.energy += self.regeneration_rate * growth_scale * delta_time * 0.2;
         }
     }
 
     pub fn is_available(&self) -> bool {
-        self.energy > 5.0 && !self.is_depleting && self.spawn_fade > 0.5
+        self.is_available_to(5.0)
+    }
+
+    /// Like `is_available`, but for a forager that can only profit from
+    /// patches holding more than `min_energy`.
+    pub fn is_available_to(&self, min_energy: f64) -> bool {
+        self.energy > min_energy && !self.is_depleting && self.spawn_fade > 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs_systems::{CombatSystem, DeathSystem, System};
+
+    #[test]
+    fn test_json_round_trip_preserves_entities() {
+        let mut ecs_world = EcsWorld::new(800.0, 600.0);
+        ecs_world.clear();
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..500 {
+            let (x, y) = (rng.gen_range(0.0..800.0), rng.gen_range(0.0..600.0));
+            ecs_world.add_agent_with_genes(x, y, random_genes(&mut thread_rng()));
+        }
+        ecs_world.add_resource(10.0, 20.0);
+        ecs_world.set_boundary_behavior(BoundaryBehavior::Bounce);
+
+        let exported = ecs_world.to_json();
+        let imported = EcsWorld::from_json(&exported).unwrap();
+
+        assert_eq!(imported.get_agent_count(), 500);
+        assert_eq!(imported.get_resource_count(), ecs_world.get_resource_count());
+        assert_eq!(imported.boundary_behavior, BoundaryBehavior::Bounce);
+        assert_eq!(imported.to_json(), exported);
+    }
+
+    #[test]
+    fn test_from_json_rejects_bad_snapshots() {
+        let snapshot = |id: u64| {
+            json!({
+                "width": 100.0,
+                "height": 100.0,
+                "max_agents": 10,
+                "max_resources": 10,
+                "entities": [{ "id": id, "components": { "Teleporter": {} } }],
+            })
+        };
+        // The generation lives in the high 32 bits and is never zero
+        assert_eq!(
+            EcsWorld::from_json(&snapshot(1)).err(),
+            Some(EcsImportError::InvalidEntityId(1))
+        );
+        assert_eq!(
+            EcsWorld::from_json(&snapshot(1 << 32)).err(),
+            Some(EcsImportError::UnknownComponent("Teleporter".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_compute_stats_counts_combat_kills() {
+        let mut ecs_world = EcsWorld::new(800.0, 600.0);
+        ecs_world.clear();
+        let mut predator_genes = random_genes(&mut thread_rng());
+        predator_genes.is_predator = 1.0;
+        predator_genes.attack_power = 3.0;
+        let mut prey_genes = random_genes(&mut thread_rng());
+        prey_genes.is_predator = 0.0;
+        prey_genes.defense = 0.1;
+        ecs_world.add_agent_with_genes(100.0, 100.0, predator_genes);
+        ecs_world.add_agent_with_genes(103.0, 100.0, prey_genes);
+        let prey_id = ecs_world
+            .world
+            .query::<(&StableId, &Genes)>()
+            .iter()
+            .find(|(_, (_, genes))| genes.is_predator < 0.5)
+            .map(|(_, (id, _))| id.value);
+        for (_, (state, genes)) in ecs_world.world.query_mut::<(&mut AgentState, &Genes)>() {
+            if genes.is_predator > 0.5 {
+                state.state = AgentStateEnum::Fighting;
+                state.target_id = prey_id;
+            }
+        }
+        for (_, (energy, genes)) in ecs_world.world.query_mut::<(&mut Energy, &Genes)>() {
+            if genes.is_predator < 0.5 {
+                energy.current = 10.0;
+            }
+        }
+
+        let stats = ecs_world.compute_stats();
+        assert_eq!(stats.agent_count, 2);
+        assert_eq!(stats.total_kills, 0);
+        assert_eq!(stats.age_distribution.iter().sum::<usize>(), 2);
+        for _ in 0..10 {
+            CombatSystem.run(&mut ecs_world.world);
+        }
+        DeathSystem { max_resources: 10 }.run(&mut ecs_world.world);
+
+        let stats = ecs_world.compute_stats();
+        assert_eq!(stats.agent_count, 1);
+        assert_eq!(stats.total_kills, 1);
+        let species = ecs_world.species_summary(0.3);
+        assert_eq!(species.species_count, 1);
+        assert_eq!(species.largest_species_fraction, 1.0);
+        // The survivor ate a primary consumer
+        assert_eq!(stats.average_trophic_level, 2.0);
+    }
+
+    #[test]
+    fn test_stable_ids_survive_reset_and_reload() {
+        let mut ecs_world = EcsWorld::new(800.0, 600.0);
+        let (id, x) = ecs_world
+            .world
+            .query::<(&StableId, &Position, &AgentTag)>()
+            .iter()
+            .map(|(_, (id, pos, _))| (id.value, pos.x))
+            .next()
+            .unwrap();
+        let found = ecs_world.get_agent_by_stable_id(id).unwrap();
+        assert_eq!(ecs_world.world.get::<&Position>(found).unwrap().x, x);
+        let saved = ecs_world.to_json();
+
+        // A reset brings in new agents under new IDs, never the old ones
+        ecs_world.reset();
+        assert_eq!(ecs_world.get_agent_by_stable_id(id), None);
+        let ids: Vec<u64> = ecs_world
+            .world
+            .query::<&StableId>()
+            .iter()
+            .map(|(_, id)| id.value)
+            .collect();
+        assert!(ids.iter().all(|&new_id| new_id > id));
+
+        let mut restored = EcsWorld::from_json(&saved).unwrap();
+        let found = restored.get_agent_by_stable_id(id).unwrap();
+        assert_eq!(restored.world.get::<&Position>(found).unwrap().x, x);
+        restored.update();
+        assert!(restored.get_agent_by_stable_id(id).is_some());
+
+        // Resources have IDs too, but aren't agents
+        let resource_id = restored
+            .world
+            .query::<(&StableId, &ResourceTag)>()
+            .iter()
+            .map(|(_, (id, _))| id.value)
+            .next()
+            .unwrap();
+        assert_eq!(restored.get_agent_by_stable_id(resource_id), None);
+    }
+
+    #[test]
+    fn test_offspring_speciate_only_when_far_from_their_parent() {
+        let mut ecs_world = EcsWorld::new(800.0, 600.0);
+        let founders = ecs_world.get_species_distribution();
+        assert_eq!(founders, vec![(SpeciesId::FOUNDERS.0, ecs_world.get_agent_count())]);
+
+        let threshold = crate::species::DEFAULT_SPECIES_THRESHOLD;
+        let parents: Vec<Entity> =
+            ecs_world.world.query::<&AgentTag>().iter().map(|(entity, _)| entity).take(2).collect();
+        let child = ecs_world.reproduce(parents[0], parents[0], threshold).unwrap();
+        assert_eq!(*ecs_world.world.get::<&SpeciesId>(child).unwrap(), SpeciesId::FOUNDERS);
+        assert_eq!(ecs_world.world.get::<&AgentState>(child).unwrap().generation, 1);
+
+        // The parent's unit-range genes pushed to the opposite end of it
+        let mut mutant = ecs_world.world.get::<&Genes>(parents[1]).map(|g| (*g).clone()).unwrap();
+        for gene in [
+            &mut mutant.aggression,
+            &mut mutant.is_predator,
+            &mut mutant.stealth,
+            &mut mutant.pack_mentality,
+            &mut mutant.omnivore,
+            &mut mutant.migration,
+            &mut mutant.cooperation,
+        ] {
+            *gene = if *gene < 0.5 { 1.0 } else { 0.0 };
+        }
+        let mutant = ecs_world.spawn_offspring(parents[1], mutant, threshold).unwrap();
+        let species = *ecs_world.world.get::<&SpeciesId>(mutant).unwrap();
+        assert_ne!(species, SpeciesId::FOUNDERS);
+
+        // Its own offspring stay in the new species
+        let grandchild = ecs_world.reproduce(mutant, mutant, threshold).unwrap();
+        assert_eq!(*ecs_world.world.get::<&SpeciesId>(grandchild).unwrap(), species);
+        let distribution = ecs_world.get_species_distribution();
+        assert_eq!(distribution.len(), 2);
+        assert_eq!(distribution[1], (species.0, 2));
     }
 }
@@ -0,0 +1,1865 @@
+use crate::agent::{
+    are_kin, digest, escape_heading, flee_cost, flee_speed, is_due_to_migrate, metabolic_cost,
+    migration_heading, mortality_rate, next_stamina, perceived_position, prey_score, r_star,
+    resource_score, share_terms, stamina_pace, PhenotypeModifiers, HUNGRY_ENERGY,
+    MAX_TROPHIC_LEVEL, MIGRATION_DURATION, MIGRATION_SPEED_MULTIPLIER, SATIATED,
+    SATIATION_PER_KILL, SHARE_RADIUS,
+};
+use crate::boundary::BoundaryBehavior;
+use crate::ecs::{
+    agent_count, from_ecs_genes, random_genes, resource_count, spawn_agent, spawn_carcass_resource,
+    spawn_random_resource, world_rng, Age, AgentDeath, AgentState, AgentStateEnum, AgentTag,
+    AllometricScaling, DeathAnimation, DeathEvents, DeathReason, Energy, EnergyShared, Genes,
+    InteractionEvents, Memory, ParallelResourceUpdate, PoisonDeaths, PopulationLimits, Position,
+    Resource, ResourceConsumption, ResourceSpawnScale, ResourceSpawnTimer, SpeciesId, StableId,
+    Velocity, Warmth,
+};
+use crate::interaction::{InteractionLog, InteractionType};
+use crate::resource::{poison_damage, ResourceType, POISON_LEARNING_INTELLIGENCE};
+use crate::simulation_core::{seasonal_growth_scale, seasonal_metabolic_scale};
+use crate::habitat::Habitat;
+use hecs::World;
+use std::collections::HashMap;
+use rand::prelude::*;
+use rayon::prelude::*;
+
+const DELTA_TIME: f64 = 1.0 / 60.0;
+/// How close a predator has to be to strike another agent.
+pub const COMBAT_RANGE: f64 = 5.0;
+/// Fraction of the energy a strike takes from its target that the attacker gains.
+pub const COMBAT_ENERGY_TRANSFER: f64 = 0.5;
+/// Below this many resources the rayon hand-off costs more than it saves.
+pub const PARALLEL_RESOURCE_THRESHOLD: usize = 200;
+/// Predators with `pack_mentality` above this hunt in packs.
+pub const PACK_MENTALITY_THRESHOLD: f64 = 0.7;
+/// Other predators that must be within `territory_size` to form a pack.
+pub const PACK_MIN_MATES: usize = 2;
+/// Weight of the pull towards pack-mates against the pull towards prey.
+const PACK_COHESION: f64 = 0.5;
+/// Pack members this close to a kill share in it.
+pub const PACK_SHARE_RADIUS: f64 = 40.0;
+/// Energy each member in reach of a pack kill gains, per unit of its own
+/// `pack_mentality`.
+pub const PACK_KILL_BONUS: f64 = 10.0;
+/// How close an agent has to come to an available resource to count as
+/// having fed there.
+pub const FEEDING_RANGE: f64 = 5.0;
+/// Seconds an agent keeps heading back to where it last fed.
+pub const MEMORY_SPAN: f64 = 30.0;
+
+// ============================================================================
+// SCHEDULER
+// ============================================================================
+
+pub trait System: Send + Sync {
+    fn name(&self) -> &str;
+    fn run(&self, world: &mut World);
+}
+
+/// Runs registered systems in an order that respects their `after` constraints.
+///
+/// Systems without constraints between them keep their registration order.
+/// Call `build` after registering systems to (re)compute the execution order.
+#[derive(Default)]
+pub struct Scheduler {
+    systems: Vec<Box<dyn System>>,
+    dependencies: Vec<Vec<String>>,
+    order: Vec<usize>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in simulation pipeline used by `EcsWorld`.
+    pub fn with_default_systems(
+        canvas_width: f64,
+        canvas_height: f64,
+        max_agents: usize,
+        max_resources: usize,
+    ) -> Self {
+        let mut scheduler = Self::new();
+        scheduler.register_system(Box::new(ResourceUpdateSystem), &[]);
+        scheduler.register_system(
+            Box::new(AgentBehaviorSystem {
+                canvas_width,
+                canvas_height,
+            }),
+            &["resource_update"],
+        );
+        scheduler.register_system(
+            Box::new(AgentMovementSystem {
+                canvas_width,
+                canvas_height,
+            }),
+            &["agent_behavior"],
+        );
+        scheduler.register_system(Box::new(CombatSystem), &["agent_movement"]);
+        scheduler.register_system(Box::new(PoisonSystem), &["agent_movement"]);
+        scheduler.register_system(
+            Box::new(DeathSystem { max_resources }),
+            &["combat", "poison"],
+        );
+        scheduler.register_system(
+            Box::new(ReproductionSystem {
+                canvas_width,
+                canvas_height,
+                max_agents,
+            }),
+            &["death"],
+        );
+        scheduler.register_system(
+            Box::new(ResourceSpawnSystem {
+                canvas_width,
+                canvas_height,
+                max_resources,
+                interval: 0.5,
+            }),
+            &["reproduction"],
+        );
+        scheduler
+            .build()
+            .expect("default systems have no cyclic dependencies");
+        scheduler
+    }
+
+    pub fn register_system(&mut self, system: Box<dyn System>, after: &[&str]) {
+        self.systems.push(system);
+        self.dependencies
+            .push(after.iter().map(|name| name.to_string()).collect());
+    }
+
+    /// Registers `system` and rebuilds the order. If that fails, the system
+    /// is taken back out, so the scheduler is left as it was.
+    pub fn try_register_system(
+        &mut self,
+        system: Box<dyn System>,
+        after: &[&str],
+    ) -> Result<(), String> {
+        self.register_system(system, after);
+        self.build().inspect_err(|_| {
+            self.systems.pop();
+            self.dependencies.pop();
+        })
+    }
+
+    /// Topologically sorts the registered systems. Fails on unknown dependency
+    /// names or cyclic constraints, leaving the previous order in place.
+    pub fn build(&mut self) -> Result<(), String> {
+        let count = self.systems.len();
+        let mut in_degree = vec![0; count];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); count];
+
+        for (i, after) in self.dependencies.iter().enumerate() {
+            for name in after {
+                let dependency = self
+                    .systems
+                    .iter()
+                    .position(|s| s.name() == name)
+                    .ok_or_else(|| {
+                        format!(
+                            "System '{}' depends on unknown system '{}'",
+                            self.systems[i].name(),
+                            name
+                        )
+                    })?;
+                dependents[dependency].push(i);
+                in_degree[i] += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(count);
+        let mut done = vec![false; count];
+        while order.len() < count {
+            let next = (0..count).find(|&i| !done[i] && in_degree[i] == 0);
+            let Some(next) = next else {
+                let cyclic: Vec<&str> = (0..count)
+                    .filter(|&i| !done[i])
+                    .map(|i| self.systems[i].name())
+                    .collect();
+                return Err(format!(
+                    "Cyclic system dependencies: {}",
+                    cyclic.join(", ")
+                ));
+            };
+
+            done[next] = true;
+            order.push(next);
+            for &dependent in &dependents[next] {
+                in_degree[dependent] -= 1;
+            }
+        }
+
+        self.order = order;
+        Ok(())
+    }
+
+    pub fn run(&self, world: &mut World) {
+        for &i in &self.order {
+            self.systems[i].run(world);
+        }
+    }
+
+    /// System names in execution order.
+    pub fn system_names(&self) -> Vec<&str> {
+        self.order.iter().map(|&i| self.systems[i].name()).collect()
+    }
+}
+
+// ============================================================================
+// SYSTEMS
+// ============================================================================
+
+/// The world's `PopulationLimits`, if it has them.
+fn population_limits(world: &World) -> Option<PopulationLimits> {
+    world.query::<&PopulationLimits>().iter().next().map(|(_, limits)| *limits)
+}
+
+/// The world's `Warmth`, 1 (high summer) without one.
+fn warmth(world: &World) -> f64 {
+    world
+        .query::<&Warmth>()
+        .iter()
+        .next()
+        .map_or(1.0, |(_, warmth)| warmth.0)
+}
+
+/// Grows and fades resources, slower in winter; see `seasonal_growth_scale`.
+/// With `ParallelResourceUpdate` enabled and more than
+/// `PARALLEL_RESOURCE_THRESHOLD` resources, they are copied out, updated on
+/// the rayon pool and written back, since `hecs` can't iterate mutably in
+/// parallel.
+pub struct ResourceUpdateSystem;
+
+impl System for ResourceUpdateSystem {
+    fn name(&self) -> &str {
+        "resource_update"
+    }
+
+    fn run(&self, world: &mut World) {
+        let parallel = world
+            .query::<&ParallelResourceUpdate>()
+            .iter()
+            .next()
+            .is_some_and(|(_, update)| update.enabled)
+            && resource_count(world) > PARALLEL_RESOURCE_THRESHOLD;
+
+        let growth_scale = seasonal_growth_scale(warmth(world));
+
+        if !parallel {
+            for (_, resource) in world.query_mut::<&mut Resource>() {
+                resource.update(DELTA_TIME, growth_scale);
+            }
+            return;
+        }
+
+        let mut resources: Vec<Resource> = world
+            .query_mut::<&Resource>()
+            .into_iter()
+            .map(|(_, resource)| resource.clone())
+            .collect();
+        resources
+            .par_iter_mut()
+            .for_each(|resource| resource.update(DELTA_TIME, growth_scale));
+        // Nothing is spawned or despawned in between, so the order matches
+        let stored = world.query_mut::<&mut Resource>().into_iter();
+        for ((_, resource), updated) in stored.zip(resources) {
+            *resource = updated;
+        }
+        for (_, update) in world.query_mut::<&mut ParallelResourceUpdate>() {
+            update.frames += 1;
+        }
+    }
+}
+
+/// Ages agents, expresses their phenotype (see
+/// `agent::PhenotypeModifiers::around`), tires or rests them (see
+/// `agent::next_stamina`), charges their metabolic cost, rolls for deaths of
+/// natural causes (see `agent::mortality_rate`), sends prey fleeing from the
+/// predators they sense, steers migrants and picks a target resource, or
+/// prey while not sated (see `agent::SATIATED`). Targets and threats are
+/// seen through sensory noise; see `agent::perceived_position`.
+pub struct AgentBehaviorSystem {
+    pub canvas_width: f64,
+    pub canvas_height: f64,
+}
+
+impl System for AgentBehaviorSystem {
+    fn name(&self) -> &str {
+        "agent_behavior"
+    }
+
+    fn run(&self, world: &mut World) {
+        // Get all resources for agent decision making
+        let resources: Vec<_> = world
+            .query::<(&Position, &Resource)>()
+            .iter()
+            .map(|(_, (pos, res))| (pos.x, pos.y, res.clone()))
+            .collect();
+        let others: Vec<PackView> = world
+            .query::<(&Position, &Energy, &Age, &Genes, Option<&StableId>, &AgentTag)>()
+            .iter()
+            .filter(|(_, (_, energy, age, _, _, _))| !is_dead(energy, age))
+            .map(|(entity, (pos, energy, _, genes, id, _))| PackView {
+                entity,
+                x: pos.x,
+                y: pos.y,
+                energy: energy.current,
+                is_predator: genes.is_predator_at(energy.current),
+                pack_mentality: genes.pack_mentality,
+                territory_size: genes.territory_size,
+                id: id.map_or(0, |id| id.value),
+            })
+            .collect();
+        let pack_ids = pack_ids(&others);
+        let allometric = world
+            .query::<&AllometricScaling>()
+            .iter()
+            .next()
+            .is_some_and(|(_, scaling)| scaling.0);
+        let winter_cost = seasonal_metabolic_scale(warmth(world));
+        let mut rng = world_rng(world);
+        let mut escapes = Vec::new();
+
+        for (entity, (pos, vel, energy, age, state, genes, mut memory, id, death)) in world
+            .query_mut::<(
+                &Position,
+                &mut Velocity,
+                &mut Energy,
+                &mut Age,
+                &mut AgentState,
+                &Genes,
+                Option<&mut Memory>,
+                Option<&StableId>,
+                Option<&mut DeathAnimation>,
+            )>()
+        {
+            age.value += DELTA_TIME;
+
+            // Crowding and scarcity shape how the genes are expressed
+            let neighbours = others
+                .iter()
+                .filter(|other| other.entity != entity)
+                .map(|other| (other.x, other.y));
+            let foraging = resources
+                .iter()
+                .map(|(rx, ry, resource)| (*rx, *ry, resource.energy));
+            state.phenotype = PhenotypeModifiers::around(pos.x, pos.y, neighbours, foraging);
+            let speed = genes.speed * state.phenotype.speed_scale;
+
+            // Last tick's chase or escape tires the agent; anything else rests it
+            let exerting = matches!(
+                state.state,
+                AgentStateEnum::Hunting
+                    | AgentStateEnum::PackHunting { .. }
+                    | AgentStateEnum::Fighting
+                    | AgentStateEnum::Fleeing
+            );
+            state.current_stamina =
+                next_stamina(state.current_stamina, genes.stamina, exerting, DELTA_TIME);
+            let pace = stamina_pace(state.current_stamina, genes.stamina);
+            // Full predators leave prey alone and graze until they've digested
+            state.satiation = digest(state.satiation, genes.metabolism, DELTA_TIME);
+            let sated = state.satiation > SATIATED;
+
+            // Energy consumption
+            let base_energy_cost = metabolic_cost(genes.size, genes.speed, allometric) * DELTA_TIME;
+            let metabolism_factor = genes.metabolism * state.phenotype.metabolism_scale;
+            let environmental_factor =
+                1.0 + (pos.x / self.canvas_width + pos.y / self.canvas_height) * 0.001;
+            let total_energy_cost =
+                base_energy_cost * metabolism_factor * environmental_factor * winter_cost;
+            energy.current -= total_energy_cost / genes.energy_efficiency;
+
+            // Dead agents are removed by the death system
+            if is_dead(energy, age) {
+                continue;
+            }
+            if rng.gen::<f64>() < mortality_rate(age.value, genes.stamina) * DELTA_TIME {
+                energy.current = 0.0;
+                if let Some(death) = death {
+                    death.reason = DeathReason::NaturalCauses;
+                    death.is_dying = true;
+                }
+                continue;
+            }
+
+            let course = if sated {
+                None
+            } else {
+                pack_course(entity, pos, genes, &others, &pack_ids)
+            };
+            if let Some(course) = course {
+                state.state = AgentStateEnum::PackHunting {
+                    pack_id: course.pack_id,
+                };
+                (state.target_x, state.target_y, state.target_id) = match course.prey {
+                    Some((x, y, id)) => (Some(x), Some(y), Some(id)),
+                    None => (None, None, None),
+                };
+                (vel.dx, vel.dy) = course.heading;
+                vel.dx *= genes.hunting_speed * pace;
+                vel.dy *= genes.hunting_speed * pace;
+                continue;
+            }
+            if matches!(state.state, AgentStateEnum::PackHunting { .. }) {
+                state.state = AgentStateEnum::Seeking;
+            }
+
+            // Prey run from every predator they sense, the closest pushing
+            // hardest; see `agent::escape_heading`
+            let is_predator = genes.is_predator_at(energy.current);
+            let distance = |other: &PackView| (other.x - pos.x).hypot(other.y - pos.y);
+            let threats: Vec<&PackView> = others
+                .iter()
+                .filter(|other| {
+                    !is_predator
+                        && other.entity != entity
+                        && other.is_predator
+                        && distance(other) <= genes.sense_range
+                })
+                .collect();
+            // Each where the prey perceives it; see `agent::perceived_position`
+            let seen: Vec<(f64, f64)> = threats
+                .iter()
+                .map(|threat| {
+                    let (x, y, range) = (threat.x, threat.y, genes.sense_range);
+                    perceived_position(x, y, distance(threat), range, genes.intelligence, &mut rng)
+                })
+                .collect();
+            let nearest = (0..threats.len())
+                .min_by(|&a, &b| distance(threats[a]).total_cmp(&distance(threats[b])));
+            if let Some(nearest) = nearest {
+                let predator = threats[nearest];
+                if state.state != AgentStateEnum::Fleeing {
+                    escapes.push((id.map_or(0, |id| id.value), predator.id));
+                }
+                state.state = AgentStateEnum::Fleeing;
+                (state.target_x, state.target_y, state.target_id) = (None, None, None);
+                let (seen_x, seen_y) = seen[nearest];
+                state.perception_error = (seen_x - predator.x).hypot(seen_y - predator.y);
+                let heading = escape_heading(pos.x, pos.y, seen.iter().copied());
+                if let Some((escape_x, escape_y)) = heading {
+                    let speed = flee_speed(speed, genes.stamina) * pace;
+                    vel.dx = escape_x * speed;
+                    vel.dy = escape_y * speed;
+                }
+                energy.current -= flee_cost(genes.stamina) * DELTA_TIME;
+                continue;
+            }
+            if state.state == AgentStateEnum::Fleeing {
+                state.state = AgentStateEnum::Seeking;
+            }
+
+            // Strong migrants set off on their compass heading every
+            // MIGRATION_INTERVAL and hold it for MIGRATION_DURATION
+            let migrating = state.state == AgentStateEnum::Migrating;
+            if !migrating
+                && is_due_to_migrate(genes.migration, age.value, state.last_migration_time)
+            {
+                let heading = migration_heading(id.map_or(0, |id| id.value));
+                state.migrating_direction = Some(heading);
+                state.last_migration_time = age.value;
+                state.state = AgentStateEnum::Migrating;
+                (state.target_x, state.target_y, state.target_id) = (None, None, None);
+            }
+            if state.state == AgentStateEnum::Migrating {
+                match state.migrating_direction {
+                    Some((dir_x, dir_y))
+                        if age.value - state.last_migration_time < MIGRATION_DURATION =>
+                    {
+                        let speed = speed * MIGRATION_SPEED_MULTIPLIER;
+                        vel.dx = dir_x * speed;
+                        vel.dy = dir_y * speed;
+                        continue;
+                    }
+                    _ => {
+                        state.migrating_direction = None;
+                        state.state = AgentStateEnum::Seeking;
+                    }
+                }
+            }
+
+            // Remember where food worth its R* was found; forget a spot that
+            // no longer has any
+            let r_star = r_star(genes.metabolism, genes.size);
+            let recalled = memory.as_deref_mut().and_then(|memory| {
+                let fed_at = resources.iter().find(|(rx, ry, resource)| {
+                    resource.is_available_to(r_star)
+                        && resource.resource_type == ResourceType::Food
+                        && (rx - pos.x).hypot(ry - pos.y) <= FEEDING_RANGE
+                });
+                if let Some((rx, ry, _)) = fed_at {
+                    if memory.is_guiding(state) {
+                        memory.visits += 1;
+                    }
+                    memory.remember(*rx, *ry, age.value);
+                } else if memory.is_guiding(state)
+                    && state.target_x.zip(state.target_y).is_some_and(|(tx, ty)| {
+                        (tx - pos.x).hypot(ty - pos.y) <= FEEDING_RANGE
+                    })
+                {
+                    memory.forget();
+                }
+                memory.recall(pos.x, pos.y, age.value, MEMORY_SPAN, genes.territory_size)
+            });
+
+            // Prey and resources compete on one score, weighted by how
+            // predatory the agent is
+            let mut best_target = None;
+            let mut best_score = f64::NEG_INFINITY;
+
+            let predation = genes.predation_at(energy.current);
+            let hunting_range = genes.sense_range * genes.territory_size / 100.0;
+            let prey_in_sight = others
+                .iter()
+                .filter(|other| !sated && other.entity != entity && !other.is_predator);
+            for prey in prey_in_sight {
+                let distance = (prey.x - pos.x).hypot(prey.y - pos.y);
+                if distance <= hunting_range {
+                    let score = prey_score(
+                        prey.energy,
+                        distance,
+                        predation,
+                        genes.stealth,
+                        genes.intelligence,
+                    );
+                    if score > best_score {
+                        best_score = score;
+                        best_target = Some((prey.x, prey.y, Some(prey.id)));
+                    }
+                }
+            }
+
+            let avoids =
+                |x: f64, y: f64| memory.as_deref().is_some_and(|m| m.avoids_poison_at(x, y));
+            for (rx, ry, resource) in resources.iter() {
+                if resource.is_available_to(r_star) && !avoids(*rx, *ry) {
+                    let distance = ((pos.x - rx).powi(2) + (pos.y - ry).powi(2)).sqrt();
+                    if distance <= genes.sense_range {
+                        let score = resource_score(resource.energy, distance, predation);
+                        if score > best_score {
+                            best_score = score;
+                            best_target = Some((*rx, *ry, None));
+                        }
+                    }
+                }
+            }
+
+            state.target_id = None;
+            if let Some((tx, ty, prey_id)) = best_target {
+                let distance = (tx - pos.x).hypot(ty - pos.y);
+                let (range, intelligence) = (genes.sense_range, genes.intelligence);
+                let (seen_x, seen_y) =
+                    perceived_position(tx, ty, distance, range, intelligence, &mut rng);
+                state.perception_error = (seen_x - tx).hypot(seen_y - ty);
+                state.target_x = Some(seen_x);
+                state.target_y = Some(seen_y);
+                state.target_id = prey_id;
+                // Chasing prey is a fight whatever the predator gene
+                state.state = if prey_id.is_some() {
+                    AgentStateEnum::Fighting
+                } else {
+                    AgentStateEnum::Hunting
+                };
+            } else if let Some((mx, my)) = recalled {
+                state.state = AgentStateEnum::Seeking;
+                state.target_x = Some(mx);
+                state.target_y = Some(my);
+                let (dx, dy) = (mx - pos.x, my - pos.y);
+                let length = dx.hypot(dy);
+                if length > 0.0 {
+                    vel.dx = dx / length * speed;
+                    vel.dy = dy / length * speed;
+                }
+            } else {
+                // Random movement
+                let angle = rng.gen_range(0.0..2.0 * std::f64::consts::PI);
+                vel.dx = angle.cos() * speed;
+                vel.dy = angle.sin() * speed;
+            }
+        }
+
+        for (prey, predator) in escapes {
+            log_interaction(world, prey, predator, InteractionType::Fleeing, 0.0);
+        }
+    }
+}
+
+/// A living agent as seen by predators deciding whether to hunt as a pack.
+struct PackView {
+    entity: hecs::Entity,
+    x: f64,
+    y: f64,
+    energy: f64,
+    is_predator: bool,
+    pack_mentality: f64,
+    territory_size: f64,
+    /// `StableId` value, or 0 without one.
+    id: u64,
+}
+
+/// Predators with `pack_mentality` over `PACK_MENTALITY_THRESHOLD` that have
+/// at least `PACK_MIN_MATES` other predators within their `territory_size`.
+fn pack_mates<'a>(me: &PackView, others: &'a [PackView]) -> Option<Vec<&'a PackView>> {
+    if !me.is_predator || me.pack_mentality <= PACK_MENTALITY_THRESHOLD {
+        return None;
+    }
+    let mates: Vec<&PackView> = others
+        .iter()
+        .filter(|other| {
+            other.entity != me.entity
+                && other.is_predator
+                && (other.x - me.x).hypot(other.y - me.y) <= me.territory_size
+        })
+        .collect();
+    (mates.len() >= PACK_MIN_MATES).then_some(mates)
+}
+
+/// The pack ID of every agent that hunts in a pack. Mates link hunters into
+/// packs even where the link only runs one way, and each pack takes the
+/// smallest `StableId` among its hunters as its ID.
+fn pack_ids(others: &[PackView]) -> HashMap<hecs::Entity, u64> {
+    let index: HashMap<hecs::Entity, usize> =
+        others.iter().enumerate().map(|(i, view)| (view.entity, i)).collect();
+    let mut parent: Vec<usize> = (0..others.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut hunters = Vec::new();
+    for (i, view) in others.iter().enumerate() {
+        let Some(mates) = pack_mates(view, others) else {
+            continue;
+        };
+        hunters.push(i);
+        for mate in mates {
+            let (a, b) = (root(&mut parent, i), root(&mut parent, index[&mate.entity]));
+            parent[a] = b;
+        }
+    }
+
+    let mut smallest: HashMap<usize, u64> = HashMap::new();
+    for &i in &hunters {
+        let id = smallest.entry(root(&mut parent, i)).or_insert(u64::MAX);
+        *id = (*id).min(others[i].id);
+    }
+    hunters
+        .into_iter()
+        .map(|i| (others[i].entity, smallest[&root(&mut parent, i)]))
+        .collect()
+}
+
+/// Where a pack hunter goes: `heading` is a unit vector mixing the way to
+/// the nearest `prey` in `sense_range` with the way to the pack's centroid.
+/// `prey` holds its position and `StableId`.
+struct PackCourse {
+    pack_id: u64,
+    heading: (f64, f64),
+    prey: Option<(f64, f64, u64)>,
+}
+
+/// The course of a pack hunter (see `pack_mates`), or `None` if it hunts
+/// alone. Its pack's centroid weighs each mate by its `pack_mentality`.
+fn pack_course(
+    me: hecs::Entity,
+    pos: &Position,
+    genes: &Genes,
+    others: &[PackView],
+    pack_ids: &HashMap<hecs::Entity, u64>,
+) -> Option<PackCourse> {
+    let pack_id = *pack_ids.get(&me)?;
+    let view = others.iter().find(|other| other.entity == me)?;
+    let mates = pack_mates(view, others)?;
+    let distance = |other: &PackView| (other.x - pos.x).hypot(other.y - pos.y);
+
+    let weight: f64 = mates.iter().map(|mate| mate.pack_mentality).sum();
+    let centroid = if weight > 0.0 {
+        let x = mates.iter().map(|mate| mate.x * mate.pack_mentality).sum::<f64>() / weight;
+        let y = mates.iter().map(|mate| mate.y * mate.pack_mentality).sum::<f64>() / weight;
+        (x, y)
+    } else {
+        let count = mates.len() as f64;
+        let x = mates.iter().map(|mate| mate.x).sum::<f64>() / count;
+        let y = mates.iter().map(|mate| mate.y).sum::<f64>() / count;
+        (x, y)
+    };
+    let prey = others
+        .iter()
+        .filter(|other| !other.is_predator && distance(other) <= genes.sense_range)
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        .map(|prey| (prey.x, prey.y, prey.id));
+
+    let toward = |(x, y): (f64, f64)| {
+        let (dx, dy) = (x - pos.x, y - pos.y);
+        let length = dx.hypot(dy);
+        if length > 0.0 {
+            (dx / length, dy / length)
+        } else {
+            (0.0, 0.0)
+        }
+    };
+    let (cx, cy) = toward(centroid);
+    let (px, py) = prey.map_or((0.0, 0.0), |(x, y, _)| toward((x, y)));
+    let heading = toward((pos.x + px + PACK_COHESION * cx, pos.y + py + PACK_COHESION * cy));
+
+    Some(PackCourse {
+        pack_id,
+        heading,
+        prey,
+    })
+}
+
+pub struct AgentMovementSystem {
+    pub canvas_width: f64,
+    pub canvas_height: f64,
+}
+
+impl System for AgentMovementSystem {
+    fn name(&self) -> &str {
+        "agent_movement"
+    }
+
+    fn run(&self, world: &mut World) {
+        let boundary = world
+            .query::<&BoundaryBehavior>()
+            .iter()
+            .next()
+            .map(|(_, boundary)| *boundary)
+            .unwrap_or_default();
+
+        for (_, (pos, vel, energy, age, death)) in world.query_mut::<(
+            &mut Position,
+            &mut Velocity,
+            &mut Energy,
+            &Age,
+            Option<&mut DeathAnimation>,
+        )>() {
+            if is_dead(energy, age) {
+                continue;
+            }
+
+            // Apply movement
+            pos.x += vel.dx * DELTA_TIME;
+            pos.y += vel.dy * DELTA_TIME;
+
+            if !boundary.apply(
+                &mut pos.x,
+                &mut pos.y,
+                &mut vel.dx,
+                &mut vel.dy,
+                self.canvas_width,
+                self.canvas_height,
+            ) {
+                // Leave removal to the death system
+                energy.current = 0.0;
+                if let Some(death) = death {
+                    death.reason = DeathReason::FellOffEdge;
+                    death.is_dying = true;
+                }
+                continue;
+            }
+
+            // Normalize direction vector
+            let length = (vel.dx * vel.dx + vel.dy * vel.dy).sqrt();
+            if length > 0.0 {
+                vel.dx /= length;
+                vel.dy /= length;
+            }
+        }
+    }
+}
+
+/// Lets predators strike the prey their behaviour picked
+/// (`AgentState::target_id`) once it is within `COMBAT_RANGE`. A strike deals
+/// `attack_power * energy * 0.01 / (defense + 1)`, and the attacker gains
+/// `COMBAT_ENERGY_TRANSFER` of what the target lost. Targets drained to zero
+/// are marked killed for the death system, and each kill adds
+/// `SATIATION_PER_KILL` to the killer's satiation, puts it a trophic level
+/// above its prey and, if it cooperates, has it feed hungry kin. A kill made while pack hunting
+/// counts as a pack kill and gives every member of the pack within
+/// `PACK_SHARE_RADIUS` of the killer `PACK_KILL_BONUS` times its
+/// `pack_mentality`.
+pub struct CombatSystem;
+
+impl System for CombatSystem {
+    fn name(&self) -> &str {
+        "combat"
+    }
+
+    fn run(&self, world: &mut World) {
+        let agents: Vec<_> = world
+            .query::<(&Position, &Energy, &Age, &AgentState, &StableId, &Genes)>()
+            .iter()
+            .filter(|(_, (_, energy, age, _, _, _))| !is_dead(energy, age))
+            .map(|(entity, (pos, energy, _, state, id, genes))| {
+                let attacks = genes.is_predator_at(energy.current)
+                    && matches!(
+                        state.state,
+                        AgentStateEnum::Fighting | AgentStateEnum::PackHunting { .. }
+                    );
+                let target = state.target_id.filter(|_| attacks);
+                (entity, pos.x, pos.y, id.value, target)
+            })
+            .collect();
+        let by_id: HashMap<u64, usize> =
+            agents.iter().enumerate().map(|(i, agent)| (agent.3, i)).collect();
+
+        for &(attacker, x, y, _, target) in &agents {
+            let attacker_energy = world.get::<&Energy>(attacker).map_or(0.0, |e| e.current);
+            if attacker_energy <= 0.0 {
+                continue;
+            }
+
+            let target = target
+                .and_then(|id| by_id.get(&id))
+                .map(|&i| agents[i])
+                .filter(|&(target, tx, ty, ..)| {
+                    (tx - x).hypot(ty - y) <= COMBAT_RANGE
+                        && world.get::<&Energy>(target).is_ok_and(|e| e.current > 0.0)
+                })
+                .map(|(target, ..)| target);
+            let Some(target) = target else {
+                continue;
+            };
+
+            let attack_power = world.get::<&Genes>(attacker).map_or(0.0, |g| g.attack_power);
+            let defense = world.get::<&Genes>(target).map_or(0.0, |g| g.defense);
+            let damage = attack_power * attacker_energy * 0.01 / (defense + 1.0);
+
+            let (lost, killed) = {
+                let mut energy = world.get::<&mut Energy>(target).expect("target is an agent");
+                let lost = damage.min(energy.current);
+                energy.current -= lost;
+                (lost, energy.current <= 0.0)
+            };
+            let gained = match world.get::<&mut Energy>(attacker) {
+                Ok(mut energy) => {
+                    let before = energy.current;
+                    energy.current = (before + lost * COMBAT_ENERGY_TRANSFER).min(energy.max);
+                    energy.current - before
+                }
+                Err(_) => 0.0,
+            };
+            let (attacker_id, target_id) = (stable_id(world, attacker), stable_id(world, target));
+            let combat = InteractionType::Combat(killed);
+            log_interaction(world, attacker_id, target_id, combat, gained);
+
+            if killed {
+                if let Ok(mut death) = world.get::<&mut DeathAnimation>(target) {
+                    death.reason = DeathReason::KilledByPredator;
+                    death.is_dying = true;
+                }
+                let prey_level = world
+                    .get::<&AgentState>(target)
+                    .map_or(1, |state| state.trophic_level);
+                let pack = match world.get::<&mut AgentState>(attacker) {
+                    Ok(mut state) => {
+                        state.kills += 1;
+                        state.trophic_level = (prey_level + 1).min(MAX_TROPHIC_LEVEL);
+                        state.satiation = (state.satiation + SATIATION_PER_KILL).min(1.0);
+                        match state.state {
+                            AgentStateEnum::PackHunting { pack_id } => {
+                                state.pack_kills += 1;
+                                Some(pack_id)
+                            }
+                            _ => None,
+                        }
+                    }
+                    Err(_) => None,
+                };
+                if let Some(pack_id) = pack {
+                    share_pack_kill(world, pack_id, x, y);
+                }
+                share_with_kin(world, attacker, x, y);
+            }
+        }
+    }
+}
+
+/// A cooperating `donor` at (x, y) feeds the hungriest of its kin within
+/// `SHARE_RADIUS`, as `Agent::share_energy` does; see `agent::share_terms`.
+fn share_with_kin(world: &mut World, donor: hecs::Entity, x: f64, y: f64) {
+    let terms = world.get::<&Genes>(donor).ok().and_then(|genes| {
+        let energy = world.get::<&Energy>(donor).map_or(0.0, |e| e.current);
+        share_terms(genes.cooperation, energy).map(|terms| (terms, from_ecs_genes(&genes)))
+    });
+    let Some(((gift, cost), donor_genes)) = terms else {
+        return;
+    };
+    let species = |entity| world.get::<&SpeciesId>(entity).map_or(0, |id| id.0);
+    let donor_species = species(donor);
+    let recipient = world
+        .query::<(&Position, &Energy, &Age, &Genes, &AgentTag)>()
+        .iter()
+        .filter(|&(entity, (pos, energy, age, _, _))| {
+            entity != donor
+                && !is_dead(energy, age)
+                && energy.current < HUNGRY_ENERGY
+                && (pos.x - x).hypot(pos.y - y) <= SHARE_RADIUS
+        })
+        .filter(|&(entity, (.., genes, _))| {
+            let genes = from_ecs_genes(genes);
+            are_kin(donor_species, species(entity), &donor_genes, &genes)
+        })
+        .min_by(|(_, (_, a, ..)), (_, (_, b, ..))| a.current.total_cmp(&b.current))
+        .map(|(entity, _)| entity);
+    let Some(recipient) = recipient else {
+        return;
+    };
+
+    if let Ok(mut energy) = world.get::<&mut Energy>(recipient) {
+        energy.current = (energy.current + gift).min(energy.max);
+    }
+    if let Ok(mut energy) = world.get::<&mut Energy>(donor) {
+        energy.current -= cost;
+    }
+    match world.query_mut::<&mut EnergyShared>().into_iter().next() {
+        Some((_, shared)) => shared.0 += gift,
+        None => {
+            world.spawn((EnergyShared(gift),));
+        }
+    }
+}
+
+fn share_pack_kill(world: &mut World, pack_id: u64, x: f64, y: f64) {
+    for (_, (pos, energy, state, genes)) in
+        world.query_mut::<(&Position, &mut Energy, &AgentState, &Genes)>()
+    {
+        let in_pack = state.state == AgentStateEnum::PackHunting { pack_id };
+        if in_pack && (pos.x - x).hypot(pos.y - y) <= PACK_SHARE_RADIUS && energy.current > 0.0 {
+            energy.current =
+                (energy.current + PACK_KILL_BONUS * genes.pack_mentality).min(energy.max);
+        }
+    }
+}
+
+/// Removes dead agents, leaving a nutrient deposit where each one fell.
+/// `max_resources` applies only to worlds without `PopulationLimits`.
+pub struct DeathSystem {
+    pub max_resources: usize,
+}
+
+impl System for DeathSystem {
+    fn name(&self) -> &str {
+        "death"
+    }
+
+    fn run(&self, world: &mut World) {
+        let max_resources =
+            population_limits(world).map_or(self.max_resources, |limits| limits.max_resources);
+        let dead: Vec<_> = world
+            .query::<(
+                &Energy,
+                &Age,
+                &Position,
+                &Genes,
+                Option<&AgentState>,
+                Option<&DeathAnimation>,
+            )>()
+            .iter()
+            .filter(|(_, (energy, age, _, _, _, _))| is_dead(energy, age))
+            .map(|(entity, (energy, age, pos, genes, state, death))| {
+                let killed = death.filter(|death| death.is_dying).map(|d| d.reason.clone());
+                let remains = energy.current * genes.size;
+                let reason = killed.unwrap_or(if remains <= 0.0 {
+                    DeathReason::Starvation
+                } else {
+                    DeathReason::OldAge
+                });
+                let death = AgentDeath {
+                    x: pos.x,
+                    y: pos.y,
+                    age: age.value,
+                    generation: state.map_or(0, |state| state.generation),
+                    reason,
+                    is_predator: genes.is_predator_at(energy.current),
+                };
+                (entity, remains, death)
+            })
+            .collect();
+
+        for (entity, remains, death) in dead {
+            if death.reason == DeathReason::Poisoned {
+                count_poison_death(world);
+            }
+            tracing::debug!("Agent {} died: {:?}", entity.id(), death.reason);
+            world.despawn(entity).ok();
+            if remains > 0.0 && resource_count(world) < max_resources {
+                spawn_carcass_resource(world, death.x, death.y, remains);
+            }
+            match world.query_mut::<&mut DeathEvents>().into_iter().next() {
+                Some((_, deaths)) => deaths.0.push(death),
+                None => {
+                    world.spawn((DeathEvents(vec![death]),));
+                }
+            }
+        }
+    }
+}
+
+/// The `StableId` value of `entity`, or 0 without one.
+fn stable_id(world: &World, entity: hecs::Entity) -> u64 {
+    world.get::<&StableId>(entity).map_or(0, |id| id.value)
+}
+
+/// Records an interaction if the world is collecting `InteractionEvents`.
+fn log_interaction(
+    world: &mut World,
+    actor_id: u64,
+    target_id: u64,
+    interaction_type: InteractionType,
+    energy_change: f64,
+) {
+    if let Some((_, events)) = world.query_mut::<&mut InteractionEvents>().into_iter().next() {
+        events.0.push(InteractionLog {
+            step: 0,
+            actor_id,
+            target_id,
+            interaction_type,
+            energy_change,
+        });
+    }
+}
+
+fn record_consumption(world: &mut World, x: f64, y: f64, energy: f64) {
+    match world.query_mut::<&mut ResourceConsumption>().into_iter().next() {
+        Some((_, consumption)) => consumption.0.push((x, y, energy)),
+        None => {
+            world.spawn((ResourceConsumption(vec![(x, y, energy)]),));
+        }
+    }
+}
+
+fn count_poison_death(world: &mut World) {
+    match world.query_mut::<&mut PoisonDeaths>().into_iter().next() {
+        Some((_, deaths)) => deaths.0 += 1,
+        None => {
+            world.spawn((PoisonDeaths(1),));
+        }
+    }
+}
+
+/// Lets agents within `FEEDING_RANGE` of an available poisonous resource eat
+/// it, losing `poison_damage`. Each poisonous resource is used up by the
+/// first agent to reach it, and its energy recorded in `ResourceConsumption`. Agents above `POISON_LEARNING_INTELLIGENCE`
+/// remember the spot and won't eat there again; agents poisoned to death are
+/// marked for the death system.
+pub struct PoisonSystem;
+
+impl System for PoisonSystem {
+    fn name(&self) -> &str {
+        "poison"
+    }
+
+    fn run(&self, world: &mut World) {
+        let mut poisons: Vec<_> = world
+            .query::<(&Position, &Resource)>()
+            .iter()
+            .filter(|(_, (_, resource))| {
+                resource.resource_type == ResourceType::Poison && resource.is_available()
+            })
+            .map(|(entity, (pos, resource))| {
+                (entity, pos.x, pos.y, resource.energy, resource.energy_delta)
+            })
+            .collect();
+        if poisons.is_empty() {
+            return;
+        }
+
+        let mut eaten = Vec::new();
+        for (_, (pos, energy, age, genes, death, memory)) in world.query_mut::<(
+            &Position,
+            &mut Energy,
+            &Age,
+            &Genes,
+            &mut DeathAnimation,
+            Option<&mut Memory>,
+        )>() {
+            if is_dead(energy, age) {
+                continue;
+            }
+            let bite = poisons.iter().position(|&(_, x, y, _, _)| {
+                (x - pos.x).hypot(y - pos.y) <= FEEDING_RANGE
+                    && !memory.as_deref().is_some_and(|m| m.avoids_poison_at(x, y))
+            });
+            let Some(index) = bite else {
+                continue;
+            };
+            let (resource, x, y, resource_energy, energy_delta) = poisons.swap_remove(index);
+            eaten.push((resource, x, y, resource_energy));
+
+            energy.current -= poison_damage(energy_delta, genes.defense);
+            if energy.current <= 0.0 {
+                death.reason = DeathReason::Poisoned;
+                death.is_dying = true;
+            }
+            if genes.intelligence > POISON_LEARNING_INTELLIGENCE {
+                if let Some(memory) = memory {
+                    memory.remember_poisoning(x, y);
+                }
+            }
+        }
+        for (resource, x, y, resource_energy) in eaten {
+            world.despawn(resource).ok();
+            record_consumption(world, x, y, resource_energy);
+        }
+    }
+}
+
+/// Simplified reproduction - spawns a new random agent occasionally.
+/// `max_agents` applies only to worlds without `PopulationLimits`.
+pub struct ReproductionSystem {
+    pub canvas_width: f64,
+    pub canvas_height: f64,
+    pub max_agents: usize,
+}
+
+impl System for ReproductionSystem {
+    fn name(&self) -> &str {
+        "reproduction"
+    }
+
+    fn run(&self, world: &mut World) {
+        let mut rng = world_rng(world);
+        let max_agents =
+            population_limits(world).map_or(self.max_agents, |limits| limits.max_agents);
+
+        if agent_count(world) < max_agents && rng.gen::<f64>() < 0.1 {
+            let x = rng.gen_range(0.0..self.canvas_width);
+            let y = rng.gen_range(0.0..self.canvas_height);
+            if !habitat_is_full(world, x, y) {
+                let newborn = spawn_agent(world, x, y, random_genes(&mut rng), 0);
+                // Births here have no parents, so the newborn stands in for both
+                let id = stable_id(world, newborn);
+                log_interaction(world, id, id, InteractionType::Reproduction, 0.0);
+            }
+        }
+    }
+}
+
+/// `max_resources` applies only to worlds without `PopulationLimits`.
+pub struct ResourceSpawnSystem {
+    pub canvas_width: f64,
+    pub canvas_height: f64,
+    pub max_resources: usize,
+    pub interval: f64,
+}
+
+impl System for ResourceSpawnSystem {
+    fn name(&self) -> &str {
+        "resource_spawn"
+    }
+
+    fn run(&self, world: &mut World) {
+        let existing = world
+            .query_mut::<&mut ResourceSpawnTimer>()
+            .into_iter()
+            .next()
+            .map(|(entity, _)| entity);
+        let timer_entity = match existing {
+            Some(entity) => entity,
+            None => world.spawn((ResourceSpawnTimer { elapsed: 0.0 },)),
+        };
+
+        let elapsed = {
+            let mut timer = world
+                .get::<&mut ResourceSpawnTimer>(timer_entity)
+                .expect("spawn timer entity exists");
+            timer.elapsed += DELTA_TIME;
+            timer.elapsed
+        };
+
+        let scale = world
+            .query::<&ResourceSpawnScale>()
+            .iter()
+            .next()
+            .map_or(1.0, |(_, scale)| scale.0);
+
+        let max_resources =
+            population_limits(world).map_or(self.max_resources, |limits| limits.max_resources);
+
+        if elapsed * scale > self.interval && resource_count(world) < max_resources {
+            spawn_random_resource(world, self.canvas_width, self.canvas_height);
+            if let Ok(mut timer) = world.get::<&mut ResourceSpawnTimer>(timer_entity) {
+                timer.elapsed = 0.0;
+            }
+        }
+    }
+}
+
+/// Whether (x, y) lies in a habitat already holding its carrying capacity.
+fn habitat_is_full(world: &World, x: f64, y: f64) -> bool {
+    world
+        .query::<&Habitat>()
+        .iter()
+        .filter(|(_, habitat)| habitat.contains(x, y))
+        .any(|(_, habitat)| {
+            let occupants = world
+                .query::<(&Position, &AgentTag)>()
+                .iter()
+                .filter(|(_, (pos, _))| habitat.contains(pos.x, pos.y))
+                .count();
+            occupants >= habitat.carrying_capacity
+        })
+}
+
+fn is_dead(energy: &Energy, age: &Age) -> bool {
+    energy.current <= 0.0 || age.value > 200.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::ResourceTag;
+    use std::sync::{Arc, Mutex};
+
+    struct RecordingSystem {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl System for RecordingSystem {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn run(&self, _world: &mut World) {
+            self.log.lock().unwrap().push(self.name.to_string());
+        }
+    }
+
+    /// Spawns an agent past the infant mortality that could otherwise kill
+    /// it in a behaviour tick.
+    fn spawn_adult(world: &mut World, x: f64, y: f64, genes: Genes) -> hecs::Entity {
+        let agent = spawn_agent(world, x, y, genes, 0);
+        world.get::<&mut Age>(agent).unwrap().value = 10.0;
+        agent
+    }
+
+    fn recording(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Box<dyn System> {
+        Box::new(RecordingSystem {
+            name,
+            log: Arc::clone(log),
+        })
+    }
+
+    #[test]
+    fn test_scheduler_respects_ordering_constraints() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = Scheduler::new();
+        scheduler.register_system(recording("render_prep", &log), &["movement", "spawn"]);
+        scheduler.register_system(recording("movement", &log), &["behavior"]);
+        scheduler.register_system(recording("spawn", &log), &[]);
+        scheduler.register_system(recording("behavior", &log), &[]);
+        scheduler.build().unwrap();
+
+        scheduler.run(&mut World::new());
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["spawn", "behavior", "movement", "render_prep"]
+        );
+
+        let mut cyclic = Scheduler::new();
+        cyclic.register_system(recording("a", &log), &["b"]);
+        cyclic.register_system(recording("b", &log), &["a"]);
+        assert!(cyclic.build().is_err());
+    }
+
+    #[test]
+    fn test_rejected_system_does_not_block_later_registrations() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = Scheduler::new();
+        scheduler.try_register_system(recording("spawn", &log), &[]).unwrap();
+        assert!(scheduler
+            .try_register_system(recording("render_prep", &log), &["missing"])
+            .is_err());
+        scheduler
+            .try_register_system(recording("movement", &log), &["spawn"])
+            .unwrap();
+
+        scheduler.run(&mut World::new());
+
+        assert_eq!(scheduler.system_names(), vec!["spawn", "movement"]);
+        assert_eq!(*log.lock().unwrap(), vec!["spawn", "movement"]);
+    }
+
+    /// Returns the agent's `(x, dx, energy)` after one movement tick, and
+    /// why it's dying if it is.
+    fn run_movement(
+        boundary: BoundaryBehavior,
+        x: f64,
+        dx: f64,
+    ) -> (f64, f64, f64, Option<DeathReason>) {
+        let mut world = World::new();
+        world.spawn((boundary,));
+        let entity = world.spawn((
+            Position { x, y: 50.0 },
+            Velocity { dx, dy: 0.0 },
+            Energy {
+                current: 50.0,
+                max: 100.0,
+            },
+            Age { value: 0.0 },
+            DeathAnimation {
+                fade: 0.0,
+                reason: DeathReason::NaturalCauses,
+                is_dying: false,
+            },
+        ));
+
+        AgentMovementSystem {
+            canvas_width: 100.0,
+            canvas_height: 100.0,
+        }
+        .run(&mut world);
+
+        let x = world.get::<&Position>(entity).unwrap().x;
+        let dx = world.get::<&Velocity>(entity).unwrap().dx;
+        let energy = world.get::<&Energy>(entity).unwrap().current;
+        let death = world.get::<&DeathAnimation>(entity).unwrap();
+        let dying = death.is_dying.then(|| death.reason.clone());
+        (x, dx, energy, dying)
+    }
+
+    #[test]
+    fn test_movement_respects_boundary_behavior() {
+        let (x, dx, energy, dying) = run_movement(BoundaryBehavior::Bounce, 99.99, 3.0);
+        assert_eq!(x, 100.0);
+        assert!(dx < 0.0);
+        assert_eq!(energy, 50.0);
+        assert_eq!(dying, None);
+
+        let (_, _, energy, dying) = run_movement(BoundaryBehavior::Lethal, 99.99, 3.0);
+        assert_eq!(energy, 0.0);
+        assert_eq!(dying, Some(DeathReason::FellOffEdge));
+
+        let (x, _, energy, dying) = run_movement(BoundaryBehavior::Wrap, 99.99, 3.0);
+        assert_eq!(x, 0.0);
+        assert_eq!(energy, 50.0);
+        assert_eq!(dying, None);
+    }
+
+    #[test]
+    fn test_strong_predator_kills_weak_prey() {
+        let mut world = World::new();
+        let mut predator_genes = random_genes(&mut thread_rng());
+        predator_genes.is_predator = 1.0;
+        predator_genes.attack_power = 3.0;
+        let mut prey_genes = random_genes(&mut thread_rng());
+        prey_genes.is_predator = 0.0;
+        prey_genes.defense = 0.1;
+        spawn_agent(&mut world, 100.0, 100.0, predator_genes, 0);
+        spawn_agent(&mut world, 103.0, 100.0, prey_genes, 0);
+
+        let find = |world: &World, predator: bool| {
+            world
+                .query::<&Genes>()
+                .iter()
+                .find(|(_, genes)| (genes.is_predator > 0.5) == predator)
+                .map(|(entity, _)| entity)
+                .unwrap()
+        };
+        let (predator, prey) = (find(&world, true), find(&world, false));
+        let prey_id = world.get::<&StableId>(prey).unwrap().value;
+        {
+            let mut state = world.get::<&mut AgentState>(predator).unwrap();
+            state.state = AgentStateEnum::Fighting;
+            state.target_id = Some(prey_id);
+        }
+        world.get::<&mut Energy>(prey).unwrap().current = 10.0;
+        // A hunter itself, so its killer ends up a level above it
+        world.get::<&mut AgentState>(prey).unwrap().trophic_level = 2;
+        let predator_energy = world.get::<&Energy>(predator).unwrap().current;
+
+        for _ in 0..10 {
+            CombatSystem.run(&mut world);
+        }
+
+        assert_eq!(world.get::<&Energy>(prey).unwrap().current, 0.0);
+        let reason = world.get::<&DeathAnimation>(prey).unwrap().reason.clone();
+        assert_eq!(reason, DeathReason::KilledByPredator);
+        assert_eq!(world.get::<&AgentState>(predator).unwrap().kills, 1);
+        assert_eq!(world.get::<&AgentState>(predator).unwrap().trophic_level, 3);
+        assert_eq!(
+            world.get::<&AgentState>(predator).unwrap().satiation,
+            SATIATION_PER_KILL
+        );
+        assert!(world.get::<&Energy>(predator).unwrap().current > predator_energy);
+
+        DeathSystem { max_resources: 10 }.run(&mut world);
+        assert!(!world.contains(prey));
+        assert!(world.contains(predator));
+    }
+
+    #[test]
+    fn test_cooperating_killers_feed_hungry_kin() {
+        let mut world = World::new();
+        let mut genes = random_genes(&mut thread_rng());
+        genes.is_predator = 1.0;
+        genes.attack_power = 3.0;
+        genes.cooperation = 0.9;
+        let killer = spawn_agent(&mut world, 100.0, 100.0, genes.clone(), 0);
+        let sibling = spawn_agent(&mut world, 110.0, 100.0, genes, 0);
+        let mut prey_genes = random_genes(&mut thread_rng());
+        prey_genes.is_predator = 0.0;
+        prey_genes.defense = 0.1;
+        let prey = spawn_agent(&mut world, 103.0, 100.0, prey_genes, 0);
+        let prey_id = world.get::<&StableId>(prey).unwrap().value;
+        {
+            let mut state = world.get::<&mut AgentState>(killer).unwrap();
+            state.state = AgentStateEnum::Fighting;
+            state.target_id = Some(prey_id);
+        }
+        world.get::<&mut Energy>(prey).unwrap().current = 1.0;
+        world.get::<&mut Energy>(sibling).unwrap().current = 10.0;
+
+        CombatSystem.run(&mut world);
+
+        assert_eq!(world.get::<&Energy>(prey).unwrap().current, 0.0);
+        // 0.9 * SHARE_GIFT
+        assert!((world.get::<&Energy>(sibling).unwrap().current - 19.0).abs() < 1e-9);
+        let mut shared = world.query::<&EnergyShared>();
+        let shared = shared.iter().next().map_or(0.0, |(_, shared)| shared.0);
+        assert!((shared - 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sated_predators_leave_prey_alone() {
+        let mut world = World::new();
+        let mut genes = random_genes(&mut thread_rng());
+        genes.is_predator = 0.0;
+        genes.omnivore = 0.0;
+        genes.migration = 0.0;
+        let prey = spawn_adult(&mut world, 110.0, 100.0, genes.clone());
+        genes.is_predator = 1.0;
+        genes.pack_mentality = 0.0;
+        genes.sense_range = 50.0;
+        genes.territory_size = 100.0;
+        let predator = spawn_adult(&mut world, 100.0, 100.0, genes);
+        let prey_id = world.get::<&StableId>(prey).unwrap().value;
+        let behavior = AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        };
+        let target = |world: &World| world.get::<&AgentState>(predator).unwrap().target_id;
+
+        behavior.run(&mut world);
+        assert_eq!(target(&world), Some(prey_id));
+
+        world.get::<&mut AgentState>(predator).unwrap().satiation = 1.0;
+        behavior.run(&mut world);
+        assert_eq!(target(&world), None);
+        assert!(world.get::<&AgentState>(predator).unwrap().satiation < 1.0);
+    }
+
+    #[test]
+    fn test_combat_strikes_only_the_chosen_prey() {
+        let mut world = World::new();
+        let agent = |world: &mut World, x: f64, is_predator: f64| {
+            let mut genes = random_genes(&mut thread_rng());
+            genes.is_predator = is_predator;
+            genes.attack_power = 3.0;
+            spawn_agent(world, x, 100.0, genes, 0)
+        };
+        let fighter = agent(&mut world, 100.0, 1.0);
+        let bystander = agent(&mut world, 101.0, 0.0);
+        let prey = agent(&mut world, 104.0, 0.0);
+        // Walking to a resource, right next to the fighter
+        let hunter = agent(&mut world, 99.0, 1.0);
+        world.get::<&mut AgentState>(hunter).unwrap().state = AgentStateEnum::Hunting;
+        let prey_id = world.get::<&StableId>(prey).unwrap().value;
+        {
+            let mut state = world.get::<&mut AgentState>(fighter).unwrap();
+            state.state = AgentStateEnum::Fighting;
+            state.target_id = Some(prey_id);
+        }
+        let energy = |world: &World, entity| world.get::<&Energy>(entity).unwrap().current;
+        let before = [hunter, fighter, bystander, prey].map(|entity| energy(&world, entity));
+
+        CombatSystem.run(&mut world);
+
+        assert_eq!(energy(&world, hunter), before[0]);
+        assert!(energy(&world, fighter) > before[1]);
+        assert_eq!(energy(&world, bystander), before[2]);
+        assert!(energy(&world, prey) < before[3]);
+    }
+
+    #[test]
+    fn test_pack_hunters_converge_and_share_kills() {
+        let mut world = World::new();
+        let hunter = |world: &mut World, x: f64, pack_mentality: f64| {
+            let mut genes = random_genes(&mut thread_rng());
+            genes.is_predator = 1.0;
+            genes.omnivore = 0.0;
+            genes.pack_mentality = pack_mentality;
+            genes.territory_size = 100.0;
+            genes.sense_range = 100.0;
+            genes.attack_power = 3.0;
+            spawn_adult(world, x, 100.0, genes)
+        };
+        let leader = hunter(&mut world, 100.0, 0.9);
+        let near = hunter(&mut world, 130.0, 0.8);
+        let far = hunter(&mut world, 180.0, 0.8);
+        // Two mates in reach, but not keen enough to hunt in a pack
+        let loner = hunter(&mut world, 150.0, 0.5);
+        let mut prey_genes = random_genes(&mut thread_rng());
+        prey_genes.is_predator = 0.0;
+        prey_genes.omnivore = 0.0;
+        prey_genes.defense = 0.1;
+        let prey = spawn_adult(&mut world, 100.0, 104.0, prey_genes);
+
+        AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        }
+        .run(&mut world);
+
+        let state = |world: &World, entity| (*world.get::<&AgentState>(entity).unwrap()).clone();
+        let pack_id = world.get::<&StableId>(leader).unwrap().value;
+        for member in [leader, near, far] {
+            assert_eq!(state(&world, member).state, AgentStateEnum::PackHunting { pack_id });
+        }
+        assert!(!matches!(state(&world, loner).state, AgentStateEnum::PackHunting { .. }));
+        let leader_state = state(&world, leader);
+        assert_eq!((leader_state.target_x, leader_state.target_y), (Some(100.0), Some(104.0)));
+        // Heading for the prey below, with a pull towards the pack on the right
+        let velocity = (*world.get::<&Velocity>(leader).unwrap()).clone();
+        assert!(velocity.dy > 0.0 && velocity.dx > 0.0, "{:?}", velocity);
+
+        world.get::<&mut Energy>(prey).unwrap().current = 1.0;
+        for member in [leader, near, far] {
+            world.get::<&mut Energy>(member).unwrap().current = 50.0;
+        }
+        CombatSystem.run(&mut world);
+
+        assert_eq!(state(&world, leader).pack_kills, 1);
+        assert_eq!(state(&world, leader).kills, 1);
+        let energy = |entity| world.get::<&Energy>(entity).unwrap().current;
+        assert_eq!(energy(near), 50.0 + PACK_KILL_BONUS * 0.8);
+        assert_eq!(energy(far), 50.0);
+        assert!(energy(leader) > 50.0 + PACK_KILL_BONUS * 0.9);
+    }
+
+    fn pack_hunter(world: &mut World, x: f64, territory_size: f64) -> hecs::Entity {
+        let mut genes = random_genes(&mut thread_rng());
+        genes.is_predator = 1.0;
+        genes.pack_mentality = 0.9;
+        genes.territory_size = territory_size;
+        genes.sense_range = 100.0;
+        genes.attack_power = 3.0;
+        spawn_adult(world, x, 100.0, genes)
+    }
+
+    #[test]
+    fn test_pack_hunters_without_prey_leave_each_other_alone() {
+        let mut world = World::new();
+        let pack: Vec<_> = [100.0, 102.0, 104.0]
+            .into_iter()
+            .map(|x| pack_hunter(&mut world, x, 100.0))
+            .collect();
+
+        AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        }
+        .run(&mut world);
+        let before: Vec<f64> = pack
+            .iter()
+            .map(|&member| world.get::<&Energy>(member).unwrap().current)
+            .collect();
+        CombatSystem.run(&mut world);
+
+        for (&member, before) in pack.iter().zip(before) {
+            let state = world.get::<&AgentState>(member).unwrap();
+            assert!(matches!(state.state, AgentStateEnum::PackHunting { .. }));
+            assert_eq!(world.get::<&Energy>(member).unwrap().current, before);
+        }
+    }
+
+    #[test]
+    fn test_chained_pack_shares_one_id() {
+        let mut world = World::new();
+        // The ends are out of each other's reach, and their own mates
+        // pick different smallest IDs
+        let members: Vec<_> = [100.0, 130.0, 160.0, 200.0, 230.0, 260.0]
+            .into_iter()
+            .map(|x| pack_hunter(&mut world, x, 70.0))
+            .collect();
+
+        AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        }
+        .run(&mut world);
+
+        let pack_id = world.get::<&StableId>(members[0]).unwrap().value;
+        for &member in &members {
+            let state = world.get::<&AgentState>(member).unwrap();
+            assert_eq!(state.state, AgentStateEnum::PackHunting { pack_id });
+        }
+    }
+
+    #[test]
+    fn test_agents_head_back_to_where_they_last_fed() {
+        let mut world = World::new();
+        let mut genes = random_genes(&mut thread_rng());
+        genes.is_predator = 0.0;
+        genes.pack_mentality = 0.0;
+        genes.sense_range = 20.0;
+        genes.territory_size = 100.0;
+        let agent = spawn_adult(&mut world, 100.0, 100.0, genes);
+        let food = world.spawn((
+            Position { x: 103.0, y: 100.0 },
+            Resource {
+                energy: 30.0,
+                max_energy: 60.0,
+                size: 3.0,
+                growth_rate: 0.3,
+                regeneration_rate: 0.05,
+                age: 0.0,
+                target_energy: 30.0,
+                is_spawning: false,
+                spawn_fade: 1.0,
+                is_depleting: false,
+                deplete_fade: 0.0,
+                is_carcass: false,
+                resource_type: ResourceType::Food,
+                energy_delta: 0.0,
+            },
+        ));
+        let behavior = AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        };
+        let memory = |world: &World| (*world.get::<&Memory>(agent).unwrap()).clone();
+        // Out of sense range of the spot but within the agent's territory
+        let wander_off = |world: &mut World| {
+            world.get::<&mut Position>(agent).unwrap().x = 160.0;
+            let mut state = world.get::<&mut AgentState>(agent).unwrap();
+            state.state = AgentStateEnum::Seeking;
+            (state.target_x, state.target_y) = (None, None);
+        };
+
+        behavior.run(&mut world);
+        assert_eq!(
+            (memory(&world).last_food_x, memory(&world).last_food_y),
+            (Some(103.0), Some(100.0))
+        );
+
+        wander_off(&mut world);
+        behavior.run(&mut world);
+        let state = (*world.get::<&AgentState>(agent).unwrap()).clone();
+        assert!(memory(&world).is_guiding(&state));
+        assert!(world.get::<&Velocity>(agent).unwrap().dx < 0.0);
+
+        // Coming back finds food again
+        world.get::<&mut Position>(agent).unwrap().x = 104.0;
+        behavior.run(&mut world);
+        assert_eq!(memory(&world).visits, 1);
+
+        // A spot that turns out to be empty is forgotten
+        world.despawn(food).unwrap();
+        wander_off(&mut world);
+        behavior.run(&mut world);
+        world.get::<&mut Position>(agent).unwrap().x = 104.0;
+        behavior.run(&mut world);
+        assert_eq!(memory(&world).last_food_x, None);
+
+        // And so is one last fed at too long ago
+        let age = world.get::<&Age>(agent).unwrap().value;
+        world
+            .get::<&mut Memory>(agent)
+            .unwrap()
+            .remember(103.0, 100.0, age - MEMORY_SPAN - 1.0);
+        wander_off(&mut world);
+        behavior.run(&mut world);
+        assert_eq!(world.get::<&AgentState>(agent).unwrap().target_x, None);
+    }
+
+    #[test]
+    fn test_hungry_agents_ignore_patches_below_their_r_star() {
+        let mut world = World::new();
+        let forager = |world: &mut World, y: f64, metabolism: f64, size: f64| {
+            let mut genes = random_genes(&mut thread_rng());
+            genes.is_predator = 0.0;
+            genes.omnivore = 0.0;
+            genes.pack_mentality = 0.0;
+            genes.migration = 0.0;
+            genes.sense_range = 50.0;
+            genes.metabolism = metabolism;
+            genes.size = size;
+            spawn_adult(world, 100.0, y, genes)
+        };
+        let lean = forager(&mut world, 100.0, 0.5, 1.0);
+        let hungry = forager(&mut world, 400.0, 2.5, 2.0);
+        for y in [100.0, 400.0] {
+            world.spawn((
+                Position { x: 120.0, y },
+                Resource {
+                    energy: 8.0,
+                    max_energy: 60.0,
+                    size: 3.0,
+                    growth_rate: 0.3,
+                    regeneration_rate: 0.05,
+                    age: 0.0,
+                    target_energy: 8.0,
+                    is_spawning: false,
+                    spawn_fade: 1.0,
+                    is_depleting: false,
+                    deplete_fade: 0.0,
+                    is_carcass: false,
+                    resource_type: ResourceType::Food,
+                    energy_delta: 0.0,
+                },
+            ));
+        }
+
+        AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        }
+        .run(&mut world);
+
+        let target_x = |agent| world.get::<&AgentState>(agent).unwrap().target_x;
+        // 20 units away, so seen within a few of where it is
+        assert!(target_x(lean).is_some_and(|x| (x - 120.0).abs() < 5.0));
+        assert_eq!(target_x(hungry), None);
+    }
+
+    #[test]
+    fn test_clever_agents_avoid_poison_after_one_bite() {
+        let mut world = World::new();
+        let mut genes = random_genes(&mut thread_rng());
+        genes.intelligence = 2.0;
+        genes.defense = 0.5;
+        let agent = spawn_agent(&mut world, 100.0, 100.0, genes, 0);
+        let poison = |world: &mut World| {
+            let entity = spawn_random_resource(world, 1.0, 1.0).unwrap();
+            world.get::<&mut Position>(entity).unwrap().x = 102.0;
+            world.get::<&mut Position>(entity).unwrap().y = 100.0;
+            let mut resource = world.get::<&mut Resource>(entity).unwrap();
+            resource.energy = 30.0;
+            resource.spawn_fade = 1.0;
+            resource.resource_type = ResourceType::Poison;
+            resource.energy_delta = crate::resource::POISON_ENERGY_DELTA;
+            drop(resource);
+            entity
+        };
+        let energy = |world: &World| world.get::<&Energy>(agent).unwrap().current;
+
+        let first = poison(&mut world);
+        let before = energy(&world);
+        PoisonSystem.run(&mut world);
+        assert_eq!(energy(&world), before - 15.0);
+        assert!(!world.contains(first));
+
+        let second = poison(&mut world);
+        PoisonSystem.run(&mut world);
+        assert_eq!(energy(&world), before - 15.0);
+        assert!(world.contains(second));
+
+        // A deadly bite is left for the death system to count
+        world.get::<&mut Memory>(agent).unwrap().poisoned_spots.clear();
+        world.get::<&mut Energy>(agent).unwrap().current = 10.0;
+        PoisonSystem.run(&mut world);
+        DeathSystem { max_resources: 10 }.run(&mut world);
+        assert!(!world.contains(agent));
+        let deaths = world.query::<&PoisonDeaths>().iter().next().map(|(_, d)| d.0);
+        assert_eq!(deaths, Some(1));
+    }
+
+    /// Energy each agent with these (size, speed) burns in one behaviour tick.
+    fn energy_burnt(agents: &[(f64, f64)], allometric: bool) -> Vec<f64> {
+        let mut world = World::new();
+        world.spawn((AllometricScaling(allometric),));
+        let entities: Vec<_> = agents
+            .iter()
+            .map(|&(size, speed)| {
+                let mut genes = random_genes(&mut thread_rng());
+                genes.size = size;
+                genes.speed = speed;
+                genes.metabolism = 1.0;
+                genes.energy_efficiency = 1.0;
+                // No predators, so nobody pays to flee
+                genes.is_predator = 0.0;
+                genes.omnivore = 0.0;
+                spawn_adult(&mut world, 400.0, 300.0, genes)
+            })
+            .collect();
+        let before: Vec<f64> = entities
+            .iter()
+            .map(|&entity| world.get::<&Energy>(entity).unwrap().current)
+            .collect();
+
+        AgentBehaviorSystem {
+            canvas_width: 800.0,
+            canvas_height: 600.0,
+        }
+        .run(&mut world);
+
+        entities
+            .iter()
+            .zip(before)
+            .map(|(&entity, before)| before - world.get::<&Energy>(entity).unwrap().current)
+            .collect()
+    }
+
+    #[test]
+    fn test_allometric_scaling_reshapes_metabolic_costs() {
+        let agents = [(1.0, 1.0), (1.0, 4.0), (4.0, 1.0)];
+        let linear = energy_burnt(&agents, false);
+        let allometric = energy_burnt(&agents, true);
+
+        // Quadratic drag makes speed dearer relative to a slow agent...
+        assert!(allometric[1] / allometric[0] > linear[1] / linear[0]);
+        // ...while Kleiber's mass^0.75 makes size cheaper
+        assert!(allometric[2] / allometric[0] < linear[2] / linear[0]);
+    }
+
+    #[test]
+    fn test_parallel_resource_update_matches_sequential() {
+        for count in [PARALLEL_RESOURCE_THRESHOLD / 2, PARALLEL_RESOURCE_THRESHOLD * 2] {
+            let mut parallel = World::new();
+            for _ in 0..count {
+                spawn_random_resource(&mut parallel, 800.0, 600.0);
+            }
+            let mut sequential = World::new();
+            for (_, (pos, resource)) in parallel.query::<(&Position, &Resource)>().iter() {
+                sequential.spawn((pos.clone(), resource.clone(), ResourceTag));
+            }
+            parallel.spawn((ParallelResourceUpdate {
+                enabled: true,
+                frames: 0,
+            },));
+
+            for _ in 0..5 {
+                ResourceUpdateSystem.run(&mut parallel);
+                ResourceUpdateSystem.run(&mut sequential);
+            }
+
+            let resources = |world: &World| {
+                let resources: Vec<Resource> = world
+                    .query::<&Resource>()
+                    .iter()
+                    .map(|(_, resource)| resource.clone())
+                    .collect();
+                serde_json::to_value(resources).unwrap()
+            };
+            assert_eq!(resources(&parallel), resources(&sequential));
+            let frames = parallel
+                .query::<&ParallelResourceUpdate>()
+                .iter()
+                .next()
+                .map(|(_, update)| update.frames);
+            let expected = if count > PARALLEL_RESOURCE_THRESHOLD { 5 } else { 0 };
+            assert_eq!(frames, Some(expected));
+        }
+    }
+}
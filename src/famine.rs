@@ -0,0 +1,91 @@
+/// A step is scarce when fewer resources than this remain...
+pub const FAMINE_RESOURCE_THRESHOLD: usize = 10;
+/// ...and more agents than this are alive.
+pub const FAMINE_AGENT_THRESHOLD: usize = 50;
+/// Consecutive scarce steps after which a famine starts by itself.
+pub const FAMINE_TRIGGER_STEPS: usize = 100;
+/// Length of a famine started by the collapse check, about ten seconds.
+pub const AUTOMATIC_FAMINE_STEPS: usize = 600;
+
+/// Tracks famines, stretches of steps during which an engine spawns no new
+/// resources. Started by `trigger` or by an overgrazed world.
+#[derive(Clone, Debug, Default)]
+pub struct Famine {
+    remaining_steps: usize,
+    /// Consecutive steps so far with few resources and many agents.
+    scarce_steps: usize,
+    /// (start step, duration) of every famine since the last `clear`.
+    events: Vec<(usize, usize)>,
+}
+
+impl Famine {
+    /// Suppresses spawning for the next `duration_steps` steps, replacing
+    /// any famine already under way.
+    pub fn trigger(&mut self, step: usize, duration_steps: usize) {
+        if duration_steps == 0 {
+            return;
+        }
+        self.remaining_steps = duration_steps;
+        self.scarce_steps = 0;
+        self.events.push((step, duration_steps));
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining_steps > 0
+    }
+
+    /// Called once at the end of every step. Counts down an active famine,
+    /// otherwise starts an `AUTOMATIC_FAMINE_STEPS` one after
+    /// `FAMINE_TRIGGER_STEPS` scarce steps in a row.
+    pub fn tick(&mut self, step: usize, resource_count: usize, agent_count: usize) {
+        if self.is_active() {
+            self.remaining_steps -= 1;
+            return;
+        }
+
+        if resource_count < FAMINE_RESOURCE_THRESHOLD && agent_count > FAMINE_AGENT_THRESHOLD {
+            self.scarce_steps += 1;
+            if self.scarce_steps >= FAMINE_TRIGGER_STEPS {
+                self.trigger(step, AUTOMATIC_FAMINE_STEPS);
+            }
+        } else {
+            self.scarce_steps = 0;
+        }
+    }
+
+    /// Oldest first.
+    pub fn events(&self) -> &[(usize, usize)] {
+        &self.events
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sustained_scarcity_starts_a_famine() {
+        let mut famine = Famine::default();
+        for step in 1..FAMINE_TRIGGER_STEPS {
+            famine.tick(step, 5, 60);
+        }
+        assert!(!famine.is_active());
+
+        // A single step with enough resources resets the count
+        famine.tick(FAMINE_TRIGGER_STEPS, 20, 60);
+        for step in 1..=FAMINE_TRIGGER_STEPS {
+            famine.tick(FAMINE_TRIGGER_STEPS + step, 5, 60);
+        }
+        assert!(famine.is_active());
+        assert_eq!(famine.events(), &[(2 * FAMINE_TRIGGER_STEPS, AUTOMATIC_FAMINE_STEPS)]);
+
+        for step in 0..AUTOMATIC_FAMINE_STEPS {
+            famine.tick(step, 100, 60);
+        }
+        assert!(!famine.is_active());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::agent::Agent;
+use serde::Serialize;
+
+/// Populations larger than this are thinned before export.
+pub const LANDSCAPE_SAMPLE_LIMIT: usize = 10_000;
+
+/// Every this-many-th agent is kept when thinning a large population.
+pub const LANDSCAPE_SAMPLE_STRIDE: usize = 100;
+
+/// One point of the gene × fitness scatter exported for plotting.
+#[derive(Clone, Debug, Serialize)]
+pub struct FitnessSample {
+    pub speed: f64,
+    pub size: f64,
+    pub aggression: f64,
+    pub energy_efficiency: f64,
+    /// Gene fitness score weighted by how well fed the agent currently is.
+    pub fitness: f64,
+}
+
+impl FitnessSample {
+    pub fn from_agent(agent: &Agent) -> Self {
+        let survival = (agent.energy / agent.max_energy).clamp(0.0, 1.0);
+        Self {
+            speed: agent.genes.speed,
+            size: agent.genes.size,
+            aggression: agent.genes.aggression,
+            energy_efficiency: agent.genes.energy_efficiency,
+            fitness: agent.genes.get_fitness_score() * survival,
+        }
+    }
+}
+
+/// Fitness samples for `agents`, keeping every `LANDSCAPE_SAMPLE_STRIDE`th
+/// agent once the population exceeds `LANDSCAPE_SAMPLE_LIMIT`.
+pub fn fitness_landscape(agents: &[Agent]) -> Vec<FitnessSample> {
+    let stride = if agents.len() > LANDSCAPE_SAMPLE_LIMIT {
+        LANDSCAPE_SAMPLE_STRIDE
+    } else {
+        1
+    };
+    agents
+        .iter()
+        .step_by(stride)
+        .map(FitnessSample::from_agent)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genes::Genes;
+
+    #[test]
+    fn test_fitness_landscape_json_fields() {
+        let mut agents: Vec<Agent> = (0..50)
+            .map(|i| Agent::new(i as f64, 0.0, Genes::new(), 0))
+            .collect();
+        agents[0].energy = -5.0;
+        agents[1].energy = 150.0;
+
+        let json = serde_json::to_value(fitness_landscape(&agents)).unwrap();
+        let samples = json.as_array().unwrap();
+        assert_eq!(samples.len(), agents.len());
+
+        for sample in samples {
+            let sample = sample.as_object().unwrap();
+            let mut fields: Vec<&str> = sample.keys().map(String::as_str).collect();
+            fields.sort_unstable();
+            assert_eq!(
+                fields,
+                ["aggression", "energy_efficiency", "fitness", "size", "speed"]
+            );
+            assert!(sample["fitness"].as_f64().unwrap() >= 0.0);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Pacing of the browser's animation frames, kept free of `web_sys` so it
+//! builds and is tested natively.
+
+use crate::simulation_core::UnifiedSimulation;
+use std::collections::VecDeque;
+
+const RATE_WINDOW_SIZE: usize = 60;
+
+/// Most simulation steps one animation frame may run, so a lagging browser
+/// can't fall into ever longer catch-up frames.
+pub const MAX_STEPS_PER_FRAME: usize = 5;
+
+/// Running rate estimate over the timestamps (in milliseconds) of the last
+/// `RATE_WINDOW_SIZE` events.
+pub struct RateTracker {
+    window: VecDeque<f64>,
+}
+
+impl RateTracker {
+    pub fn new() -> Self {
+        Self {
+            window: VecDeque::with_capacity(RATE_WINDOW_SIZE),
+        }
+    }
+
+    pub fn record(&mut self, timestamp_ms: f64) {
+        if self.window.len() == RATE_WINDOW_SIZE {
+            self.window.pop_front();
+        }
+        self.window.push_back(timestamp_ms);
+    }
+
+    /// Events per second, or 0.0 until the window has filled.
+    pub fn rate(&self) -> f64 {
+        if self.window.len() < RATE_WINDOW_SIZE {
+            return 0.0;
+        }
+
+        let oldest = self.window.front().copied().unwrap_or(0.0);
+        let newest = self.window.back().copied().unwrap_or(0.0);
+        let elapsed_seconds = (newest - oldest) / 1000.0;
+        if elapsed_seconds <= 0.0 {
+            return 0.0;
+        }
+        (RATE_WINDOW_SIZE - 1) as f64 / elapsed_seconds
+    }
+}
+
+impl Default for RateTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Updates `simulation` once per requested step, up to `MAX_STEPS_PER_FRAME`,
+/// and returns how many steps ran.
+pub fn run_frame(simulation: &mut UnifiedSimulation, steps_per_frame: usize) -> usize {
+    let steps = steps_per_frame.clamp(1, MAX_STEPS_PER_FRAME);
+    for _ in 0..steps {
+        simulation.update();
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_core::SimulationConfig;
+
+    #[test]
+    fn test_fps_is_zero_before_first_frame() {
+        let mut tracker = RateTracker::new();
+        assert_eq!(tracker.rate(), 0.0);
+
+        for frame in 0..RATE_WINDOW_SIZE {
+            tracker.record(frame as f64 * 1000.0 / 60.0);
+        }
+        assert!((tracker.rate() - 60.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_frame_runs_requested_steps() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 1,
+            initial_resources: 0,
+            use_ecs: false,
+            ..Default::default()
+        });
+        let age = |simulation: &UnifiedSimulation| simulation.get_agents()[0].age;
+
+        let before = age(&simulation);
+        assert_eq!(run_frame(&mut simulation, 3), 3);
+        // The legacy engine ages agents 1/60 s per step
+        assert!((age(&simulation) - before - 3.0 / 60.0).abs() < 1e-9);
+
+        assert_eq!(run_frame(&mut simulation, 20), MAX_STEPS_PER_FRAME);
+        assert_eq!(run_frame(&mut simulation, 0), 1);
+    }
+}
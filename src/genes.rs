@@ -1,7 +1,248 @@
 use rand::prelude::*;
-use rand_distr::Normal;
+use rand_distr::{Cauchy, Normal};
 use serde::{Deserialize, Serialize};
 
+/// Number of genes in `Genes::normalized`.
+pub const GENE_COUNT: usize = 21;
+
+/// Extra push given to whichever of attack/defense lags behind in the population.
+const ARMS_RACE_STEP: f64 = 0.05;
+
+/// Chance per offspring that `mutation_strength` itself mutates.
+pub const MUTATION_STRENGTH_MUTATION_RATE: f64 = 0.01;
+/// Standard deviation of the log-normal step applied when `mutation_strength`
+/// mutates.
+const MUTATION_STRENGTH_STEP: f64 = 0.2;
+
+/// Normalized distance an offspring gene must land outside its parents' range
+/// to count as a fresh mutation in `mutated_gene_mask`.
+pub const MUTATION_HIGHLIGHT_THRESHOLD: f64 = 0.05;
+
+/// Shape of the random step added to a gene when it mutates. Widths are in
+/// units of the offspring's `mutation_strength`, so step sizes still
+/// self-adapt whichever shape is chosen.
+///
+/// Cauchy steps are mostly small but occasionally huge. That lets a lineage
+/// leap to a distant trait combination in one generation, at the cost of
+/// more offspring thrown to the edge of a gene's range, where they are
+/// usually unfit.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum MutationDistribution {
+    Gaussian { std_dev: f64 },
+    /// Any step within `±range`, equally likely.
+    Uniform { range: f64 },
+    Cauchy { scale: f64 },
+}
+
+impl Default for MutationDistribution {
+    /// A Gaussian with standard deviation `mutation_strength`.
+    fn default() -> Self {
+        MutationDistribution::Gaussian { std_dev: 1.0 }
+    }
+}
+
+impl MutationDistribution {
+    /// The same shape with its width multiplied by `factor`.
+    pub fn scaled(self, factor: f64) -> Self {
+        match self {
+            MutationDistribution::Gaussian { std_dev } => MutationDistribution::Gaussian {
+                std_dev: std_dev * factor,
+            },
+            MutationDistribution::Uniform { range } => MutationDistribution::Uniform {
+                range: range * factor,
+            },
+            MutationDistribution::Cauchy { scale } => MutationDistribution::Cauchy {
+                scale: scale * factor,
+            },
+        }
+    }
+
+    /// Whether the width is finite and positive.
+    pub fn is_valid(&self) -> bool {
+        let width = match *self {
+            MutationDistribution::Gaussian { std_dev } => std_dev,
+            MutationDistribution::Uniform { range } => range,
+            MutationDistribution::Cauchy { scale } => scale,
+        };
+        width.is_finite() && width > 0.0
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        match *self {
+            MutationDistribution::Gaussian { std_dev } => {
+                Normal::new(0.0, std_dev).unwrap().sample(rng)
+            }
+            MutationDistribution::Uniform { range } => rng.gen_range(-range..=range),
+            MutationDistribution::Cauchy { scale } => Cauchy::new(0.0, scale).unwrap().sample(rng),
+        }
+    }
+}
+
+/// How founders' genes are drawn when an engine spawns its initial population.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum InitialGeneDistribution {
+    /// Each gene uniform over the `Genes::random` starting range.
+    #[default]
+    Uniform,
+    /// Each gene normal around `mean`, with `std_dev` a fraction of the gene's
+    /// `gene_bounds_for` width, clamped to those bounds. `mutation_strength`
+    /// is copied from `mean`.
+    Gaussian { mean: Genes, std_dev: f64 },
+    /// Two starting populations: a `fraction_a` share from `config_a`, the
+    /// rest from `config_b`.
+    Bimodal {
+        config_a: Box<InitialGeneDistribution>,
+        config_b: Box<InitialGeneDistribution>,
+        fraction_a: f64,
+    },
+    /// A JSON array of `Genes`, each founder a copy of one picked at random.
+    FromJson(String),
+}
+
+impl InitialGeneDistribution {
+    /// Why the distribution can't be sampled, if it can't.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            InitialGeneDistribution::Uniform => Ok(()),
+            InitialGeneDistribution::Gaussian { std_dev, .. } => {
+                if std_dev.is_finite() && *std_dev >= 0.0 {
+                    Ok(())
+                } else {
+                    Err(format!("std_dev ({}) must be finite and non-negative", std_dev))
+                }
+            }
+            InitialGeneDistribution::Bimodal {
+                config_a,
+                config_b,
+                fraction_a,
+            } => {
+                if !(0.0..=1.0).contains(fraction_a) {
+                    return Err(format!("fraction_a ({}) must be between 0 and 1", fraction_a));
+                }
+                config_a.validate()?;
+                config_b.validate()
+            }
+            InitialGeneDistribution::FromJson(json) => {
+                if parse_gene_list(json)?.is_empty() {
+                    Err("gene list is empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+fn parse_gene_list(json: &str) -> Result<Vec<Genes>, String> {
+    serde_json::from_str(json).map_err(|e| format!("invalid gene list: {}", e))
+}
+
+/// One founder's genes from `dist`. A `FromJson` list that doesn't parse, or
+/// is empty, falls back to `Uniform`; `InitialGeneDistribution::validate`
+/// reports it.
+pub fn sample_genes(dist: &InitialGeneDistribution, rng: &mut impl Rng) -> Genes {
+    match dist {
+        InitialGeneDistribution::Uniform => Genes::random(rng),
+        InitialGeneDistribution::Gaussian { mean, std_dev } => {
+            let mut genes = mean.clone();
+            for (index, name) in GENE_NAMES.iter().enumerate() {
+                let GeneBounds { min, max } = gene_bounds_for(name);
+                // An invalid std_dev leaves the gene at its mean
+                let step = Normal::new(0.0, std_dev * (max - min))
+                    .map_or(0.0, |normal| normal.sample(rng));
+                let gene = genes.gene_mut(index);
+                *gene = (*gene + step).clamp(min, max);
+            }
+            genes
+        }
+        InitialGeneDistribution::Bimodal {
+            config_a,
+            config_b,
+            fraction_a,
+        } => {
+            if rng.gen::<f64>() < *fraction_a {
+                sample_genes(config_a, rng)
+            } else {
+                sample_genes(config_b, rng)
+            }
+        }
+        InitialGeneDistribution::FromJson(json) => {
+            match parse_gene_list(json).ok().and_then(|list| list.choose(rng).cloned()) {
+                Some(genes) => genes,
+                None => Genes::random(rng),
+            }
+        }
+    }
+}
+
+/// Inclusive range a gene is clamped to when inherited.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeneBounds {
+    pub min: f64,
+    pub max: f64,
+}
+
+pub const fn bounds(min: f64, max: f64) -> GeneBounds {
+    GeneBounds { min, max }
+}
+
+const UNBOUNDED: GeneBounds = bounds(f64::NEG_INFINITY, f64::INFINITY);
+
+/// Gene field names, in `Genes::normalized` order.
+pub const GENE_NAMES: [&str; GENE_COUNT] = [
+    "speed",
+    "sense_range",
+    "size",
+    "energy_efficiency",
+    "reproduction_threshold",
+    "mutation_rate",
+    "aggression",
+    "color_hue",
+    "is_predator",
+    "hunting_speed",
+    "attack_power",
+    "defense",
+    "stealth",
+    "pack_mentality",
+    "territory_size",
+    "metabolism",
+    "intelligence",
+    "stamina",
+    "omnivore",
+    "migration",
+    "cooperation",
+];
+
+/// Bounds of the gene field called `field_name`; unknown names are unbounded.
+pub fn gene_bounds_for(field_name: &str) -> GeneBounds {
+    match field_name {
+        "speed" => bounds(0.1, 3.0),
+        "sense_range" => bounds(5.0, 150.0),
+        "size" => bounds(0.3, 2.5),
+        "energy_efficiency" => bounds(0.1, 2.5),
+        "reproduction_threshold" => bounds(10.0, 200.0),
+        "mutation_rate" => bounds(0.001, 0.3),
+        "aggression" => bounds(0.0, 1.0),
+        "color_hue" => bounds(0.0, 360.0),
+        "is_predator" => bounds(0.0, 1.0),
+        "hunting_speed" => bounds(0.5, 3.0),
+        "attack_power" => bounds(0.1, 3.0),
+        "defense" => bounds(0.1, 3.0),
+        "stealth" => bounds(0.0, 1.0),
+        "pack_mentality" => bounds(0.0, 1.0),
+        "territory_size" => bounds(10.0, 300.0),
+        "metabolism" => bounds(0.1, 3.0),
+        "intelligence" => bounds(0.1, 3.0),
+        "stamina" => bounds(0.1, 3.0),
+        "omnivore" => bounds(0.0, 1.0),
+        "migration" => bounds(0.0, 1.0),
+        "cooperation" => bounds(0.0, 1.0),
+        "mutation_strength" => bounds(0.001, 0.5),
+        _ => UNBOUNDED,
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Genes {
     pub speed: f64,                  // Movement speed multiplier
@@ -24,12 +265,30 @@ pub struct Genes {
     pub metabolism: f64,             // How fast energy is consumed
     pub intelligence: f64,           // Learning and adaptation ability
     pub stamina: f64,                // How long can chase prey
+    #[serde(default)]
+    pub omnivore: f64,               // Dietary flexibility: switches to hunting when well fed
+    #[serde(default)]
+    pub migration: f64,              // Urge to periodically strike out across the world
+    #[serde(default)]
+    pub cooperation: f64,            // Willingness to feed hungry kin at a cost to self
+    /// Meta-gene: standard deviation of the Gaussian step applied when any
+    /// other gene mutates. Not part of `normalized`, so it doesn't count
+    /// towards genetic distance.
+    #[serde(default = "default_mutation_strength")]
+    pub mutation_strength: f64,
+}
+
+fn default_mutation_strength() -> f64 {
+    0.05
 }
 
 impl Genes {
     pub fn new() -> Self {
-        let mut rng = thread_rng();
+        Self::random(&mut thread_rng())
+    }
 
+    /// Every gene drawn uniformly from its starting range.
+    pub fn random(rng: &mut impl Rng) -> Self {
         Self {
             speed: rng.gen_range(0.8..1.5), // Reduced from 0.5..2.0
             sense_range: rng.gen_range(30.0..80.0), // Reduced from 20.0..100.0
@@ -51,117 +310,195 @@ impl Genes {
             metabolism: rng.gen_range(0.8..1.4), // Energy consumption rate
             intelligence: rng.gen_range(0.5..1.5), // Learning ability
             stamina: rng.gen_range(0.5..1.5), // Chase endurance
+            omnivore: rng.gen_range(0.0..1.0), // Dietary flexibility
+            migration: rng.gen_range(0.0..1.0), // Migratory urge
+            cooperation: rng.gen_range(0.0..1.0), // Kin food sharing
+            mutation_strength: rng.gen_range(0.03..0.08), // Std dev of mutations
         }
     }
 
-    pub fn inherit_from(&self, other: &Genes, mutation_rate: f64) -> Self {
-        let mut rng = thread_rng();
+    /// Blends both parents' genes, mutating each with probability `mutation_rate`.
+    ///
+    /// The offspring's `mutation_strength` is inherited first, occasionally
+    /// scaled by a log-normal step, and then sets the size of its other
+    /// mutations. Strengths that produce fitter offspring hitch-hike with them,
+    /// so the step size self-adapts as in evolution strategies.
+    ///
+    /// `population_avg_attack` / `population_avg_defense` drive the predator-prey
+    /// arms race: whichever side is behind gets an extra push in the offspring.
+    pub fn inherit_from(
+        &self,
+        other: &Genes,
+        mutation_rate: f64,
+        distribution: MutationDistribution,
+        population_avg_attack: f64,
+        population_avg_defense: f64,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let blend_factor = rng.gen_range(0.3..0.7);
+        let mut mutation_strength =
+            self.mutation_strength * blend_factor + other.mutation_strength * (1.0 - blend_factor);
+        if rng.gen::<f64>() < MUTATION_STRENGTH_MUTATION_RATE {
+            let step = Normal::new(0.0, MUTATION_STRENGTH_STEP).unwrap().sample(rng);
+            mutation_strength *= step.exp();
+        }
+        let GeneBounds { min, max } = gene_bounds_for("mutation_strength");
+        let mutation_strength = mutation_strength.clamp(min, max);
+        let step = distribution.scaled(mutation_strength);
 
-        Self {
-            speed: self.mutate_gene(self.speed, other.speed, mutation_rate, &mut rng),
-            sense_range: self.mutate_gene(
-                self.sense_range,
-                other.sense_range,
-                mutation_rate,
-                &mut rng,
-            ),
-            size: self.mutate_gene(self.size, other.size, mutation_rate, &mut rng),
-            energy_efficiency: self.mutate_gene(
+        let mut mutate = |name: &str, gene1: f64, gene2: f64| {
+            self.mutate_gene(name, gene1, gene2, mutation_rate, step, rng)
+        };
+
+        let mut offspring = Self {
+            speed: mutate("speed", self.speed, other.speed),
+            sense_range: mutate("sense_range", self.sense_range, other.sense_range),
+            size: mutate("size", self.size, other.size),
+            energy_efficiency: mutate(
+                "energy_efficiency",
                 self.energy_efficiency,
                 other.energy_efficiency,
-                mutation_rate,
-                &mut rng,
             ),
-            reproduction_threshold: self.mutate_gene(
+            reproduction_threshold: mutate(
+                "reproduction_threshold",
                 self.reproduction_threshold,
                 other.reproduction_threshold,
-                mutation_rate,
-                &mut rng,
-            ),
-            mutation_rate: self.mutate_gene(
-                self.mutation_rate,
-                other.mutation_rate,
-                mutation_rate,
-                &mut rng,
             ),
-            aggression: self.mutate_gene(
-                self.aggression,
-                other.aggression,
-                mutation_rate,
-                &mut rng,
-            ),
-            color_hue: self.mutate_gene(self.color_hue, other.color_hue, mutation_rate, &mut rng),
-            
+            mutation_rate: mutate("mutation_rate", self.mutation_rate, other.mutation_rate),
+            aggression: mutate("aggression", self.aggression, other.aggression),
+            color_hue: mutate("color_hue", self.color_hue, other.color_hue),
+
             // NEW PREDATOR GENES
-            is_predator: self.mutate_gene(self.is_predator, other.is_predator, mutation_rate, &mut rng),
-            hunting_speed: self.mutate_gene(self.hunting_speed, other.hunting_speed, mutation_rate, &mut rng),
-            attack_power: self.mutate_gene(self.attack_power, other.attack_power, mutation_rate, &mut rng),
-            defense: self.mutate_gene(self.defense, other.defense, mutation_rate, &mut rng),
-            stealth: self.mutate_gene(self.stealth, other.stealth, mutation_rate, &mut rng),
-            pack_mentality: self.mutate_gene(self.pack_mentality, other.pack_mentality, mutation_rate, &mut rng),
-            territory_size: self.mutate_gene(self.territory_size, other.territory_size, mutation_rate, &mut rng),
-            metabolism: self.mutate_gene(self.metabolism, other.metabolism, mutation_rate, &mut rng),
-            intelligence: self.mutate_gene(self.intelligence, other.intelligence, mutation_rate, &mut rng),
-            stamina: self.mutate_gene(self.stamina, other.stamina, mutation_rate, &mut rng),
+            is_predator: mutate("is_predator", self.is_predator, other.is_predator),
+            hunting_speed: mutate("hunting_speed", self.hunting_speed, other.hunting_speed),
+            attack_power: mutate("attack_power", self.attack_power, other.attack_power),
+            defense: mutate("defense", self.defense, other.defense),
+            stealth: mutate("stealth", self.stealth, other.stealth),
+            pack_mentality: mutate("pack_mentality", self.pack_mentality, other.pack_mentality),
+            territory_size: mutate("territory_size", self.territory_size, other.territory_size),
+            metabolism: mutate("metabolism", self.metabolism, other.metabolism),
+            intelligence: mutate("intelligence", self.intelligence, other.intelligence),
+            stamina: mutate("stamina", self.stamina, other.stamina),
+            omnivore: mutate("omnivore", self.omnivore, other.omnivore),
+            migration: mutate("migration", self.migration, other.migration),
+            cooperation: mutate("cooperation", self.cooperation, other.cooperation),
+            mutation_strength,
+        };
+
+        // Red Queen dynamics: prey defense chases predator attack and vice versa
+        if population_avg_attack > population_avg_defense {
+            offspring.defense = (offspring.defense + ARMS_RACE_STEP).min(3.0);
+        } else if population_avg_defense > population_avg_attack {
+            offspring.attack_power = (offspring.attack_power + ARMS_RACE_STEP).min(3.0);
         }
+
+        offspring
+    }
+
+    /// `mutate_gene` with `distribution` scaled by this genome's own
+    /// `mutation_strength`.
+    pub fn blend_and_mutate_gene(
+        &self,
+        name: &str,
+        gene1: f64,
+        gene2: f64,
+        mutation_rate: f64,
+        distribution: MutationDistribution,
+        rng: &mut impl Rng,
+    ) -> f64 {
+        let step = distribution.scaled(self.mutation_strength);
+        self.mutate_gene(name, gene1, gene2, mutation_rate, step, rng)
     }
 
-    fn mutate_gene(&self, gene1: f64, gene2: f64, mutation_rate: f64, rng: &mut ThreadRng) -> f64 {
+    /// Blends two parent values of the gene `name`, occasionally adds a step
+    /// drawn from `step` and clamps the result to `gene_bounds_for(name)`.
+    pub fn mutate_gene(
+        &self,
+        name: &str,
+        gene1: f64,
+        gene2: f64,
+        mutation_rate: f64,
+        step: MutationDistribution,
+        rng: &mut impl Rng,
+    ) -> f64 {
         // Blend genes from both parents
         let blend_factor = rng.gen_range(0.3..0.7);
         let mut gene = gene1 * blend_factor + gene2 * (1.0 - blend_factor);
 
         // Apply mutation
         if rng.gen::<f64>() < mutation_rate {
-            let mutation_dist = Normal::new(0.0, 0.05).unwrap(); // Reduced from 0.1
-            let mutation = mutation_dist.sample(rng);
-            gene += mutation;
-        }
-
-        // Clamp values to reasonable ranges
-        match gene {
-            speed if speed < 0.1 => 0.1,
-            speed if speed > 3.0 => 3.0, // Reduced from 5.0
-            sense_range if sense_range < 5.0 => 5.0,
-            sense_range if sense_range > 150.0 => 150.0, // Reduced from 200.0
-            size if size < 0.3 => 0.3,
-            size if size > 2.5 => 2.5, // Reduced from 3.0
-            energy_efficiency if energy_efficiency < 0.1 => 0.1,
-            energy_efficiency if energy_efficiency > 2.5 => 2.5, // Reduced from 3.0
-            reproduction_threshold if reproduction_threshold < 10.0 => 10.0,
-            reproduction_threshold if reproduction_threshold > 200.0 => 200.0, // Reduced from 300.0
-            mutation_rate if mutation_rate < 0.001 => 0.001,
-            mutation_rate if mutation_rate > 0.3 => 0.3, // Reduced from 0.5
-            aggression if aggression < 0.0 => 0.0,
-            aggression if aggression > 1.0 => 1.0,
-            color_hue if color_hue < 0.0 => 0.0,
-            color_hue if color_hue > 360.0 => 360.0,
-            
-            // NEW PREDATOR GENE CLAMPING
-            is_predator if is_predator < 0.0 => 0.0,
-            is_predator if is_predator > 1.0 => 1.0,
-            hunting_speed if hunting_speed < 0.5 => 0.5,
-            hunting_speed if hunting_speed > 3.0 => 3.0,
-            attack_power if attack_power < 0.1 => 0.1,
-            attack_power if attack_power > 3.0 => 3.0,
-            defense if defense < 0.1 => 0.1,
-            defense if defense > 3.0 => 3.0,
-            stealth if stealth < 0.0 => 0.0,
-            stealth if stealth > 1.0 => 1.0,
-            pack_mentality if pack_mentality < 0.0 => 0.0,
-            pack_mentality if pack_mentality > 1.0 => 1.0,
-            territory_size if territory_size < 10.0 => 10.0,
-            territory_size if territory_size > 300.0 => 300.0,
-            metabolism if metabolism < 0.1 => 0.1,
-            metabolism if metabolism > 3.0 => 3.0,
-            intelligence if intelligence < 0.1 => 0.1,
-            intelligence if intelligence > 3.0 => 3.0,
-            stamina if stamina < 0.1 => 0.1,
-            stamina if stamina > 3.0 => 3.0,
-            _ => gene,
+            gene += step.sample(rng);
+        }
+
+        let bounds = gene_bounds_for(name);
+        gene.clamp(bounds.min, bounds.max)
+    }
+
+    /// Value of the gene at `index` in `GENE_NAMES` order.
+    pub fn gene(&self, index: usize) -> f64 {
+        *self.clone().gene_mut(index)
+    }
+
+    /// The gene at `index` in `GENE_NAMES` order.
+    pub fn gene_mut(&mut self, index: usize) -> &mut f64 {
+        match index {
+            0 => &mut self.speed,
+            1 => &mut self.sense_range,
+            2 => &mut self.size,
+            3 => &mut self.energy_efficiency,
+            4 => &mut self.reproduction_threshold,
+            5 => &mut self.mutation_rate,
+            6 => &mut self.aggression,
+            7 => &mut self.color_hue,
+            8 => &mut self.is_predator,
+            9 => &mut self.hunting_speed,
+            10 => &mut self.attack_power,
+            11 => &mut self.defense,
+            12 => &mut self.stealth,
+            13 => &mut self.pack_mentality,
+            14 => &mut self.territory_size,
+            15 => &mut self.metabolism,
+            16 => &mut self.intelligence,
+            17 => &mut self.stamina,
+            18 => &mut self.omnivore,
+            19 => &mut self.migration,
+            20 => &mut self.cooperation,
+            _ => panic!("gene index {} out of range", index),
         }
     }
 
+    /// All genes mapped onto [0, 1] by their `gene_bounds_for` range.
+    pub fn normalized(&self) -> [f64; GENE_COUNT] {
+        let norm = |value: f64, name: &str| {
+            let GeneBounds { min, max } = gene_bounds_for(name);
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        };
+
+        [
+            norm(self.speed, "speed"),
+            norm(self.sense_range, "sense_range"),
+            norm(self.size, "size"),
+            norm(self.energy_efficiency, "energy_efficiency"),
+            norm(self.reproduction_threshold, "reproduction_threshold"),
+            norm(self.mutation_rate, "mutation_rate"),
+            norm(self.aggression, "aggression"),
+            norm(self.color_hue, "color_hue"),
+            norm(self.is_predator, "is_predator"),
+            norm(self.hunting_speed, "hunting_speed"),
+            norm(self.attack_power, "attack_power"),
+            norm(self.defense, "defense"),
+            norm(self.stealth, "stealth"),
+            norm(self.pack_mentality, "pack_mentality"),
+            norm(self.territory_size, "territory_size"),
+            norm(self.metabolism, "metabolism"),
+            norm(self.intelligence, "intelligence"),
+            norm(self.stamina, "stamina"),
+            norm(self.omnivore, "omnivore"),
+            norm(self.migration, "migration"),
+            norm(self.cooperation, "cooperation"),
+        ]
+    }
+
     pub fn get_fitness_score(&self) -> f64 {
         // Calculate overall fitness based on gene combinations
         let speed_score = self.speed * 0.2;
@@ -177,8 +514,206 @@ impl Genes {
         let defense_score = self.defense * 0.1;
         let intelligence_score = self.intelligence * 0.1;
         let stamina_score = self.stamina * 0.1;
+        let omnivore_bonus = self.omnivore * 0.2; // Dietary flexibility
 
         speed_score + sense_score + efficiency_score + size_score + reproduction_score + 
-        predator_bonus + hunting_score + attack_score + defense_score + intelligence_score + stamina_score
+        predator_bonus + hunting_score + attack_score + defense_score + intelligence_score + stamina_score +
+        omnivore_bonus
+    }
+}
+
+/// Euclidean distance between two genomes in normalized gene space.
+pub fn genetic_distance(a: &Genes, b: &Genes) -> f64 {
+    a.normalized()
+        .iter()
+        .zip(b.normalized().iter())
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Bitmask of the genes `offspring` carries a noticeable mutation in, with bit
+/// `i` standing for `GENE_NAMES[i]`. Blending alone keeps a gene between the
+/// parents' values, so a gene counts once its normalized value lands more than
+/// `MUTATION_HIGHLIGHT_THRESHOLD` outside that range.
+pub fn mutated_gene_mask(offspring: &Genes, parent1: &Genes, parent2: &Genes) -> u32 {
+    let (child, a, b) = (offspring.normalized(), parent1.normalized(), parent2.normalized());
+    (0..GENE_COUNT)
+        .filter(|&i| {
+            let (low, high) = (a[i].min(b[i]), a[i].max(b[i]));
+            child[i] < low - MUTATION_HIGHLIGHT_THRESHOLD
+                || child[i] > high + MUTATION_HIGHLIGHT_THRESHOLD
+        })
+        .fold(0, |mask, i| mask | 1 << i)
+}
+
+/// Mean predator `attack_power` and mean prey `defense`, the inputs to the
+/// arms race in `Genes::inherit_from`. Both are zero unless both sides exist.
+pub fn arms_race_averages<'a>(population: impl IntoIterator<Item = &'a Genes>) -> (f64, f64) {
+    let (mut attack_sum, mut predators) = (0.0, 0usize);
+    let (mut defense_sum, mut prey) = (0.0, 0usize);
+    for genes in population {
+        if genes.is_predator > 0.5 {
+            attack_sum += genes.attack_power;
+            predators += 1;
+        } else {
+            defense_sum += genes.defense;
+            prey += 1;
+        }
+    }
+
+    if predators == 0 || prey == 0 {
+        return (0.0, 0.0);
+    }
+    (attack_sum / predators as f64, defense_sum / prey as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next_generation(parents: &[Genes], avg_attack: f64, avg_defense: f64) -> Vec<Genes> {
+        let mut rng = thread_rng();
+        (0..parents.len())
+            .map(|_| {
+                let a = parents.choose(&mut rng).unwrap();
+                let b = parents.choose(&mut rng).unwrap();
+                let distribution = MutationDistribution::default();
+                a.inherit_from(b, a.mutation_rate, distribution, avg_attack, avg_defense, &mut rng)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_attack_and_defense_coevolve() {
+        let spawn = |is_predator: f64| {
+            (0..50)
+                .map(|_| Genes {
+                    is_predator,
+                    ..Genes::new()
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut predators = spawn(1.0);
+        let mut prey = spawn(0.0);
+        let (initial_attack, initial_defense) = arms_race_averages(predators.iter().chain(&prey));
+
+        for _ in 0..2000 {
+            let (avg_attack, avg_defense) = arms_race_averages(predators.iter().chain(&prey));
+            predators = next_generation(&predators, avg_attack, avg_defense);
+            prey = next_generation(&prey, avg_attack, avg_defense);
+        }
+
+        let (final_attack, final_defense) = arms_race_averages(predators.iter().chain(&prey));
+        assert!(final_attack > initial_attack + 0.5);
+        assert!(final_defense > initial_defense + 0.5);
+        assert!((final_attack - final_defense).abs() < 0.25);
+    }
+
+    #[test]
+    fn test_mutation_strength_shrinks_under_stable_selection() {
+        // A fixed optimum: the fittest genomes are those closest to `target`.
+        // The mutation rate is held fixed so only the step size can adapt.
+        const MUTATION_RATE: f64 = 0.2;
+        let target = Genes::new();
+        let mut rng = thread_rng();
+        let mut population: Vec<Genes> = (0..100).map(|_| Genes::new()).collect();
+        let average_strength = |population: &[Genes]| {
+            population.iter().map(|g| g.mutation_strength).sum::<f64>() / population.len() as f64
+        };
+        let initial_strength = average_strength(&population);
+
+        for _ in 0..2000 {
+            let mut offspring: Vec<Genes> = (0..200)
+                .map(|_| {
+                    let a = population.choose(&mut rng).unwrap();
+                    let b = population.choose(&mut rng).unwrap();
+                    let distribution = MutationDistribution::default();
+                    a.inherit_from(b, MUTATION_RATE, distribution, 0.0, 0.0, &mut rng)
+                })
+                .collect();
+            offspring.sort_by(|a, b| {
+                genetic_distance(a, &target).total_cmp(&genetic_distance(b, &target))
+            });
+            offspring.truncate(population.len());
+            population = offspring;
+        }
+
+        let final_strength = average_strength(&population);
+        assert!(
+            final_strength < initial_strength * 0.7,
+            "{} -> {}",
+            initial_strength,
+            final_strength
+        );
+    }
+
+    #[test]
+    fn test_mutation_distributions_differ_in_tail_weight() {
+        // Mutations on identical parents, measured in units of mutation_strength
+        let parent = Genes::new();
+        let mut rng = thread_rng();
+        let mut magnitudes = |distribution: MutationDistribution| {
+            let mut steps: Vec<f64> = (0..20_000)
+                .map(|_| {
+                    let step = distribution.scaled(parent.mutation_strength);
+                    let gene =
+                        parent.mutate_gene("territory_size", 150.0, 150.0, 1.0, step, &mut rng);
+                    (gene - 150.0).abs() / parent.mutation_strength
+                })
+                .collect();
+            steps.sort_by(f64::total_cmp);
+            let mean = steps.iter().sum::<f64>() / steps.len() as f64;
+            let beyond_ten = steps.iter().filter(|&&step| step > 10.0).count();
+            (mean, steps[steps.len() - 1], beyond_ten as f64 / steps.len() as f64)
+        };
+
+        let (uniform_mean, uniform_max, uniform_tail) =
+            magnitudes(MutationDistribution::Uniform { range: 1.0 });
+        let (gaussian_mean, _, gaussian_tail) = magnitudes(MutationDistribution::default());
+        let (cauchy_mean, _, cauchy_tail) = magnitudes(MutationDistribution::Cauchy { scale: 1.0 });
+
+        assert!((uniform_mean - 0.5).abs() < 0.05, "{}", uniform_mean);
+        assert!(uniform_max <= 1.0 + 1e-9);
+        let half_normal_mean = (2.0 / std::f64::consts::PI).sqrt();
+        assert!((gaussian_mean - half_normal_mean).abs() < 0.05, "{}", gaussian_mean);
+        assert!(cauchy_mean > 2.0 * gaussian_mean, "{}", cauchy_mean);
+
+        // About 6% of Cauchy steps are ten widths or more; Gaussian ones never are
+        assert_eq!((uniform_tail, gaussian_tail), (0.0, 0.0));
+        assert!((0.04..0.09).contains(&cauchy_tail), "{}", cauchy_tail);
+    }
+
+    #[test]
+    fn test_mutated_gene_mask_bit_positions() {
+        let parent = Genes::new();
+        assert_eq!(mutated_gene_mask(&parent, &parent, &parent), 0);
+
+        let normalized = parent.normalized();
+        for (bit, name) in GENE_NAMES.iter().enumerate() {
+            // Push the gene to whichever end of its range is further away
+            let GeneBounds { min, max } = gene_bounds_for(name);
+            let extreme = if normalized[bit] > 0.5 { min } else { max };
+            let mut value = serde_json::to_value(&parent).unwrap();
+            value[*name] = extreme.into();
+            let offspring: Genes = serde_json::from_value(value).unwrap();
+
+            assert_eq!(
+                mutated_gene_mask(&offspring, &parent, &parent),
+                1 << bit,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_gene_has_bounds() {
+        for name in GENE_NAMES {
+            let bounds = gene_bounds_for(name);
+            assert_ne!(bounds, UNBOUNDED, "{} has no registered bounds", name);
+            assert!(bounds.min < bounds.max, "{} has an empty range", name);
+        }
+        assert_eq!(gene_bounds_for("wingspan"), UNBOUNDED);
     }
 }
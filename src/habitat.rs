@@ -0,0 +1,189 @@
+use crate::agent::Agent;
+use serde::{Deserialize, Serialize};
+
+/// Circular region that caps how many agents can be born inside it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Habitat {
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub carrying_capacity: usize,
+}
+
+impl Habitat {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let dx = x - self.x;
+        let dy = y - self.y;
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+}
+
+/// Agents whose `migration` gene is above this travel between habitats.
+pub const MIGRANT_THRESHOLD: f64 = 0.5;
+
+/// Habitats as nodes, indexed like the engine's habitat list, joined by edges
+/// weighted with the distance between their centres.
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct ConnectivityGraph {
+    pub nodes: Vec<usize>,
+    pub edges: Vec<(usize, usize, f64)>,
+}
+
+impl ConnectivityGraph {
+    /// Connects every pair of `habitats`.
+    pub fn new(habitats: &[Habitat]) -> Self {
+        let mut edges = Vec::new();
+        for (i, a) in habitats.iter().enumerate() {
+            for (j, b) in habitats.iter().enumerate().skip(i + 1) {
+                edges.push((i, j, (a.x - b.x).hypot(a.y - b.y)));
+            }
+        }
+        Self {
+            nodes: (0..habitats.len()).collect(),
+            edges,
+        }
+    }
+
+    /// Dijkstra from `from`: the distance to every node, infinite when
+    /// unreachable, and the node before it on the shortest path.
+    pub fn shortest_paths(&self, from: usize) -> (Vec<f64>, Vec<Option<usize>>) {
+        let n = self.nodes.len();
+        let mut distances = vec![f64::INFINITY; n];
+        let mut previous = vec![None; n];
+        let mut visited = vec![false; n];
+        if from >= n {
+            return (distances, previous);
+        }
+        distances[from] = 0.0;
+
+        // Habitat counts are small, so a linear scan beats a heap
+        while let Some(node) = (0..n)
+            .filter(|&i| !visited[i] && distances[i].is_finite())
+            .min_by(|&a, &b| distances[a].total_cmp(&distances[b]))
+        {
+            visited[node] = true;
+            for &(a, b, weight) in &self.edges {
+                let next = match (a == node, b == node) {
+                    (true, _) => b,
+                    (_, true) => a,
+                    _ => continue,
+                };
+                if distances[node] + weight < distances[next] {
+                    distances[next] = distances[node] + weight;
+                    previous[next] = Some(node);
+                }
+            }
+        }
+        (distances, previous)
+    }
+
+    /// First node after `from` on the shortest path to the nearest node for
+    /// which `has_room` holds. `None` when `from` itself has room or no such
+    /// node can be reached.
+    pub fn next_hop(&self, from: usize, has_room: impl Fn(usize) -> bool) -> Option<usize> {
+        let (distances, previous) = self.shortest_paths(from);
+        let target = (0..self.nodes.len())
+            .filter(|&i| distances[i].is_finite() && has_room(i))
+            .min_by(|&a, &b| distances[a].total_cmp(&distances[b]))?;
+        let mut hop = target;
+        while let Some(before) = previous[hop] {
+            if before == from {
+                return Some(hop);
+            }
+            hop = before;
+        }
+        None
+    }
+}
+
+/// Tags agents inside a habitat with it as their `home_habitat_id`, then
+/// points each migrant in a full habitat at the next habitat on its way to
+/// the nearest one with room. Migrants between habitats keep their
+/// `migration_waypoint` until they arrive; other agents lose it.
+pub fn route_migrants(agents: &mut [Agent], habitats: &[Habitat], graph: &ConnectivityGraph) {
+    let inside: Vec<Option<usize>> = agents
+        .iter()
+        .map(|agent| habitats.iter().position(|h| h.contains(agent.x, agent.y)))
+        .collect();
+    let mut occupants = vec![0; habitats.len()];
+    for id in inside.iter().flatten() {
+        occupants[*id] += 1;
+    }
+
+    let has_room = |id: usize| occupants[id] < habitats[id].carrying_capacity;
+    for (agent, inside) in agents.iter_mut().zip(inside) {
+        if inside.is_some() {
+            agent.home_habitat_id = inside;
+        }
+        if agent.genes.migration <= MIGRANT_THRESHOLD {
+            agent.migration_waypoint = None;
+        } else if let Some(home) = inside {
+            agent.migration_waypoint = graph
+                .next_hop(home, has_room)
+                .map(|hop| (habitats[hop].x, habitats[hop].y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundary::BoundaryBehavior;
+    use crate::genes::Genes;
+
+    #[test]
+    fn test_migrants_leave_a_full_habitat_for_one_with_room() {
+        let habitats = [
+            Habitat {
+                x: 100.0,
+                y: 100.0,
+                radius: 30.0,
+                carrying_capacity: 1,
+            },
+            Habitat {
+                x: 250.0,
+                y: 100.0,
+                radius: 30.0,
+                carrying_capacity: 10,
+            },
+        ];
+        let graph = ConnectivityGraph::new(&habitats);
+        assert_eq!(graph.edges, vec![(0, 1, 150.0)]);
+
+        let mut agents: Vec<Agent> = [(95.0, 100.0), (105.0, 100.0)]
+            .into_iter()
+            .map(|(x, y)| {
+                let genes = Genes {
+                    is_predator: 0.0,
+                    omnivore: 0.0,
+                    migration: 0.6,
+                    speed: 4.0,
+                    ..Genes::new()
+                };
+                let mut agent = Agent::new(x, y, genes, 0);
+                agent.age = 30.0;
+                agent.last_reproduction = 1000.0;
+                agent
+            })
+            .collect();
+
+        for _ in 0..1500 {
+            route_migrants(&mut agents, &habitats, &graph);
+            let others = agents.clone();
+            for agent in &mut agents {
+                agent.update(0.1, &[], &others, 1000.0, 800.0, BoundaryBehavior::Wrap);
+            }
+            if agents.iter().all(|a| a.home_habitat_id == Some(1)) {
+                break;
+            }
+        }
+
+        assert!(
+            agents.iter().all(|a| a.home_habitat_id == Some(1)),
+            "{:?}",
+            agents.iter().map(|a| (a.x, a.y)).collect::<Vec<_>>()
+        );
+        route_migrants(&mut agents, &habitats, &graph);
+        assert!(agents.iter().all(|a| a.migration_waypoint.is_none()));
+    }
+}
@@ -1,6 +1,70 @@
-use crate::simulation_core::{SimulationConfig, SimulationStats, UnifiedSimulation};
+use crate::simulation_core::{
+    format_config_errors, ConfigError, SimulationCommand, SimulationConfig, SimulationSnapshot,
+    SimulationStats, UnifiedSimulation,
+};
+use crate::agent::{generation_distribution, Agent, GeneBoxplot};
+use crate::archetype::AgentArchetype;
+use crate::biome::BiomeMap;
+use crate::boundary::BoundaryBehavior;
+use crate::death::DEFAULT_MAX_DEATH_HISTORY;
+use crate::genes::{InitialGeneDistribution, MutationDistribution, GENE_NAMES};
+use crate::habitat::Habitat;
+use crate::interaction::InteractionLog;
+use crate::spatial::SpatialBackend;
+use crate::species::{gene_diversity, DEFAULT_SPECIES_THRESHOLD};
+use crate::stats::{fit_logistic_growth, least_squares_line};
+use crate::teleporter::Teleporter;
+use rand::Rng;
 use serde::Serialize;
-use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const CHECKPOINT_INTERVAL: usize = 10_000;
+/// Steps between entries in `SimulationDiagnostics::gene_boxplot_history`.
+const GENE_BOXPLOT_INTERVAL: usize = 600;
+/// Steps between entries in `SimulationDiagnostics::generation_pyramid_history`.
+const GENERATION_PYRAMID_INTERVAL: usize = 1000;
+
+/// Agents per resource above which the population is heading for starvation.
+const STARVATION_PRESSURE: f64 = 5.0;
+const STARVATION_WARNING_STEPS: usize = 60;
+/// Agents per resource at which a run is abandoned as a slow extinction.
+const COLLAPSE_PRESSURE: f64 = 10.0;
+const COLLAPSE_TERMINATION_STEPS: usize = 120;
+/// Rescues in a row, without the population recovering in between, after
+/// which a run is abandoned.
+const MAX_CONSECUTIVE_RESCUES: usize = 5;
+/// Steps the population has to stay at or above `min_agent_count` after a
+/// rescue to count as recovered.
+const RESCUE_RECOVERY_STEPS: usize = 600;
+/// Steps between population viability analyses.
+const PVA_INTERVAL: usize = 500;
+/// Latest `agent_count_history` entries the viability fit looks at.
+const PVA_WINDOW: usize = 50;
+/// Growth rate per history entry below which a small population is at risk.
+const PVA_GROWTH_THRESHOLD: f64 = 0.001;
+/// Fraction of the fitted carrying capacity below which a population is small.
+const PVA_SMALL_POPULATION: f64 = 0.1;
+/// History entries ahead that the extinction probability covers.
+const PVA_HORIZON: f64 = 250.0;
+/// Extinction probability at which a run is abandoned.
+const PVA_TERMINATION_RISK: f64 = 0.99;
+/// Steps between Lotka-Volterra fits.
+const LV_INTERVAL: usize = 3600;
+/// Steps of history the Lotka-Volterra fit looks at.
+const LV_WINDOW_STEPS: usize = 1200;
+/// Steps run by `HeadlessSimulationV2::run_benchmark`.
+pub const BENCHMARK_STEPS: usize = 10_000;
+/// First line of `SimulationDiagnostics::to_csv_string` and of the file
+/// written to `HeadlessSimulationConfig::csv_output_path`.
+pub const CSV_HEADER: &str =
+    "step,agents,resources,total_energy,avg_fitness,avg_speed,avg_aggression,avg_generation";
 
 #[derive(Clone, Serialize)]
 pub struct HeadlessSimulationConfig {
@@ -16,7 +80,36 @@ pub struct HeadlessSimulationConfig {
     pub min_agent_count: usize,
     pub max_agent_count: usize,
     pub use_ecs: bool,
+    pub boundary_behavior: BoundaryBehavior,
+    pub season_period_seconds: f64,
+    pub season_amplitude: f64,
+    pub log_interactions: bool,
+    pub spatial_backend: SpatialBackend,
+    pub adaptive_spatial_grid: bool,
+    pub speciation_threshold: f64,
+    pub max_death_history: usize,
+    pub resource_dynamics: bool,
+    pub mutation_distribution: MutationDistribution,
+    pub initial_gene_distribution: InitialGeneDistribution,
+    pub teleporters: Vec<Teleporter>,
+    pub agent_archetypes: Vec<AgentArchetype>,
+    pub use_allometric_scaling: bool,
+    pub rescue_on_near_extinction: bool,
+    pub rescue_agent_count: usize,
+    pub seed: Option<u64>,
+    pub biome_map: Option<BiomeMap>,
     pub speed_multiplier: f64, // For high-speed evaluation
+    pub bottleneck: Option<BottleneckEvent>,
+    /// File `run` writes a `CsvRow` to at every history sample. Ignored on
+    /// wasm, which has no file system; use `to_csv_string` there.
+    pub csv_output_path: Option<PathBuf>,
+}
+
+/// Culls the population down to `survival_fraction` at `at_step`, chosen uniformly.
+#[derive(Clone, Serialize)]
+pub struct BottleneckEvent {
+    pub at_step: usize,
+    pub survival_fraction: f64,
 }
 
 impl Default for HeadlessSimulationConfig {
@@ -34,7 +127,27 @@ impl Default for HeadlessSimulationConfig {
             min_agent_count: 10,
             max_agent_count: 3000,
             use_ecs: true,
+            boundary_behavior: BoundaryBehavior::Wrap,
+            season_period_seconds: 60.0,
+            season_amplitude: 0.5,
+            log_interactions: false,
+            spatial_backend: SpatialBackend::Grid,
+            adaptive_spatial_grid: false,
+            speciation_threshold: DEFAULT_SPECIES_THRESHOLD,
+            max_death_history: DEFAULT_MAX_DEATH_HISTORY,
+            resource_dynamics: false,
+            mutation_distribution: MutationDistribution::default(),
+            initial_gene_distribution: InitialGeneDistribution::Uniform,
+            teleporters: Vec::new(),
+            agent_archetypes: Vec::new(),
+            use_allometric_scaling: false,
+            rescue_on_near_extinction: false,
+            rescue_agent_count: 50,
+            seed: None,
+            biome_map: None,
             speed_multiplier: 10.0, // 10x faster than real-time
+            bottleneck: None,
+            csv_output_path: None,
         }
     }
 }
@@ -54,6 +167,24 @@ impl From<HeadlessSimulationConfig> for SimulationConfig {
             min_agent_count: config.min_agent_count,
             max_agent_count: config.max_agent_count,
             use_ecs: config.use_ecs,
+            boundary_behavior: config.boundary_behavior,
+            season_period_seconds: config.season_period_seconds,
+            season_amplitude: config.season_amplitude,
+            log_interactions: config.log_interactions,
+            spatial_backend: config.spatial_backend,
+            adaptive_spatial_grid: config.adaptive_spatial_grid,
+            speciation_threshold: config.speciation_threshold,
+            max_death_history: config.max_death_history,
+            resource_dynamics: config.resource_dynamics,
+            mutation_distribution: config.mutation_distribution,
+            initial_gene_distribution: config.initial_gene_distribution,
+            teleporters: config.teleporters,
+            agent_archetypes: config.agent_archetypes,
+            use_allometric_scaling: config.use_allometric_scaling,
+            rescue_on_near_extinction: config.rescue_on_near_extinction,
+            rescue_agent_count: config.rescue_agent_count,
+            seed: config.seed,
+            biome_map: config.biome_map,
         }
     }
 }
@@ -72,12 +203,309 @@ pub struct SimulationDiagnostics {
     pub resource_count_history: Vec<usize>,
     pub energy_history: Vec<f64>,
     pub fitness_history: Vec<f64>,
+    /// `SimulationStats::average_perception_error`, sampled alongside
+    /// `agent_count_history`.
+    pub perception_error_history: Vec<f64>,
+    /// `MemoryStats::heap_used_bytes`, sampled alongside
+    /// `agent_count_history`.
+    pub memory_usage_history: Vec<f64>,
+    /// `SimulationStats::average_speed`, `average_aggression` and
+    /// `average_generation`, sampled alongside `agent_count_history`.
+    pub speed_history: Vec<f64>,
+    pub aggression_history: Vec<f64>,
+    pub generation_history: Vec<f64>,
     pub extinction_occurred: bool,
     pub population_explosion: bool,
     pub average_generations: f64,
     pub total_reproductions: usize,
     pub total_deaths: usize,
     pub simulation_quality_score: f64,
+    pub bottleneck_occurred: bool,
+    pub post_bottleneck_diversity: f64,
+    pub gene_diversity_recovery_steps: Option<usize>,
+    /// Set once resource pressure has stayed high long enough to predict collapse.
+    pub starvation_warning: bool,
+    pub starvation_warning_step: Option<usize>,
+    /// Most agents seen while resources spawn faster than average.
+    pub season_peak_agent_count: usize,
+    /// Fewest agents seen while resources spawn slower than average.
+    pub season_trough_agent_count: usize,
+    /// Whole seasons the run has gone through.
+    pub season_cycles_completed: u32,
+    /// Resources left by dead agents that are still on the map.
+    pub death_resource_count: usize,
+    /// Living agents per generation at the end of the run; see
+    /// `generation_distribution`.
+    pub generation_distribution: Vec<usize>,
+    pub max_living_generation: u32,
+    /// Generation with the most living agents.
+    pub modal_generation: u32,
+    /// `generation_distribution` every `GENERATION_PYRAMID_INTERVAL` steps.
+    pub generation_pyramid_history: Vec<Vec<usize>>,
+    /// Every gene's `GeneBoxplot`, by name, every `GENE_BOXPLOT_INTERVAL`
+    /// steps.
+    pub gene_boxplot_history: HashMap<String, Vec<GeneBoxplot>>,
+    /// New neighbour index cell size after each adaptation; see
+    /// `SimulationConfig::adaptive_spatial_grid`.
+    pub spatial_grid_cell_size_history: Vec<f64>,
+    /// Deaths per `DeathReason`, by name, among the last `max_death_history`
+    /// deaths. Empty when the ECS engine is running.
+    pub causes_of_death: HashMap<String, usize>,
+    /// Resource patches at the end of the run; see `count_resource_clusters`.
+    pub resource_cluster_count: usize,
+    /// (start step, duration) of every famine, whether triggered by hand or
+    /// by a collapsing resource supply.
+    pub famine_events: Vec<(usize, usize)>,
+    /// Latest `pva_extinction_risk` estimate, updated every `PVA_INTERVAL`
+    /// steps.
+    pub pva_extinction_risk: f64,
+    /// Every `pva_extinction_risk` estimate so far, oldest first.
+    pub extinction_probability_history: Vec<f64>,
+    /// Latest `fit_lotka_volterra` estimates, with agents as predators and
+    /// resources as prey, per step. Refitted every `LV_INTERVAL` steps.
+    pub lv_alpha: f64,
+    pub lv_beta: f64,
+    pub lv_delta: f64,
+    pub lv_gamma: f64,
+    /// Whether the latest fit has its coexistence point at a negative
+    /// population; see `lv_predicts_extinction`.
+    pub lv_extinction_predicted: bool,
+    /// Times immigrants were brought in to save a dwindling population; see
+    /// `SimulationConfig::rescue_on_near_extinction`.
+    pub rescue_event_count: usize,
+    /// Agents killed by poisonous resources over the whole run.
+    pub total_poison_deaths: u32,
+}
+
+/// One line of the per-step CSV export; see `CSV_HEADER`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CsvRow {
+    pub step: usize,
+    pub agents: usize,
+    pub resources: usize,
+    pub total_energy: f64,
+    pub avg_fitness: f64,
+    pub avg_speed: f64,
+    pub avg_aggression: f64,
+    pub avg_generation: f64,
+}
+
+impl CsvRow {
+    pub fn from_stats(step: usize, stats: &SimulationStats) -> Self {
+        Self {
+            step,
+            agents: stats.agent_count,
+            resources: stats.resource_count,
+            total_energy: stats.total_energy,
+            avg_fitness: stats.average_fitness,
+            avg_speed: stats.average_speed,
+            avg_aggression: stats.average_aggression,
+            avg_generation: stats.average_generation,
+        }
+    }
+}
+
+impl fmt::Display for CsvRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{}",
+            self.step,
+            self.agents,
+            self.resources,
+            self.total_energy,
+            self.avg_fitness,
+            self.avg_speed,
+            self.avg_aggression,
+            self.avg_generation
+        )
+    }
+}
+
+/// `CSV_HEADER` followed by one line per row.
+pub fn rows_to_csv(rows: impl IntoIterator<Item = CsvRow>) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for row in rows {
+        csv.push_str(&format!("{}\n", row));
+    }
+    csv
+}
+
+impl SimulationDiagnostics {
+    /// The `index`th history sample, taken at step `(index + 1) *
+    /// history_interval`.
+    pub fn csv_row(&self, index: usize) -> CsvRow {
+        CsvRow {
+            step: (index + 1) * history_interval(&self.config),
+            agents: self.agent_count_history[index],
+            resources: self.resource_count_history[index],
+            total_energy: self.energy_history[index],
+            avg_fitness: self.fitness_history[index],
+            avg_speed: self.speed_history[index],
+            avg_aggression: self.aggression_history[index],
+            avg_generation: self.generation_history[index],
+        }
+    }
+
+    /// The history as CSV, the same as `run` writes to
+    /// `HeadlessSimulationConfig::csv_output_path`.
+    pub fn to_csv_string(&self) -> String {
+        rows_to_csv((0..self.agent_count_history.len()).map(|index| self.csv_row(index)))
+    }
+}
+
+/// Steps between history samples: about one simulated second, fewer at high
+/// `speed_multiplier`.
+fn history_interval(config: &HeadlessSimulationConfig) -> usize {
+    (60.0 / config.speed_multiplier).max(1.0) as usize
+}
+
+/// Everything needed to resume a headless run where it left off.
+#[derive(Clone, Serialize)]
+pub struct SimulationCheckpoint {
+    /// JSON-serialized `SimulationSnapshot` of the engine state.
+    pub state: String,
+    pub step_count: usize,
+    pub diagnostics: SimulationDiagnostics,
+    /// Wall-clock seconds the run had taken when the checkpoint was made.
+    pub start_time_offset: f64,
+    pub pre_bottleneck_diversity: f64,
+}
+
+/// Timing and population figures from a fixed-length benchmark run.
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkResult {
+    pub steps_per_second: f64,
+    pub ns_per_step: f64,
+    pub peak_agents: usize,
+    pub peak_resources: usize,
+    /// `steps_per_second` scaled by the fraction of the starting population
+    /// still alive, capped at 1, so an engine can't score well by letting
+    /// everything die.
+    pub final_score: f64,
+    /// "ECS" or "Legacy".
+    pub engine_type: String,
+}
+
+impl BenchmarkResult {
+    /// How many times faster this run was than `other`.
+    pub fn compare_to(&self, other: &BenchmarkResult) -> f64 {
+        self.steps_per_second / other.steps_per_second
+    }
+}
+
+/// Probability that a population with this `agent_count_history` dies out
+/// within the next `PVA_HORIZON` entries. A logistic fit over the last
+/// `PVA_WINDOW` entries gives r and K, and only a population that has stopped
+/// growing and fallen below a tenth of K is at risk. Its shortfall from
+/// `PVA_GROWTH_THRESHOLD` is treated as exponential decline, which takes
+/// ln(N) / decline entries to reach one agent, giving a risk of
+/// 1 - exp(-horizon / time to extinction).
+pub fn pva_extinction_risk(history: &[usize]) -> f64 {
+    let window = &history[history.len().saturating_sub(PVA_WINDOW)..];
+    let counts: Vec<f64> = window.iter().map(|&count| count as f64).collect();
+    let Some(&current) = counts.last() else {
+        return 0.0;
+    };
+    if current == 0.0 {
+        return 1.0;
+    }
+    let Some((r, k)) = fit_logistic_growth(&counts) else {
+        return 0.0;
+    };
+    if r >= PVA_GROWTH_THRESHOLD || current >= PVA_SMALL_POPULATION * k {
+        return 0.0;
+    }
+
+    let time_to_extinction = current.ln() / (PVA_GROWTH_THRESHOLD - r);
+    1.0 - (-PVA_HORIZON / time_to_extinction).exp()
+}
+
+/// Least-squares estimates (α, β, δ, γ) of the Lotka-Volterra model
+///
+///   dM/dt = αM - βMN,   dN/dt = δMN - γN
+///
+/// for `predators` N and `prey` M sampled every `dt`. Dividing through by each
+/// population makes the model linear: d ln M/dt = α - βN and
+/// d ln N/dt = δM - γ, so each pair comes from a straight-line fit of central
+/// differences of the logs. `None` without three samples in a row where both
+/// populations are alive.
+pub fn fit_lotka_volterra(
+    predators: &[usize],
+    prey: &[usize],
+    dt: f64,
+) -> Option<(f64, f64, f64, f64)> {
+    let samples: Vec<(f64, f64)> = predators
+        .iter()
+        .zip(prey)
+        .map(|(&n, &m)| (n as f64, m as f64))
+        .collect();
+    let mut predator_counts = Vec::new();
+    let mut prey_counts = Vec::new();
+    let mut predator_growth = Vec::new();
+    let mut prey_growth = Vec::new();
+    for triple in samples.windows(3) {
+        if triple.iter().any(|&(n, m)| n == 0.0 || m == 0.0) {
+            continue;
+        }
+        let [(n0, m0), (n1, m1), (n2, m2)] = [triple[0], triple[1], triple[2]];
+        predator_counts.push(n1);
+        prey_counts.push(m1);
+        predator_growth.push((n2.ln() - n0.ln()) / (2.0 * dt));
+        prey_growth.push((m2.ln() - m0.ln()) / (2.0 * dt));
+    }
+    if predator_counts.is_empty() {
+        return None;
+    }
+
+    let (alpha, minus_beta) = least_squares_line(&predator_counts, &prey_growth);
+    let (minus_gamma, delta) = least_squares_line(&prey_counts, &predator_growth);
+    Some((alpha, -minus_beta, delta, -minus_gamma))
+}
+
+/// Whether Lotka-Volterra parameters put the coexistence point
+/// (M, N) = (γ/δ, α/β) at a negative population, leaving no state where
+/// predators and prey persist together.
+pub fn lv_predicts_extinction((alpha, beta, delta, gamma): (f64, f64, f64, f64)) -> bool {
+    alpha / beta < 0.0 || gamma / delta < 0.0
+}
+
+/// Runs `simulation` for `steps` updates and times them with `now_seconds`,
+/// which only needs to be monotonic. Shared by the native and browser
+/// benchmarks, which have different clocks.
+pub fn benchmark_simulation(
+    simulation: &mut UnifiedSimulation,
+    steps: usize,
+    now_seconds: impl Fn() -> f64,
+) -> BenchmarkResult {
+    let initial_agents = simulation.agent_count();
+    let mut peak_agents = initial_agents;
+    let mut peak_resources = simulation.resource_count();
+
+    let start = now_seconds();
+    for _ in 0..steps {
+        simulation.update();
+        peak_agents = peak_agents.max(simulation.agent_count());
+        peak_resources = peak_resources.max(simulation.resource_count());
+    }
+    let elapsed = now_seconds() - start;
+
+    let steps_per_second = steps as f64 / elapsed;
+    let survival = if initial_agents > 0 {
+        (simulation.agent_count() as f64 / initial_agents as f64).min(1.0)
+    } else {
+        0.0
+    };
+    let engine_type = if simulation.get_config().use_ecs { "ECS" } else { "Legacy" };
+
+    BenchmarkResult {
+        steps_per_second,
+        ns_per_step: elapsed * 1e9 / steps as f64,
+        peak_agents,
+        peak_resources,
+        final_score: steps_per_second * survival,
+        engine_type: engine_type.to_string(),
+    }
 }
 
 pub struct HeadlessSimulationV2 {
@@ -88,11 +516,34 @@ pub struct HeadlessSimulationV2 {
     start_time: Instant,
     history_interval: usize,
     _last_stats_time: f64,
+    pre_bottleneck_diversity: f64,
+    checkpoints: Vec<SimulationCheckpoint>,
+    consecutive_high_pressure_steps: usize,
+    consecutive_collapse_pressure_steps: usize,
+    /// Rescues since the population last recovered; see
+    /// `MAX_CONSECUTIVE_RESCUES`.
+    consecutive_rescue_events: usize,
+    /// Steps since the latest rescue without the population dropping below
+    /// `min_agent_count`.
+    steps_since_rescue: usize,
+    spatial_cell_size: Option<f64>,
+    /// Commands from `from_replay` still waiting for their step.
+    pending_commands: VecDeque<(usize, SimulationCommand)>,
+    /// Open `csv_output_path` while `run` is going.
+    #[cfg(not(target_arch = "wasm32"))]
+    csv_writer: Option<BufWriter<File>>,
 }
 
 impl HeadlessSimulationV2 {
+    /// Panics listing every problem if `config` fails
+    /// `SimulationConfig::validate`; use `try_new` to handle them instead.
     pub fn new(config: HeadlessSimulationConfig) -> Self {
+        Self::try_new(config).unwrap_or_else(|errors| panic!("{}", format_config_errors(&errors)))
+    }
+
+    pub fn try_new(config: HeadlessSimulationConfig) -> Result<Self, Vec<ConfigError>> {
         let simulation_config: SimulationConfig = config.clone().into();
+        simulation_config.validate()?;
         let simulation = UnifiedSimulation::new(simulation_config);
 
         let diagnostics = SimulationDiagnostics {
@@ -108,19 +559,50 @@ impl HeadlessSimulationV2 {
             resource_count_history: Vec::new(),
             energy_history: Vec::new(),
             fitness_history: Vec::new(),
+            perception_error_history: Vec::new(),
+            memory_usage_history: Vec::new(),
+            speed_history: Vec::new(),
+            aggression_history: Vec::new(),
+            generation_history: Vec::new(),
             extinction_occurred: false,
             population_explosion: false,
             average_generations: 0.0,
             total_reproductions: 0,
             total_deaths: 0,
             simulation_quality_score: 0.0,
+            bottleneck_occurred: false,
+            post_bottleneck_diversity: 0.0,
+            gene_diversity_recovery_steps: None,
+            starvation_warning: false,
+            starvation_warning_step: None,
+            season_peak_agent_count: simulation.agent_count(),
+            season_trough_agent_count: simulation.agent_count(),
+            season_cycles_completed: simulation.season_cycles_completed(),
+            death_resource_count: 0,
+            generation_distribution: Vec::new(),
+            max_living_generation: 0,
+            modal_generation: 0,
+            generation_pyramid_history: Vec::new(),
+            gene_boxplot_history: HashMap::new(),
+            spatial_grid_cell_size_history: Vec::new(),
+            causes_of_death: HashMap::new(),
+            resource_cluster_count: 0,
+            famine_events: Vec::new(),
+            pva_extinction_risk: 0.0,
+            extinction_probability_history: Vec::new(),
+            lv_alpha: 0.0,
+            lv_beta: 0.0,
+            lv_delta: 0.0,
+            lv_gamma: 0.0,
+            lv_extinction_predicted: false,
+            rescue_event_count: 0,
+            total_poison_deaths: 0,
         };
 
-        // Calculate history interval based on speed multiplier
-        // Record history every 60 steps (1 second at 60 FPS) adjusted for speed
-        let history_interval = (60.0 / config.speed_multiplier).max(1.0) as usize;
+        let history_interval = history_interval(&config);
 
-        Self {
+        Ok(Self {
+            spatial_cell_size: simulation.spatial_cell_size(),
             simulation,
             config,
             diagnostics,
@@ -128,9 +610,93 @@ impl HeadlessSimulationV2 {
             start_time: Instant::now(),
             history_interval,
             _last_stats_time: 0.0,
+            pre_bottleneck_diversity: 0.0,
+            checkpoints: Vec::new(),
+            consecutive_high_pressure_steps: 0,
+            consecutive_collapse_pressure_steps: 0,
+            consecutive_rescue_events: 0,
+            steps_since_rescue: 0,
+            pending_commands: VecDeque::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            csv_writer: None,
+        })
+    }
+
+    /// A run that applies each of `commands` once it reaches the step the
+    /// command was logged at, reproducing a `UnifiedSimulation::command_log`.
+    pub fn from_replay(
+        config: HeadlessSimulationConfig,
+        commands: &[(usize, SimulationCommand)],
+    ) -> Self {
+        let mut simulation = Self::new(config);
+        simulation.pending_commands = commands.iter().cloned().collect();
+        simulation.apply_due_commands();
+        simulation
+    }
+
+    fn apply_due_commands(&mut self) {
+        while let Some((step, _)) = self.pending_commands.front() {
+            if *step > self.simulation.current_step() {
+                break;
+            }
+            let (_, command) = self.pending_commands.pop_front().unwrap();
+            // A command that failed when it was logged wouldn't be in the log
+            let _ = self.simulation.apply_command(command);
         }
     }
 
+    /// Resumes a run from `checkpoint`, keeping its step count, diagnostics
+    /// and elapsed time.
+    pub fn from_checkpoint(checkpoint: SimulationCheckpoint) -> Self {
+        let snapshot: SimulationSnapshot = serde_json::from_str(&checkpoint.state)
+            .expect("checkpoint state is a serialized SimulationSnapshot");
+        let config = checkpoint.diagnostics.config.clone();
+        let history_interval = history_interval(&config);
+        let now = Instant::now();
+        let start_time = now
+            .checked_sub(Duration::from_secs_f64(checkpoint.start_time_offset))
+            .unwrap_or(now);
+
+        let simulation = UnifiedSimulation::from_snapshot(snapshot)
+            .expect("checkpoint state was written by this version");
+
+        Self {
+            spatial_cell_size: simulation.spatial_cell_size(),
+            simulation,
+            config,
+            diagnostics: checkpoint.diagnostics,
+            step_count: checkpoint.step_count,
+            start_time,
+            history_interval,
+            _last_stats_time: 0.0,
+            pre_bottleneck_diversity: checkpoint.pre_bottleneck_diversity,
+            checkpoints: Vec::new(),
+            consecutive_high_pressure_steps: 0,
+            consecutive_collapse_pressure_steps: 0,
+            consecutive_rescue_events: 0,
+            steps_since_rescue: 0,
+            pending_commands: VecDeque::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            csv_writer: None,
+        }
+    }
+
+    pub fn checkpoint(&self) -> SimulationCheckpoint {
+        SimulationCheckpoint {
+            state: serde_json::to_string(&self.simulation.snapshot())
+                .expect("simulation snapshot serializes to JSON"),
+            step_count: self.step_count,
+            diagnostics: self.diagnostics.clone(),
+            start_time_offset: self.start_time.elapsed().as_secs_f64(),
+            pre_bottleneck_diversity: self.pre_bottleneck_diversity,
+        }
+    }
+
+    /// The checkpoint `run` recorded at exactly `step`, if any.
+    pub fn get_checkpoint(&self, step: usize) -> Option<&SimulationCheckpoint> {
+        self.checkpoints.iter().find(|cp| cp.step_count == step)
+    }
+
     pub fn run(&mut self) -> SimulationDiagnostics {
         let target_steps = (self.config.target_duration_minutes * 60.0 * 60.0 * self.config.speed_multiplier) as usize;
 
@@ -138,10 +704,16 @@ impl HeadlessSimulationV2 {
         println!("Target duration: {:.1} minutes", self.config.target_duration_minutes);
         println!("Target steps: {}", target_steps);
         println!("Using {} engine", if self.config.use_ecs { "ECS" } else { "Legacy" });
+        #[cfg(not(target_arch = "wasm32"))]
+        self.open_csv_output();
 
         while self.step_count < target_steps {
             self.step();
 
+            if self.step_count.is_multiple_of(CHECKPOINT_INTERVAL) {
+                self.checkpoints.push(self.checkpoint());
+            }
+
             // Check for early termination conditions
             if self.should_terminate_early() {
                 println!("Early termination at step {}", self.step_count);
@@ -162,9 +734,39 @@ impl HeadlessSimulationV2 {
         self.diagnostics.clone()
     }
 
-    fn step(&mut self) {
+    /// Times `BENCHMARK_STEPS` bare engine updates, without the diagnostics
+    /// `step` records, and leaves the simulation where they end.
+    pub fn run_benchmark(&mut self) -> BenchmarkResult {
+        let start = Instant::now();
+        benchmark_simulation(&mut self.simulation, BENCHMARK_STEPS, || {
+            start.elapsed().as_secs_f64()
+        })
+    }
+
+    pub fn step(&mut self) {
         self.simulation.update();
         self.step_count += 1;
+        self.apply_due_commands();
+
+        if let Some(bottleneck) = self.config.bottleneck.clone() {
+            if self.step_count == bottleneck.at_step {
+                self.apply_bottleneck(&bottleneck);
+            } else if self.diagnostics.bottleneck_occurred
+                && self.diagnostics.gene_diversity_recovery_steps.is_none()
+            {
+                let diversity = gene_diversity(&self.simulation.get_agents());
+                if diversity > self.pre_bottleneck_diversity * 0.8 {
+                    self.diagnostics.gene_diversity_recovery_steps =
+                        Some(self.step_count - bottleneck.at_step);
+                }
+            }
+        }
+
+        self.rescue_if_near_extinction();
+        self.track_resource_pressure();
+        self.track_season();
+        self.diagnostics.death_resource_count = self.simulation.death_resource_count();
+        self.track_spatial_cell_size();
 
         // Record history periodically
         if self.step_count % self.history_interval == 0 {
@@ -173,22 +775,196 @@ impl HeadlessSimulationV2 {
             self.diagnostics.resource_count_history.push(stats.resource_count);
             self.diagnostics.energy_history.push(stats.total_energy);
             self.diagnostics.fitness_history.push(stats.average_fitness);
+            self.diagnostics
+                .perception_error_history
+                .push(stats.average_perception_error);
+            let memory = self.simulation.get_memory_stats();
+            self.diagnostics
+                .memory_usage_history
+                .push(memory.heap_used_bytes as f64);
+            self.diagnostics.speed_history.push(stats.average_speed);
+            self.diagnostics.aggression_history.push(stats.average_aggression);
+            self.diagnostics.generation_history.push(stats.average_generation);
+            #[cfg(not(target_arch = "wasm32"))]
+            self.write_csv_row(CsvRow::from_stats(self.step_count, &stats));
+        }
+
+        if self.step_count.is_multiple_of(PVA_INTERVAL) {
+            let risk = pva_extinction_risk(&self.diagnostics.agent_count_history);
+            self.diagnostics.pva_extinction_risk = risk;
+            self.diagnostics.extinction_probability_history.push(risk);
+        }
+
+        if self.step_count.is_multiple_of(LV_INTERVAL) {
+            self.fit_lotka_volterra();
+        }
+
+        if self.step_count.is_multiple_of(GENE_BOXPLOT_INTERVAL) {
+            for gene in GENE_NAMES {
+                if let Some(boxplot) = self.simulation.gene_percentiles(gene) {
+                    let history = self.diagnostics.gene_boxplot_history.entry(gene.to_string());
+                    history.or_default().push(boxplot);
+                }
+            }
+        }
+
+        if self.step_count.is_multiple_of(GENERATION_PYRAMID_INTERVAL) {
+            let distribution = generation_distribution(&self.simulation.get_agents());
+            self.diagnostics.generation_pyramid_history.push(distribution);
+        }
+    }
+
+    /// Starts `csv_output_path` over with `CSV_HEADER`. A file that can't be
+    /// created is reported and the run goes on without it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_csv_output(&mut self) {
+        let Some(path) = &self.config.csv_output_path else {
+            return;
+        };
+        let opened = File::create(path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            writeln!(writer, "{}", CSV_HEADER)?;
+            Ok(writer)
+        });
+        match opened {
+            Ok(writer) => self.csv_writer = Some(writer),
+            Err(e) => println!("Can't write CSV to {}: {}", path.display(), e),
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_csv_row(&mut self, row: CsvRow) {
+        let Some(writer) = &mut self.csv_writer else {
+            return;
+        };
+        if let Err(e) = writeln!(writer, "{}", row) {
+            println!("Stopped writing CSV: {}", e);
+            self.csv_writer = None;
+        }
+    }
+
+    fn track_spatial_cell_size(&mut self) {
+        let size = self.simulation.spatial_cell_size();
+        if size != self.spatial_cell_size {
+            self.spatial_cell_size = size;
+            self.diagnostics.spatial_grid_cell_size_history.extend(size);
+        }
+    }
+
+    fn track_season(&mut self) {
+        self.diagnostics.season_cycles_completed = self.simulation.season_cycles_completed();
+        let agent_count = self.simulation.agent_count();
+        if self.simulation.season_phase() < 0.5 {
+            self.diagnostics.season_peak_agent_count =
+                self.diagnostics.season_peak_agent_count.max(agent_count);
+        } else {
+            self.diagnostics.season_trough_agent_count =
+                self.diagnostics.season_trough_agent_count.min(agent_count);
+        }
+    }
+
+    fn track_resource_pressure(&mut self) {
+        let resource_pressure = self.simulation.agent_count() as f64
+            / self.simulation.resource_count().max(1) as f64;
+
+        if resource_pressure > STARVATION_PRESSURE {
+            self.consecutive_high_pressure_steps += 1;
+        } else {
+            self.consecutive_high_pressure_steps = 0;
+        }
+        if resource_pressure > COLLAPSE_PRESSURE {
+            self.consecutive_collapse_pressure_steps += 1;
+        } else {
+            self.consecutive_collapse_pressure_steps = 0;
+        }
+
+        if !self.diagnostics.starvation_warning
+            && self.consecutive_high_pressure_steps >= STARVATION_WARNING_STEPS
+        {
+            self.diagnostics.starvation_warning = true;
+            self.diagnostics.starvation_warning_step = Some(self.step_count);
+            println!(
+                "Starvation warning at step {}: {:.1} agents per resource",
+                self.step_count, resource_pressure
+            );
+        }
+    }
+
+    fn apply_bottleneck(&mut self, bottleneck: &BottleneckEvent) {
+        self.pre_bottleneck_diversity = gene_diversity(&self.simulation.get_agents());
+        self.simulation.cull_agents(bottleneck.survival_fraction);
+        self.diagnostics.bottleneck_occurred = true;
+        self.diagnostics.post_bottleneck_diversity =
+            gene_diversity(&self.simulation.get_agents());
+
+        println!(
+            "Bottleneck at step {}: diversity {:.3} -> {:.3}",
+            self.step_count,
+            self.pre_bottleneck_diversity,
+            self.diagnostics.post_bottleneck_diversity
+        );
+    }
+
+    /// Brings in `rescue_agent_count` agents with fresh random genes at
+    /// random positions, modelling immigration from outside the world, when
+    /// rescue is on and the population is below `min_agent_count`.
+    fn rescue_if_near_extinction(&mut self) {
+        if !self.config.rescue_on_near_extinction {
+            return;
+        }
+        if self.simulation.agent_count() >= self.config.min_agent_count {
+            self.steps_since_rescue += 1;
+            if self.steps_since_rescue >= RESCUE_RECOVERY_STEPS {
+                self.consecutive_rescue_events = 0;
+            }
+            return;
+        }
+        if self.consecutive_rescue_events >= MAX_CONSECUTIVE_RESCUES {
+            // Leave the collapse for `should_terminate_early`
+            return;
+        }
+
+        for _ in 0..self.config.rescue_agent_count {
+            let x = self.simulation.rng().gen_range(0.0..self.config.width);
+            let y = self.simulation.rng().gen_range(0.0..self.config.height);
+            self.simulation.add_agent(x, y);
+        }
+        self.diagnostics.rescue_event_count += 1;
+        self.consecutive_rescue_events += 1;
+        self.steps_since_rescue = 0;
+    }
+
     fn should_terminate_early(&self) -> bool {
-        let stats = self.simulation.get_stats();
+        // Sustained resource collapse ends in a long, uninformative extinction
+        if self.consecutive_collapse_pressure_steps >= COLLAPSE_TERMINATION_STEPS {
+            return true;
+        }
 
-        // Check for extinction
-        if stats.agent_count == 0 {
+        // A shrinking remnant is as good as extinct
+        if self.diagnostics.pva_extinction_risk > PVA_TERMINATION_RISK {
             return true;
         }
 
+        let stats = self.simulation.get_stats();
+
         // Check for population explosion
         if stats.agent_count > self.config.max_agent_count {
             return true;
         }
 
+        // A collapse, extinction included, can still be rescued next step
+        let rescue_available = self.config.rescue_on_near_extinction
+            && self.config.rescue_agent_count > 0
+            && self.consecutive_rescue_events < MAX_CONSECUTIVE_RESCUES;
+        if rescue_available {
+            return false;
+        }
+
+        // Check for extinction
+        if stats.agent_count == 0 {
+            return true;
+        }
+
         // Check for population collapse
         if stats.agent_count < self.config.min_agent_count {
             return true;
@@ -202,9 +978,24 @@ impl HeadlessSimulationV2 {
         self.diagnostics.duration_seconds = duration.as_secs_f64();
         self.diagnostics.total_steps = self.step_count;
         self.diagnostics.steps_per_second = self.step_count as f64 / duration.as_secs_f64();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(mut writer) = self.csv_writer.take() {
+            if let Err(e) = writer.flush() {
+                println!("Couldn't finish writing CSV: {}", e);
+            }
+        }
 
         let final_stats = self.simulation.get_stats();
         self.diagnostics.final_stats = final_stats.clone();
+        self.diagnostics.causes_of_death = self
+            .simulation
+            .deaths_by_reason()
+            .into_iter()
+            .map(|(reason, count)| (format!("{:?}", reason), count))
+            .collect();
+        self.diagnostics.resource_cluster_count = self.simulation.resource_cluster_count();
+        self.diagnostics.famine_events = self.simulation.famine_events().to_vec();
+        self.diagnostics.total_poison_deaths = self.simulation.poison_deaths();
 
         // Calculate stability score
         self.diagnostics.stability_score = self.calculate_stability_score();
@@ -218,12 +1009,24 @@ impl HeadlessSimulationV2 {
         self.diagnostics.population_explosion = final_stats.agent_count > self.config.max_agent_count;
 
         // Calculate average generations and reproduction stats
-        let total_generations: u32 = self.simulation.get_agents().iter().map(|a| a.generation).sum();
+        let agents = self.simulation.get_agents();
+        let total_generations: u32 = agents.iter().map(|a| a.generation).sum();
         self.diagnostics.average_generations = if final_stats.agent_count > 0 {
             total_generations as f64 / final_stats.agent_count as f64
         } else {
             0.0
         };
+        self.diagnostics.max_living_generation =
+            agents.iter().map(|a| a.generation).max().unwrap_or(0);
+        let distribution = generation_distribution(&agents);
+        // max_by_key keeps the last of equal counts, so ties go to the earliest generation
+        self.diagnostics.modal_generation = distribution
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, &count)| count)
+            .map_or(0, |(generation, _)| generation as u32);
+        self.diagnostics.generation_distribution = distribution;
 
         // Count total reproductions and deaths (approximate)
         let initial_agents = self.config.initial_agents;
@@ -344,10 +1147,85 @@ impl HeadlessSimulationV2 {
         score.max(0.0).min(1.0)
     }
 
+    /// Pushes population gauges to the Prometheus push gateway at `push_url`.
+    #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+    pub fn enable_metrics(&mut self, push_url: &str) -> Result<(), String> {
+        let recorder = crate::metrics::push_gateway_recorder(push_url)?;
+        self.simulation.set_metrics_recorder(recorder);
+        Ok(())
+    }
+
+    pub fn add_habitat(&mut self, habitat: Habitat) {
+        self.simulation.add_habitat(habitat);
+    }
+
+    pub fn get_interaction_log(&self) -> &[InteractionLog] {
+        self.simulation.get_interaction_log()
+    }
+
+    pub fn apply_command(&mut self, command: SimulationCommand) -> Result<(), String> {
+        self.simulation.apply_command(command)
+    }
+
+    pub fn command_log(&self) -> &[(usize, SimulationCommand)] {
+        self.simulation.command_log()
+    }
+
+    pub fn get_agents(&self) -> Vec<Agent> {
+        self.simulation.get_agents()
+    }
+
+    pub fn famine_events(&self) -> &[(usize, usize)] {
+        self.simulation.famine_events()
+    }
+
     pub fn get_current_stats(&self) -> SimulationStats {
         self.simulation.get_stats()
     }
 
+    /// Current fitness landscape as CSV, with every row tagged with `step`.
+    pub fn export_fitness_landscape_csv(&self, step: usize) -> String {
+        let mut csv = String::from("step,speed,size,aggression,energy_efficiency,fitness\n");
+        for sample in self.simulation.fitness_landscape() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                step,
+                sample.speed,
+                sample.size,
+                sample.aggression,
+                sample.energy_efficiency,
+                sample.fitness
+            ));
+        }
+        csv
+    }
+
+    /// Refits the Lotka-Volterra diagnostics to the last `LV_WINDOW_STEPS`
+    /// steps of history, keeping the previous estimates if that's too little.
+    fn fit_lotka_volterra(&mut self) {
+        let entries = (LV_WINDOW_STEPS / self.history_interval).max(3);
+        let agents = &self.diagnostics.agent_count_history;
+        let resources = &self.diagnostics.resource_count_history;
+        let fit = fit_lotka_volterra(
+            &agents[agents.len().saturating_sub(entries)..],
+            &resources[resources.len().saturating_sub(entries)..],
+            self.history_interval as f64,
+        );
+        if let Some(params) = fit {
+            let (alpha, beta, delta, gamma) = params;
+            self.diagnostics.lv_alpha = alpha;
+            self.diagnostics.lv_beta = beta;
+            self.diagnostics.lv_delta = delta;
+            self.diagnostics.lv_gamma = gamma;
+            self.diagnostics.lv_extinction_predicted = lv_predicts_extinction(params);
+        }
+    }
+
+    /// Latest population viability estimate; see `pva_extinction_risk`.
+    pub fn get_extinction_probability(&self) -> f64 {
+        self.diagnostics.pva_extinction_risk
+    }
+
     pub fn get_diagnostics(&self) -> &SimulationDiagnostics {
         &self.diagnostics
     }
@@ -17,6 +17,7 @@ pub struct HeadlessSimulationConfig {
     pub min_agent_count: usize,
     pub max_agent_count: usize,
     pub use_ecs: bool,
+    pub seed: Option<u64>,
     pub speed_multiplier: f64, // For high-speed evaluation
 }
 
@@ -35,6 +36,7 @@ impl Default for HeadlessSimulationConfig {
             min_agent_count: 10,
             max_agent_count: 3000,
             use_ecs: true,
+            seed: None,
             speed_multiplier: 10.0, // 10x faster than real-time
         }
     }
@@ -55,6 +57,7 @@ impl From<HeadlessSimulationConfig> for SimulationConfig {
             min_agent_count: config.min_agent_count,
             max_agent_count: config.max_agent_count,
             use_ecs: config.use_ecs,
+            seed: config.seed,
         }
     }
 }
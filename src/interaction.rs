@@ -0,0 +1,77 @@
+use serde::Serialize;
+
+/// How many interactions a simulation keeps before dropping the oldest.
+pub const INTERACTION_LOG_CAPACITY: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum InteractionType {
+    Feeding,
+    /// Whether the actor won.
+    Combat(bool),
+    Fleeing,
+    Reproduction,
+}
+
+/// One agent acting on another agent or a resource, for debugging
+/// predator-prey dynamics.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct InteractionLog {
+    pub step: usize,
+    pub actor_id: u64,
+    pub target_id: u64,
+    pub interaction_type: InteractionType,
+    /// Energy the actor gained (negative if lost).
+    pub energy_change: f64,
+}
+
+/// The most recent `INTERACTION_LOG_CAPACITY` interactions. Older entries
+/// are dropped in batches once twice that many pile up, so the rest stay
+/// contiguous.
+#[derive(Clone, Debug, Default)]
+pub struct InteractionLogBuffer {
+    entries: Vec<InteractionLog>,
+}
+
+impl InteractionLogBuffer {
+    pub fn push(&mut self, entry: InteractionLog) {
+        if self.entries.len() == 2 * INTERACTION_LOG_CAPACITY {
+            self.entries.drain(..INTERACTION_LOG_CAPACITY);
+        }
+        self.entries.push(entry);
+    }
+
+    /// Oldest first.
+    pub fn as_slice(&self) -> &[InteractionLog] {
+        let start = self.entries.len().saturating_sub(INTERACTION_LOG_CAPACITY);
+        &self.entries[start..]
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_keeps_most_recent_entries() {
+        let mut buffer = InteractionLogBuffer::default();
+        // Past a batch drop as well as a partly filled batch
+        for step in 0..3 * INTERACTION_LOG_CAPACITY + 5 {
+            buffer.push(InteractionLog {
+                step,
+                actor_id: 1,
+                target_id: 2,
+                interaction_type: InteractionType::Feeding,
+                energy_change: 0.5,
+            });
+        }
+
+        let entries = buffer.as_slice();
+        assert_eq!(entries.len(), INTERACTION_LOG_CAPACITY);
+        assert_eq!(entries[0].step, 2 * INTERACTION_LOG_CAPACITY + 5);
+        assert_eq!(entries.last().unwrap().step, 3 * INTERACTION_LOG_CAPACITY + 4);
+    }
+}
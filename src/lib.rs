@@ -1,24 +1,222 @@
 use wasm_bindgen::prelude::*;
 
 pub mod agent;
+pub mod archetype;
+pub mod biome;
+pub mod boundary;
+pub mod death;
 pub mod ecs;
+pub mod ecs_systems;
+pub mod famine;
+pub mod fitness;
+pub mod frame_pacing;
 pub mod genes;
+pub mod habitat;
 pub mod headless_simulation;
+pub mod interaction;
+pub mod logging;
+#[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+pub mod metrics;
+#[cfg(all(feature = "napi", not(target_arch = "wasm32")))]
+pub mod node_api;
+pub mod optimizer;
+pub mod render_layout;
 pub mod resource;
+pub mod resource_dynamics;
+pub mod rng;
+pub mod scenario;
 pub mod simulation_core;
+pub mod spatial;
+pub mod species;
+pub mod stats;
+pub mod teleporter;
+pub mod test_harness;
+pub mod toxin;
+#[cfg(target_arch = "wasm32")]
 pub mod web_simulation;
+#[cfg(target_arch = "wasm32")]
 pub mod webgl_renderer;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use logging::init_tracing;
+
+/// TypeScript shapes of the `JsValue`s returned to JavaScript, referenced by
+/// `unchecked_return_type` below. Keep in sync with `SimulationStats`,
+/// `WebSimulationStats`, `SimulationStatsDiff`, `InteractionLog`, `FitnessSample`,
+/// `BenchmarkResult`, `MemoryStats`, `RenderStats`, `AgentInfo` and
+/// `ResourceInfo`.
+pub const SIMULATION_STATS_TS: &str = r#"
+export interface SimulationStats {
+    agent_count: number;
+    resource_count: number;
+    total_energy: number;
+    average_age: number;
+    average_speed: number;
+    average_size: number;
+    average_aggression: number;
+    average_sense_range: number;
+    average_energy_efficiency: number;
+    max_generation: number;
+    average_generation: number;
+    total_kills: number;
+    total_pack_kills: number;
+    average_fitness: number;
+    species_count: number;
+    largest_species_size: number;
+    largest_species_fraction: number;
+    average_reproduction_suppression: number;
+    resource_competition_index: number;
+    average_stamina: number;
+    average_mutation_strength: number;
+    age_distribution: number[];
+    average_trophic_level: number;
+    apex_predator_count: number;
+    trophic_diversity: number;
+    total_energy_shared: number;
+    average_predator_satiation: number;
+    average_perception_error: number;
+    average_memory_utilization: number;
+    fps: number;
+    simulation_tps: number;
+}
+
+export interface SimulationStatsDiff {
+    agent_count_delta: number;
+    resource_count_delta: number;
+    energy_delta: number;
+    avg_age_delta: number;
+    avg_speed_delta: number;
+    avg_size_delta: number;
+    avg_aggression_delta: number;
+    avg_sense_range_delta: number;
+    avg_energy_efficiency_delta: number;
+    max_generation_delta: number;
+    avg_generation_delta: number;
+    total_kills_delta: number;
+    total_pack_kills_delta: number;
+    avg_fitness_delta: number;
+    species_count_delta: number;
+    largest_species_size_delta: number;
+    largest_species_fraction_delta: number;
+    avg_reproduction_suppression_delta: number;
+    resource_competition_index_delta: number;
+    avg_stamina_delta: number;
+    avg_mutation_strength_delta: number;
+    age_distribution_delta: number[];
+    avg_trophic_level_delta: number;
+    apex_predator_count_delta: number;
+    trophic_diversity_delta: number;
+    energy_shared_delta: number;
+    avg_predator_satiation_delta: number;
+    avg_perception_error_delta: number;
+    avg_memory_utilization_delta: number;
+    max_normalized_change: number;
+}
+
+export type InteractionType = "Feeding" | "Fleeing" | "Reproduction" | { Combat: boolean };
+
+export interface InteractionLog {
+    step: number;
+    actor_id: number;
+    target_id: number;
+    interaction_type: InteractionType;
+    energy_change: number;
+}
+
+export interface FitnessSample {
+    speed: number;
+    size: number;
+    aggression: number;
+    energy_efficiency: number;
+    fitness: number;
+}
+
+export interface BenchmarkResult {
+    steps_per_second: number;
+    ns_per_step: number;
+    peak_agents: number;
+    peak_resources: number;
+    final_score: number;
+    engine_type: string;
+}
+
+export interface TestSuiteStatistics {
+    mean_score: number;
+    std_dev_score: number;
+    ci_95_low: number;
+    ci_95_high: number;
+    mean_stability: number;
+    mean_steps_per_second: number;
+}
+
+export interface MemoryStats {
+    heap_used_bytes: number;
+    agent_vec_capacity: number;
+    resource_vec_capacity: number;
+    spatial_grid_cells: number;
+    wasm_memory_pages: number;
+    entity_count: number;
+}
+
+export interface GeneBoxplot {
+    p5: number;
+    p25: number;
+    median: number;
+    p75: number;
+    p95: number;
+    mean: number;
+}
+
+export interface RenderStats {
+    rendered_agent_count: number;
+    culled_agent_count: number;
+    max_render_agents: number;
+}
+
+export interface GenesSummary {
+    speed: number;
+    size: number;
+    aggression: number;
+    is_predator: number;
+    intelligence: number;
+}
+
+export interface AgentInfo {
+    id: number;
+    x: number;
+    y: number;
+    energy: number;
+    generation: number;
+    state: string;
+    genes: GenesSummary;
+}
+
+export interface ResourceInfo {
+    id: number;
+    x: number;
+    y: number;
+    energy: number;
+    max_energy: number;
+    resource_type: string;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_CUSTOM_SECTION: &'static str = SIMULATION_STATS_TS;
+
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub struct BattleSimulation {
     web_simulation: web_simulation::WebSimulation,
 }
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 impl BattleSimulation {
     #[wasm_bindgen(constructor)]
     pub fn new(canvas_id: &str) -> Result<BattleSimulation, JsValue> {
         console_error_panic_hook::set_once();
+        logging::init_console_tracing();
 
         let web_simulation = web_simulation::WebSimulation::new(canvas_id)?;
 
@@ -37,10 +235,50 @@ impl BattleSimulation {
         self.web_simulation.step();
     }
 
+    /// Simulation steps per animation frame, 1-5 (1 is real time).
+    pub fn set_speed(&mut self, steps_per_frame: usize) {
+        self.web_simulation.set_speed(steps_per_frame);
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SimulationStats")]
     pub fn get_stats(&self) -> JsValue {
         self.web_simulation.get_stats()
     }
 
+    pub fn get_fps(&self) -> f64 {
+        self.web_simulation.get_fps()
+    }
+
+    pub fn get_simulation_tps(&self) -> f64 {
+        self.web_simulation.get_simulation_tps()
+    }
+
+    pub fn get_species_count(&self) -> usize {
+        self.web_simulation.get_species_count()
+    }
+
+    /// Deaths per cause, e.g. `{Starvation: 12, OldAge: 3}`, over the last
+    /// `max_death_history` deaths. Empty on the ECS engine.
+    #[wasm_bindgen(unchecked_return_type = "Record<string, number>")]
+    pub fn get_deaths_by_reason(&self) -> JsValue {
+        self.web_simulation.get_deaths_by_reason()
+    }
+
+    /// Times 10 000 steps of a copy of the current simulation. Blocks the
+    /// page while it runs.
+    #[wasm_bindgen(unchecked_return_type = "BenchmarkResult")]
+    pub fn run_benchmark(&self) -> JsValue {
+        self.web_simulation.run_benchmark()
+    }
+
+    /// Times `n` runs of 1 000 steps on copies of the current simulation and
+    /// returns the mean score with its 95% confidence interval. Blocks the
+    /// page while it runs.
+    #[wasm_bindgen(unchecked_return_type = "TestSuiteStatistics")]
+    pub fn run_benchmark_statistics(&self, n: u32) -> JsValue {
+        self.web_simulation.run_benchmark_statistics(n)
+    }
+
     pub fn get_rendering_mode(&self) -> String {
         self.web_simulation.get_rendering_mode()
     }
@@ -57,6 +295,27 @@ impl BattleSimulation {
         self.web_simulation.force_webgl()
     }
 
+    /// Agents drawn and skipped in the last WebGL frame; `undefined` when
+    /// rendering with Canvas 2D.
+    #[wasm_bindgen(unchecked_return_type = "RenderStats | undefined")]
+    pub fn get_render_stats(&self) -> JsValue {
+        self.web_simulation.get_render_stats()
+    }
+
+    /// Approximate heap use, container capacities and wasm memory pages, for
+    /// watching memory grow over a long run.
+    #[wasm_bindgen(unchecked_return_type = "MemoryStats")]
+    pub fn get_memory_stats(&self) -> JsValue {
+        self.web_simulation.get_memory_stats()
+    }
+
+    /// Nanoseconds the GPU spends per frame, averaged over recent frames.
+    /// 0 when the browser offers no `EXT_disjoint_timer_query`, so compare
+    /// against `get_simulation_tps` only when it is positive.
+    pub fn get_gpu_timing(&self) -> f64 {
+        self.web_simulation.get_gpu_timing()
+    }
+
     pub fn add_agent(&mut self, x: f64, y: f64) {
         self.web_simulation.add_agent(x, y);
     }
@@ -69,18 +328,224 @@ impl BattleSimulation {
         self.web_simulation.reset();
     }
 
+    pub fn find_agent_at(&mut self, x: f64, y: f64, radius: f64) -> Option<u64> {
+        self.web_simulation.find_agent_at(x, y, radius)
+    }
+
+    /// The agent nearest to (x, y) within `radius`, for inspecting it
+    /// without selecting it; `null` if there is none.
+    #[wasm_bindgen(unchecked_return_type = "AgentInfo | null")]
+    pub fn get_agent_at(&self, x: f64, y: f64, radius: f64) -> JsValue {
+        self.web_simulation.get_agent_at(x, y, radius)
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "ResourceInfo | null")]
+    pub fn get_resource_at(&self, x: f64, y: f64, radius: f64) -> JsValue {
+        self.web_simulation.get_resource_at(x, y, radius)
+    }
+
+    pub fn get_selected_agent_id(&self) -> Option<u64> {
+        self.web_simulation.get_selected_agent_id()
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.web_simulation.clear_selection();
+    }
+
+    /// Starts a rectangular selection at (x, y); drag with `update_lasso`.
+    pub fn start_lasso(&mut self, x: f64, y: f64) {
+        self.web_simulation.start_lasso(x, y);
+    }
+
+    pub fn update_lasso(&mut self, x: f64, y: f64) {
+        self.web_simulation.update_lasso(x, y);
+    }
+
+    /// Selects every agent inside the lasso, replacing the previous lasso
+    /// selection, and returns their IDs.
+    #[wasm_bindgen(unchecked_return_type = "number[]")]
+    pub fn end_lasso(&mut self) -> JsValue {
+        self.web_simulation.end_lasso()
+    }
+
+    /// Full snapshots of the agents picked by the last lasso that are still
+    /// alive.
+    pub fn get_selected_agents(&self) -> JsValue {
+        self.web_simulation.get_selected_agents()
+    }
+
+    /// The agent with ECS stable ID `stable_id`, or `undefined` once it has
+    /// died. IDs survive world resets and JSON round trips.
+    pub fn find_agent_by_id(&self, stable_id: u64) -> JsValue {
+        self.web_simulation.find_agent_by_id(stable_id)
+    }
+
+    pub fn toggle_connections(&mut self) -> bool {
+        self.web_simulation.toggle_connections()
+    }
+
+    /// Pulses agents born with a fresh gene mutation for their first 5
+    /// seconds. Returns whether the highlight is now on. WebGL1 rendering only.
+    pub fn toggle_mutation_display(&mut self) -> bool {
+        self.web_simulation.toggle_mutation_display()
+    }
+
+    /// Colours agents by trophic level: blue for resource eaters, orange for
+    /// predators of those, red for level 3 and crimson above. Returns whether
+    /// trophic colouring is now on. WebGL rendering only.
+    pub fn toggle_trophic_display(&mut self) -> bool {
+        self.web_simulation.toggle_trophic_display()
+    }
+
+    /// Counts of agents across `buckets` equal slices of a gene's range, e.g.
+    /// `get_gene_histogram("speed", 10)`. Empty for unknown gene names.
+    pub fn get_gene_histogram(&self, gene_name: &str, buckets: usize) -> Vec<u32> {
+        self.web_simulation.get_gene_histogram(gene_name, buckets)
+    }
+
+    /// Shows a histogram of `gene_name` in the top-right corner, refreshed
+    /// every 120 frames. Returns false and hides it for unknown gene names.
+    pub fn set_histogram_gene(&mut self, gene_name: &str) -> bool {
+        self.web_simulation.set_histogram_gene(gene_name)
+    }
+
+    /// `{p5, p25, median, p75, p95, mean}` of a gene across the population,
+    /// e.g. `get_gene_boxplot("speed")`. Null for unknown gene names.
+    pub fn get_gene_boxplot(&self, gene_name: &str) -> JsValue {
+        self.web_simulation.get_gene_boxplot(gene_name)
+    }
+
+    /// Shows a box-and-whisker plot of `gene_name` in the top-left corner, or
+    /// hides it if it's already shown. Returns whether it's now shown.
+    pub fn toggle_boxplot(&mut self, gene_name: &str) -> bool {
+        self.web_simulation.toggle_boxplot(gene_name)
+    }
+
+    pub fn add_habitat(&mut self, x: f64, y: f64, radius: f64, capacity: usize) {
+        self.web_simulation.add_habitat(x, y, radius, capacity);
+    }
+
+    /// Adds a worm-hole: agents coming within `radius` of (from_x, from_y)
+    /// reappear next to (to_x, to_y).
+    pub fn add_teleporter(&mut self, from_x: f64, from_y: f64, to_x: f64, to_y: f64, radius: f64) {
+        self.web_simulation
+            .add_teleporter(from_x, from_y, to_x, to_y, radius);
+    }
+
+    pub fn get_season_phase(&self) -> f64 {
+        self.web_simulation.get_season_phase()
+    }
+
+    /// Stops new resources spawning for the next `steps` simulation steps.
+    pub fn trigger_famine(&mut self, steps: usize) {
+        self.web_simulation.trigger_famine(steps);
+    }
+
+    pub fn is_famine_active(&self) -> bool {
+        self.web_simulation.is_famine_active()
+    }
+
+    /// Recent agent interactions; empty unless the config enables
+    /// `log_interactions`.
+    #[wasm_bindgen(unchecked_return_type = "InteractionLog[]")]
+    pub fn get_interaction_log_json(&self) -> JsValue {
+        self.web_simulation.get_interaction_log_json()
+    }
+
+    /// Recent resource consumption per 50px cell, row by row from the top.
+    /// Empty when the ECS engine is running.
+    pub fn get_energy_heatmap_flat(&self) -> Vec<f64> {
+        self.web_simulation.get_energy_heatmap_flat()
+    }
+
+    /// Toxin left by recent kills per 50px cell, row by row from the top.
+    /// Empty when the ECS engine is running.
+    pub fn get_toxin_grid(&self) -> Vec<f32> {
+        self.web_simulation.get_toxin_grid()
+    }
+
+    /// Living agents per generation, indexed by generation; generations past
+    /// 100 are counted in the last slot.
+    pub fn get_generation_distribution(&self) -> Vec<u32> {
+        self.web_simulation.get_generation_distribution()
+    }
+
+    /// Change in the stats since the previous call (since an empty world on
+    /// the first call). Skip redraws while `max_normalized_change` is small.
+    #[wasm_bindgen(unchecked_return_type = "SimulationStatsDiff")]
+    pub fn get_stats_diff(&mut self) -> JsValue {
+        self.web_simulation.get_stats_diff()
+    }
+
+    pub fn set_boundary_behavior(&mut self, mode: &str) -> bool {
+        self.web_simulation.set_boundary_behavior(mode)
+    }
+
+    /// Lays `zones`, an array of `{rect: [x, y, width, height],
+    /// resource_spawn_multiplier, energy_multiplier, name}`, over the world.
+    pub fn set_biome_zones(&mut self, zones: JsValue) -> bool {
+        self.web_simulation.set_biome_zones(zones)
+    }
+
+    /// Everything needed to resume this run later with `import_snapshot`.
+    pub fn export_snapshot(&self) -> JsValue {
+        self.web_simulation.export_snapshot()
+    }
+
+    pub fn import_snapshot(&mut self, data: JsValue) -> bool {
+        self.web_simulation.import_snapshot(data)
+    }
+
+    /// Stats every simulated second since the simulation last started over,
+    /// as CSV with the columns `step,agents,resources,total_energy,
+    /// avg_fitness,avg_speed,avg_aggression,avg_generation`.
+    pub fn export_csv(&self) -> String {
+        self.web_simulation.export_csv()
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "FitnessSample[]")]
+    pub fn get_fitness_landscape(&self) -> JsValue {
+        self.web_simulation.get_fitness_landscape()
+    }
+
+    /// Changes config fields mid-run, e.g. `{"resource_spawn_rate": 0.5}`.
+    pub fn update_config(&mut self, json: &str) -> bool {
+        self.web_simulation.update_config(json)
+    }
+
+    /// Applies a logged `SimulationCommand`, e.g. `{"AddAgent": {"x": 10, "y": 20}}`
+    /// or `"Reset"`.
+    pub fn apply_command_json(&mut self, json: &str) -> bool {
+        self.web_simulation.apply_command_json(json)
+    }
+
+    pub fn load_scenario(&mut self, json: &str) -> Result<(), JsValue> {
+        self.web_simulation.load_scenario(json)
+    }
+
+    pub fn load_builtin_scenario(&mut self, name: &str) -> Result<(), JsValue> {
+        self.web_simulation.load_builtin_scenario(name)
+    }
+
+    /// Restarts with `json`, a list of `{genes, initial_energy, count,
+    /// position_distribution}` founder groups, in place of random agents.
+    pub fn load_archetypes(&mut self, json: &str) -> bool {
+        self.web_simulation.load_archetypes(json)
+    }
+
     pub fn animate(&mut self) {
         self.web_simulation.animate();
     }
 }
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
-#[wasm_bindgen]
 // Removed init_rayon_pool function - using ParallelProcessor instead
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub struct ParallelProcessor {
     initialized: bool,
@@ -89,6 +554,7 @@ pub struct ParallelProcessor {
     _closure: Option<Closure<dyn FnMut(JsValue)>>,
 }
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 impl ParallelProcessor {
     #[wasm_bindgen(constructor)]
@@ -101,23 +567,18 @@ impl ParallelProcessor {
         }
     }
 
+    #[wasm_bindgen(unchecked_return_type = "Promise<void>")]
     pub fn initialize(&mut self) -> js_sys::Promise {
         #[cfg(all(target_arch = "wasm32", feature = "wasm-bindgen-rayon"))]
         {
             #[cfg(all(target_arch = "wasm32", feature = "wasm-bindgen-rayon"))]
             use wasm_bindgen_rayon::init_thread_pool;
 
-            web_sys::console::log_1(
-                &format!(
-                    "Starting Rayon initialization with {} workers",
-                    self.worker_count
-                )
-                .into(),
-            );
+            tracing::info!("Starting Rayon initialization with {} workers", self.worker_count);
 
             // Check if already initialized
             if self.initialized {
-                web_sys::console::warn_1(&"Thread pool already initialized".into());
+                tracing::warn!("Thread pool already initialized");
                 // ecs_simulation::EcsSimulation::set_rayon_initialized(true); // This line was removed
                 return js_sys::Promise::resolve(&wasm_bindgen::JsValue::NULL);
             }
@@ -125,21 +586,16 @@ impl ParallelProcessor {
             {
                 let worker_count = self.worker_count;
                 let closure = Closure::wrap(Box::new(move |result: JsValue| {
-                    web_sys::console::log_1(
-                        &format!("Rayon initialization callback received: {:?}", result).into(),
-                    );
+                    tracing::debug!("Rayon initialization callback received: {:?}", result);
                     match result.as_f64() {
                         Some(_) => {
                             // ecs_simulation::EcsSimulation::set_rayon_initialized(true); // This line was removed
-                            web_sys::console::log_1(
-                                &format!("Thread pool initialized with {} workers", worker_count)
-                                    .into(),
-                            );
+                            tracing::info!("Rayon initialized with {} workers", worker_count);
                         }
                         None => {
                             // ecs_simulation::EcsSimulation::set_rayon_initialized(false); // This line was removed
-                            web_sys::console::log_1(
-                                &format!("Failed to initialize thread pool - SharedArrayBuffer may not be available").into(),
+                            tracing::warn!(
+                                "Failed to initialize thread pool - SharedArrayBuffer may not be available"
                             );
                         }
                     }
@@ -172,7 +628,7 @@ impl ParallelProcessor {
 
     pub fn initialize_fallback(&mut self) -> js_sys::Promise {
         // Fallback initialization that works without SharedArrayBuffer
-        web_sys::console::warn_1(&"Using fallback mode - SharedArrayBuffer not available".into());
+        tracing::warn!("Using fallback mode - SharedArrayBuffer not available");
         self.initialized = true;
         // ecs_simulation::EcsSimulation::set_rayon_initialized(false); // This line was removed
         let promise = js_sys::Promise::resolve(&wasm_bindgen::JsValue::NULL);
@@ -181,7 +637,7 @@ impl ParallelProcessor {
 
     pub fn parallel_sum(&self, data: Vec<f64>) -> f64 {
         if !self.initialized {
-            web_sys::console::warn_1(&"Thread pool not initialized, using sequential".into());
+            tracing::warn!("Thread pool not initialized, using sequential");
             return data.iter().sum();
         }
 
@@ -282,4 +738,847 @@ mod tests {
 
         println!("Headless Simulation V2 test passed!");
     }
+
+    #[test]
+    fn test_headless_bottleneck_recovery() {
+        use crate::headless_simulation::{
+            BottleneckEvent, HeadlessSimulationConfig, HeadlessSimulationV2,
+        };
+
+        let config = HeadlessSimulationConfig {
+            target_duration_minutes: 3000.0 / 3600.0, // 3000 steps at 1x speed
+            speed_multiplier: 1.0,
+            min_agent_count: 0,
+            use_ecs: true,
+            bottleneck: Some(BottleneckEvent {
+                at_step: 1000,
+                survival_fraction: 0.1,
+            }),
+            ..Default::default()
+        };
+
+        let mut simulation = HeadlessSimulationV2::new(config);
+        let diagnostics = simulation.run();
+
+        assert!(diagnostics.bottleneck_occurred);
+        assert!(diagnostics.post_bottleneck_diversity > 0.0);
+        assert!(diagnostics.gene_diversity_recovery_steps.is_some());
+    }
+
+    #[test]
+    fn test_rescue_keeps_a_collapsed_run_going() {
+        use crate::headless_simulation::{
+            BottleneckEvent, HeadlessSimulationConfig, HeadlessSimulationV2,
+        };
+
+        // Culling 95% of 100 agents leaves 5, below the minimum of 10
+        let config = HeadlessSimulationConfig {
+            target_duration_minutes: 300.0 / 3600.0,
+            speed_multiplier: 1.0,
+            initial_agents: 100,
+            min_agent_count: 10,
+            use_ecs: false,
+            seed: Some(3),
+            bottleneck: Some(BottleneckEvent {
+                at_step: 100,
+                survival_fraction: 0.05,
+            }),
+            ..Default::default()
+        };
+
+        let collapsed = HeadlessSimulationV2::new(config.clone()).run();
+        assert_eq!(collapsed.total_steps, 100);
+        assert_eq!(collapsed.rescue_event_count, 0);
+
+        let rescued = HeadlessSimulationV2::new(HeadlessSimulationConfig {
+            rescue_on_near_extinction: true,
+            rescue_agent_count: 40,
+            ..config
+        })
+        .run();
+        assert_eq!(rescued.total_steps, 300);
+        assert_eq!(rescued.rescue_event_count, 1);
+        assert!(rescued.final_stats.agent_count >= 40, "{}", rescued.final_stats.agent_count);
+    }
+
+    #[test]
+    fn test_headless_checkpoint_resume() {
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        let config = HeadlessSimulationConfig {
+            initial_agents: 200,
+            use_ecs: false,
+            ..Default::default()
+        };
+
+        let mut original = HeadlessSimulationV2::new(config);
+        for _ in 0..500 {
+            original.step();
+        }
+        let checkpoint = original.checkpoint();
+        assert_eq!(checkpoint.step_count, 500);
+
+        let mut resumed = HeadlessSimulationV2::from_checkpoint(checkpoint);
+        for _ in 0..500 {
+            original.step();
+            resumed.step();
+        }
+
+        let expected = original.get_current_stats().agent_count as f64;
+        let actual = resumed.get_current_stats().agent_count as f64;
+        assert!(expected > 0.0);
+        assert!((actual - expected).abs() <= expected * 0.05);
+    }
+
+    #[test]
+    fn test_headless_csv_export_matches_history() {
+        use crate::headless_simulation::{
+            HeadlessSimulationConfig, HeadlessSimulationV2, CSV_HEADER,
+        };
+
+        let path = std::env::temp_dir().join(format!("battleo-{}.csv", std::process::id()));
+        let config = HeadlessSimulationConfig {
+            target_duration_minutes: 0.02,
+            initial_agents: 100,
+            use_ecs: false,
+            csv_output_path: Some(path.clone()),
+            ..Default::default()
+        };
+
+        let diagnostics = HeadlessSimulationV2::new(config).run();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, diagnostics.to_csv_string());
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        // A row every 6 steps at the default 10x speed
+        assert_eq!(lines.len() - 1, diagnostics.total_steps / 6);
+        for (index, line) in lines[1..].iter().enumerate() {
+            let row: Vec<&str> = line.split(',').collect();
+            assert_eq!(row.len(), 8);
+            assert_eq!(row[0], ((index + 1) * 6).to_string());
+            assert_eq!(row[1], diagnostics.agent_count_history[index].to_string());
+            assert_eq!(row[2], diagnostics.resource_count_history[index].to_string());
+            let energy: f64 = row[3].parse().unwrap();
+            assert_eq!(energy, diagnostics.energy_history[index]);
+        }
+    }
+
+    #[test]
+    fn test_headless_starvation_warning() {
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        // Twenty agents per resource with no room for more resources
+        let config = HeadlessSimulationConfig {
+            target_duration_minutes: 1.0,
+            speed_multiplier: 1.0,
+            initial_agents: 200,
+            initial_resources: 10,
+            max_resources: 10,
+            min_agent_count: 0,
+            use_ecs: false,
+            ..Default::default()
+        };
+
+        let mut simulation = HeadlessSimulationV2::new(config);
+        let diagnostics = simulation.run();
+
+        assert!(diagnostics.starvation_warning);
+        let warning_step = diagnostics.starvation_warning_step.unwrap();
+        assert!(warning_step < diagnostics.total_steps);
+        assert!(!diagnostics.extinction_occurred);
+        // Terminated early instead of running the full 3600 steps
+        assert!(diagnostics.total_steps < 3600);
+    }
+
+    #[test]
+    fn test_pva_flags_declining_population() {
+        use crate::headless_simulation::pva_extinction_risk;
+
+        // Shrinking 9% per history entry from 1000 agents
+        let declining: Vec<usize> = (0..50)
+            .map(|i| (1000.0 * 0.91f64.powi(i)).round() as usize)
+            .collect();
+        let risk = pva_extinction_risk(&declining);
+        assert!(risk > 0.99, "risk {}", risk);
+
+        // Logistic growth towards 500 agents
+        let growing: Vec<usize> = (0..50)
+            .map(|i| (500.0 / (1.0 + 49.0 * (-0.2 * i as f64).exp())).round() as usize)
+            .collect();
+        assert_eq!(pva_extinction_risk(&growing), 0.0);
+        assert_eq!(pva_extinction_risk(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_lotka_volterra_fit_recovers_oscillation_parameters() {
+        use crate::headless_simulation::{fit_lotka_volterra, lv_predicts_extinction};
+
+        let (alpha, beta, delta, gamma): (f64, f64, f64, f64) = (0.2, 0.001, 0.0003, 0.3);
+        let (prey_equilibrium, predator_equilibrium) = (gamma / delta, alpha / beta);
+        // Small oscillations about the coexistence point are sinusoidal in
+        // the log populations, a quarter period apart
+        let omega = (alpha * gamma).sqrt();
+        let amplitude: f64 = 0.1;
+        let prey: Vec<usize> = (0..60)
+            .map(|t| prey_equilibrium * (amplitude * (omega * t as f64).cos()).exp())
+            .map(|m| m.round() as usize)
+            .collect();
+        let predators: Vec<usize> = (0..60)
+            .map(|t| {
+                let phase = amplitude * omega / alpha * (omega * t as f64).sin();
+                (predator_equilibrium * phase.exp()).round() as usize
+            })
+            .collect();
+
+        let fit = fit_lotka_volterra(&predators, &prey, 1.0).unwrap();
+        let expected = [alpha, beta, delta, gamma];
+        for (estimate, truth) in [fit.0, fit.1, fit.2, fit.3].into_iter().zip(expected) {
+            assert!((estimate - truth).abs() < 0.2 * truth, "{:?}", fit);
+        }
+        assert!(!lv_predicts_extinction(fit));
+        assert!(lv_predicts_extinction((0.2, 0.001, -0.0003, 0.3)));
+        assert_eq!(fit_lotka_volterra(&[0, 0, 0], &prey[..3], 1.0), None);
+    }
+
+    #[test]
+    fn test_replaying_the_command_log_reproduces_the_run() {
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+        use crate::simulation_core::{SimulationCommand, SimulationConfigDelta};
+
+        // Seeded, so a newborn's chance of an early death plays out alike
+        let config = HeadlessSimulationConfig {
+            initial_agents: 0,
+            min_agent_count: 0,
+            use_ecs: false,
+            seed: Some(3),
+            ..Default::default()
+        };
+
+        let mut original = HeadlessSimulationV2::new(config.clone());
+        original.apply_command(SimulationCommand::AddAgent { x: 100.0, y: 100.0 }).unwrap();
+        for _ in 0..5 {
+            original.step();
+        }
+        let delta = SimulationConfigDelta {
+            resource_spawn_rate: Some(0.5),
+            ..Default::default()
+        };
+        original.apply_command(SimulationCommand::SetConfig { delta }).unwrap();
+        for _ in 0..5 {
+            original.step();
+        }
+        original.apply_command(SimulationCommand::TriggerFamine { steps: 20 }).unwrap();
+        for _ in 0..5 {
+            original.step();
+        }
+
+        let log = original.command_log().to_vec();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.iter().map(|(step, _)| *step).collect::<Vec<_>>(), vec![0, 5, 10]);
+
+        let mut replay = HeadlessSimulationV2::from_replay(config, &log);
+        for _ in 0..15 {
+            replay.step();
+        }
+
+        assert_eq!(replay.command_log(), original.command_log());
+        let (before, after) = (original.get_current_stats(), replay.get_current_stats());
+        assert_eq!(after.agent_count, before.agent_count);
+        assert_eq!(after.agent_count, 1);
+        assert_eq!(replay.famine_events(), original.famine_events());
+        assert_eq!(replay.famine_events(), &[(10, 20)]);
+    }
+
+    #[test]
+    fn test_seeded_runs_repeat_exactly() {
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        for use_ecs in [false, true] {
+            let config = HeadlessSimulationConfig {
+                target_duration_minutes: 300.0 / 3600.0,
+                speed_multiplier: 1.0,
+                initial_agents: 100,
+                initial_resources: 200,
+                use_ecs,
+                seed: Some(17),
+                ..Default::default()
+            };
+            let first = HeadlessSimulationV2::new(config.clone()).run();
+            let second = HeadlessSimulationV2::new(config.clone()).run();
+            assert_eq!(first.total_steps, second.total_steps);
+            assert_eq!(first.agent_count_history, second.agent_count_history);
+            assert_eq!(first.resource_count_history, second.resource_count_history);
+            assert_eq!(first.energy_history, second.energy_history);
+            assert_eq!(first.total_reproductions, second.total_reproductions);
+            assert_eq!(first.total_deaths, second.total_deaths);
+
+            let reseeded = HeadlessSimulationV2::new(HeadlessSimulationConfig {
+                seed: Some(18),
+                ..config
+            })
+            .run();
+            assert_ne!(first.energy_history, reseeded.energy_history);
+        }
+    }
+
+    #[test]
+    fn test_rich_biome_grows_more_resource_energy() {
+        use crate::biome::{BiomeMap, BiomeZone};
+        use crate::simulation_core::{SimulationConfig, UnifiedSimulation};
+
+        let zone = |left: f64, multiplier: f64, name: &str| BiomeZone {
+            rect: (left, 0.0, 500.0, 800.0),
+            resource_spawn_multiplier: multiplier,
+            energy_multiplier: multiplier,
+            name: name.to_string(),
+        };
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                use_ecs,
+                biome_map: Some(BiomeMap::new(vec![
+                    zone(0.0, 2.0, "meadow"),
+                    zone(500.0, 0.5, "scrub"),
+                ])),
+                ..Default::default()
+            });
+            for _ in 0..500 {
+                simulation.update();
+            }
+
+            // Mean over grown resources, as agents graze and leave carcasses
+            // wherever they roam
+            let resources = simulation.get_resources();
+            let mean_energy = |rich: bool| {
+                let energies: Vec<f64> = resources
+                    .iter()
+                    .filter(|r| !r.is_carcass && (r.x < 500.0) == rich)
+                    .map(|r| r.energy)
+                    .collect();
+                energies.iter().sum::<f64>() / energies.len() as f64
+            };
+            let (meadow, scrub) = (mean_energy(true), mean_energy(false));
+            assert!(meadow > 2.0 * scrub, "ecs {}: {} vs {}", use_ecs, meadow, scrub);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore_round_trip() {
+        use crate::simulation_core::{
+            SimulationConfig, SimulationError, UnifiedSimulation, SNAPSHOT_VERSION,
+        };
+
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 30,
+                use_ecs,
+                ..Default::default()
+            });
+            for _ in 0..100 {
+                simulation.update();
+            }
+            let snapshot = simulation.snapshot();
+            let json = serde_json::to_value(&snapshot).unwrap();
+
+            let mut restored = UnifiedSimulation::new(SimulationConfig::default());
+            restored.restore(snapshot.clone()).unwrap();
+            assert_eq!(serde_json::to_value(restored.snapshot()).unwrap(), json);
+            assert_eq!(
+                serde_json::to_value(restored.get_stats()).unwrap(),
+                serde_json::to_value(simulation.get_stats()).unwrap(),
+                "ecs {}",
+                use_ecs
+            );
+
+            let mut stale = snapshot;
+            stale.version = SNAPSHOT_VERSION + 1;
+            assert!(matches!(
+                restored.restore(stale),
+                Err(SimulationError::SnapshotVersionMismatch { .. })
+            ));
+            assert_eq!(serde_json::to_value(restored.snapshot()).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_poison_kills_weak_agents_on_both_engines() {
+        use crate::agent::Agent;
+        use crate::genes::Genes;
+        use crate::resource::Resource;
+        use crate::simulation_core::{
+            SimulationConfig, SimulationSnapshot, UnifiedSimulation, SNAPSHOT_VERSION,
+        };
+
+        for use_ecs in [false, true] {
+            let config = SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                use_ecs,
+                ..Default::default()
+            };
+            let agent = |x: f64, defense: f64, energy: f64| {
+                let genes = Genes {
+                    defense,
+                    ..Genes::new()
+                };
+                let mut agent = Agent::new(x, 300.0, genes, 1);
+                agent.energy = energy;
+                agent
+            };
+            let poison = |x: f64| {
+                let mut resource = Resource::new(x, 300.0);
+                resource.make_poisonous();
+                resource.energy = 30.0;
+                resource.spawn_fade = 1.0;
+                resource.is_spawning = false;
+                resource
+            };
+            let mut simulation = UnifiedSimulation::from_snapshot(SimulationSnapshot {
+                version: SNAPSHOT_VERSION,
+                config,
+                agents: vec![agent(100.0, 0.5, 80.0), agent(500.0, 0.0, 10.0)],
+                resources: vec![poison(100.0), poison(500.0)],
+                step: 0,
+                engine: None,
+            })
+            .unwrap();
+
+            simulation.update();
+
+            assert_eq!(simulation.poison_deaths(), 1, "ecs {}", use_ecs);
+            let survivor = simulation
+                .get_agents()
+                .into_iter()
+                .find(|agent| (agent.x - 100.0).abs() < 5.0)
+                .unwrap();
+            assert!((60.0..66.0).contains(&survivor.energy), "{}", survivor.energy);
+        }
+    }
+
+    #[test]
+    fn test_headless_habitat_carrying_capacity() {
+        use crate::habitat::Habitat;
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        let config = HeadlessSimulationConfig {
+            min_agent_count: 0,
+            use_ecs: true,
+            ..Default::default()
+        };
+        let capacity = 150;
+
+        // One habitat covering the whole world confines every birth to it
+        let mut simulation = HeadlessSimulationV2::new(config.clone());
+        simulation.add_habitat(Habitat {
+            x: config.width / 2.0,
+            y: config.height / 2.0,
+            radius: config.width.hypot(config.height),
+            carrying_capacity: capacity,
+        });
+
+        let mut counts = Vec::new();
+        for _ in 0..3000 {
+            simulation.step();
+            counts.push(simulation.get_current_stats().agent_count);
+        }
+
+        assert!(counts.iter().all(|&count| count <= capacity));
+        let settled = &counts[counts.len() - 500..];
+        assert!(settled.iter().all(|&count| count >= capacity * 9 / 10));
+    }
+
+    #[test]
+    fn test_headless_seasonal_resource_spawning() {
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        // Without agents nothing eats, so the resource count follows
+        // spawning alone.
+        let config = HeadlessSimulationConfig {
+            use_ecs: false,
+            initial_agents: 0,
+            initial_resources: 0,
+            min_agent_count: 0,
+            resource_spawn_rate: 6.0,
+            season_period_seconds: 10.0,
+            season_amplitude: 0.9,
+            ..Default::default()
+        };
+        let mut simulation = HeadlessSimulationV2::new(config);
+
+        // Resources spawned in each half season (5 s at 60 steps/s) over three seasons
+        let mut spawned = Vec::new();
+        for _ in 0..6 {
+            let before = simulation.get_current_stats().resource_count;
+            for _ in 0..300 {
+                simulation.step();
+            }
+            spawned.push(simulation.get_current_stats().resource_count - before);
+        }
+
+        for season in spawned.chunks(2) {
+            assert!(season[0] > season[1] * 2, "boom/bust spawns: {:?}", spawned);
+        }
+        let diagnostics = simulation.get_diagnostics();
+        assert!(diagnostics.season_peak_agent_count >= diagnostics.season_trough_agent_count);
+    }
+
+    #[test]
+    fn test_headless_interaction_logging() {
+        use crate::archetype::{AgentArchetype, PositionDistribution};
+        use crate::genes::Genes;
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+        use crate::interaction::InteractionType;
+        use rand::SeedableRng;
+
+        let logged_types = |config: HeadlessSimulationConfig, steps| {
+            let mut simulation = HeadlessSimulationV2::new(config.clone());
+            for _ in 0..steps {
+                simulation.step();
+            }
+
+            let log = simulation.get_interaction_log();
+            assert!(log.iter().all(|entry| (1..=steps).contains(&entry.step)));
+            assert!(log.windows(2).all(|pair| pair[0].step <= pair[1].step));
+
+            let mut silent = HeadlessSimulationV2::new(HeadlessSimulationConfig {
+                log_interactions: false,
+                ..config
+            });
+            for _ in 0..steps {
+                silent.step();
+            }
+            assert!(silent.get_interaction_log().is_empty());
+
+            log.iter().map(|entry| entry.interaction_type).collect::<Vec<_>>()
+        };
+
+        // Legacy agents feed and breed; lean grazers, with a low R*, don't
+        // wait for the patches to regrow
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let grazers = AgentArchetype {
+            genes: Genes {
+                metabolism: 0.1,
+                size: 0.5,
+                reproduction_threshold: 60.0,
+                ..Genes::random(&mut rng)
+            },
+            initial_energy: 80.0,
+            position_distribution: PositionDistribution::Uniform,
+            count: 100,
+        };
+        let legacy = logged_types(
+            HeadlessSimulationConfig {
+                use_ecs: false,
+                agent_archetypes: vec![grazers],
+                initial_resources: 300,
+                min_agent_count: 0,
+                log_interactions: true,
+                seed: Some(11),
+                ..Default::default()
+            },
+            500,
+        );
+        assert!(legacy.contains(&InteractionType::Feeding));
+        assert!(legacy.contains(&InteractionType::Reproduction));
+
+        // ECS predators dropped among prey fight them, the prey flee, and
+        // the reproduction system keeps spawning. Few enough steps that the
+        // strikes don't push the rest out of the log
+        let mut archetype = |is_predator, count| AgentArchetype {
+            genes: Genes {
+                is_predator,
+                omnivore: 0.0,
+                ..Genes::random(&mut rng)
+            },
+            initial_energy: 80.0,
+            position_distribution: PositionDistribution::Fixed { x: 300.0, y: 200.0 },
+            count,
+        };
+        let ecs = logged_types(
+            HeadlessSimulationConfig {
+                use_ecs: true,
+                agent_archetypes: vec![archetype(0.0, 30), archetype(1.0, 5)],
+                initial_resources: 100,
+                min_agent_count: 0,
+                log_interactions: true,
+                seed: Some(11),
+                ..Default::default()
+            },
+            150,
+        );
+        assert!(ecs.iter().any(|kind| matches!(kind, InteractionType::Combat(_))));
+        assert!(ecs.contains(&InteractionType::Fleeing));
+        assert!(ecs.contains(&InteractionType::Reproduction));
+    }
+
+    #[test]
+    fn test_headless_migrants_spread_further() {
+        use crate::archetype::{AgentArchetype, PositionDistribution};
+        use crate::genes::Genes;
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+        use rand::SeedableRng;
+
+        for use_ecs in [false, true] {
+            let archetype = |migration| AgentArchetype {
+                genes: Genes {
+                    is_predator: 0.0,
+                    omnivore: 0.0,
+                    migration,
+                    speed: 10.0,
+                    stamina: 1.0,
+                    reproduction_threshold: f64::INFINITY,
+                    ..Genes::random(&mut rand::rngs::StdRng::seed_from_u64(5))
+                },
+                initial_energy: 100.0,
+                position_distribution: PositionDistribution::Fixed { x: 500.0, y: 400.0 },
+                count: 30,
+            };
+            let mut simulation = HeadlessSimulationV2::new(HeadlessSimulationConfig {
+                use_ecs,
+                agent_archetypes: vec![archetype(1.0), archetype(0.0)],
+                // Births only replace the dead
+                max_agents: 60,
+                initial_resources: 0,
+                min_agent_count: 0,
+                seed: Some(5),
+                ..Default::default()
+            });
+            // Long enough for one migration, MIGRATION_INTERVAL seconds in
+            for _ in 0..3360 {
+                simulation.step();
+            }
+
+            // Mean distance from the start, skipping ECS newborns, which
+            // have random genes
+            let spread = |migration: f64| {
+                let distances: Vec<f64> = simulation
+                    .get_agents()
+                    .iter()
+                    .filter(|agent| agent.genes.migration == migration)
+                    .map(|agent| agent.distance_to(500.0, 400.0))
+                    .collect();
+                distances.iter().sum::<f64>() / distances.len() as f64
+            };
+            let (migrants, residents) = (spread(1.0), spread(0.0));
+            // Residents drift about a unit a second; a migration alone covers
+            // 3 * 10 * MIGRATION_DURATION
+            assert!(
+                migrants > residents * 1.5,
+                "ecs {}: migrants {:.1} vs residents {:.1}",
+                use_ecs,
+                migrants,
+                residents
+            );
+        }
+    }
+
+    #[test]
+    fn test_headless_legacy_agents_breed_with_the_configured_mutations() {
+        use crate::genes::MutationDistribution;
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        let offspring_mutations = |mutation_distribution| {
+            let mut simulation = HeadlessSimulationV2::new(HeadlessSimulationConfig {
+                use_ecs: false,
+                initial_agents: 100,
+                initial_resources: 300,
+                min_agent_count: 0,
+                mutation_distribution,
+                seed: Some(7),
+                ..Default::default()
+            });
+            for _ in 0..300 {
+                simulation.step();
+            }
+            let offspring: Vec<_> =
+                simulation.get_agents().into_iter().filter(|agent| agent.generation > 1).collect();
+            assert!(!offspring.is_empty());
+            offspring.iter().filter(|agent| agent.mutated_genes != 0).count()
+        };
+
+        assert_eq!(offspring_mutations(MutationDistribution::Uniform { range: 1e-9 }), 0);
+        assert!(offspring_mutations(MutationDistribution::Cauchy { scale: 10.0 }) > 0);
+    }
+
+    #[test]
+    fn test_benchmark_runs_both_engines_from_the_same_population() {
+        use crate::headless_simulation::benchmark_simulation;
+        use crate::simulation_core::{SimulationConfig, UnifiedSimulation};
+        use std::time::Instant;
+
+        // Far short of `BENCHMARK_STEPS`, which takes minutes in a debug build
+        const STEPS: usize = 300;
+
+        // The ECS engine spawns its own fixed starting population, so both
+        // engines are restored from one legacy snapshot instead
+        let legacy = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 20,
+            initial_resources: 40,
+            use_ecs: false,
+            ..Default::default()
+        });
+        let snapshot = legacy.snapshot();
+
+        let mut results = Vec::new();
+        for use_ecs in [true, false] {
+            let mut snapshot = snapshot.clone();
+            snapshot.config.use_ecs = use_ecs;
+            let mut simulation = UnifiedSimulation::from_snapshot(snapshot).unwrap();
+            assert_eq!(simulation.agent_count(), 20);
+
+            let start = Instant::now();
+            results.push(benchmark_simulation(&mut simulation, STEPS, || {
+                start.elapsed().as_secs_f64()
+            }));
+        }
+
+        let (ecs, legacy) = (&results[0], &results[1]);
+        assert_eq!(ecs.engine_type, "ECS");
+        assert_eq!(legacy.engine_type, "Legacy");
+        for result in &results {
+            assert!(result.peak_agents >= 20);
+            assert!(result.steps_per_second > 0.0);
+            let seconds = STEPS as f64 / result.steps_per_second;
+            assert!((result.ns_per_step * STEPS as f64 / 1e9 - seconds).abs() < 1e-6);
+            assert!(result.final_score <= result.steps_per_second);
+        }
+        assert!((ecs.compare_to(legacy) * legacy.compare_to(ecs) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_headless_omnivores_outlast_prey_without_food() {
+        use crate::archetype::{AgentArchetype, PositionDistribution};
+        use crate::genes::Genes;
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+        use rand::SeedableRng;
+
+        let base = Genes::random(&mut rand::rngs::StdRng::seed_from_u64(7));
+        let founders = |omnivore: f64| {
+            let mut genes = base.clone();
+            genes.is_predator = 0.0;
+            genes.omnivore = omnivore;
+            genes.sense_range = 100.0;
+            genes.territory_size = 100.0;
+            genes.metabolism = 3.0;
+            genes.energy_efficiency = 0.2;
+            genes.size = 2.5;
+            genes.speed = 2.0;
+            AgentArchetype {
+                genes,
+                initial_energy: 80.0,
+                position_distribution: PositionDistribution::Gaussian {
+                    cx: 500.0,
+                    cy: 400.0,
+                    std: 15.0,
+                },
+                count: 30,
+            }
+        };
+        let config = HeadlessSimulationConfig {
+            max_agents: 60,
+            resource_spawn_rate: 0.0,
+            min_agent_count: 0,
+            use_ecs: true,
+            seed: Some(3),
+            agent_archetypes: vec![founders(0.0), founders(0.9)],
+            ..Default::default()
+        };
+        let mut simulation = HeadlessSimulationV2::new(config);
+        let founders_alive = |simulation: &HeadlessSimulationV2, omnivore: f64| {
+            let agents = simulation.get_agents();
+            agents.iter().filter(|agent| agent.genes.omnivore == omnivore).count()
+        };
+
+        // Well-fed omnivores turn on the prey around them, so they are still
+        // going when the last of the prey starves
+        let mut steps = 0;
+        while founders_alive(&simulation, 0.0) > 0 {
+            assert!(steps < 3000, "prey should starve without food");
+            simulation.step();
+            steps += 1;
+        }
+        assert!(founders_alive(&simulation, 0.9) > 0);
+    }
+
+    #[test]
+    fn test_headless_generation_distribution() {
+        use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+
+        let config = HeadlessSimulationConfig {
+            target_duration_minutes: 0.3,
+            speed_multiplier: 1.0,
+            initial_agents: 40,
+            initial_resources: 80,
+            use_ecs: false,
+            ..Default::default()
+        };
+        let mut simulation = HeadlessSimulationV2::new(config);
+        let diagnostics = simulation.run();
+
+        let distribution = &diagnostics.generation_distribution;
+        assert_eq!(
+            distribution.iter().sum::<usize>(),
+            diagnostics.final_stats.agent_count
+        );
+        if diagnostics.final_stats.agent_count > 0 {
+            assert_eq!(
+                distribution.len(),
+                diagnostics.max_living_generation as usize + 1
+            );
+            let modal = diagnostics.modal_generation as usize;
+            assert_eq!(distribution[modal], *distribution.iter().max().unwrap());
+        }
+        // 0.3 minutes at 60 steps per second
+        assert_eq!(diagnostics.generation_pyramid_history.len(), 1);
+        for pyramid in &diagnostics.generation_pyramid_history {
+            assert!(pyramid.len() <= 101);
+        }
+    }
+
+    #[test]
+    fn test_typescript_section_covers_returned_fields() {
+        use crate::agent::AgentInfo;
+        use crate::fitness::FitnessSample;
+        use crate::headless_simulation::benchmark_simulation;
+        use crate::resource::ResourceInfo;
+        use crate::simulation_core::{SimulationConfig, UnifiedSimulation};
+
+        assert!(!SIMULATION_STATS_TS.trim().is_empty());
+
+        let simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 5,
+            initial_resources: 5,
+            use_ecs: false,
+            ..Default::default()
+        });
+        let agent = &simulation.get_agents()[0];
+        let mut copy = UnifiedSimulation::from_snapshot(simulation.snapshot()).unwrap();
+        let start = std::time::Instant::now();
+        let benchmark = benchmark_simulation(&mut copy, 1, || start.elapsed().as_secs_f64());
+        let returned = [
+            serde_json::to_value(simulation.get_stats()).unwrap(),
+            serde_json::to_value(FitnessSample::from_agent(agent)).unwrap(),
+            serde_json::to_value(benchmark).unwrap(),
+            serde_json::to_value(AgentInfo::from_agent(agent)).unwrap(),
+            serde_json::to_value(AgentInfo::from_agent(agent).genes).unwrap(),
+            serde_json::to_value(ResourceInfo::from_resource(&simulation.get_resources()[0]))
+                .unwrap(),
+        ];
+        for value in returned {
+            for field in value.as_object().unwrap().keys() {
+                let declared =
+                    |ty: &str| SIMULATION_STATS_TS.contains(&format!("    {}: {};", field, ty));
+                assert!(
+                    declared("number")
+                        || declared("number[]")
+                        || declared("string")
+                        || declared("GenesSummary"),
+                    "{} is missing from the TypeScript declarations",
+                    field
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,150 @@
+//! Simulation logging goes through `tracing`. Native hosts install a
+//! `tracing_subscriber` formatter with `init_tracing`; the browser build routes
+//! events to the developer console through `ConsoleSubscriber`.
+
+/// Install a formatting subscriber that writes events up to `max_level` to
+/// stdout. Calling it again after a subscriber is set is a no-op.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_tracing_with_level(max_level: tracing::Level) {
+    let subscriber = tracing_subscriber::fmt().with_max_level(max_level).finish();
+    tracing::subscriber::set_global_default(subscriber).ok();
+}
+
+/// `init_tracing_with_level` at `INFO`, so per-agent `DEBUG` events stay quiet.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_tracing() {
+    init_tracing_with_level(tracing::Level::INFO);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use console::{init_console_tracing, ConsoleSubscriber};
+
+#[cfg(target_arch = "wasm32")]
+mod console {
+    use std::fmt::{self, Write};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    /// Writes events to `console.log`/`warn`/`error` by level. Spans aren't
+    /// tracked; only their events are printed.
+    pub struct ConsoleSubscriber {
+        max_level: Level,
+    }
+
+    impl ConsoleSubscriber {
+        pub fn new(max_level: Level) -> Self {
+            Self { max_level }
+        }
+    }
+
+    /// Collects an event's message and fields into one line.
+    #[derive(Default)]
+    struct LineVisitor {
+        line: String,
+    }
+
+    impl Visit for LineVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if !self.line.is_empty() {
+                self.line.push(' ');
+            }
+            if field.name() == "message" {
+                write!(self.line, "{:?}", value).ok();
+            } else {
+                write!(self.line, "{}={:?}", field.name(), value).ok();
+            }
+        }
+    }
+
+    impl Subscriber for ConsoleSubscriber {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            *metadata.level() <= self.max_level
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = LineVisitor::default();
+            event.record(&mut visitor);
+            let metadata = event.metadata();
+            let line = format!("[{}] {}: {}", metadata.level(), metadata.target(), visitor.line);
+
+            match *metadata.level() {
+                Level::ERROR => web_sys::console::error_1(&line.into()),
+                Level::WARN => web_sys::console::warn_1(&line.into()),
+                _ => web_sys::console::log_1(&line.into()),
+            }
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    /// Route `tracing` events at `INFO` and above to the browser console.
+    /// Ignored if a subscriber is already installed.
+    pub fn init_console_tracing() {
+        tracing::subscriber::set_global_default(ConsoleSubscriber::new(Level::INFO)).ok();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use crate::agent::Agent;
+    use crate::genes::Genes;
+    use crate::simulation_core::{LegacySimulationEngine, SimulationConfig, SimulationEngine};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_agent_death_is_logged_at_debug() {
+        let output = CapturedOutput::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let mut engine = LegacySimulationEngine::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            ..Default::default()
+        });
+        let mut starving = Agent::new(100.0, 100.0, Genes::new(), 1);
+        starving.energy = 0.0001;
+        let id = starving.id();
+        engine.restore(vec![starving], Vec::new());
+
+        tracing::subscriber::with_default(subscriber, || engine.update());
+
+        let logged = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let line = logged
+            .lines()
+            .find(|line| line.contains(&format!("Agent {} died", id)))
+            .unwrap_or_else(|| panic!("no death event in {:?}", logged));
+        assert!(line.contains("DEBUG"), "{}", line);
+        assert!(line.contains("Starvation"), "{}", line);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::simulation_core::SimulationStats;
+use metrics::{gauge, Recorder};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often gauges are pushed to a Prometheus push gateway.
+pub const PUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+pub type SharedRecorder = Arc<dyn Recorder + Send + Sync>;
+
+/// Publishes the population gauges for one simulation step through `recorder`.
+pub fn record_stats(recorder: &dyn Recorder, stats: &SimulationStats, genetic_diversity: f64) {
+    metrics::with_local_recorder(recorder, || {
+        gauge!("battleo.agents").set(stats.agent_count as f64);
+        gauge!("battleo.resources").set(stats.resource_count as f64);
+        gauge!("battleo.genetic_diversity").set(genetic_diversity);
+        gauge!("battleo.species").set(stats.species_count as f64);
+        gauge!("battleo.total_energy").set(stats.total_energy);
+        gauge!("battleo.average_fitness").set(stats.average_fitness);
+        gauge!("battleo.average_stamina").set(stats.average_stamina);
+        gauge!("battleo.average_mutation_strength").set(stats.average_mutation_strength);
+        gauge!("battleo.max_generation").set(stats.max_generation as f64);
+        gauge!("battleo.total_kills").set(stats.total_kills as f64);
+        gauge!("battleo.average_trophic_level").set(stats.average_trophic_level);
+        gauge!("battleo.apex_predators").set(stats.apex_predator_count as f64);
+    });
+}
+
+/// Builds a Prometheus recorder that pushes to `push_url` every `PUSH_INTERVAL`
+/// from a background thread.
+pub fn push_gateway_recorder(push_url: &str) -> Result<SharedRecorder, String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start metrics runtime: {}", e))?;
+
+    // The builder spawns its upkeep task onto the current runtime
+    let (recorder, exporter) = {
+        let _guard = runtime.enter();
+        PrometheusBuilder::new()
+            .with_push_gateway(push_url, PUSH_INTERVAL, None, None)
+            .and_then(|builder| builder.build())
+            .map_err(|e| format!("Invalid push gateway {}: {}", push_url, e))?
+    };
+
+    std::thread::spawn(move || runtime.block_on(exporter));
+    Ok(Arc::new(recorder))
+}
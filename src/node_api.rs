@@ -0,0 +1,110 @@
+use crate::boundary::BoundaryBehavior;
+use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+use crate::simulation_core::format_config_errors;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::path::PathBuf;
+
+/// Plain-object form of `HeadlessSimulationConfig`; omitted fields keep their
+/// defaults.
+#[napi(object, js_name = "HeadlessSimulationConfig")]
+#[derive(Default)]
+pub struct NodeSimulationConfig {
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub max_agents: Option<u32>,
+    pub max_resources: Option<u32>,
+    pub initial_agents: Option<u32>,
+    pub initial_resources: Option<u32>,
+    pub resource_spawn_rate: Option<f64>,
+    pub target_duration_minutes: Option<f64>,
+    pub stability_threshold: Option<f64>,
+    pub min_agent_count: Option<u32>,
+    pub max_agent_count: Option<u32>,
+    pub use_ecs: Option<bool>,
+    /// "wrap", "bounce" or "lethal".
+    pub boundary_behavior: Option<String>,
+    pub season_period_seconds: Option<f64>,
+    pub season_amplitude: Option<f64>,
+    pub log_interactions: Option<bool>,
+    pub speed_multiplier: Option<f64>,
+    /// File `run` writes per-step stats to as CSV.
+    pub csv_output_path: Option<String>,
+}
+
+impl TryFrom<NodeSimulationConfig> for HeadlessSimulationConfig {
+    type Error = Error;
+
+    fn try_from(config: NodeSimulationConfig) -> Result<Self> {
+        let defaults = HeadlessSimulationConfig::default();
+        let count = |value: Option<u32>, default: usize| value.map_or(default, |v| v as usize);
+        let boundary_behavior = match config.boundary_behavior {
+            Some(mode) => mode
+                .parse::<BoundaryBehavior>()
+                .map_err(|e| Error::new(Status::InvalidArg, e))?,
+            None => defaults.boundary_behavior,
+        };
+
+        Ok(HeadlessSimulationConfig {
+            width: config.width.unwrap_or(defaults.width),
+            height: config.height.unwrap_or(defaults.height),
+            max_agents: count(config.max_agents, defaults.max_agents),
+            max_resources: count(config.max_resources, defaults.max_resources),
+            initial_agents: count(config.initial_agents, defaults.initial_agents),
+            initial_resources: count(config.initial_resources, defaults.initial_resources),
+            resource_spawn_rate: config
+                .resource_spawn_rate
+                .unwrap_or(defaults.resource_spawn_rate),
+            target_duration_minutes: config
+                .target_duration_minutes
+                .unwrap_or(defaults.target_duration_minutes),
+            stability_threshold: config
+                .stability_threshold
+                .unwrap_or(defaults.stability_threshold),
+            min_agent_count: count(config.min_agent_count, defaults.min_agent_count),
+            max_agent_count: count(config.max_agent_count, defaults.max_agent_count),
+            use_ecs: config.use_ecs.unwrap_or(defaults.use_ecs),
+            boundary_behavior,
+            season_period_seconds: config
+                .season_period_seconds
+                .unwrap_or(defaults.season_period_seconds),
+            season_amplitude: config.season_amplitude.unwrap_or(defaults.season_amplitude),
+            log_interactions: config.log_interactions.unwrap_or(defaults.log_interactions),
+            speed_multiplier: config.speed_multiplier.unwrap_or(defaults.speed_multiplier),
+            csv_output_path: config.csv_output_path.map(PathBuf::from),
+            ..defaults
+        })
+    }
+}
+
+/// `HeadlessSimulationV2` exported to Node.js as `HeadlessSimulation`.
+#[napi(js_name = "HeadlessSimulation")]
+pub struct NodeHeadlessSimulation {
+    inner: HeadlessSimulationV2,
+}
+
+#[napi]
+impl NodeHeadlessSimulation {
+    #[napi(constructor)]
+    pub fn new(config: Option<NodeSimulationConfig>) -> Result<Self> {
+        let config = HeadlessSimulationConfig::try_from(config.unwrap_or_default())?;
+        let inner = HeadlessSimulationV2::try_new(config)
+            .map_err(|errors| Error::new(Status::InvalidArg, format_config_errors(&errors)))?;
+        Ok(Self { inner })
+    }
+
+    /// Runs to completion and returns the `SimulationDiagnostics` as a plain object.
+    #[napi(ts_return_type = "Record<string, unknown>")]
+    pub fn run(&mut self) -> Result<serde_json::Value> {
+        to_json(&self.inner.run())
+    }
+
+    #[napi(ts_return_type = "Record<string, unknown>")]
+    pub fn get_diagnostics(&self) -> Result<serde_json::Value> {
+        to_json(self.inner.get_diagnostics())
+    }
+}
+
+fn to_json(diagnostics: &impl serde::Serialize) -> Result<serde_json::Value> {
+    serde_json::to_value(diagnostics).map_err(|e| Error::from_reason(e.to_string()))
+}
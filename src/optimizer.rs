@@ -0,0 +1,236 @@
+//! Searches for the `HeadlessSimulationConfig` that gives the most stable
+//! runs.
+
+use crate::headless_simulation::{HeadlessSimulationConfig, HeadlessSimulationV2};
+use rand::seq::index::sample;
+use rand::Rng;
+
+/// Candidate configs kept per generation.
+pub const POPULATION_SIZE: usize = 20;
+/// Weight F of the difference vector added to the base vector.
+pub const MUTATION_FACTOR: f64 = 0.8;
+/// Probability CR of taking each parameter from the mutant.
+pub const CROSSOVER_PROBABILITY: f64 = 0.9;
+/// Length of the headless run behind each default fitness evaluation.
+pub const EVALUATION_MINUTES: f64 = 0.5;
+
+/// (min, max) searched for `initial_agents`, `initial_resources`,
+/// `resource_spawn_rate` and `stability_threshold`, in that order.
+pub const PARAMETER_BOUNDS: [(f64, f64); 4] =
+    [(10.0, 1000.0), (10.0, 1500.0), (0.0, 1.0), (0.01, 0.5)];
+
+/// One candidate: each searched parameter as a fraction of its bounds.
+type Vector = [f64; 4];
+
+/// `base` with the searched parameters taken from `vector`.
+fn config_from_vector(
+    base: &HeadlessSimulationConfig,
+    vector: &Vector,
+) -> HeadlessSimulationConfig {
+    let [agents, resources, spawn_rate, threshold] = std::array::from_fn(|i| {
+        let (min, max) = PARAMETER_BOUNDS[i];
+        min + vector[i].clamp(0.0, 1.0) * (max - min)
+    });
+    HeadlessSimulationConfig {
+        initial_agents: (agents.round() as usize).min(base.max_agents),
+        initial_resources: (resources.round() as usize).min(base.max_resources),
+        resource_spawn_rate: spawn_rate,
+        stability_threshold: threshold,
+        ..base.clone()
+    }
+}
+
+fn random_vector(rng: &mut impl Rng) -> Vector {
+    std::array::from_fn(|_| rng.gen_range(0.0..=1.0))
+}
+
+/// `stability_score` of a `EVALUATION_MINUTES` run of `config`.
+pub fn stability_fitness(config: &HeadlessSimulationConfig) -> f64 {
+    let config = HeadlessSimulationConfig {
+        target_duration_minutes: EVALUATION_MINUTES,
+        ..config.clone()
+    };
+    HeadlessSimulationV2::new(config).run().stability_score
+}
+
+/// DE/rand/1/bin over `PARAMETER_BOUNDS`: each generation, every vector is
+/// challenged by a trial that takes the mutant a + F (b - c) of three other
+/// random vectors in each parameter with probability CR, at least one
+/// parameter guaranteed, and is replaced if the trial scores at least as
+/// well.
+pub struct DifferentialEvolutionOptimizer {
+    base_config: HeadlessSimulationConfig,
+    fitness: Box<dyn FnMut(&HeadlessSimulationConfig) -> f64>,
+    population: Vec<Vector>,
+    scores: Vec<f64>,
+    best_fitness_history: Vec<f64>,
+    evaluations: usize,
+}
+
+impl DifferentialEvolutionOptimizer {
+    /// Scores candidates with `stability_fitness`. Everything but the
+    /// searched parameters and the run length comes from `base_config`.
+    pub fn new(base_config: HeadlessSimulationConfig) -> Self {
+        Self::with_fitness(base_config, stability_fitness)
+    }
+
+    /// Scores candidates with `fitness`, higher being better.
+    pub fn with_fitness(
+        base_config: HeadlessSimulationConfig,
+        fitness: impl FnMut(&HeadlessSimulationConfig) -> f64 + 'static,
+    ) -> Self {
+        Self {
+            base_config,
+            fitness: Box::new(fitness),
+            population: Vec::new(),
+            scores: Vec::new(),
+            best_fitness_history: Vec::new(),
+            evaluations: 0,
+        }
+    }
+
+    fn evaluate(&mut self, vector: &Vector) -> f64 {
+        self.evaluations += 1;
+        (self.fitness)(&config_from_vector(&self.base_config, vector))
+    }
+
+    /// Evolves for `generations` more generations, scoring a random initial
+    /// population first if there isn't one yet, and returns the best config
+    /// found. Costs `POPULATION_SIZE` evaluations per generation, plus
+    /// `POPULATION_SIZE` for the initial population.
+    pub fn run(&mut self, generations: usize) -> HeadlessSimulationConfig {
+        let mut rng = rand::thread_rng();
+        if self.population.is_empty() {
+            for _ in 0..POPULATION_SIZE {
+                let vector = random_vector(&mut rng);
+                let score = self.evaluate(&vector);
+                self.population.push(vector);
+                self.scores.push(score);
+            }
+            self.best_fitness_history.push(self.best_fitness());
+        }
+
+        for _ in 0..generations {
+            for target in 0..POPULATION_SIZE {
+                let others: Vec<usize> = sample(&mut rng, POPULATION_SIZE - 1, 3)
+                    .into_iter()
+                    .map(|i| if i >= target { i + 1 } else { i })
+                    .collect();
+                let [a, b, c] = [0, 1, 2].map(|i| self.population[others[i]]);
+                let forced = rng.gen_range(0..PARAMETER_BOUNDS.len());
+                let trial: Vector = std::array::from_fn(|i| {
+                    if i == forced || rng.gen_bool(CROSSOVER_PROBABILITY) {
+                        (a[i] + MUTATION_FACTOR * (b[i] - c[i])).clamp(0.0, 1.0)
+                    } else {
+                        self.population[target][i]
+                    }
+                });
+
+                let score = self.evaluate(&trial);
+                if score >= self.scores[target] {
+                    self.population[target] = trial;
+                    self.scores[target] = score;
+                }
+            }
+            self.best_fitness_history.push(self.best_fitness());
+        }
+
+        self.best_config()
+    }
+
+    fn best_index(&self) -> Option<usize> {
+        (0..self.scores.len()).max_by(|&i, &j| self.scores[i].total_cmp(&self.scores[j]))
+    }
+
+    /// Score of the best config found so far; negative infinity before `run`.
+    pub fn best_fitness(&self) -> f64 {
+        self.best_index()
+            .map_or(f64::NEG_INFINITY, |i| self.scores[i])
+    }
+
+    /// The best config found so far; the base config before `run`.
+    pub fn best_config(&self) -> HeadlessSimulationConfig {
+        match self.best_index() {
+            Some(i) => config_from_vector(&self.base_config, &self.population[i]),
+            None => self.base_config.clone(),
+        }
+    }
+
+    /// Best score after the initial population and after each generation.
+    pub fn best_fitness_history(&self) -> &[f64] {
+        &self.best_fitness_history
+    }
+
+    /// Fitness evaluations so far.
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+}
+
+/// The best of `evaluations` configs drawn uniformly from
+/// `PARAMETER_BOUNDS`, with its score; a baseline for
+/// `DifferentialEvolutionOptimizer`.
+pub fn random_search(
+    base_config: &HeadlessSimulationConfig,
+    evaluations: usize,
+    mut fitness: impl FnMut(&HeadlessSimulationConfig) -> f64,
+) -> (HeadlessSimulationConfig, f64) {
+    let mut rng = rand::thread_rng();
+    let mut best = (base_config.clone(), f64::NEG_INFINITY);
+    for _ in 0..evaluations {
+        let config = config_from_vector(base_config, &random_vector(&mut rng));
+        let score = fitness(&config);
+        if score > best.1 {
+            best = (config, score);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A smooth single peak away from the middle of the searched box.
+    fn peaked_fitness(config: &HeadlessSimulationConfig) -> f64 {
+        let values = [
+            config.initial_agents as f64,
+            config.initial_resources as f64,
+            config.resource_spawn_rate,
+            config.stability_threshold,
+        ];
+        let peak = [0.3, 0.7, 0.2, 0.6];
+        let distance: f64 = (0..4)
+            .map(|i| {
+                let (min, max) = PARAMETER_BOUNDS[i];
+                ((values[i] - min) / (max - min) - peak[i]).powi(2)
+            })
+            .sum();
+        (-distance / 0.02).exp()
+    }
+
+    #[test]
+    fn test_differential_evolution_beats_random_search() {
+        // With 100 evaluations (4 generations) DE is still mostly its random
+        // start and wins only about half the time, so give both 400
+        let base = HeadlessSimulationConfig::default();
+        let trials = 20;
+        let mut wins = 0;
+        for _ in 0..trials {
+            let mut optimizer =
+                DifferentialEvolutionOptimizer::with_fitness(base.clone(), peaked_fitness);
+            let best = optimizer.run(19);
+            assert_eq!(optimizer.evaluations(), 400);
+            assert_eq!(optimizer.best_fitness(), peaked_fitness(&best));
+            let history = optimizer.best_fitness_history();
+            assert_eq!(history.len(), 20);
+            assert!(history.windows(2).all(|pair| pair[0] <= pair[1]));
+
+            let (_, random_best) = random_search(&base, 400, peaked_fitness);
+            if optimizer.best_fitness() > random_best {
+                wins += 1;
+            }
+        }
+        assert!(wins >= trials * 4 / 5, "DE won {} of {}", wins, trials);
+    }
+}
@@ -0,0 +1,474 @@
+//! Buffer layouts and screen geometry for `webgl_renderer`, kept free of
+//! `web_sys` so they build and are tested natively.
+
+use crate::agent::{Agent, AgentState};
+
+// Agent vertex layout: 2 floats position + 3 color + 1 size + 1 energy + 1 selected
+// + 1 atlas index + 1 mutated gene mask + 1 spawn time + 1 satiation
+pub const AGENT_POSITION_OFFSET: i32 = 0;
+pub const AGENT_COLOR_OFFSET: i32 = 8;
+pub const AGENT_SIZE_OFFSET: i32 = 20;
+pub const AGENT_ENERGY_OFFSET: i32 = 24;
+pub const AGENT_SELECTED_OFFSET: i32 = 28;
+pub const AGENT_STATE_OFFSET: i32 = 32;
+pub const AGENT_MUTATED_OFFSET: i32 = 36;
+pub const AGENT_SPAWN_TIME_OFFSET: i32 = 40;
+pub const AGENT_SATIATION_OFFSET: i32 = 44;
+pub const AGENT_STRIDE: i32 = 48;
+
+// Agent icon atlas: a row of square white masks, tinted by the agent color
+pub const ATLAS_CELL_SIZE: usize = 16;
+pub const ATLAS_CELLS: usize = 8;
+pub const ATLAS_WIDTH: usize = ATLAS_CELL_SIZE * ATLAS_CELLS;
+
+// Pack connection vertex layout: 2 floats position + 3 color + 1 alpha
+pub const CONNECTION_POSITION_OFFSET: i32 = 0;
+pub const CONNECTION_COLOR_OFFSET: i32 = 8;
+pub const CONNECTION_ALPHA_OFFSET: i32 = 20;
+pub const CONNECTION_STRIDE: i32 = 24;
+
+// Habitat vertex layout: 2 floats corner position + 2 center + 1 radius,
+// six vertices (two triangles) per habitat
+pub const HABITAT_POSITION_OFFSET: i32 = 0;
+pub const HABITAT_CENTER_OFFSET: i32 = 8;
+pub const HABITAT_RADIUS_OFFSET: i32 = 16;
+pub const HABITAT_STRIDE: i32 = 20;
+pub const HABITAT_VERTICES: usize = 6;
+
+// Teleporter vertex layout: the habitat layout plus 1 float spin direction,
+// one quad at the entrance (+1) and one at the exit (-1) per teleporter
+pub const TELEPORTER_POSITION_OFFSET: i32 = 0;
+pub const TELEPORTER_CENTER_OFFSET: i32 = 8;
+pub const TELEPORTER_RADIUS_OFFSET: i32 = 16;
+pub const TELEPORTER_SPIN_OFFSET: i32 = 20;
+pub const TELEPORTER_STRIDE: i32 = 24;
+pub const TELEPORTER_VERTICES: usize = 2 * HABITAT_VERTICES;
+
+// Gene box-and-whisker panel in the top-left corner, in canvas pixels, drawn
+// as solid lines with the lasso program
+pub const BOXPLOT_WIDTH: f32 = 180.0;
+pub const BOXPLOT_HEIGHT: f32 = 30.0;
+pub const BOXPLOT_MARGIN: f32 = 10.0;
+
+// Gene histogram panel in the top-right corner, in canvas pixels. Each bar is
+// the unit quad strip scaled to its rectangle by a uniform
+pub const HISTOGRAM_WIDTH: f32 = 180.0;
+pub const HISTOGRAM_HEIGHT: f32 = 90.0;
+pub const HISTOGRAM_MARGIN: f32 = 10.0;
+pub const HISTOGRAM_BAR_GAP: f32 = 1.0;
+
+// Keeps sprites centred just off screen visible as they cross the edge
+pub const AGENT_CULL_MARGIN: f32 = 64.0;
+
+/// Weight of the newest frame in `avg_gpu_time_ns`.
+pub const GPU_TIME_SMOOTHING: f64 = 0.1;
+
+/// Most agents uploaded per frame unless `set_max_render_agents` says
+/// otherwise. Past this the upload alone can stall the browser.
+pub const DEFAULT_MAX_RENDER_AGENTS: usize = 5000;
+
+// Instanced (WebGL2) agent layout: the WebGL1 vertex followed by 1 float age
+pub const INSTANCE_AGE_OFFSET: i32 = AGENT_STRIDE;
+pub const INSTANCE_STRIDE: i32 = AGENT_STRIDE + 4;
+
+/// The WebGL2 per-instance buffer for `agents`: each agent's WebGL1
+/// `vertex` attributes followed by its age, `INSTANCE_STRIDE` bytes apiece.
+pub fn instance_data(agents: &[Agent], vertex: impl Fn(&Agent) -> [f32; 12]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(agents.len() * INSTANCE_STRIDE as usize);
+    for agent in agents {
+        for value in vertex(agent) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&(agent.age as f32).to_le_bytes());
+    }
+    data
+}
+
+/// Two triangles covering [-1, 1]², scaled per instance into an agent billboard.
+pub const BILLBOARD_QUAD: [f32; 12] = [
+    -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, //
+    -1.0, -1.0, 1.0, 1.0, -1.0, 1.0,
+];
+
+/// Atlas cell drawn for an agent in `state`. Predators on the hunt get their
+/// own icon so they stand out from prey heading for food.
+pub fn atlas_index(state: &AgentState, is_predator: bool) -> usize {
+    match state {
+        AgentState::Hunting | AgentState::PackHunting { .. } if is_predator => 7,
+        AgentState::Seeking => 0,
+        AgentState::Hunting | AgentState::PackHunting { .. } => 1,
+        AgentState::Feeding => 2,
+        AgentState::Reproducing => 3,
+        AgentState::Fighting => 4,
+        AgentState::Fleeing => 5,
+        AgentState::Migrating => 6,
+    }
+}
+
+/// Whether the pixel at `(u, v)`, in [-1, 1] across one cell, is inside
+/// the icon for `cell`.
+pub fn atlas_shape_contains(cell: usize, u: f32, v: f32) -> bool {
+    let (au, av) = (u.abs(), v.abs());
+    let radius = (u * u + v * v).sqrt();
+    match cell {
+        // Seeking: disc
+        0 => radius <= 0.8,
+        // Hunting: diamond
+        1 => au + av <= 0.9,
+        // Feeding: ring
+        2 => (0.45..=0.85).contains(&radius),
+        // Reproducing: plus
+        3 => (au <= 0.3 && av <= 0.85) || (av <= 0.3 && au <= 0.85),
+        // Fighting: cross
+        4 => (au - av).abs() <= 0.3 && au.max(av) <= 0.85,
+        // Fleeing: triangle
+        5 => v <= 0.85 && au <= (v + 0.85) * 0.5,
+        // Migrating: square
+        6 => au.max(av) <= 0.75,
+        // Predator hunting: four-pointed star
+        _ => radius <= 0.35 || (au * av <= 0.04 && radius <= 0.9),
+    }
+}
+
+/// RGBA pixels of the `ATLAS_WIDTH` x `ATLAS_CELL_SIZE` icon atlas, top row
+/// first. Icons are opaque white on transparent.
+pub fn atlas_pixels() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(ATLAS_WIDTH * ATLAS_CELL_SIZE * 4);
+    for y in 0..ATLAS_CELL_SIZE {
+        for x in 0..ATLAS_WIDTH {
+            let to_unit = |i: usize| (i as f32 + 0.5) / ATLAS_CELL_SIZE as f32 * 2.0 - 1.0;
+            let (u, v) = (to_unit(x % ATLAS_CELL_SIZE), to_unit(y));
+            let alpha = if atlas_shape_contains(x / ATLAS_CELL_SIZE, u, v) {
+                255
+            } else {
+                0
+            };
+            pixels.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    pixels
+}
+
+/// `[x, y, width, height]` of the box-and-whisker panel.
+pub fn boxplot_panel() -> [f32; 4] {
+    [BOXPLOT_MARGIN, BOXPLOT_MARGIN, BOXPLOT_WIDTH, BOXPLOT_HEIGHT]
+}
+
+/// `[x1, y1, x2, y2]` line segments of a horizontal box-and-whisker plot
+/// across `panel`, from `[p5, p25, median, p75, p95]` as fractions of its
+/// width: whiskers out to capped ends at p5 and p95, a box from p25 to p75
+/// and a bar at the median.
+pub fn boxplot_segments(fractions: [f32; 5], panel: [f32; 4]) -> Vec<[f32; 4]> {
+    let [x, y, width, height] = panel;
+    let [p5, p25, median, p75, p95] = fractions.map(|f| x + f.clamp(0.0, 1.0) * width);
+    let middle = y + height / 2.0;
+    let (box_top, box_bottom) = (y + height * 0.2, y + height * 0.8);
+    let (cap_top, cap_bottom) = (y + height * 0.35, y + height * 0.65);
+    vec![
+        [p5, middle, p25, middle],
+        [p75, middle, p95, middle],
+        [p5, cap_top, p5, cap_bottom],
+        [p95, cap_top, p95, cap_bottom],
+        [p25, box_top, p75, box_top],
+        [p25, box_bottom, p75, box_bottom],
+        [p25, box_top, p25, box_bottom],
+        [p75, box_top, p75, box_bottom],
+        [median, box_top, median, box_bottom],
+    ]
+}
+
+/// `[x, y, width, height]` of the histogram panel on a canvas `canvas_width`
+/// pixels wide.
+pub fn histogram_panel(canvas_width: f32) -> [f32; 4] {
+    [
+        canvas_width - HISTOGRAM_WIDTH - HISTOGRAM_MARGIN,
+        HISTOGRAM_MARGIN,
+        HISTOGRAM_WIDTH,
+        HISTOGRAM_HEIGHT,
+    ]
+}
+
+/// One `[x, y, width, height]` rectangle per bar, side by side across `panel`
+/// and standing on its bottom edge.
+pub fn histogram_bar_rects(bars: &[f32], panel: [f32; 4]) -> Vec<[f32; 4]> {
+    let [x, y, width, height] = panel;
+    let slot = width / bars.len() as f32;
+    bars.iter()
+        .enumerate()
+        .map(|(i, &fraction)| {
+            let bar_height = fraction * height;
+            [
+                x + i as f32 * slot + HISTOGRAM_BAR_GAP / 2.0,
+                y + height - bar_height,
+                (slot - HISTOGRAM_BAR_GAP).max(1.0),
+                bar_height,
+            ]
+        })
+        .collect()
+}
+
+/// `average` moved towards a new GPU frame time; the first sample is taken
+/// as is.
+pub fn smooth_gpu_time(average: f64, elapsed_ns: f64) -> f64 {
+    if average == 0.0 {
+        elapsed_ns
+    } else {
+        average + (elapsed_ns - average) * GPU_TIME_SMOOTHING
+    }
+}
+
+/// Indices of at most `limit` agents to draw, most energetic first. Agents
+/// further than a quarter of the `width` x `height` canvas diagonal from its
+/// centre are thinned to one in `1 + lod_bias` of those reached, leaving more
+/// room for the centre.
+pub fn render_subset(
+    agents: &[Agent],
+    limit: usize,
+    lod_bias: f64,
+    width: f32,
+    height: f32,
+) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..agents.len()).collect();
+    order.sort_by(|&a, &b| agents[b].energy.total_cmp(&agents[a].energy));
+
+    let (center_x, center_y) = (width / 2.0, height / 2.0);
+    let far = width.hypot(height) / 4.0;
+    let mut far_credit = 0.0;
+    let mut chosen = Vec::with_capacity(limit.min(agents.len()));
+    for i in order {
+        if chosen.len() == limit {
+            break;
+        }
+        let agent = &agents[i];
+        let distance = (agent.x as f32 - center_x).hypot(agent.y as f32 - center_y);
+        if distance > far {
+            far_credit += 1.0 / (1.0 + lod_bias);
+            if far_credit < 1.0 {
+                continue;
+            }
+            far_credit -= 1.0;
+        }
+        chosen.push(i);
+    }
+    chosen
+}
+
+/// Simulation-space region the agent shader draws, widened by
+/// `AGENT_CULL_MARGIN`. With no camera the view is the whole canvas.
+pub fn agent_viewport_bounds(width: f32, height: f32) -> [f32; 4] {
+    [
+        -AGENT_CULL_MARGIN,
+        -AGENT_CULL_MARGIN,
+        width + AGENT_CULL_MARGIN,
+        height + AGENT_CULL_MARGIN,
+    ]
+}
+
+/// CPU copy of the agent vertex shader's culling test.
+#[cfg(test)]
+fn agent_point_size(x: f32, y: f32, bounds: [f32; 4], point_size: f32) -> f32 {
+    let [min_x, min_y, max_x, max_y] = bounds;
+    if x < min_x || y < min_y || x > max_x || y > max_y {
+        0.0
+    } else {
+        point_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genes::Genes;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_agents_outside_viewport_are_culled() {
+        let bounds = [0.0, 0.0, 800.0, 800.0];
+        assert_eq!(agent_point_size(10000.0, 10000.0, bounds, 30.0), 0.0);
+        assert_eq!(agent_point_size(400.0, 400.0, bounds, 30.0), 30.0);
+
+        let [min_x, min_y, max_x, max_y] = agent_viewport_bounds(1200.0, 900.0);
+        assert!(min_x < 0.0 && min_y < 0.0);
+        assert!(max_x > 1200.0 && max_y > 900.0);
+    }
+
+    #[test]
+    fn test_render_limit_keeps_most_energetic_agents() {
+        let agents: Vec<Agent> = (0..10_000)
+            .map(|i| {
+                let (x, y) = ((i % 800) as f64, (i / 800 * 40) as f64);
+                let mut agent = Agent::new(x, y, Genes::new(), 1);
+                agent.energy = (i % 100) as f64;
+                agent
+            })
+            .collect();
+
+        let subset = render_subset(&agents, DEFAULT_MAX_RENDER_AGENTS, 0.0, 800.0, 600.0);
+        assert_eq!(subset.len(), 5000);
+        let kept: HashSet<usize> = subset.iter().copied().collect();
+        let lowest_kept = subset.iter().map(|&i| agents[i].energy).fold(f64::MAX, f64::min);
+        assert!(agents
+            .iter()
+            .enumerate()
+            .all(|(i, agent)| kept.contains(&i) || agent.energy <= lowest_kept));
+
+        // A bias makes room for more agents near the centre
+        let is_near = |&i: &usize| {
+            let (dx, dy) = (agents[i].x - 400.0, agents[i].y - 300.0);
+            dx.hypot(dy) <= 250.0
+        };
+        let near = subset.iter().filter(|i| is_near(i)).count();
+        let biased = render_subset(&agents, DEFAULT_MAX_RENDER_AGENTS, 3.0, 800.0, 600.0);
+        assert_eq!(biased.len(), 5000);
+        assert!(biased.iter().filter(|i| is_near(i)).count() > near);
+    }
+
+    #[test]
+    fn test_agent_vertex_layout() {
+        let float_size = std::mem::size_of::<f32>() as i32;
+
+        assert_eq!(AGENT_POSITION_OFFSET, 0);
+        assert_eq!(AGENT_COLOR_OFFSET, AGENT_POSITION_OFFSET + 2 * float_size);
+        assert_eq!(AGENT_SIZE_OFFSET, AGENT_COLOR_OFFSET + 3 * float_size);
+        assert_eq!(AGENT_ENERGY_OFFSET, AGENT_SIZE_OFFSET + float_size);
+        assert_eq!(AGENT_SELECTED_OFFSET, AGENT_ENERGY_OFFSET + float_size);
+        assert_eq!(AGENT_STATE_OFFSET, AGENT_SELECTED_OFFSET + float_size);
+        assert_eq!(AGENT_MUTATED_OFFSET, AGENT_STATE_OFFSET + float_size);
+        assert_eq!(AGENT_SPAWN_TIME_OFFSET, AGENT_MUTATED_OFFSET + float_size);
+        assert_eq!(AGENT_SATIATION_OFFSET, AGENT_SPAWN_TIME_OFFSET + float_size);
+        assert_eq!(AGENT_STRIDE, AGENT_SATIATION_OFFSET + float_size);
+        assert_eq!(AGENT_STRIDE, 48);
+    }
+
+    #[test]
+    fn test_atlas_index_mapping() {
+        let states = [
+            (AgentState::Seeking, 0),
+            (AgentState::Hunting, 1),
+            (AgentState::Feeding, 2),
+            (AgentState::Reproducing, 3),
+            (AgentState::Fighting, 4),
+            (AgentState::Fleeing, 5),
+            (AgentState::Migrating, 6),
+        ];
+        for (state, index) in states {
+            assert_eq!(atlas_index(&state, false), index, "{:?}", state);
+        }
+        assert_eq!(atlas_index(&AgentState::Hunting, true), 7);
+        assert_eq!(atlas_index(&AgentState::Fleeing, true), 5);
+
+        // Every cell draws something, and the shader's divisor matches
+        let pixels = atlas_pixels();
+        assert_eq!(pixels.len(), ATLAS_WIDTH * ATLAS_CELL_SIZE * 4);
+        assert_eq!(ATLAS_CELLS, 8);
+        for cell in 0..ATLAS_CELLS {
+            let alpha = |x: usize, y: usize| {
+                pixels[(y * ATLAS_WIDTH + cell * ATLAS_CELL_SIZE + x) * 4 + 3]
+            };
+            let filled = (0..ATLAS_CELL_SIZE)
+                .flat_map(|y| (0..ATLAS_CELL_SIZE).map(move |x| (x, y)))
+                .filter(|&(x, y)| alpha(x, y) > 0)
+                .count();
+            assert!(filled > 0, "atlas cell {} is empty", cell);
+        }
+    }
+
+    #[test]
+    fn test_connection_vertex_layout() {
+        let float_size = std::mem::size_of::<f32>() as i32;
+
+        assert_eq!(CONNECTION_POSITION_OFFSET, 0);
+        assert_eq!(CONNECTION_COLOR_OFFSET, CONNECTION_POSITION_OFFSET + 2 * float_size);
+        assert_eq!(CONNECTION_ALPHA_OFFSET, CONNECTION_COLOR_OFFSET + 3 * float_size);
+        assert_eq!(CONNECTION_STRIDE, CONNECTION_ALPHA_OFFSET + float_size);
+        assert_eq!(CONNECTION_STRIDE, 24);
+    }
+
+    #[test]
+    fn test_habitat_vertex_layout() {
+        let float_size = std::mem::size_of::<f32>() as i32;
+
+        assert_eq!(HABITAT_POSITION_OFFSET, 0);
+        assert_eq!(HABITAT_CENTER_OFFSET, HABITAT_POSITION_OFFSET + 2 * float_size);
+        assert_eq!(HABITAT_RADIUS_OFFSET, HABITAT_CENTER_OFFSET + 2 * float_size);
+        assert_eq!(HABITAT_STRIDE, HABITAT_RADIUS_OFFSET + float_size);
+    }
+
+    #[test]
+    fn test_teleporter_vertex_layout() {
+        let float_size = std::mem::size_of::<f32>() as i32;
+
+        assert_eq!(TELEPORTER_POSITION_OFFSET, 0);
+        assert_eq!(TELEPORTER_CENTER_OFFSET, TELEPORTER_POSITION_OFFSET + 2 * float_size);
+        assert_eq!(TELEPORTER_RADIUS_OFFSET, TELEPORTER_CENTER_OFFSET + 2 * float_size);
+        assert_eq!(TELEPORTER_SPIN_OFFSET, TELEPORTER_RADIUS_OFFSET + float_size);
+        assert_eq!(TELEPORTER_STRIDE, TELEPORTER_SPIN_OFFSET + float_size);
+    }
+
+    #[test]
+    fn test_histogram_bars_stand_in_top_right_panel() {
+        let panel = histogram_panel(800.0);
+        assert_eq!(panel[0] + panel[2], 800.0 - HISTOGRAM_MARGIN);
+        assert_eq!(panel[1], HISTOGRAM_MARGIN);
+
+        let rects = histogram_bar_rects(&[1.0, 0.5, 0.0], panel);
+        assert_eq!(rects.len(), 3);
+        let bottom = panel[1] + panel[3];
+        for rect in &rects {
+            assert_eq!(rect[1] + rect[3], bottom);
+            assert!(rect[0] >= panel[0] && rect[0] + rect[2] <= panel[0] + panel[2]);
+        }
+        assert_eq!(rects[0][3], HISTOGRAM_HEIGHT);
+        assert_eq!(rects[1][3], HISTOGRAM_HEIGHT / 2.0);
+        assert_eq!(rects[2][3], 0.0);
+    }
+
+    #[test]
+    fn test_boxplot_stays_in_top_left_panel() {
+        let panel = boxplot_panel();
+        let segments = boxplot_segments([0.1, 0.3, 0.5, 0.7, 0.9], panel);
+        assert_eq!(segments.len(), 9);
+        for [x1, y1, x2, y2] in &segments {
+            for (x, y) in [(x1, y1), (x2, y2)] {
+                assert!(*x >= panel[0] && *x <= panel[0] + panel[2]);
+                assert!(*y >= panel[1] && *y <= panel[1] + panel[3]);
+            }
+        }
+
+        // The median bar sits halfway across the panel
+        let median = segments[8];
+        assert_eq!(median[0], BOXPLOT_MARGIN + BOXPLOT_WIDTH / 2.0);
+        assert_eq!(median[0], median[2]);
+    }
+
+    #[test]
+    fn test_gpu_time_average_follows_frames() {
+        let mut average = smooth_gpu_time(0.0, 2_000_000.0);
+        assert_eq!(average, 2_000_000.0);
+        for _ in 0..100 {
+            average = smooth_gpu_time(average, 1_000_000.0);
+        }
+        assert!((average - 1_000_000.0).abs() < 1_000.0);
+    }
+
+    #[test]
+    fn test_instance_layout_appends_age() {
+        assert_eq!(INSTANCE_AGE_OFFSET, AGENT_STRIDE);
+        assert_eq!(INSTANCE_STRIDE, INSTANCE_AGE_OFFSET + 4);
+        assert_eq!(BILLBOARD_QUAD.len(), 6 * 2);
+
+        let mut agent = Agent::new(10.0, 20.0, Genes::new(), 1);
+        agent.age = 3.5;
+        let data = instance_data(&[agent.clone(), agent], |agent| {
+            let mut vertex = [0.0; 12];
+            vertex[0] = agent.x as f32;
+            vertex
+        });
+        assert_eq!(data.len(), 2 * INSTANCE_STRIDE as usize);
+        let float_at =
+            |offset: usize| f32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let second = INSTANCE_STRIDE as usize;
+        assert_eq!(float_at(second + AGENT_POSITION_OFFSET as usize), 10.0);
+        assert_eq!(float_at(second + INSTANCE_AGE_OFFSET as usize), 3.5);
+    }
+}
@@ -1,6 +1,78 @@
 use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// Fraction of a dead agent's remains that survives decomposition.
+pub const DECOMPOSITION_RETAINED: f64 = 0.5;
+/// Chance that a freshly spawned resource is poisonous.
+pub const POISON_CHANCE: f64 = 0.05;
+/// `energy_delta` of a poisonous resource.
+pub const POISON_ENERGY_DELTA: f64 = -30.0;
+/// Agents smarter than this remember where they were poisoned and stay off
+/// resources there.
+pub const POISON_LEARNING_INTELLIGENCE: f64 = 1.5;
+/// How close to a remembered poisoning a resource has to be to be shunned.
+pub const POISON_AVOID_RADIUS: f64 = 10.0;
+/// Poisonings an agent remembers; older ones are forgotten.
+pub const MAX_POISON_MEMORIES: usize = 8;
+
+/// What eating a resource does to an agent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResourceType {
+    #[default]
+    Food,
+    /// Used up in one bite that costs the eater `poison_damage`.
+    Poison,
+    /// Neither feeds nor harms.
+    Neutral,
+}
+
+/// Energy an agent with `defense` loses eating a resource with
+/// `energy_delta`: `-energy_delta * (1 - defense)`, never negative.
+pub fn poison_damage(energy_delta: f64, defense: f64) -> f64 {
+    (-energy_delta * (1.0 - defense)).max(0.0)
+}
+
+/// Remembers a poisoning at (x, y), dropping the oldest beyond
+/// `MAX_POISON_MEMORIES`.
+pub fn remember_poisoning(spots: &mut Vec<(f64, f64)>, x: f64, y: f64) {
+    spots.push((x, y));
+    if spots.len() > MAX_POISON_MEMORIES {
+        spots.remove(0);
+    }
+}
+
+/// Whether (x, y) is within `POISON_AVOID_RADIUS` of any of `spots`.
+pub fn near_poisoning(spots: &[(f64, f64)], x: f64, y: f64) -> bool {
+    spots
+        .iter()
+        .any(|&(px, py)| (px - x).hypot(py - y) <= POISON_AVOID_RADIUS)
+}
+
+/// What the inspector panel shows about one resource.
+#[derive(Clone, Debug, Serialize)]
+pub struct ResourceInfo {
+    pub id: u64,
+    pub x: f64,
+    pub y: f64,
+    pub energy: f64,
+    pub max_energy: f64,
+    /// `ResourceType` variant name, e.g. `"Poison"`.
+    pub resource_type: String,
+}
+
+impl ResourceInfo {
+    pub fn from_resource(resource: &Resource) -> Self {
+        Self {
+            id: resource.id(),
+            x: resource.x,
+            y: resource.y,
+            energy: resource.energy,
+            max_energy: resource.max_energy,
+            resource_type: format!("{:?}", resource.resource_type),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Resource {
     pub x: f64,
@@ -16,11 +88,23 @@ pub struct Resource {
     pub spawn_fade: f64,    // Spawn fade timer (0.0 = invisible, 1.0 = fully visible)
     pub is_depleting: bool, // Whether resource is being depleted (fading out)
     pub deplete_fade: f64,  // Deplete fade timer (0.0 = fully visible, 1.0 = invisible)
+    #[serde(default)]
+    pub is_carcass: bool,   // Left behind by a dead agent rather than grown
+    #[serde(default)]
+    pub toxin: f64,         // Toxin level of the ground under it
+    #[serde(default)]
+    pub resource_type: ResourceType,
+    #[serde(default)]
+    pub energy_delta: f64,  // Energy an eater gains or, if negative, loses outright
 }
 
 impl Resource {
     pub fn new(x: f64, y: f64) -> Self {
-        let mut rng = thread_rng();
+        Self::random(x, y, &mut thread_rng())
+    }
+
+    /// A fresh resource at (x, y) with its energy and growth drawn from `rng`.
+    pub fn random(x: f64, y: f64, rng: &mut impl Rng) -> Self {
         let initial_energy = rng.gen_range(15.0..40.0); // Much lower initial energy
         let max_energy = rng.gen_range(30.0..60.0); // Much lower max energy
 
@@ -38,6 +122,29 @@ impl Resource {
             spawn_fade: 0.0,   // Start invisible
             is_depleting: false,
             deplete_fade: 0.0,
+            is_carcass: false,
+            toxin: 0.0,
+            resource_type: ResourceType::Food,
+            energy_delta: 0.0,
+        }
+    }
+
+    /// Turns this into a `ResourceType::Poison` resource.
+    pub fn make_poisonous(&mut self) {
+        self.resource_type = ResourceType::Poison;
+        self.energy_delta = POISON_ENERGY_DELTA;
+    }
+
+    /// Nutrient deposit where an agent died. `remains` is the agent's final
+    /// energy times its size; the deposit starts full and never grows past it.
+    pub fn carcass(x: f64, y: f64, remains: f64, rng: &mut impl Rng) -> Self {
+        let energy = remains * DECOMPOSITION_RETAINED;
+        Self {
+            energy,
+            max_energy: energy,
+            target_energy: energy,
+            is_carcass: true,
+            ..Self::random(x, y, rng)
         }
     }
 
@@ -62,8 +169,8 @@ impl Resource {
             }
         }
 
-        // Smooth energy growth towards target
-        if !self.is_spawning && !self.is_depleting {
+        // Smooth energy growth towards target, starting while still fading in
+        if !self.is_depleting {
             let energy_diff = self.target_energy - self.energy;
             if energy_diff.abs() > 0.1 {
                 let growth_direction = if energy_diff > 0.0 { 1.0 } else { -1.0 };
@@ -118,7 +225,18 @@ impl Resource {
     }
 
     pub fn is_available(&self) -> bool {
-        self.energy > 5.0 && !self.is_depleting && self.spawn_fade > 0.5
+        self.is_available_to(5.0)
+    }
+
+    /// Like `is_available`, but for a forager that can only profit from
+    /// patches holding more than `min_energy`.
+    pub fn is_available_to(&self, min_energy: f64) -> bool {
+        self.energy > min_energy && !self.is_depleting && self.spawn_fade > 0.5
+    }
+
+    /// Position-based ID, built the same way as `Agent::id`.
+    pub fn id(&self) -> u64 {
+        ((self.x * 1000.0) as u64) ^ ((self.y * 1000.0) as u64)
     }
 
     pub fn distance_to(&self, x: f64, y: f64) -> f64 {
@@ -127,3 +245,18 @@ impl Resource {
         (dx * dx + dy * dy).sqrt()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_energy_grows_while_spawning() {
+        let mut resource = Resource::new(0.0, 0.0);
+        resource.update(0.1);
+
+        assert!(resource.is_spawning);
+        assert!(resource.spawn_fade < 1.0);
+        assert!(resource.energy >= resource.growth_rate * 0.1);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::resource::Resource;
+use crate::spatial::{SpatialGrid, SpatialIndex};
+use crate::species::find_root;
+
+/// Frames between applications of `ResourceDynamics`.
+pub const RESOURCE_DYNAMICS_INTERVAL: usize = 30;
+/// Resources closer than this push each other apart.
+pub const RESOURCE_REPULSION_RADIUS: f64 = 20.0;
+/// Resources between these distances pull weakly on each other.
+pub const RESOURCE_ATTRACTION_MIN: f64 = 100.0;
+pub const RESOURCE_ATTRACTION_MAX: f64 = 200.0;
+/// Fastest a resource drifts, averaged over the interval.
+pub const RESOURCE_MAX_DRIFT_PER_FRAME: f64 = 0.1;
+/// Resources this close belong to the same patch in `count_resource_clusters`.
+pub const RESOURCE_CLUSTER_LINK_DISTANCE: f64 = 50.0;
+
+// Repulsion at zero distance; it falls off linearly to nothing at the radius
+const REPULSION_STRENGTH: f64 = 2.0;
+const ATTRACTION_STRENGTH: f64 = 0.2;
+
+/// Nudges resources so that close neighbours spread out and distant ones
+/// drift together, which over time gathers them into separate patches.
+pub struct ResourceDynamics {
+    width: f64,
+    height: f64,
+    index: SpatialGrid,
+}
+
+impl ResourceDynamics {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            index: SpatialGrid::new(width, height, RESOURCE_ATTRACTION_MAX),
+        }
+    }
+
+    /// Moves every resource by the net force from its neighbours, at most
+    /// `RESOURCE_MAX_DRIFT_PER_FRAME` per frame of the interval, keeping it
+    /// inside the world. Forces are all computed from the old positions.
+    pub fn apply(&mut self, resources: &mut [Resource]) {
+        self.index.clear();
+        for (i, resource) in resources.iter().enumerate() {
+            self.index.insert(resource.x, resource.y, i);
+        }
+
+        let max_step = RESOURCE_MAX_DRIFT_PER_FRAME * RESOURCE_DYNAMICS_INTERVAL as f64;
+        let moves: Vec<(f64, f64)> = resources
+            .iter()
+            .enumerate()
+            .map(|(i, resource)| {
+                let (mut fx, mut fy) = (0.0, 0.0);
+                let nearby = self
+                    .index
+                    .query_radius(resource.x, resource.y, RESOURCE_ATTRACTION_MAX);
+                for j in nearby.into_iter().filter(|&j| j != i) {
+                    let (dx, dy) = (resources[j].x - resource.x, resources[j].y - resource.y);
+                    let distance = dx.hypot(dy);
+                    if distance == 0.0 {
+                        continue;
+                    }
+                    let pull = if distance < RESOURCE_REPULSION_RADIUS {
+                        -REPULSION_STRENGTH * (1.0 - distance / RESOURCE_REPULSION_RADIUS)
+                    } else if distance >= RESOURCE_ATTRACTION_MIN {
+                        ATTRACTION_STRENGTH
+                    } else {
+                        0.0
+                    };
+                    fx += pull * dx / distance;
+                    fy += pull * dy / distance;
+                }
+
+                let magnitude = fx.hypot(fy);
+                if magnitude > max_step {
+                    (fx * max_step / magnitude, fy * max_step / magnitude)
+                } else {
+                    (fx, fy)
+                }
+            })
+            .collect();
+
+        for (resource, (dx, dy)) in resources.iter_mut().zip(moves) {
+            resource.x = (resource.x + dx).clamp(0.0, self.width);
+            resource.y = (resource.y + dy).clamp(0.0, self.height);
+        }
+    }
+}
+
+/// Number of patches, where resources within `RESOURCE_CLUSTER_LINK_DISTANCE`
+/// of each other, directly or through a chain, share a patch.
+pub fn count_resource_clusters(resources: &[Resource]) -> usize {
+    let mut parent: Vec<usize> = (0..resources.len()).collect();
+    for i in 0..resources.len() {
+        for j in (i + 1)..resources.len() {
+            let (a, b) = (&resources[i], &resources[j]);
+            if (a.x - b.x).hypot(a.y - b.y) <= RESOURCE_CLUSTER_LINK_DISTANCE {
+                let root_a = find_root(&mut parent, i);
+                let root_b = find_root(&mut parent, j);
+                if root_a != root_b {
+                    parent[root_b] = root_a;
+                }
+            }
+        }
+    }
+    (0..resources.len())
+        .filter(|&i| find_root(&mut parent, i) == i)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_dynamics_gather_resources_into_fewer_patches() {
+        let (width, height) = (1000.0, 800.0);
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut resources: Vec<Resource> = (0..150)
+            .map(|_| Resource::new(rng.gen_range(0.0..width), rng.gen_range(0.0..height)))
+            .collect();
+        let uniform_clusters = count_resource_clusters(&resources);
+
+        let mut dynamics = ResourceDynamics::new(width, height);
+        for _ in 0..200 {
+            dynamics.apply(&mut resources);
+        }
+        let clustered = count_resource_clusters(&resources);
+
+        // Same resources in fewer patches means larger patches on average
+        assert!(
+            clustered * 2 <= uniform_clusters,
+            "{} patches before, {} after",
+            uniform_clusters,
+            clustered
+        );
+        assert!(resources
+            .iter()
+            .all(|r| (0.0..=width).contains(&r.x) && (0.0..=height).contains(&r.y)));
+
+        // Repulsion keeps resources from piling onto one spot
+        let stacked = resources.iter().enumerate().any(|(i, a)| {
+            resources[i + 1..]
+                .iter()
+                .any(|b| (a.x - b.x).hypot(a.y - b.y) < 1.0)
+        });
+        assert!(!stacked);
+    }
+}
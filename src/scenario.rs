@@ -0,0 +1,96 @@
+//! Preset ecologies that can be loaded instead of a random initial population.
+//!
+//! A scenario is a JSON document of the form:
+//!
+//! ```json
+//! {
+//!   "name": "balanced",
+//!   "config": { "width": 1000.0, "height": 800.0, "use_ecs": true },
+//!   "initial_agents": [
+//!     { "count": 80, "genes": { "speed": 1.1, "sense_range": 55.0, ... } },
+//!     { "x": 500.0, "y": 400.0, "genes": { ... } }
+//!   ],
+//!   "initial_resources": [[100.0, 100.0], [300.0, 100.0]]
+//! }
+//! ```
+//!
+//! - `config` uses the `SimulationConfig` field names; omitted fields take their
+//!   default values. `initial_agents` / `initial_resources` inside `config` are
+//!   ignored, the lists below are authoritative.
+//! - Each agent template spawns `count` agents (default 1) with exactly the given
+//!   genes. All gene fields except `omnivore`, `migration` and `cooperation`
+//!   (default 0) are required. `x` / `y` are optional; missing coordinates are
+//!   chosen uniformly within the world.
+//! - `initial_resources` lists the `[x, y]` position of every starting resource.
+
+use crate::genes::Genes;
+use crate::simulation_core::SimulationConfig;
+use serde::{Deserialize, Serialize};
+
+pub const BUILTIN_SCENARIOS: [&str; 3] = ["balanced", "predator_heavy", "resource_scarce"];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub config: SimulationConfig,
+    pub initial_agents: Vec<AgentTemplate>,
+    pub initial_resources: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgentTemplate {
+    #[serde(default)]
+    pub x: Option<f64>,
+    #[serde(default)]
+    pub y: Option<f64>,
+    #[serde(default = "default_template_count")]
+    pub count: usize,
+    pub genes: Genes,
+}
+
+fn default_template_count() -> usize {
+    1
+}
+
+impl Scenario {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid scenario: {}", e))
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        let json = match name {
+            "balanced" => include_str!("../scenarios/balanced.json"),
+            "predator_heavy" => include_str!("../scenarios/predator_heavy.json"),
+            "resource_scarce" => include_str!("../scenarios/resource_scarce.json"),
+            _ => return None,
+        };
+        Self::from_json(json).ok()
+    }
+
+    /// Total number of agents the scenario spawns.
+    pub fn agent_count(&self) -> usize {
+        self.initial_agents.iter().map(|t| t.count).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_core::UnifiedSimulation;
+
+    #[test]
+    fn test_builtin_scenarios_spawn_expected_agents() {
+        for name in BUILTIN_SCENARIOS {
+            let scenario = Scenario::builtin(name).expect("built-in scenario should parse");
+            assert_eq!(scenario.name, name);
+
+            let expected_agents = scenario.agent_count();
+            let expected_resources = scenario.initial_resources.len();
+            let simulation = UnifiedSimulation::from_scenario(scenario);
+            let stats = simulation.get_stats();
+
+            assert_eq!(stats.agent_count, expected_agents);
+            assert_eq!(stats.resource_count, expected_resources);
+        }
+    }
+}
@@ -1,18 +1,85 @@
-use crate::agent::{Agent, AgentState};
+use crate::agent::{
+    age_distribution, digest, gene_boxplot, gene_histogram, perceived_position, trophic_summary,
+    Agent, AgentState, DeathReason, GeneBoxplot, PhenotypeModifiers, AGE_BUCKETS, SHARE_RADIUS,
+    SOCIAL_LEARNING_INTERVAL,
+};
+use crate::archetype::{spawn_archetypes, AgentArchetype};
+use crate::biome::BiomeMap;
+use crate::boundary::BoundaryBehavior;
+use crate::death::{count_by_reason, DeathHistory, DeathRecord, DEFAULT_MAX_DEATH_HISTORY};
 use crate::ecs::{
-    AgentStateEnum, EcsWorld, Energy, Genes as EcsGenes, Position, Resource as EcsResource,
-    Size, Velocity,
+    from_ecs_genes, to_ecs_genes, Age, AgentComponents, AgentState as EcsAgentState,
+    AgentStateEnum, AgentTag, DeathAnimation, DeathReason as EcsDeathReason, EcsImportError,
+    EcsWorld, Energy, Memory, Position, Resource as EcsResource, ResourceTag, Size, SpawnAnimation,
+    SpeciesId, StableId, Velocity,
+};
+use crate::famine::Famine;
+use crate::fitness::{fitness_landscape, FitnessSample};
+use crate::genes::{
+    arms_race_averages, sample_genes, Genes, InitialGeneDistribution, MutationDistribution,
+};
+use crate::habitat::{route_migrants, ConnectivityGraph, Habitat};
+use crate::interaction::{InteractionLog, InteractionLogBuffer, InteractionType};
+use crate::resource::{Resource, ResourceType, POISON_CHANCE};
+use crate::resource_dynamics::{
+    count_resource_clusters, ResourceDynamics, RESOURCE_DYNAMICS_INTERVAL,
+};
+use crate::rng::{simulation_rng, SimulationRng};
+use crate::scenario::Scenario;
+use crate::spatial::{SpatialBackend, SpatialIndex};
+use crate::species::{
+    assign_species_ids, detect_species, species_summary, SpeciesCache, DEFAULT_SPECIES_THRESHOLD,
 };
-use crate::genes::Genes;
-use crate::resource::Resource;
+use crate::stats::sample_variance;
+use crate::teleporter::{teleport, Teleporter};
+use crate::toxin::{toxin_damage, ToxinGrid, TOXIN_PER_KILL};
 use rand::prelude::*;
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Fraction of each energy heatmap cell kept from one frame to the next.
+pub const HEATMAP_DECAY: f64 = 0.99;
+/// Side of an energy heatmap cell, in world units.
+const HEATMAP_CELL_SIZE: f64 = 50.0;
+/// How close an agent has to be to a resource to feed from it.
+const FEEDING_RADIUS: f64 = 10.0;
+/// Energy per second an agent draws from a resource it is feeding on.
+const FEEDING_RATE: f64 = 20.0;
+/// Frames between `LegacySimulationEngine::adapt_spatial_grid` runs when
+/// `adaptive_spatial_grid` is set.
+const SPATIAL_ADAPT_INTERVAL: usize = 300;
+/// Relative difference from the optimal cell size that triggers a rebuild.
+const SPATIAL_ADAPT_TOLERANCE: f64 = 0.1;
+/// Adaptive cells never shrink below this, however crowded the world gets.
+const MIN_SPATIAL_CELL_SIZE: f64 = 5.0;
+/// Below this many agents `get_stats` sums them on one thread, as handing
+/// them to the rayon pool costs more than it saves.
+const PARALLEL_STATS_MIN_AGENTS: usize = 1000;
+/// Steps between re-tagging agents with `assign_species_ids`, and between
+/// refreshes of the species statistics.
+const SPECIATION_INTERVAL: usize = 100;
+/// Fraction of their summer rate that resources still grow at in deepest
+/// winter.
+pub const WINTER_GROWTH_FLOOR: f64 = 0.2;
+/// Extra metabolic cost in deepest winter, as a fraction of the summer cost.
+pub const WINTER_METABOLIC_PENALTY: f64 = 0.3;
+
+/// Multiplier on resource growth at `warmth`; see `SimulationConfig::warmth`.
+pub fn seasonal_growth_scale(warmth: f64) -> f64 {
+    WINTER_GROWTH_FLOOR + (1.0 - WINTER_GROWTH_FLOOR) * warmth
+}
+
+/// Multiplier on agents' energy costs at `warmth`.
+pub fn seasonal_metabolic_scale(warmth: f64) -> f64 {
+    1.0 + WINTER_METABOLIC_PENALTY * (1.0 - warmth)
+}
 
 static mut THREAD_POOL_AVAILABLE: bool = false;
 static mut RAYON_INITIALIZED: bool = false;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Default, Serialize)]
 pub struct SimulationStats {
     pub agent_count: usize,
     pub resource_count: usize,
@@ -24,11 +91,283 @@ pub struct SimulationStats {
     pub average_sense_range: f64,
     pub average_energy_efficiency: f64,
     pub max_generation: u32,
+    pub average_generation: f64,
     pub total_kills: u32,
+    /// Of `total_kills`, those made while pack hunting. Pack hunting is
+    /// ECS-only, so this is always 0 on the legacy engine.
+    pub total_pack_kills: u32,
     pub average_fitness: f64,
+    /// The species fields lag by up to `SPECIATION_INTERVAL` steps.
+    pub species_count: usize,
+    pub largest_species_size: usize,
+    pub largest_species_fraction: f64,
+    /// Mean `1 - reproduction_scale` across agents.
+    pub average_reproduction_suppression: f64,
+    /// Variance of `r_star` across agents; higher means more resource partitioning.
+    pub resource_competition_index: f64,
+    pub average_stamina: f64,
+    pub average_mutation_strength: f64,
+    /// Agents per tenth of the maximum lifespan, youngest first.
+    pub age_distribution: [usize; AGE_BUCKETS],
+    pub average_trophic_level: f64,
+    /// Agents at `APEX_TROPHIC_LEVEL` or above.
+    pub apex_predator_count: usize,
+    /// Shannon entropy, in nats, of the trophic level distribution.
+    pub trophic_diversity: f64,
+    /// Energy given to hungry kin by cooperators over the whole run.
+    pub total_energy_shared: f64,
+    /// Mean `satiation` across predators; 0 when there are none.
+    pub average_predator_satiation: f64,
+    /// Mean distance between where agents last saw a resource and where it
+    /// was; see `sensory_noise`.
+    pub average_perception_error: f64,
+    /// Share of agents heading back to where they last fed. Only ECS agents
+    /// remember; 0 on the legacy engine.
+    pub average_memory_utilization: f64,
+}
+
+/// Signed change between two `SimulationStats`, `newer - older`.
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct SimulationStatsDiff {
+    pub agent_count_delta: i64,
+    pub resource_count_delta: i64,
+    pub energy_delta: f64,
+    pub avg_age_delta: f64,
+    pub avg_speed_delta: f64,
+    pub avg_size_delta: f64,
+    pub avg_aggression_delta: f64,
+    pub avg_sense_range_delta: f64,
+    pub avg_energy_efficiency_delta: f64,
+    pub max_generation_delta: i64,
+    pub avg_generation_delta: f64,
+    pub total_kills_delta: i64,
+    pub total_pack_kills_delta: i64,
+    pub avg_fitness_delta: f64,
+    pub species_count_delta: i64,
+    pub largest_species_size_delta: i64,
+    pub largest_species_fraction_delta: f64,
+    pub avg_reproduction_suppression_delta: f64,
+    pub resource_competition_index_delta: f64,
+    pub avg_stamina_delta: f64,
+    pub avg_mutation_strength_delta: f64,
+    pub age_distribution_delta: [i64; AGE_BUCKETS],
+    pub avg_trophic_level_delta: f64,
+    pub apex_predator_count_delta: i64,
+    pub trophic_diversity_delta: f64,
+    pub energy_shared_delta: f64,
+    pub avg_predator_satiation_delta: f64,
+    pub avg_perception_error_delta: f64,
+    pub avg_memory_utilization_delta: f64,
+    /// Largest `|newer - older| / max(|newer|, |older|)` over all fields, in [0, 1].
+    pub max_normalized_change: f64,
+}
+
+impl SimulationStatsDiff {
+    /// Whether any field moved by more than `threshold` relative to its size.
+    pub fn significant_change(&self, threshold: f64) -> bool {
+        self.max_normalized_change > threshold
+    }
+}
+
+impl SimulationStats {
+    /// Change from `other` (the earlier frame) to `self`.
+    pub fn diff(&self, other: &SimulationStats) -> SimulationStatsDiff {
+        let max_normalized_change = std::cell::Cell::new(0.0_f64);
+        let delta = |newer: f64, older: f64| -> f64 {
+            let scale = newer.abs().max(older.abs());
+            if scale > 0.0 {
+                let change = (newer - older).abs() / scale;
+                max_normalized_change.set(max_normalized_change.get().max(change));
+            }
+            newer - older
+        };
+        let count_delta = |newer: u64, older: u64| -> i64 {
+            delta(newer as f64, older as f64);
+            newer as i64 - older as i64
+        };
+
+        let age_distribution_delta = std::array::from_fn(|bucket| {
+            count_delta(
+                self.age_distribution[bucket] as u64,
+                other.age_distribution[bucket] as u64,
+            )
+        });
+
+        SimulationStatsDiff {
+            agent_count_delta: count_delta(self.agent_count as u64, other.agent_count as u64),
+            resource_count_delta: count_delta(
+                self.resource_count as u64,
+                other.resource_count as u64,
+            ),
+            max_generation_delta: count_delta(
+                self.max_generation as u64,
+                other.max_generation as u64,
+            ),
+            avg_generation_delta: delta(self.average_generation, other.average_generation),
+            total_kills_delta: count_delta(self.total_kills as u64, other.total_kills as u64),
+            total_pack_kills_delta: count_delta(
+                self.total_pack_kills as u64,
+                other.total_pack_kills as u64,
+            ),
+            species_count_delta: count_delta(
+                self.species_count as u64,
+                other.species_count as u64,
+            ),
+            largest_species_size_delta: count_delta(
+                self.largest_species_size as u64,
+                other.largest_species_size as u64,
+            ),
+            apex_predator_count_delta: count_delta(
+                self.apex_predator_count as u64,
+                other.apex_predator_count as u64,
+            ),
+            age_distribution_delta,
+            energy_delta: delta(self.total_energy, other.total_energy),
+            avg_age_delta: delta(self.average_age, other.average_age),
+            avg_speed_delta: delta(self.average_speed, other.average_speed),
+            avg_size_delta: delta(self.average_size, other.average_size),
+            avg_aggression_delta: delta(self.average_aggression, other.average_aggression),
+            avg_sense_range_delta: delta(self.average_sense_range, other.average_sense_range),
+            avg_energy_efficiency_delta: delta(
+                self.average_energy_efficiency,
+                other.average_energy_efficiency,
+            ),
+            avg_fitness_delta: delta(self.average_fitness, other.average_fitness),
+            largest_species_fraction_delta: delta(
+                self.largest_species_fraction,
+                other.largest_species_fraction,
+            ),
+            avg_reproduction_suppression_delta: delta(
+                self.average_reproduction_suppression,
+                other.average_reproduction_suppression,
+            ),
+            resource_competition_index_delta: delta(
+                self.resource_competition_index,
+                other.resource_competition_index,
+            ),
+            avg_stamina_delta: delta(self.average_stamina, other.average_stamina),
+            avg_mutation_strength_delta: delta(
+                self.average_mutation_strength,
+                other.average_mutation_strength,
+            ),
+            avg_trophic_level_delta: delta(self.average_trophic_level, other.average_trophic_level),
+            trophic_diversity_delta: delta(self.trophic_diversity, other.trophic_diversity),
+            energy_shared_delta: delta(self.total_energy_shared, other.total_energy_shared),
+            avg_predator_satiation_delta: delta(
+                self.average_predator_satiation,
+                other.average_predator_satiation,
+            ),
+            avg_perception_error_delta: delta(
+                self.average_perception_error,
+                other.average_perception_error,
+            ),
+            avg_memory_utilization_delta: delta(
+                self.average_memory_utilization,
+                other.average_memory_utilization,
+            ),
+            max_normalized_change: max_normalized_change.get(),
+        }
+    }
+}
+
+/// Per-agent totals behind the averages in `SimulationStats`. Built from one
+/// agent with `From` and merged with `combine`, so the agents can be summed
+/// on one thread or split across the rayon pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AgentAccumulator {
+    pub count: usize,
+    pub total_energy: f64,
+    pub age: f64,
+    pub speed: f64,
+    pub size: f64,
+    pub aggression: f64,
+    pub sense_range: f64,
+    pub energy_efficiency: f64,
+    pub max_generation: u32,
+    pub generation: f64,
+    pub total_kills: u32,
+    /// Energy as a fraction of capacity.
+    pub fitness: f64,
+    pub reproduction_suppression: f64,
+    pub stamina: f64,
+    pub mutation_strength: f64,
+    pub perception_error: f64,
+    pub predator_count: usize,
+    pub predator_satiation: f64,
+}
+
+impl From<&Agent> for AgentAccumulator {
+    fn from(agent: &Agent) -> Self {
+        let is_predator = agent.is_predator();
+        Self {
+            count: 1,
+            total_energy: agent.energy,
+            age: agent.age,
+            speed: agent.genes.speed,
+            size: agent.genes.size,
+            aggression: agent.genes.aggression,
+            sense_range: agent.genes.sense_range,
+            energy_efficiency: agent.genes.energy_efficiency,
+            max_generation: agent.generation,
+            generation: agent.generation as f64,
+            total_kills: agent.kills,
+            fitness: agent.energy / agent.max_energy,
+            reproduction_suppression: 1.0 - agent.phenotype.reproduction_scale,
+            stamina: agent.current_stamina,
+            mutation_strength: agent.genes.mutation_strength,
+            perception_error: agent.perception_error,
+            predator_count: usize::from(is_predator),
+            predator_satiation: if is_predator { agent.satiation } else { 0.0 },
+        }
+    }
+}
+
+impl AgentAccumulator {
+    pub fn combine(self, other: AgentAccumulator) -> AgentAccumulator {
+        AgentAccumulator {
+            count: self.count + other.count,
+            total_energy: self.total_energy + other.total_energy,
+            age: self.age + other.age,
+            speed: self.speed + other.speed,
+            size: self.size + other.size,
+            aggression: self.aggression + other.aggression,
+            sense_range: self.sense_range + other.sense_range,
+            energy_efficiency: self.energy_efficiency + other.energy_efficiency,
+            max_generation: self.max_generation.max(other.max_generation),
+            generation: self.generation + other.generation,
+            total_kills: self.total_kills + other.total_kills,
+            fitness: self.fitness + other.fitness,
+            reproduction_suppression: self.reproduction_suppression
+                + other.reproduction_suppression,
+            stamina: self.stamina + other.stamina,
+            mutation_strength: self.mutation_strength + other.mutation_strength,
+            perception_error: self.perception_error + other.perception_error,
+            predator_count: self.predator_count + other.predator_count,
+            predator_satiation: self.predator_satiation + other.predator_satiation,
+        }
+    }
+}
+
+/// `AgentAccumulator` totals of `agents`, summed in order on this thread.
+pub fn accumulate_agent_stats(agents: &[Agent]) -> AgentAccumulator {
+    agents
+        .iter()
+        .map(AgentAccumulator::from)
+        .fold(AgentAccumulator::default(), AgentAccumulator::combine)
+}
+
+/// `accumulate_agent_stats` split across the rayon pool. Sums may differ
+/// from the sequential ones in the last bits, as they're added in a
+/// different order.
+pub fn accumulate_agent_stats_parallel(agents: &[Agent]) -> AgentAccumulator {
+    agents
+        .par_iter()
+        .map(AgentAccumulator::from)
+        .reduce(AgentAccumulator::default, AgentAccumulator::combine)
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SimulationConfig {
     pub width: f64,
     pub height: f64,
@@ -42,6 +381,52 @@ pub struct SimulationConfig {
     pub min_agent_count: usize,
     pub max_agent_count: usize,
     pub use_ecs: bool, // Whether to use ECS or legacy simulation
+    pub boundary_behavior: BoundaryBehavior,
+    /// Length of one boom-bust cycle of resource spawning; 0 disables seasons.
+    pub season_period_seconds: f64,
+    /// Fractional swing of the spawn rate around `resource_spawn_rate`.
+    /// Above 1, spawning stops entirely for part of each winter.
+    pub season_amplitude: f64,
+    /// Record agent interactions for `UnifiedSimulation::get_interaction_log`.
+    pub log_interactions: bool,
+    /// Index the legacy engine uses for neighbour lookups.
+    pub spatial_backend: SpatialBackend,
+    /// Resize the legacy engine's neighbour index cells to the population
+    /// every few seconds instead of keeping them at 50 units.
+    pub adaptive_spatial_grid: bool,
+    /// Genetic distance below which agents count as one species, for tagging
+    /// and for `SimulationStats::species_count`.
+    pub speciation_threshold: f64,
+    /// Most deaths kept for `UnifiedSimulation::get_death_history`.
+    pub max_death_history: usize,
+    /// Let the legacy engine's resources drift apart when crowded and
+    /// together when far apart, forming patches.
+    pub resource_dynamics: bool,
+    /// Shape of the steps offspring genes mutate by; see
+    /// `MutationDistribution`.
+    pub mutation_distribution: MutationDistribution,
+    /// Where the initial population's genes are drawn from.
+    pub initial_gene_distribution: InitialGeneDistribution,
+    /// Teleporters in place from the start and after every `clear`; more can
+    /// be added with `UnifiedSimulation::add_teleporter`.
+    pub teleporters: Vec<Teleporter>,
+    /// Founders spawned instead of `initial_agents` random agents when
+    /// non-empty.
+    pub agent_archetypes: Vec<AgentArchetype>,
+    /// Charge ECS agents Kleiber's size^0.75 and quadratic drag on speed
+    /// instead of linear costs; see `metabolic_cost`.
+    pub use_allometric_scaling: bool,
+    /// In headless runs, bring in `rescue_agent_count` random immigrants
+    /// whenever the population drops below `min_agent_count`, instead of
+    /// ending the run there.
+    pub rescue_on_near_extinction: bool,
+    pub rescue_agent_count: usize,
+    /// Draw every random number from a `StdRng` seeded with this, so the
+    /// same config replays the same run; `None` seeds from the OS.
+    pub seed: Option<u64>,
+    /// Regions where grown resources spawn more or less often and hold more
+    /// or less energy; `None` spreads them evenly.
+    pub biome_map: Option<BiomeMap>,
 }
 
 impl Default for SimulationConfig {
@@ -59,17 +444,418 @@ impl Default for SimulationConfig {
             min_agent_count: 10,
             max_agent_count: 3000,
             use_ecs: true,
+            boundary_behavior: BoundaryBehavior::Wrap,
+            season_period_seconds: 60.0,
+            season_amplitude: 0.5,
+            log_interactions: false,
+            spatial_backend: SpatialBackend::Grid,
+            adaptive_spatial_grid: false,
+            speciation_threshold: DEFAULT_SPECIES_THRESHOLD,
+            max_death_history: DEFAULT_MAX_DEATH_HISTORY,
+            resource_dynamics: false,
+            mutation_distribution: MutationDistribution::default(),
+            initial_gene_distribution: InitialGeneDistribution::Uniform,
+            teleporters: Vec::new(),
+            agent_archetypes: Vec::new(),
+            use_allometric_scaling: false,
+            rescue_on_near_extinction: false,
+            rescue_agent_count: 50,
+            seed: None,
+            biome_map: None,
+        }
+    }
+}
+
+/// Smallest world, on either axis, that `SimulationConfig::validate` accepts.
+pub const MIN_CANVAS_SIZE: f64 = 100.0;
+
+/// One problem found by `SimulationConfig::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    InitialAgentsExceedMax { initial: usize, max: usize },
+    CanvasTooSmall { width: f64, height: f64 },
+    InvalidSpawnRate(f64),
+    StabilityThresholdOutOfRange(f64),
+    InvalidMutationDistribution(MutationDistribution),
+    InvalidInitialGeneDistribution(String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InitialAgentsExceedMax { initial, max } => write!(
+                f,
+                "initial_agents ({}) exceeds max_agents ({})",
+                initial, max
+            ),
+            ConfigError::CanvasTooSmall { width, height } => write!(
+                f,
+                "canvas {}x{} is smaller than {}x{}",
+                width, height, MIN_CANVAS_SIZE, MIN_CANVAS_SIZE
+            ),
+            ConfigError::InvalidSpawnRate(rate) => write!(
+                f,
+                "resource_spawn_rate ({}) must be a finite, non-negative number",
+                rate
+            ),
+            ConfigError::StabilityThresholdOutOfRange(threshold) => write!(
+                f,
+                "stability_threshold ({}) must be between 0 and 1",
+                threshold
+            ),
+            ConfigError::InvalidMutationDistribution(distribution) => write!(
+                f,
+                "mutation_distribution ({:?}) must have a finite, positive width",
+                distribution
+            ),
+            ConfigError::InvalidInitialGeneDistribution(reason) => {
+                write!(f, "initial_gene_distribution: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// All of `errors` on one line, for hosts that can only report a string.
+pub fn format_config_errors(errors: &[ConfigError]) -> String {
+    let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    format!("Invalid simulation config: {}", messages.join("; "))
+}
+
+/// Why `UnifiedSimulation::restore` rejected a snapshot.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimulationError {
+    /// The snapshot was written with a different `SNAPSHOT_VERSION`.
+    SnapshotVersionMismatch { found: u32, expected: u32 },
+    InvalidEcsWorld(EcsImportError),
+}
+
+impl std::fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::SnapshotVersionMismatch { found, expected } => write!(
+                f,
+                "snapshot version {} doesn't match version {}",
+                found, expected
+            ),
+            SimulationError::InvalidEcsWorld(error) => write!(f, "invalid ECS world: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+impl SimulationConfig {
+    /// Checks every rule and reports all violations, not just the first.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        if self.initial_agent_count() > self.max_agents {
+            errors.push(ConfigError::InitialAgentsExceedMax {
+                initial: self.initial_agent_count(),
+                max: self.max_agents,
+            });
+        }
+        // Negated so NaN sizes are rejected too
+        if !(self.width >= MIN_CANVAS_SIZE && self.height >= MIN_CANVAS_SIZE) {
+            errors.push(ConfigError::CanvasTooSmall {
+                width: self.width,
+                height: self.height,
+            });
         }
+        if !self.resource_spawn_rate.is_finite() || self.resource_spawn_rate < 0.0 {
+            errors.push(ConfigError::InvalidSpawnRate(self.resource_spawn_rate));
+        }
+        if !(0.0..=1.0).contains(&self.stability_threshold) {
+            errors.push(ConfigError::StabilityThresholdOutOfRange(self.stability_threshold));
+        }
+        if !self.mutation_distribution.is_valid() {
+            errors.push(ConfigError::InvalidMutationDistribution(self.mutation_distribution));
+        }
+        if let Err(reason) = self.initial_gene_distribution.validate() {
+            errors.push(ConfigError::InvalidInitialGeneDistribution(reason));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Agents in the initial population: the archetypes' total when any are
+    /// set, `initial_agents` otherwise.
+    pub fn initial_agent_count(&self) -> usize {
+        if self.agent_archetypes.is_empty() {
+            self.initial_agents
+        } else {
+            self.agent_archetypes.iter().map(|a| a.count).sum()
+        }
+    }
+
+    /// Multiplier on `resource_spawn_rate` at `time` seconds into the run,
+    /// never negative.
+    pub fn season_factor(&self, time: f64) -> f64 {
+        if self.season_period_seconds <= 0.0 {
+            return 1.0;
+        }
+        let angle = 2.0 * std::f64::consts::PI * time / self.season_period_seconds;
+        (1.0 + self.season_amplitude * angle.sin()).max(0.0)
+    }
+
+    /// Position within the current season in [0, 1); spawning peaks at 0.25.
+    pub fn season_phase(&self, time: f64) -> f64 {
+        if self.season_period_seconds <= 0.0 {
+            return 0.0;
+        }
+        (time / self.season_period_seconds).rem_euclid(1.0)
+    }
+
+    /// 1 at the height of summer, which coincides with the spawning peak,
+    /// falling to 0 in deepest winter half a season later. Always 1 without
+    /// seasons.
+    pub fn warmth(&self, time: f64) -> f64 {
+        if self.season_period_seconds <= 0.0 {
+            return 1.0;
+        }
+        let angle = 2.0 * std::f64::consts::PI * (self.season_phase(time) - 0.25);
+        0.5 + 0.5 * angle.cos()
+    }
+
+    /// Whole seasons that have passed `time` seconds into the run.
+    pub fn season_cycles_completed(&self, time: f64) -> u32 {
+        if self.season_period_seconds <= 0.0 {
+            return 0;
+        }
+        (time / self.season_period_seconds).floor() as u32
+    }
+}
+
+/// Config fields that can change mid-run; `None` leaves a field as it is.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfigDelta {
+    pub resource_spawn_rate: Option<f64>,
+    pub max_agents: Option<usize>,
+    pub max_resources: Option<usize>,
+    pub stability_threshold: Option<f64>,
+    pub min_agent_count: Option<usize>,
+    pub max_agent_count: Option<usize>,
+    pub season_period_seconds: Option<f64>,
+    pub season_amplitude: Option<f64>,
+}
+
+impl SimulationConfigDelta {
+    /// Copies every field that is set onto `config`.
+    pub fn apply_to(&self, config: &mut SimulationConfig) {
+        if let Some(rate) = self.resource_spawn_rate {
+            config.resource_spawn_rate = rate;
+        }
+        if let Some(max) = self.max_agents {
+            config.max_agents = max;
+        }
+        if let Some(max) = self.max_resources {
+            config.max_resources = max;
+        }
+        if let Some(threshold) = self.stability_threshold {
+            config.stability_threshold = threshold;
+        }
+        if let Some(min) = self.min_agent_count {
+            config.min_agent_count = min;
+        }
+        if let Some(max) = self.max_agent_count {
+            config.max_agent_count = max;
+        }
+        if let Some(period) = self.season_period_seconds {
+            config.season_period_seconds = period;
+        }
+        if let Some(amplitude) = self.season_amplitude {
+            config.season_amplitude = amplitude;
+        }
+    }
+}
+
+/// Builds a `SimulationConfig` from the defaults, checked by `validate` on
+/// the way out.
+#[derive(Clone, Default)]
+pub struct SimulationConfigBuilder {
+    config: SimulationConfig,
+}
+
+impl SimulationConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn size(mut self, width: f64, height: f64) -> Self {
+        self.config.width = width;
+        self.config.height = height;
+        self
+    }
+
+    pub fn initial_agents(mut self, count: usize) -> Self {
+        self.config.initial_agents = count;
+        self
+    }
+
+    pub fn initial_resources(mut self, count: usize) -> Self {
+        self.config.initial_resources = count;
+        self
     }
+
+    pub fn max_agents(mut self, max: usize) -> Self {
+        self.config.max_agents = max;
+        self
+    }
+
+    pub fn resource_spawn_rate(mut self, rate: f64) -> Self {
+        self.config.resource_spawn_rate = rate;
+        self
+    }
+
+    pub fn stability_threshold(mut self, threshold: f64) -> Self {
+        self.config.stability_threshold = threshold;
+        self
+    }
+
+    pub fn mutation_distribution(mut self, distribution: MutationDistribution) -> Self {
+        self.config.mutation_distribution = distribution;
+        self
+    }
+
+    pub fn use_ecs(mut self, use_ecs: bool) -> Self {
+        self.config.use_ecs = use_ecs;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// The config, or every rule it breaks.
+    pub fn build(self) -> Result<SimulationConfig, Vec<ConfigError>> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Notable things that happened to a `UnifiedSimulation`, oldest first.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum SimulationEvent {
+    ConfigChanged(SimulationConfigDelta),
 }
 
-pub trait SimulationEngine {
+/// An outside change to a `UnifiedSimulation`. Applying changes through
+/// `UnifiedSimulation::apply_command` records them in its command log, so a
+/// run can be audited and replayed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SimulationCommand {
+    AddAgent { x: f64, y: f64 },
+    AddResource { x: f64, y: f64 },
+    TriggerFamine { steps: usize },
+    SetConfig { delta: SimulationConfigDelta },
+    Reset,
+}
+
+/// Rough memory footprint of an engine, for spotting growth over long runs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct MemoryStats {
+    /// See `estimated_heap_bytes`.
+    pub heap_used_bytes: u32,
+    /// 0 for engines that don't keep agents in a `Vec`.
+    pub agent_vec_capacity: usize,
+    /// 0 for engines that don't keep resources in a `Vec`.
+    pub resource_vec_capacity: usize,
+    /// 0 for engines without a spatial grid.
+    pub spatial_grid_cells: usize,
+    /// 64 KiB pages of wasm linear memory; 0 outside the browser.
+    pub wasm_memory_pages: u32,
+    /// Agents and resources, plus the ECS world's singletons.
+    pub entity_count: usize,
+}
+
+/// Bytes taken by `agents` agents and `resources` resources themselves,
+/// ignoring anything they point to on the heap.
+pub fn estimated_heap_bytes(agents: usize, resources: usize) -> u32 {
+    let bytes = agents * std::mem::size_of::<Agent>() + resources * std::mem::size_of::<Resource>();
+    bytes.try_into().unwrap_or(u32::MAX)
+}
+
+pub trait SimulationEngine: Send + Sync {
     fn update(&mut self);
     fn add_agent(&mut self, x: f64, y: f64);
+    fn add_agent_with_genes(&mut self, x: f64, y: f64, genes: Genes);
     fn add_resource(&mut self, x: f64, y: f64);
+    /// Leaves a decomposing deposit where an agent died; `energy` is the
+    /// agent's final energy times its size.
+    fn spawn_death_resource(&mut self, x: f64, y: f64, energy: f64);
     fn reset(&mut self);
+    fn clear(&mut self);
+    /// Restarts the engine's random stream from `seed`, or from the OS for
+    /// `None`.
+    fn set_seed(&mut self, seed: Option<u64>);
+    /// The engine's random stream, for callers building on its population.
+    fn rng(&mut self) -> &mut SimulationRng;
+    fn cull_agents(&mut self, survival_fraction: f64);
+    /// Replaces the whole population with exact copies of `agents` and `resources`.
+    fn restore(&mut self, agents: Vec<Agent>, resources: Vec<Resource>);
+    /// What a snapshot needs beyond the agents and resources to carry on
+    /// exactly where this engine is.
+    fn save_state(&self) -> EngineState;
+    /// Takes back `save_state` output after `restore`. State saved by the
+    /// other engine is ignored.
+    fn load_state(&mut self, state: EngineState) -> Result<(), SimulationError>;
+    fn add_habitat(&mut self, habitat: Habitat);
+    fn get_habitats(&self) -> Vec<Habitat>;
+    /// Routes between the habitats, rebuilt whenever one is added or removed.
+    fn connectivity_graph(&self) -> &ConnectivityGraph;
+    fn add_teleporter(&mut self, teleporter: Teleporter);
+    /// Every teleporter with its running `teleportations` count.
+    fn get_teleporters(&self) -> Vec<Teleporter>;
+    fn set_boundary_behavior(&mut self, boundary: BoundaryBehavior);
+    /// Switches to `config` without touching agents or resources.
+    fn apply_config(&mut self, config: SimulationConfig);
+    fn season_phase(&self) -> f64;
+    /// `SimulationConfig::warmth` now.
+    fn warmth(&self) -> f64;
+    fn season_cycles_completed(&self) -> u32;
     fn get_stats(&self) -> SimulationStats;
+    fn agent_count(&self) -> usize;
+    fn resource_count(&self) -> usize;
+    /// Resources currently on the map that were left by dead agents.
+    fn death_resource_count(&self) -> usize;
+    /// Times an agent has copied part of a neighbour's genome since the
+    /// engine was created. Zero for engines without social learning.
+    fn social_learning_events(&self) -> u64;
+    /// Agents killed by poisonous resources since the last `clear`.
+    fn poison_deaths(&self) -> u32;
+    /// Recently consumed resource energy per spatial grid cell, indexed
+    /// `[x][y]`. Empty for engines that don't track feeding.
+    fn energy_heatmap(&self) -> &[Vec<f64>];
+    /// Toxin left by recent kills. `None` for engines that don't model it.
+    fn toxin_grid(&self) -> Option<&ToxinGrid>;
+    /// Cell size of the neighbour index. `None` for engines without one.
+    fn spatial_cell_size(&self) -> Option<f64>;
+    fn memory_stats(&self) -> MemoryStats;
+    /// The most recent interactions, oldest first, if `log_interactions` is
+    /// set.
+    fn interaction_log(&self) -> &[InteractionLog];
+    /// Up to `max_death_history` of the latest deaths, oldest first. `None`
+    /// for engines that don't record them.
+    fn death_history(&self) -> Option<&VecDeque<DeathRecord>>;
+    /// Stops periodic resource spawning for the next `duration_steps` updates.
+    fn trigger_famine(&mut self, duration_steps: usize);
+    fn famine(&self) -> &Famine;
     fn get_agents(&self) -> Vec<Agent>;
+    /// The agents without copying them, for engines that already keep a
+    /// `Vec<Agent>`. `None` means `get_agents` has to build one.
+    fn agent_slice(&self) -> Option<&[Agent]> {
+        None
+    }
+    /// The agent whose ECS `StableId` is `stable_id`. `None` for engines
+    /// without stable IDs.
+    fn find_agent(&self, stable_id: u64) -> Option<Agent>;
     fn get_resources(&self) -> Vec<Resource>;
     fn get_config(&self) -> &SimulationConfig;
 }
@@ -78,16 +864,86 @@ pub struct EcsSimulationEngine {
     ecs_world: EcsWorld,
     config: SimulationConfig,
     time: f64,
+    /// The ECS spawns on its own fixed interval; this is the current
+    /// `resource_spawn_rate` relative to the one the engine started with.
+    spawn_rate_scale: f64,
+    step: usize,
+    famine: Famine,
+    teleporters: Vec<Teleporter>,
+    /// Kept in step with the world's habitats; ECS migrants keep to their
+    /// compass heading rather than routing along it.
+    connectivity_graph: ConnectivityGraph,
+    /// Seeds the world's own stream and serves `SimulationEngine::rng`.
+    rng: SimulationRng,
+    interactions: InteractionLogBuffer,
+    /// Refreshed every `SPECIATION_INTERVAL` steps.
+    species: SpeciesCache,
+    /// Resource energy eaten per `HEATMAP_CELL_SIZE` cell, indexed `[x][y]`.
+    energy_consumption_grid: Vec<Vec<f64>>,
+    death_history: DeathHistory,
 }
 
 impl EcsSimulationEngine {
     pub fn new(config: SimulationConfig) -> Self {
-        let ecs_world = EcsWorld::new(config.width, config.height);
+        let mut rng = simulation_rng(config.seed);
+        let mut ecs_world = EcsWorld::with_initial_genes(
+            config.width,
+            config.height,
+            config.initial_gene_distribution.clone(),
+            SimulationRng::seed_from_u64(rng.gen()),
+        );
+        ecs_world.set_limits(config.max_agents, config.max_resources);
+        ecs_world.set_boundary_behavior(config.boundary_behavior);
+        ecs_world.set_biome_map(config.biome_map.clone());
+        ecs_world.set_archetypes(config.agent_archetypes.clone());
+        if !config.agent_archetypes.is_empty() || config.biome_map.is_some() {
+            ecs_world.reset();
+        }
+        let teleporters = config.teleporters.clone();
+        let grid_width = (config.width / HEATMAP_CELL_SIZE).ceil() as usize;
+        let grid_height = (config.height / HEATMAP_CELL_SIZE).ceil() as usize;
+        let energy_consumption_grid = vec![vec![0.0; grid_height]; grid_width];
 
         Self {
             ecs_world,
             config,
             time: 0.0,
+            spawn_rate_scale: 1.0,
+            step: 0,
+            famine: Famine::default(),
+            teleporters,
+            connectivity_graph: ConnectivityGraph::default(),
+            rng,
+            interactions: InteractionLogBuffer::default(),
+            species: SpeciesCache::default(),
+            energy_consumption_grid,
+            death_history: DeathHistory::default(),
+        }
+    }
+
+    /// Fades the heatmap by `HEATMAP_DECAY` and adds what the world's agents
+    /// ate this tick.
+    fn record_consumption(&mut self) {
+        for cell in self.energy_consumption_grid.iter_mut().flatten() {
+            *cell *= HEATMAP_DECAY;
+        }
+        let (columns, rows) = (
+            self.energy_consumption_grid.len(),
+            self.energy_consumption_grid.first().map_or(0, Vec::len),
+        );
+        if columns == 0 || rows == 0 {
+            return;
+        }
+        for (x, y, energy) in self.ecs_world.take_consumption() {
+            let grid_x = ((x / HEATMAP_CELL_SIZE).floor() as usize).min(columns - 1);
+            let grid_y = ((y / HEATMAP_CELL_SIZE).floor() as usize).min(rows - 1);
+            self.energy_consumption_grid[grid_x][grid_y] += energy;
+        }
+    }
+
+    fn clear_heatmap(&mut self) {
+        for cell in self.energy_consumption_grid.iter_mut().flatten() {
+            *cell = 0.0;
         }
     }
 
@@ -100,13 +956,62 @@ impl EcsSimulationEngine {
             RAYON_INITIALIZED = initialized;
         }
     }
+
+    /// Frames whose resources were updated on the rayon pool since the
+    /// engine was last cleared.
+    pub fn rayon_resource_updates(&self) -> u64 {
+        self.ecs_world.parallel_resource_update_frames()
+    }
 }
 
 impl SimulationEngine for EcsSimulationEngine {
     fn update(&mut self) {
         let delta_time = 1.0 / 60.0;
         self.time += delta_time;
+        self.step += 1;
+        let spawn_scale = if self.famine.is_active() {
+            0.0
+        } else {
+            self.config.season_factor(self.time) * self.spawn_rate_scale
+        };
+        self.ecs_world.set_resource_spawn_scale(spawn_scale);
+        self.ecs_world.set_warmth(self.config.warmth(self.time));
+        self.ecs_world
+            .set_parallel_resource_update(Self::is_rayon_available());
+        self.ecs_world
+            .set_allometric_scaling(self.config.use_allometric_scaling);
+        self.ecs_world
+            .set_interaction_logging(self.config.log_interactions);
         self.ecs_world.update();
+        self.record_consumption();
+        for death in self.ecs_world.take_deaths() {
+            let record = DeathRecord {
+                step: self.step,
+                x: death.x,
+                y: death.y,
+                age: death.age,
+                generation: death.generation,
+                reason: from_ecs_death_reason(&death.reason),
+                is_predator: death.is_predator,
+            };
+            self.death_history
+                .push(record, self.config.max_death_history);
+        }
+        for entry in self.ecs_world.take_interactions() {
+            self.interactions.push(InteractionLog {
+                step: self.step,
+                ..entry
+            });
+        }
+        if !self.teleporters.is_empty() {
+            self.ecs_world.teleport_agents(&mut self.teleporters);
+        }
+        if self.step.is_multiple_of(SPECIATION_INTERVAL) {
+            self.ecs_world
+                .assign_species_ids(self.config.speciation_threshold);
+        }
+        let (resources, agents) = (self.resource_count(), self.agent_count());
+        self.famine.tick(self.step, resources, agents);
     }
 
     fn add_agent(&mut self, x: f64, y: f64) {
@@ -115,74 +1020,169 @@ impl SimulationEngine for EcsSimulationEngine {
         }
     }
 
+    fn add_agent_with_genes(&mut self, x: f64, y: f64, genes: Genes) {
+        if self.ecs_world.get_agent_count() < self.config.max_agents {
+            self.ecs_world.add_agent_with_genes(x, y, to_ecs_genes(&genes));
+        }
+    }
+
     fn add_resource(&mut self, x: f64, y: f64) {
         if self.ecs_world.get_resource_count() < self.config.max_resources {
             self.ecs_world.add_resource(x, y);
         }
     }
 
+    fn spawn_death_resource(&mut self, x: f64, y: f64, energy: f64) {
+        if self.ecs_world.get_resource_count() < self.config.max_resources {
+            self.ecs_world.add_death_resource(x, y, energy);
+        }
+    }
+
     fn reset(&mut self) {
         self.ecs_world.reset();
         self.time = 0.0;
+        self.step = 0;
+        self.famine.clear();
+        self.teleporters = self.config.teleporters.clone();
+        self.connectivity_graph = ConnectivityGraph::default();
+        self.species.invalidate();
+        self.clear_heatmap();
+        self.death_history.clear();
     }
 
-    fn get_stats(&self) -> SimulationStats {
-        let agent_count = self.ecs_world.get_agent_count();
-        let resource_count = self.ecs_world.get_resource_count();
+    fn clear(&mut self) {
+        self.ecs_world.clear();
+        self.time = 0.0;
+        self.step = 0;
+        self.famine.clear();
+        self.teleporters = self.config.teleporters.clone();
+        self.connectivity_graph = ConnectivityGraph::default();
+        self.interactions.clear();
+        self.species.invalidate();
+        self.clear_heatmap();
+        self.death_history.clear();
+    }
 
-        if agent_count == 0 {
-            return SimulationStats {
-                agent_count: 0,
-                resource_count,
-                total_energy: 0.0,
-                average_age: 0.0,
-                average_speed: 0.0,
-                average_size: 0.0,
-                average_aggression: 0.0,
-                average_sense_range: 0.0,
-                average_energy_efficiency: 0.0,
-                max_generation: 0,
-                total_kills: 0,
-                average_fitness: 0.0,
-            };
+    fn set_seed(&mut self, seed: Option<u64>) {
+        self.rng = simulation_rng(seed);
+        self.ecs_world.set_rng(SimulationRng::seed_from_u64(self.rng.gen()));
+    }
+
+    fn rng(&mut self) -> &mut SimulationRng {
+        &mut self.rng
+    }
+
+    fn cull_agents(&mut self, survival_fraction: f64) {
+        self.ecs_world.cull_agents(survival_fraction);
+        self.species.invalidate();
+    }
+
+    fn restore(&mut self, agents: Vec<Agent>, resources: Vec<Resource>) {
+        let habitats = self.ecs_world.get_habitats();
+        self.ecs_world.clear();
+        for habitat in habitats {
+            self.ecs_world.add_habitat(habitat);
         }
+        let world = &mut self.ecs_world.world;
 
-        // Get all agents for statistics
-        let agents = self.get_agents();
+        for agent in agents {
+            world.spawn((
+                Position {
+                    x: agent.x,
+                    y: agent.y,
+                },
+                Velocity {
+                    dx: agent.dx,
+                    dy: agent.dy,
+                },
+                Energy {
+                    current: agent.energy,
+                    max: agent.max_energy,
+                },
+                Age { value: agent.age },
+                to_ecs_genes(&agent.genes),
+                EcsAgentState {
+                    state: to_ecs_state(&agent.state),
+                    target_x: agent.target_x,
+                    target_y: agent.target_y,
+                    target_id: None,
+                    last_reproduction: agent.last_reproduction,
+                    kills: agent.kills,
+                    generation: agent.generation,
+                    pack_kills: 0,
+                    migrating_direction: agent.migrating_direction,
+                    last_migration_time: agent.last_migration_time,
+                    phenotype: agent.phenotype.clone(),
+                    current_stamina: agent.current_stamina,
+                    satiation: agent.satiation,
+                    perception_error: agent.perception_error,
+                    trophic_level: agent.trophic_level,
+                },
+                Memory::default(),
+                DeathAnimation {
+                    fade: agent.death_fade,
+                    reason: agent
+                        .death_reason
+                        .as_ref()
+                        .map_or(EcsDeathReason::NaturalCauses, to_ecs_death_reason),
+                    is_dying: agent.is_dying,
+                },
+                SpawnAnimation {
+                    fade: agent.spawn_fade,
+                    spawn_position: agent.spawn_position,
+                },
+                Size {
+                    value: agent.genes.size * 3.0,
+                },
+                AgentTag,
+                StableId::next(),
+                SpeciesId(agent.species_id),
+            ));
+        }
 
-        let total_energy: f64 = agents.iter().map(|a| a.energy).sum();
-        let average_age: f64 = agents.iter().map(|a| a.age).sum::<f64>() / agent_count as f64;
-        let average_speed: f64 =
-            agents.iter().map(|a| a.genes.speed).sum::<f64>() / agent_count as f64;
-        let average_size: f64 =
-            agents.iter().map(|a| a.genes.size).sum::<f64>() / agent_count as f64;
-        let average_aggression: f64 =
-            agents.iter().map(|a| a.genes.aggression).sum::<f64>() / agent_count as f64;
-        let average_sense_range: f64 =
-            agents.iter().map(|a| a.genes.sense_range).sum::<f64>() / agent_count as f64;
-        let average_energy_efficiency: f64 = agents
-            .iter()
-            .map(|a| a.genes.energy_efficiency)
-            .sum::<f64>()
-            / agent_count as f64;
-        let max_generation = agents.iter().map(|a| a.generation).max().unwrap_or(0);
-        let total_kills: u32 = agents.iter().map(|a| a.kills).sum();
-        let average_fitness: f64 =
-            agents.iter().map(|a| a.energy / a.max_energy).sum::<f64>() / agent_count as f64;
+        for resource in resources {
+            world.spawn((
+                Position {
+                    x: resource.x,
+                    y: resource.y,
+                },
+                EcsResource {
+                    energy: resource.energy,
+                    max_energy: resource.max_energy,
+                    size: resource.size,
+                    growth_rate: resource.growth_rate,
+                    regeneration_rate: resource.regeneration_rate,
+                    age: resource.age,
+                    target_energy: resource.target_energy,
+                    is_spawning: resource.is_spawning,
+                    spawn_fade: resource.spawn_fade,
+                    is_depleting: resource.is_depleting,
+                    deplete_fade: resource.deplete_fade,
+                    is_carcass: resource.is_carcass,
+                    resource_type: resource.resource_type,
+                    energy_delta: resource.energy_delta,
+                },
+                Size {
+                    value: resource.size,
+                },
+                ResourceTag,
+                StableId::next(),
+            ));
+        }
+        self.ecs_world.rebuild_stable_id_index();
+        self.ecs_world.reserve_species_ids();
+        self.species.invalidate();
+    }
 
+    fn get_stats(&self) -> SimulationStats {
+        let species = self.species.get(self.step / SPECIATION_INTERVAL, || {
+            self.ecs_world.species_summary(self.config.speciation_threshold)
+        });
         SimulationStats {
-            agent_count,
-            resource_count,
-            total_energy,
-            average_age,
-            average_speed,
-            average_size,
-            average_aggression,
-            average_sense_range,
-            average_energy_efficiency,
-            max_generation,
-            total_kills,
-            average_fitness,
+            species_count: species.species_count,
+            largest_species_size: species.largest_species_size,
+            largest_species_fraction: species.largest_species_fraction,
+            ..self.ecs_world.compute_stats()
         }
     }
 
@@ -191,56 +1191,15 @@ impl SimulationEngine for EcsSimulationEngine {
         self.ecs_world
             .get_agents()
             .into_iter()
-            .map(|(pos, vel, energy, age, state, genes, _size)| Agent {
-                x: pos.x,
-                y: pos.y,
-                dx: vel.dx,
-                dy: vel.dy,
-                energy: energy.current,
-                max_energy: energy.max,
-                age: age.value,
-                genes: Genes {
-                    speed: genes.speed,
-                    sense_range: genes.sense_range,
-                    size: genes.size,
-                    energy_efficiency: genes.energy_efficiency,
-                    reproduction_threshold: genes.reproduction_threshold,
-                    mutation_rate: genes.mutation_rate,
-                    aggression: genes.aggression,
-                    color_hue: genes.color_hue,
-                    is_predator: genes.is_predator,
-                    hunting_speed: genes.hunting_speed,
-                    attack_power: genes.attack_power,
-                    defense: genes.defense,
-                    stealth: genes.stealth,
-                    pack_mentality: genes.pack_mentality,
-                    territory_size: genes.territory_size,
-                    metabolism: genes.metabolism,
-                    intelligence: genes.intelligence,
-                    stamina: genes.stamina,
-                },
-                target_x: state.target_x,
-                target_y: state.target_y,
-                state: match state.state {
-                    AgentStateEnum::Seeking => AgentState::Seeking,
-                    AgentStateEnum::Hunting => AgentState::Hunting,
-                    AgentStateEnum::Feeding => AgentState::Feeding,
-                    AgentStateEnum::Reproducing => AgentState::Reproducing,
-                    AgentStateEnum::Fighting => AgentState::Fighting,
-                    AgentStateEnum::Fleeing => AgentState::Fleeing,
-                },
-                last_reproduction: state.last_reproduction,
-                kills: state.kills,
-                generation: state.generation,
-                death_fade: 0.0,
-                death_reason: None,
-                is_dying: false,
-                spawn_fade: 0.0,
-                spawn_position: None,
-            })
+            .map(from_ecs_agent)
             .collect()
     }
 
+    fn find_agent(&self, stable_id: u64) -> Option<Agent> {
+        let entity = self.ecs_world.get_agent_by_stable_id(stable_id)?;
+        self.ecs_world.get_agent(entity).map(from_ecs_agent)
+    }
+
     fn get_resources(&self) -> Vec<Resource> {
         // Convert ECS resources to legacy Resource format for compatibility
         self.ecs_world
@@ -260,122 +1219,467 @@ impl SimulationEngine for EcsSimulationEngine {
                 spawn_fade: ecs_resource.spawn_fade,
                 is_depleting: ecs_resource.is_depleting,
                 deplete_fade: ecs_resource.deplete_fade,
+                is_carcass: ecs_resource.is_carcass,
+                toxin: 0.0,
+                resource_type: ecs_resource.resource_type,
+                energy_delta: ecs_resource.energy_delta,
             })
             .collect()
     }
 
-    fn get_config(&self) -> &SimulationConfig {
-        &self.config
+    fn save_state(&self) -> EngineState {
+        EngineState::Ecs {
+            time: self.time,
+            step: self.step,
+            spawn_rate_scale: self.spawn_rate_scale,
+            world: self.ecs_world.to_json(),
+        }
     }
-}
 
-pub struct LegacySimulationEngine {
-    agents: Vec<Agent>,
-    resources: Vec<Resource>,
-    config: SimulationConfig,
-    time: f64,
-    resource_spawn_timer: f64,
-    grid_cell_size: f64,
-    grid_width: usize,
-    grid_height: usize,
-    spatial_grid: Vec<Vec<Vec<usize>>>,
-}
+    fn load_state(&mut self, state: EngineState) -> Result<(), SimulationError> {
+        let EngineState::Ecs {
+            time,
+            step,
+            spawn_rate_scale,
+            world,
+        } = state
+        else {
+            return Ok(());
+        };
+        let mut ecs_world = EcsWorld::from_json(&world).map_err(SimulationError::InvalidEcsWorld)?;
+        ecs_world.set_initial_genes(self.config.initial_gene_distribution.clone());
+        ecs_world.set_archetypes(self.config.agent_archetypes.clone());
+        self.ecs_world = ecs_world;
+        self.time = time;
+        self.step = step;
+        self.spawn_rate_scale = spawn_rate_scale;
+        self.species.invalidate();
+        Ok(())
+    }
 
-impl LegacySimulationEngine {
-    pub fn new(config: SimulationConfig) -> Self {
-        let grid_cell_size = 50.0;
-        let grid_width = (config.width / grid_cell_size).ceil() as usize;
-        let grid_height = (config.height / grid_cell_size).ceil() as usize;
-        let spatial_grid = vec![vec![Vec::new(); grid_height]; grid_width];
+    fn add_habitat(&mut self, habitat: Habitat) {
+        self.ecs_world.add_habitat(habitat);
+        self.connectivity_graph = ConnectivityGraph::new(&self.ecs_world.get_habitats());
+    }
 
-        let mut engine = Self {
-            agents: Vec::new(),
-            resources: Vec::new(),
-            config,
-            time: 0.0,
-            resource_spawn_timer: 0.0,
-            grid_cell_size,
-            grid_width,
-            grid_height,
-            spatial_grid,
-        };
+    fn get_habitats(&self) -> Vec<Habitat> {
+        self.ecs_world.get_habitats()
+    }
 
-        engine.spawn_initial_population();
-        engine
+    fn connectivity_graph(&self) -> &ConnectivityGraph {
+        &self.connectivity_graph
     }
 
-    fn get_grid_position(&self, x: f64, y: f64) -> (usize, usize) {
-        let grid_x = (x / self.grid_cell_size).floor() as usize;
-        let grid_y = (y / self.grid_cell_size).floor() as usize;
-        (
-            grid_x.min(self.grid_width - 1),
-            grid_y.min(self.grid_height - 1),
-        )
+    fn add_teleporter(&mut self, teleporter: Teleporter) {
+        self.teleporters.push(teleporter);
     }
 
-    fn get_nearby_agents(&self, x: f64, y: f64, radius: f64) -> Vec<usize> {
-        let mut nearby = Vec::new();
-        let (center_x, center_y) = self.get_grid_position(x, y);
-
-        // Check current cell and adjacent cells
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                let check_x = center_x as i32 + dx;
-                let check_y = center_y as i32 + dy;
-
-                if check_x >= 0
-                    && check_x < self.grid_width as i32
-                    && check_y >= 0
-                    && check_y < self.grid_height as i32
+    fn get_teleporters(&self) -> Vec<Teleporter> {
+        self.teleporters.clone()
+    }
+
+    fn set_boundary_behavior(&mut self, boundary: BoundaryBehavior) {
+        self.config.boundary_behavior = boundary;
+        self.ecs_world.set_boundary_behavior(boundary);
+    }
+
+    fn apply_config(&mut self, config: SimulationConfig) {
+        if self.config.resource_spawn_rate > 0.0 {
+            self.spawn_rate_scale *= config.resource_spawn_rate / self.config.resource_spawn_rate;
+        }
+        self.ecs_world.set_limits(config.max_agents, config.max_resources);
+        self.ecs_world.set_archetypes(config.agent_archetypes.clone());
+        self.ecs_world.set_biome_map(config.biome_map.clone());
+        self.config = config;
+    }
+
+    fn season_phase(&self) -> f64 {
+        self.config.season_phase(self.time)
+    }
+
+    fn warmth(&self) -> f64 {
+        self.config.warmth(self.time)
+    }
+
+    fn season_cycles_completed(&self) -> u32 {
+        self.config.season_cycles_completed(self.time)
+    }
+
+    fn agent_count(&self) -> usize {
+        self.ecs_world.get_agent_count()
+    }
+
+    fn resource_count(&self) -> usize {
+        self.ecs_world.get_resource_count()
+    }
+
+    fn death_resource_count(&self) -> usize {
+        self.ecs_world.get_death_resource_count()
+    }
+
+    fn social_learning_events(&self) -> u64 {
+        0
+    }
+
+    fn poison_deaths(&self) -> u32 {
+        self.ecs_world.poison_deaths()
+    }
+
+    // ECS agents don't feed from grown resources, so this maps the poison
+    // they blunder into
+    fn energy_heatmap(&self) -> &[Vec<f64>] {
+        &self.energy_consumption_grid
+    }
+
+    fn toxin_grid(&self) -> Option<&ToxinGrid> {
+        None
+    }
+
+    fn spatial_cell_size(&self) -> Option<f64> {
+        None
+    }
+
+    fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            heap_used_bytes: estimated_heap_bytes(self.agent_count(), self.resource_count()),
+            entity_count: self.ecs_world.entity_count(),
+            ..MemoryStats::default()
+        }
+    }
+
+    fn interaction_log(&self) -> &[InteractionLog] {
+        self.interactions.as_slice()
+    }
+
+    fn death_history(&self) -> Option<&VecDeque<DeathRecord>> {
+        Some(self.death_history.records())
+    }
+
+    fn trigger_famine(&mut self, duration_steps: usize) {
+        self.famine.trigger(self.step, duration_steps);
+    }
+
+    fn famine(&self) -> &Famine {
+        &self.famine
+    }
+
+    fn get_config(&self) -> &SimulationConfig {
+        &self.config
+    }
+}
+
+pub struct LegacySimulationEngine {
+    agents: Vec<Agent>,
+    resources: Vec<Resource>,
+    config: SimulationConfig,
+    time: f64,
+    resource_spawn_timer: f64,
+    grid_cell_size: f64,
+    grid_width: usize,
+    grid_height: usize,
+    /// Cell size of `spatial_index`; starts at `grid_cell_size` and only
+    /// changes through `adapt_spatial_grid`.
+    spatial_cell_size: f64,
+    spatial_index: Box<dyn SpatialIndex>,
+    energy_consumption_grid: Vec<Vec<f64>>,
+    toxin_grid: ToxinGrid,
+    step: usize,
+    interactions: InteractionLogBuffer,
+    death_history: DeathHistory,
+    famine: Famine,
+    resource_dynamics: ResourceDynamics,
+    population_avg_attack: f64,
+    population_avg_defense: f64,
+    habitats: Vec<Habitat>,
+    connectivity_graph: ConnectivityGraph,
+    teleporters: Vec<Teleporter>,
+    social_learning_events: u64,
+    /// Next fresh ID handed out by `assign_species_ids`.
+    next_species_id: u32,
+    /// Energy cooperators have given to hungry kin since the last `clear`.
+    total_energy_shared: f64,
+    poison_deaths: u32,
+    rng: SimulationRng,
+    /// Refreshed every `SPECIATION_INTERVAL` steps.
+    species: SpeciesCache,
+}
+
+impl LegacySimulationEngine {
+    pub fn new(config: SimulationConfig) -> Self {
+        let rng = simulation_rng(config.seed);
+        let grid_cell_size = 50.0;
+        let grid_width = (config.width / grid_cell_size).ceil() as usize;
+        let grid_height = (config.height / grid_cell_size).ceil() as usize;
+        let spatial_index =
+            config.spatial_backend.build(config.width, config.height, grid_cell_size);
+        let energy_consumption_grid = vec![vec![0.0; grid_height]; grid_width];
+        let toxin_grid = ToxinGrid::new(config.width, config.height, grid_cell_size);
+        let resource_dynamics = ResourceDynamics::new(config.width, config.height);
+        let teleporters = config.teleporters.clone();
+
+        let mut engine = Self {
+            agents: Vec::new(),
+            resources: Vec::new(),
+            config,
+            time: 0.0,
+            resource_spawn_timer: 0.0,
+            grid_cell_size,
+            grid_width,
+            grid_height,
+            spatial_cell_size: grid_cell_size,
+            spatial_index,
+            energy_consumption_grid,
+            toxin_grid,
+            step: 0,
+            interactions: InteractionLogBuffer::default(),
+            death_history: DeathHistory::default(),
+            famine: Famine::default(),
+            resource_dynamics,
+            population_avg_attack: 0.0,
+            population_avg_defense: 0.0,
+            habitats: Vec::new(),
+            connectivity_graph: ConnectivityGraph::default(),
+            teleporters,
+            social_learning_events: 0,
+            next_species_id: 1,
+            total_energy_shared: 0.0,
+            poison_deaths: 0,
+            rng,
+            species: SpeciesCache::default(),
+        };
+
+        engine.spawn_initial_population();
+        engine
+    }
+
+    fn get_grid_position(&self, x: f64, y: f64) -> (usize, usize) {
+        let grid_x = (x / self.grid_cell_size).floor() as usize;
+        let grid_y = (y / self.grid_cell_size).floor() as usize;
+        (
+            grid_x.min(self.grid_width - 1),
+            grid_y.min(self.grid_height - 1),
+        )
+    }
+
+    fn get_nearby_agents(&self, x: f64, y: f64, radius: f64) -> Vec<usize> {
+        self.spatial_index
+            .query_radius(x, y, radius)
+            .into_iter()
+            .filter(|&agent_idx| agent_idx < self.agents.len())
+            .collect()
+    }
+
+    fn update_spatial_grid(&mut self) {
+        self.spatial_index.clear();
+        for (i, agent) in self.agents.iter().enumerate() {
+            self.spatial_index.insert(agent.x, agent.y, i);
+        }
+    }
+
+    /// Rebuilds the neighbour index with `sqrt(area / agents)` cells, about one
+    /// agent per cell, if that differs from the current size by more than
+    /// `SPATIAL_ADAPT_TOLERANCE`. Returns whether the index was rebuilt.
+    pub fn adapt_spatial_grid(&mut self) -> bool {
+        if self.agents.is_empty() {
+            return false;
+        }
+        let area = self.config.width * self.config.height;
+        let optimal = (area / self.agents.len() as f64)
+            .sqrt()
+            .clamp(MIN_SPATIAL_CELL_SIZE, self.config.width.max(self.config.height));
+        let tolerance = self.spatial_cell_size * SPATIAL_ADAPT_TOLERANCE;
+        if (optimal - self.spatial_cell_size).abs() <= tolerance {
+            return false;
+        }
+
+        let (width, height) = (self.config.width, self.config.height);
+        self.spatial_cell_size = optimal;
+        self.spatial_index = self.config.spatial_backend.build(width, height, optimal);
+        self.update_spatial_grid();
+        true
+    }
+
+    /// Lets agents near a resource draw energy from it, recording what was
+    /// eaten in `energy_consumption_grid`. A poisonous resource is used up by
+    /// the first agent to reach it, through `Agent::eat_poison`; neutral ones
+    /// aren't eaten, and agents skip resources they've learned to avoid.
+    /// Returns the indices of every agent that ate, in ascending order.
+    fn feed_agents(&mut self, delta_time: f64) -> Vec<usize> {
+        let mut fed = Vec::new();
+        for i in 0..self.resources.len() {
+            let (x, y) = (self.resources[i].x, self.resources[i].y);
+            let (grid_x, grid_y) = self.get_grid_position(x, y);
+            for agent_idx in self.get_nearby_agents(x, y, FEEDING_RADIUS) {
+                let resource = &mut self.resources[i];
+                if resource.energy <= 0.0
+                    || resource.is_depleting
+                    || resource.resource_type == ResourceType::Neutral
                 {
-                    let cell_agents = &self.spatial_grid[check_x as usize][check_y as usize];
-                    for &agent_idx in cell_agents {
-                        if agent_idx < self.agents.len() {
-                            let agent = &self.agents[agent_idx];
-                            let distance = ((agent.x - x).powi(2) + (agent.y - y).powi(2)).sqrt();
-                            if distance <= radius {
-                                nearby.push(agent_idx);
-                            }
-                        }
-                    }
+                    break;
+                }
+                if self.agents[agent_idx].avoids_poison_at(x, y) {
+                    continue;
+                }
+                if resource.resource_type == ResourceType::Poison {
+                    resource.consume(resource.energy);
+                    self.agents[agent_idx].eat_poison(x, y, resource.energy_delta);
+                    break;
+                }
+                // Patches at or below an agent's R* aren't worth its while
+                if resource.energy <= self.agents[agent_idx].r_star {
+                    continue;
+                }
+                let consumed = resource.consume(FEEDING_RATE * delta_time);
+                let resource_id = resource.id();
+                let agent = &mut self.agents[agent_idx];
+                let energy_before = agent.energy;
+                agent.energy = (agent.energy + consumed).min(agent.max_energy);
+                self.energy_consumption_grid[grid_x][grid_y] += consumed;
+                fed.push(agent_idx);
+
+                if self.config.log_interactions {
+                    self.interactions.push(InteractionLog {
+                        step: self.step,
+                        actor_id: agent.id(),
+                        target_id: resource_id,
+                        interaction_type: InteractionType::Feeding,
+                        energy_change: agent.energy - energy_before,
+                    });
                 }
             }
         }
-        nearby
+        fed.sort_unstable();
+        fed.dedup();
+        fed
     }
 
-    fn update_spatial_grid(&mut self) {
-        // Clear grid
-        for row in &mut self.spatial_grid {
-            for cell in row {
-                cell.clear();
+    /// Each cooperator in `fed` may feed one hungry relative, through
+    /// `Agent::share_energy` on a snapshot of its neighbours.
+    fn share_food(&mut self, fed: &[usize]) {
+        for &i in fed {
+            let donor = &self.agents[i];
+            if !donor.is_cooperator() {
+                continue;
+            }
+            let nearby: Vec<usize> = self
+                .get_nearby_agents(donor.x, donor.y, SHARE_RADIUS)
+                .into_iter()
+                .filter(|&j| j != i)
+                .collect();
+            let mut neighbors: Vec<Agent> =
+                nearby.iter().map(|&j| self.agents[j].clone()).collect();
+
+            let shared = self.agents[i].share_energy(&mut neighbors);
+            if shared > 0.0 {
+                for (j, neighbor) in nearby.into_iter().zip(neighbors) {
+                    self.agents[j].energy = neighbor.energy;
+                }
+                self.total_energy_shared += shared;
             }
         }
+    }
 
-        // Rebuild grid
-        for (i, agent) in self.agents.iter().enumerate() {
-            let (grid_x, grid_y) = self.get_grid_position(agent.x, agent.y);
-            if grid_x < self.grid_width && grid_y < self.grid_height {
-                self.spatial_grid[grid_x][grid_y].push(i);
+    /// Agents holding more energy than their `reproduction_threshold` gene
+    /// breed with the nearest agent they can sense, through
+    /// `Agent::create_offspring`, and hand half their energy to the child.
+    /// No child is born into a habitat already at its carrying capacity.
+    fn breed_agents(&mut self) {
+        let mut offspring = Vec::new();
+        for i in 0..self.agents.len() {
+            if self.agents.len() + offspring.len() >= self.config.max_agents {
+                break;
+            }
+            let parent = &self.agents[i];
+            if parent.energy < parent.genes.reproduction_threshold || !parent.can_reproduce() {
+                continue;
+            }
+            let mate = self
+                .get_nearby_agents(parent.x, parent.y, parent.genes.sense_range)
+                .into_iter()
+                .filter(|&j| j != i)
+                .min_by(|&a, &b| {
+                    let distance = |j: usize| self.agents[j].distance_to(parent.x, parent.y);
+                    distance(a).total_cmp(&distance(b))
+                });
+            let Some(mate) = mate else {
+                continue;
+            };
+
+            let mut child = parent.create_offspring(
+                &self.agents[mate],
+                self.config.mutation_distribution,
+                self.population_avg_attack,
+                self.population_avg_defense,
+                &mut self.rng,
+            );
+            if self.habitat_is_full(child.x, child.y, &offspring) {
+                continue;
             }
+            let mate_id = self.agents[mate].id();
+            let parent = &mut self.agents[i];
+            child.energy = parent.energy / 2.0;
+            parent.energy -= child.energy;
+            parent.last_reproduction = parent.age;
+            if self.config.log_interactions {
+                self.interactions.push(InteractionLog {
+                    step: self.step,
+                    actor_id: parent.id(),
+                    target_id: mate_id,
+                    interaction_type: InteractionType::Reproduction,
+                    energy_change: -child.energy,
+                });
+            }
+            offspring.push(child);
         }
+        self.agents.extend(offspring);
     }
 
-    fn spawn_initial_population(&mut self) {
-        let mut rng = rand::thread_rng();
+    /// Whether (x, y) lies in a habitat already holding its carrying
+    /// capacity, counting the `offspring` born so far this step.
+    fn habitat_is_full(&self, x: f64, y: f64, offspring: &[Agent]) -> bool {
+        self.habitats
+            .iter()
+            .filter(|habitat| habitat.contains(x, y))
+            .any(|habitat| {
+                let occupants = self
+                    .agents
+                    .iter()
+                    .chain(offspring)
+                    .filter(|agent| habitat.contains(agent.x, agent.y))
+                    .count();
+                occupants >= habitat.carrying_capacity
+            })
+    }
 
+    fn spawn_initial_population(&mut self) {
         // Spawn initial agents
-        for _ in 0..self.config.initial_agents {
-            let x = rng.gen_range(0.0..self.config.width);
-            let y = rng.gen_range(0.0..self.config.height);
-            self.add_agent(x, y);
+        if self.config.agent_archetypes.is_empty() {
+            for _ in 0..self.config.initial_agents {
+                let x = self.rng.gen_range(0.0..self.config.width);
+                let y = self.rng.gen_range(0.0..self.config.height);
+                let genes = sample_genes(&self.config.initial_gene_distribution, &mut self.rng);
+                self.add_agent_with_genes(x, y, genes);
+            }
+        } else {
+            let mut founders = Vec::new();
+            spawn_archetypes(
+                &self.config.agent_archetypes,
+                self.config.width,
+                self.config.height,
+                &mut self.rng,
+                |x, y, genes, energy| founders.push((x, y, genes, energy)),
+            );
+            let room = self.config.max_agents.saturating_sub(self.agents.len());
+            for (x, y, genes, energy) in founders.into_iter().take(room) {
+                let mut agent = Agent::random(x, y, genes, 1, &mut self.rng);
+                agent.energy = energy;
+                self.agents.push(agent);
+            }
         }
 
         // Spawn initial resources
         for _ in 0..self.config.initial_resources {
-            let x = rng.gen_range(0.0..self.config.width);
-            let y = rng.gen_range(0.0..self.config.height);
-            self.add_resource(x, y);
+            self.spawn_resource();
         }
 
         self.update_spatial_grid();
@@ -383,15 +1687,195 @@ impl LegacySimulationEngine {
 
     fn spawn_resource(&mut self) {
         if self.resources.len() < self.config.max_resources {
-            let mut rng = rand::thread_rng();
-            let x = rng.gen_range(0.0..self.config.width);
-            let y = rng.gen_range(0.0..self.config.height);
-            self.add_resource(x, y);
+            let x = self.rng.gen_range(0.0..self.config.width);
+            let y = self.rng.gen_range(0.0..self.config.height);
+            let accepted = match &self.config.biome_map {
+                Some(biome_map) => biome_map.accepts_spawn(x, y, &mut self.rng),
+                None => true,
+            };
+            if accepted {
+                let count = self.resources.len();
+                self.add_resource(x, y);
+                if self.resources.len() > count && self.rng.gen_bool(POISON_CHANCE) {
+                    self.resources[count].make_poisonous();
+                }
+            }
+        }
+    }
+
+    /// Mean predator attack and prey defense as of the last update, passed to
+    /// `Agent::create_offspring` by `breed_agents`.
+    pub fn arms_race_averages(&self) -> (f64, f64) {
+        (self.population_avg_attack, self.population_avg_defense)
+    }
+
+    /// Agents whose age just crossed a multiple of `SOCIAL_LEARNING_INTERVAL`
+    /// try to learn from a snapshot of the neighbours in the spatial index.
+    fn social_learning(&mut self, delta_time: f64) {
+        for i in 0..self.agents.len() {
+            let agent = &self.agents[i];
+            if agent.age % SOCIAL_LEARNING_INTERVAL >= delta_time || !agent.is_social_learner() {
+                continue;
+            }
+            let neighbors: Vec<Agent> = self
+                .get_nearby_agents(agent.x, agent.y, agent.genes.sense_range)
+                .into_iter()
+                .filter(|&j| j != i)
+                .map(|j| self.agents[j].clone())
+                .collect();
+            if self.agents[i].learn_from_neighbors(&neighbors, &mut self.rng) {
+                self.social_learning_events += 1;
+            }
+        }
+    }
+
+    /// Sets each agent's `phenotype` from its neighbours in the spatial
+    /// index and the resources around it; see `PhenotypeModifiers::around`.
+    fn express_phenotypes(&mut self) {
+        for i in 0..self.agents.len() {
+            let (x, y) = (self.agents[i].x, self.agents[i].y);
+            let neighbours = self
+                .get_nearby_agents(x, y, PhenotypeModifiers::CROWDING_RADIUS)
+                .into_iter()
+                .filter(|&j| j != i)
+                .map(|j| (self.agents[j].x, self.agents[j].y));
+            let resources = self.resources.iter().map(|r| (r.x, r.y, r.energy));
+            let phenotype = PhenotypeModifiers::around(x, y, neighbours, resources);
+            self.agents[i].phenotype = phenotype;
+        }
+    }
+
+    /// Agents with a strong `migration` gene set off on their compass
+    /// heading every `MIGRATION_INTERVAL`; see `Agent::update_migration`.
+    fn migrate_agents(&mut self) {
+        for agent in &mut self.agents {
+            agent.update_migration();
+        }
+    }
+
+    /// Prey that sense a predator, and agents already fleeing, run from the
+    /// predators in their `sense_range` through `Agent::flee_from_danger` on
+    /// a snapshot of them from the spatial index, each where the prey
+    /// perceives it (see `perceived_position`).
+    fn flee_predators(&mut self, delta_time: f64) {
+        for i in 0..self.agents.len() {
+            let agent = &self.agents[i];
+            let fleeing = agent.state == AgentState::Fleeing;
+            if !fleeing && !agent.is_prey() {
+                continue;
+            }
+            let mut error = (f64::INFINITY, 0.0);
+            let predators: Vec<Agent> = self
+                .get_nearby_agents(agent.x, agent.y, agent.genes.sense_range)
+                .into_iter()
+                .filter(|&j| j != i && self.agents[j].is_predator())
+                .map(|j| {
+                    let mut predator = self.agents[j].clone();
+                    let distance = predator.distance_to(agent.x, agent.y);
+                    let (x, y) = perceived_position(
+                        predator.x,
+                        predator.y,
+                        distance,
+                        agent.genes.sense_range,
+                        agent.genes.intelligence,
+                        &mut self.rng,
+                    );
+                    // Remember how far off the nearest one seemed
+                    if distance < error.0 {
+                        error = (distance, (x - predator.x).hypot(y - predator.y));
+                    }
+                    (predator.x, predator.y) = (x, y);
+                    predator
+                })
+                .collect();
+            if predators.is_empty() && !fleeing {
+                continue;
+            }
+
+            let agent = &mut self.agents[i];
+            agent.flee_from_danger(delta_time, &predators);
+            if !predators.is_empty() {
+                agent.perception_error = error.1;
+            }
+            if !fleeing && agent.state == AgentState::Fleeing && self.config.log_interactions {
+                let nearest = predators.iter().min_by(|a, b| {
+                    let distance = |other: &Agent| other.distance_to(agent.x, agent.y);
+                    distance(a).total_cmp(&distance(b))
+                });
+                if let Some(predator) = nearest {
+                    self.interactions.push(InteractionLog {
+                        step: self.step,
+                        actor_id: agent.id(),
+                        target_id: predator.id(),
+                        interaction_type: InteractionType::Fleeing,
+                        energy_change: 0.0,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Moves agents along their heading, handling the world's edges by
+    /// `boundary_behavior`, and drains or restores their stamina. Agents
+    /// that cross a lethal edge are left with no energy for
+    /// `cleanup_dead_agents`.
+    fn move_agents(&mut self, delta_time: f64) {
+        let (width, height) = (self.config.width, self.config.height);
+        let boundary = self.config.boundary_behavior;
+        for agent in &mut self.agents {
+            agent.update_stamina(delta_time);
+            if !agent.move_agent(delta_time, width, height, boundary, &mut self.rng) {
+                agent.energy = 0.0;
+                agent.death_reason = Some(DeathReason::FellOffEdge);
+            }
+        }
+        self.update_spatial_grid();
+    }
+
+    /// Agents inside a teleporter entrance reappear beside its exit.
+    fn teleport_agents(&mut self) {
+        if self.teleporters.is_empty() {
+            return;
+        }
+        for agent in &mut self.agents {
+            teleport(&mut self.teleporters, &mut agent.x, &mut agent.y, &mut self.rng);
+        }
+    }
+
+    /// Agents standing in toxic cells lose energy, less so with high defense.
+    fn poison_agents(&mut self, delta_time: f64) {
+        for agent in &mut self.agents {
+            let level = self.toxin_grid.at(agent.x, agent.y);
+            agent.energy -= toxin_damage(level, agent.genes.defense) * delta_time;
         }
     }
 
     fn cleanup_dead_agents(&mut self) {
-        self.agents.retain(|agent| agent.energy > 0.0);
+        let step = self.step;
+        let capacity = self.config.max_death_history;
+        let death_history = &mut self.death_history;
+        let poison_deaths = &mut self.poison_deaths;
+        self.agents.retain(|agent| {
+            let alive = agent.energy > 0.0 && !agent.is_dying;
+            if !alive {
+                let reason = agent.death_reason.clone().unwrap_or(DeathReason::Starvation);
+                if reason == DeathReason::Poisoned {
+                    *poison_deaths += 1;
+                }
+                tracing::debug!("Agent {} died: {:?}", agent.id(), reason);
+                let record = DeathRecord {
+                    step,
+                    x: agent.x,
+                    y: agent.y,
+                    age: agent.age,
+                    generation: agent.generation,
+                    reason,
+                    is_predator: agent.is_predator(),
+                };
+                death_history.push(record, capacity);
+            }
+            alive
+        });
     }
 
     fn cleanup_depleted_resources(&mut self) {
@@ -403,53 +1887,197 @@ impl SimulationEngine for LegacySimulationEngine {
     fn update(&mut self) {
         let delta_time = 1.0 / 60.0;
         self.time += delta_time;
+        self.step += 1;
         self.resource_spawn_timer += delta_time;
 
-        // Spawn resources periodically
-        if self.resource_spawn_timer >= 1.0 / self.config.resource_spawn_rate {
+        // Spawn resources periodically, faster in the boom half of each season
+        // and not at all during a famine
+        let spawn_rate = self.config.resource_spawn_rate * self.config.season_factor(self.time);
+        if self.resource_spawn_timer >= 1.0 / spawn_rate && !self.famine.is_active() {
             self.spawn_resource();
             self.resource_spawn_timer = 0.0;
         }
 
-        // Update agents and resources (simplified for now)
+        // Update agents and resources (simplified for now); winter costs
+        // energy and slows regrowth
+        let warmth = self.config.warmth(self.time);
+        let metabolic_scale = seasonal_metabolic_scale(warmth);
+        self.express_phenotypes();
         for agent in &mut self.agents {
             agent.age += delta_time;
-            agent.energy -= delta_time * 0.1; // Basic energy consumption
+            // Basic energy consumption, raised by scarcity
+            agent.energy -= delta_time * 0.1 * metabolic_scale * agent.phenotype.metabolism_scale;
+            agent.satiation = digest(agent.satiation, agent.genes.metabolism, delta_time);
+            if agent.energy > 0.0 {
+                agent.check_mortality(delta_time, &mut self.rng);
+            }
         }
+        self.migrate_agents();
+        self.flee_predators(delta_time);
+        self.move_agents(delta_time);
+        self.teleport_agents();
+        route_migrants(&mut self.agents, &self.habitats, &self.connectivity_graph);
+        self.social_learning(delta_time);
 
         for resource in &mut self.resources {
             resource.age += delta_time;
             if resource.energy < resource.max_energy {
-                resource.energy += delta_time * resource.regeneration_rate;
+                resource.energy +=
+                    delta_time * resource.regeneration_rate * seasonal_growth_scale(warmth);
             }
         }
 
+        self.toxin_grid.decay();
+        self.poison_agents(delta_time);
+        for resource in &mut self.resources {
+            resource.toxin = self.toxin_grid.at(resource.x, resource.y);
+        }
+
+        for cell in self.energy_consumption_grid.iter_mut().flatten() {
+            *cell *= HEATMAP_DECAY;
+        }
+        let fed = self.feed_agents(delta_time);
+        self.share_food(&fed);
+        self.breed_agents();
+
         self.cleanup_dead_agents();
         self.cleanup_depleted_resources();
         self.update_spatial_grid();
+        if self.config.adaptive_spatial_grid && self.step.is_multiple_of(SPATIAL_ADAPT_INTERVAL) {
+            self.adapt_spatial_grid();
+        }
+        if self.config.resource_dynamics && self.step.is_multiple_of(RESOURCE_DYNAMICS_INTERVAL) {
+            self.resource_dynamics.apply(&mut self.resources);
+        }
+        if self.step.is_multiple_of(SPECIATION_INTERVAL) {
+            let threshold = self.config.speciation_threshold;
+            assign_species_ids(&mut self.agents, threshold, &mut self.next_species_id);
+        }
+
+        (self.population_avg_attack, self.population_avg_defense) =
+            arms_race_averages(self.agents.iter().map(|a| &a.genes));
+        self.famine.tick(self.step, self.resources.len(), self.agents.len());
     }
 
     fn add_agent(&mut self, x: f64, y: f64) {
+        let genes = Genes::random(&mut self.rng);
+        self.add_agent_with_genes(x, y, genes);
+    }
+
+    fn add_agent_with_genes(&mut self, x: f64, y: f64, genes: Genes) {
         if self.agents.len() < self.config.max_agents {
-            let genes = Genes::new();
-            let agent = Agent::new(x, y, genes, 1);
+            let agent = Agent::random(x, y, genes, 1, &mut self.rng);
             self.agents.push(agent);
         }
     }
 
     fn add_resource(&mut self, x: f64, y: f64) {
         if self.resources.len() < self.config.max_resources {
-            let resource = Resource::new(x, y);
+            let mut resource = Resource::random(x, y, &mut self.rng);
+            if let Some(biome_map) = &self.config.biome_map {
+                let multiplier = biome_map.energy_multiplier_at(x, y);
+                resource.max_energy *= multiplier;
+                resource.growth_rate *= multiplier;
+                resource.regeneration_rate *= multiplier;
+            }
             self.resources.push(resource);
         }
     }
 
+    fn spawn_death_resource(&mut self, x: f64, y: f64, energy: f64) {
+        if energy > 0.0 && self.resources.len() < self.config.max_resources {
+            self.resources.push(Resource::carcass(x, y, energy, &mut self.rng));
+        }
+    }
+
     fn reset(&mut self) {
+        self.clear();
+        self.spawn_initial_population();
+    }
+
+    fn clear(&mut self) {
         self.agents.clear();
         self.resources.clear();
+        self.habitats.clear();
+        self.connectivity_graph = ConnectivityGraph::default();
+        self.teleporters = self.config.teleporters.clone();
         self.time = 0.0;
+        self.step = 0;
         self.resource_spawn_timer = 0.0;
-        self.spawn_initial_population();
+        for cell in self.energy_consumption_grid.iter_mut().flatten() {
+            *cell = 0.0;
+        }
+        self.toxin_grid.clear();
+        self.interactions.clear();
+        self.death_history.clear();
+        self.famine.clear();
+        self.total_energy_shared = 0.0;
+        self.poison_deaths = 0;
+        self.species.invalidate();
+        self.update_spatial_grid();
+    }
+
+    fn set_seed(&mut self, seed: Option<u64>) {
+        self.rng = simulation_rng(seed);
+    }
+
+    fn rng(&mut self) -> &mut SimulationRng {
+        &mut self.rng
+    }
+
+    fn cull_agents(&mut self, survival_fraction: f64) {
+        let survivors =
+            (self.agents.len() as f64 * survival_fraction.clamp(0.0, 1.0)).round() as usize;
+        self.agents.shuffle(&mut self.rng);
+        let culled: Vec<Agent> = self.agents.drain(survivors.min(self.agents.len())..).collect();
+        for agent in culled {
+            self.toxin_grid.add_toxin(agent.x, agent.y, TOXIN_PER_KILL);
+            self.spawn_death_resource(agent.x, agent.y, agent.energy * agent.genes.size);
+        }
+        self.species.invalidate();
+        self.update_spatial_grid();
+    }
+
+    fn restore(&mut self, agents: Vec<Agent>, resources: Vec<Resource>) {
+        self.agents = agents;
+        self.resources = resources;
+        self.species.invalidate();
+        self.update_spatial_grid();
+    }
+
+    fn save_state(&self) -> EngineState {
+        EngineState::Legacy {
+            time: self.time,
+            step: self.step,
+            resource_spawn_timer: self.resource_spawn_timer,
+            social_learning_events: self.social_learning_events,
+            next_species_id: self.next_species_id,
+            total_energy_shared: self.total_energy_shared,
+            poison_deaths: self.poison_deaths,
+        }
+    }
+
+    fn load_state(&mut self, state: EngineState) -> Result<(), SimulationError> {
+        if let EngineState::Legacy {
+            time,
+            step,
+            resource_spawn_timer,
+            social_learning_events,
+            next_species_id,
+            total_energy_shared,
+            poison_deaths,
+        } = state
+        {
+            self.time = time;
+            self.step = step;
+            self.resource_spawn_timer = resource_spawn_timer;
+            self.social_learning_events = social_learning_events;
+            self.next_species_id = next_species_id;
+            self.total_energy_shared = total_energy_shared;
+            self.poison_deaths = poison_deaths;
+            self.species.invalidate();
+        }
+        Ok(())
     }
 
     fn get_stats(&self) -> SimulationStats {
@@ -468,49 +2096,81 @@ impl SimulationEngine for LegacySimulationEngine {
                 average_sense_range: 0.0,
                 average_energy_efficiency: 0.0,
                 max_generation: 0,
+                average_generation: 0.0,
                 total_kills: 0,
+                total_pack_kills: 0,
                 average_fitness: 0.0,
+                species_count: 0,
+                largest_species_size: 0,
+                largest_species_fraction: 0.0,
+                average_reproduction_suppression: 0.0,
+                resource_competition_index: 0.0,
+                average_stamina: 0.0,
+                average_mutation_strength: 0.0,
+                age_distribution: [0; AGE_BUCKETS],
+                average_trophic_level: 0.0,
+                apex_predator_count: 0,
+                trophic_diversity: 0.0,
+                total_energy_shared: self.total_energy_shared,
+                average_predator_satiation: 0.0,
+                average_perception_error: 0.0,
+                average_memory_utilization: 0.0,
             };
         }
 
-        let total_energy: f64 = self.agents.iter().map(|a| a.energy).sum();
-        let average_age: f64 = self.agents.iter().map(|a| a.age).sum::<f64>() / agent_count as f64;
-        let average_speed: f64 =
-            self.agents.iter().map(|a| a.genes.speed).sum::<f64>() / agent_count as f64;
-        let average_size: f64 =
-            self.agents.iter().map(|a| a.genes.size).sum::<f64>() / agent_count as f64;
-        let average_aggression: f64 =
-            self.agents.iter().map(|a| a.genes.aggression).sum::<f64>() / agent_count as f64;
-        let average_sense_range: f64 =
-            self.agents.iter().map(|a| a.genes.sense_range).sum::<f64>() / agent_count as f64;
-        let average_energy_efficiency: f64 = self
-            .agents
-            .iter()
-            .map(|a| a.genes.energy_efficiency)
-            .sum::<f64>()
-            / agent_count as f64;
-        let max_generation = self.agents.iter().map(|a| a.generation).max().unwrap_or(0);
-        let total_kills: u32 = self.agents.iter().map(|a| a.kills).sum();
-        let average_fitness: f64 = self
-            .agents
-            .iter()
-            .map(|a| a.energy / a.max_energy)
-            .sum::<f64>()
-            / agent_count as f64;
+        // The browser only has a rayon pool once the worker threads are up
+        let parallel = agent_count >= PARALLEL_STATS_MIN_AGENTS
+            && (cfg!(not(target_arch = "wasm32")) || EcsSimulationEngine::is_rayon_available());
+        let totals = if parallel {
+            accumulate_agent_stats_parallel(&self.agents)
+        } else {
+            accumulate_agent_stats(&self.agents)
+        };
+        let count = agent_count as f64;
+        let species = self.species.get(self.step / SPECIATION_INTERVAL, || {
+            species_summary(&self.agents, self.config.speciation_threshold)
+        });
+        let r_stars: Vec<f64> = self.agents.iter().map(|a| a.r_star).collect();
+        let resource_competition_index = sample_variance(&r_stars);
+        let age_distribution = age_distribution(&self.agents);
+        let (average_trophic_level, apex_predator_count, trophic_diversity) =
+            trophic_summary(&self.agents);
+        let average_predator_satiation = if totals.predator_count == 0 {
+            0.0
+        } else {
+            totals.predator_satiation / totals.predator_count as f64
+        };
 
         SimulationStats {
             agent_count,
             resource_count,
-            total_energy,
-            average_age,
-            average_speed,
-            average_size,
-            average_aggression,
-            average_sense_range,
-            average_energy_efficiency,
-            max_generation,
-            total_kills,
-            average_fitness,
+            total_energy: totals.total_energy,
+            average_age: totals.age / count,
+            average_speed: totals.speed / count,
+            average_size: totals.size / count,
+            average_aggression: totals.aggression / count,
+            average_sense_range: totals.sense_range / count,
+            average_energy_efficiency: totals.energy_efficiency / count,
+            max_generation: totals.max_generation,
+            average_generation: totals.generation / count,
+            total_kills: totals.total_kills,
+            total_pack_kills: 0,
+            average_fitness: totals.fitness / count,
+            species_count: species.species_count,
+            largest_species_size: species.largest_species_size,
+            largest_species_fraction: species.largest_species_fraction,
+            average_reproduction_suppression: totals.reproduction_suppression / count,
+            resource_competition_index,
+            average_stamina: totals.stamina / count,
+            average_mutation_strength: totals.mutation_strength / count,
+            age_distribution,
+            average_trophic_level,
+            apex_predator_count,
+            trophic_diversity,
+            total_energy_shared: self.total_energy_shared,
+            average_predator_satiation,
+            average_perception_error: totals.perception_error / count,
+            average_memory_utilization: 0.0,
         }
     }
 
@@ -518,68 +2178,2233 @@ impl SimulationEngine for LegacySimulationEngine {
         self.agents.clone()
     }
 
+    fn agent_slice(&self) -> Option<&[Agent]> {
+        Some(&self.agents)
+    }
+
+    fn find_agent(&self, _stable_id: u64) -> Option<Agent> {
+        None
+    }
+
     fn get_resources(&self) -> Vec<Resource> {
         self.resources.clone()
     }
 
-    fn get_config(&self) -> &SimulationConfig {
-        &self.config
+    fn add_habitat(&mut self, habitat: Habitat) {
+        self.habitats.push(habitat);
+        self.connectivity_graph = ConnectivityGraph::new(&self.habitats);
     }
-}
 
-pub struct UnifiedSimulation {
-    engine: Box<dyn SimulationEngine>,
-    config: SimulationConfig,
-}
+    fn get_habitats(&self) -> Vec<Habitat> {
+        self.habitats.clone()
+    }
 
-impl UnifiedSimulation {
-    pub fn new(config: SimulationConfig) -> Self {
-        let engine: Box<dyn SimulationEngine> = if config.use_ecs {
-            Box::new(EcsSimulationEngine::new(config.clone()))
-        } else {
-            Box::new(LegacySimulationEngine::new(config.clone()))
-        };
+    fn connectivity_graph(&self) -> &ConnectivityGraph {
+        &self.connectivity_graph
+    }
 
-        Self { engine, config }
+    fn add_teleporter(&mut self, teleporter: Teleporter) {
+        self.teleporters.push(teleporter);
     }
 
-    pub fn update(&mut self) {
-        self.engine.update();
+    fn get_teleporters(&self) -> Vec<Teleporter> {
+        self.teleporters.clone()
     }
 
-    pub fn add_agent(&mut self, x: f64, y: f64) {
-        self.engine.add_agent(x, y);
+    fn set_boundary_behavior(&mut self, boundary: BoundaryBehavior) {
+        self.config.boundary_behavior = boundary;
     }
 
-    pub fn add_resource(&mut self, x: f64, y: f64) {
-        self.engine.add_resource(x, y);
+    fn apply_config(&mut self, config: SimulationConfig) {
+        self.config = config;
     }
 
-    pub fn reset(&mut self) {
-        self.engine.reset();
+    fn season_phase(&self) -> f64 {
+        self.config.season_phase(self.time)
     }
 
-    pub fn get_stats(&self) -> SimulationStats {
-        self.engine.get_stats()
+    fn warmth(&self) -> f64 {
+        self.config.warmth(self.time)
     }
 
-    pub fn get_agents(&self) -> Vec<Agent> {
-        self.engine.get_agents()
+    fn season_cycles_completed(&self) -> u32 {
+        self.config.season_cycles_completed(self.time)
     }
 
-    pub fn get_resources(&self) -> Vec<Resource> {
-        self.engine.get_resources()
+    fn agent_count(&self) -> usize {
+        self.agents.len()
     }
 
-    pub fn get_config(&self) -> &SimulationConfig {
-        &self.config
+    fn resource_count(&self) -> usize {
+        self.resources.len()
     }
 
-    pub fn is_rayon_available() -> bool {
-        EcsSimulationEngine::is_rayon_available()
+    fn death_resource_count(&self) -> usize {
+        self.resources.iter().filter(|r| r.is_carcass).count()
     }
 
-    pub fn set_rayon_initialized(initialized: bool) {
-        EcsSimulationEngine::set_rayon_initialized(initialized);
+    fn social_learning_events(&self) -> u64 {
+        self.social_learning_events
+    }
+
+    fn poison_deaths(&self) -> u32 {
+        self.poison_deaths
+    }
+
+    fn energy_heatmap(&self) -> &[Vec<f64>] {
+        &self.energy_consumption_grid
+    }
+
+    fn toxin_grid(&self) -> Option<&ToxinGrid> {
+        Some(&self.toxin_grid)
+    }
+
+    fn spatial_cell_size(&self) -> Option<f64> {
+        Some(self.spatial_cell_size)
+    }
+
+    fn memory_stats(&self) -> MemoryStats {
+        MemoryStats {
+            heap_used_bytes: estimated_heap_bytes(self.agents.len(), self.resources.len()),
+            agent_vec_capacity: self.agents.capacity(),
+            resource_vec_capacity: self.resources.capacity(),
+            spatial_grid_cells: self.grid_width * self.grid_height,
+            wasm_memory_pages: 0,
+            entity_count: self.agents.len() + self.resources.len(),
+        }
+    }
+
+    fn interaction_log(&self) -> &[InteractionLog] {
+        self.interactions.as_slice()
+    }
+
+    fn death_history(&self) -> Option<&VecDeque<DeathRecord>> {
+        Some(self.death_history.records())
+    }
+
+    fn trigger_famine(&mut self, duration_steps: usize) {
+        self.famine.trigger(self.step, duration_steps);
+    }
+
+    fn famine(&self) -> &Famine {
+        &self.famine
+    }
+
+    fn get_config(&self) -> &SimulationConfig {
+        &self.config
+    }
+}
+
+fn from_ecs_agent(
+    (pos, vel, energy, age, state, genes, _size, death, spawn, species): AgentComponents,
+) -> Agent {
+    let genes = from_ecs_genes(&genes);
+    let r_star = Agent::r_star_for(&genes);
+
+    Agent {
+        x: pos.x,
+        y: pos.y,
+        dx: vel.dx,
+        dy: vel.dy,
+        energy: energy.current,
+        max_energy: energy.max,
+        age: age.value,
+        genes,
+        target_x: state.target_x,
+        target_y: state.target_y,
+        state: match state.state {
+            AgentStateEnum::Seeking => AgentState::Seeking,
+            AgentStateEnum::Hunting => AgentState::Hunting,
+            AgentStateEnum::PackHunting { pack_id } => AgentState::PackHunting {
+                target_x: state.target_x.unwrap_or(pos.x),
+                target_y: state.target_y.unwrap_or(pos.y),
+                pack_id,
+            },
+            AgentStateEnum::Feeding => AgentState::Feeding,
+            AgentStateEnum::Reproducing => AgentState::Reproducing,
+            AgentStateEnum::Fighting => AgentState::Fighting,
+            AgentStateEnum::Fleeing => AgentState::Fleeing,
+            AgentStateEnum::Migrating => AgentState::Migrating,
+        },
+        last_reproduction: state.last_reproduction,
+        kills: state.kills,
+        generation: state.generation,
+        death_fade: death.fade,
+        death_reason: death
+            .is_dying
+            .then(|| from_ecs_death_reason(&death.reason)),
+        is_dying: death.is_dying,
+        spawn_fade: spawn.fade,
+        spawn_position: spawn.spawn_position,
+        phenotype: state.phenotype,
+        migrating_direction: state.migrating_direction,
+        last_migration_time: state.last_migration_time,
+        r_star,
+        current_stamina: state.current_stamina,
+        mutated_genes: 0,
+        species_id: species.0,
+        trophic_level: state.trophic_level,
+        satiation: state.satiation,
+        home_habitat_id: None,
+        migration_waypoint: None,
+        perception_error: state.perception_error,
+        poisoned_spots: None,
+    }
+}
+
+fn to_ecs_state(state: &AgentState) -> AgentStateEnum {
+    match state {
+        AgentState::Seeking => AgentStateEnum::Seeking,
+        AgentState::Hunting => AgentStateEnum::Hunting,
+        AgentState::PackHunting { pack_id, .. } => AgentStateEnum::PackHunting {
+            pack_id: *pack_id,
+        },
+        AgentState::Feeding => AgentStateEnum::Feeding,
+        AgentState::Reproducing => AgentStateEnum::Reproducing,
+        AgentState::Fighting => AgentStateEnum::Fighting,
+        AgentState::Fleeing => AgentStateEnum::Fleeing,
+        AgentState::Migrating => AgentStateEnum::Migrating,
+    }
+}
+
+fn to_ecs_death_reason(reason: &DeathReason) -> EcsDeathReason {
+    match reason {
+        DeathReason::Starvation => EcsDeathReason::Starvation,
+        DeathReason::OldAge => EcsDeathReason::OldAge,
+        DeathReason::KilledByPredator => EcsDeathReason::KilledByPredator,
+        DeathReason::Combat => EcsDeathReason::Combat,
+        DeathReason::NaturalCauses => EcsDeathReason::NaturalCauses,
+        DeathReason::Poisoned => EcsDeathReason::Poisoned,
+        DeathReason::FellOffEdge => EcsDeathReason::FellOffEdge,
+    }
+}
+
+fn from_ecs_death_reason(reason: &EcsDeathReason) -> DeathReason {
+    match reason {
+        EcsDeathReason::Starvation => DeathReason::Starvation,
+        EcsDeathReason::OldAge => DeathReason::OldAge,
+        EcsDeathReason::KilledByPredator => DeathReason::KilledByPredator,
+        EcsDeathReason::Combat => DeathReason::Combat,
+        EcsDeathReason::NaturalCauses => DeathReason::NaturalCauses,
+        EcsDeathReason::Poisoned => DeathReason::Poisoned,
+        EcsDeathReason::FellOffEdge => DeathReason::FellOffEdge,
+    }
+}
+
+/// Bumped whenever `SimulationSnapshot` changes shape or meaning.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Engine internals carried by a `SimulationSnapshot`.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum EngineState {
+    Legacy {
+        time: f64,
+        step: usize,
+        resource_spawn_timer: f64,
+        social_learning_events: u64,
+        next_species_id: u32,
+        total_energy_shared: f64,
+        #[serde(default)]
+        poison_deaths: u32,
+    },
+    /// The whole world as `EcsWorld::to_json`, as converting agents to and
+    /// from `Agent` loses ECS-only components.
+    Ecs {
+        time: f64,
+        step: usize,
+        spawn_rate_scale: f64,
+        world: serde_json::Value,
+    },
+}
+
+/// Serializable copy of a simulation's config, full population and clock.
+/// Snapshots without a version, or from another one, don't restore.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SimulationSnapshot {
+    #[serde(default)]
+    pub version: u32,
+    pub config: SimulationConfig,
+    pub agents: Vec<Agent>,
+    pub resources: Vec<Resource>,
+    /// `UnifiedSimulation::current_step` when it was taken.
+    #[serde(default)]
+    pub step: usize,
+    /// `None` or state from the other engine, e.g. after switching
+    /// `config.use_ecs`, restores just the population.
+    #[serde(default)]
+    pub engine: Option<EngineState>,
+}
+
+pub struct UnifiedSimulation {
+    engine: Box<dyn SimulationEngine>,
+    config: SimulationConfig,
+    scenario: Option<Scenario>,
+    events: Vec<SimulationEvent>,
+    /// Updates run so far.
+    step: usize,
+    /// (step, command) for every command applied, oldest first.
+    command_log: Vec<(usize, SimulationCommand)>,
+    /// Result of the last `get_stats`, or `None` once something has changed
+    /// since. A mutex so `get_stats` can fill it through `&self`.
+    cached_stats: Mutex<Option<SimulationStats>>,
+    /// `get_agents` for engines without an `agent_slice`, kept for
+    /// `with_agents` and dropped whenever `cached_stats` is.
+    cached_agents: Mutex<Option<Vec<Agent>>>,
+    #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+    metrics_recorder: Option<crate::metrics::SharedRecorder>,
+}
+
+impl UnifiedSimulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        let engine: Box<dyn SimulationEngine> = if config.use_ecs {
+            Box::new(EcsSimulationEngine::new(config.clone()))
+        } else {
+            Box::new(LegacySimulationEngine::new(config.clone()))
+        };
+
+        Self {
+            engine,
+            config,
+            scenario: None,
+            events: Vec::new(),
+            step: 0,
+            command_log: Vec::new(),
+            cached_stats: Mutex::new(None),
+            cached_agents: Mutex::new(None),
+            #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+            metrics_recorder: None,
+        }
+    }
+
+    /// Publishes population gauges through `recorder` after every update.
+    #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+    pub fn with_metrics(mut self, recorder: crate::metrics::SharedRecorder) -> Self {
+        self.set_metrics_recorder(recorder);
+        self
+    }
+
+    #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+    pub fn set_metrics_recorder(&mut self, recorder: crate::metrics::SharedRecorder) {
+        self.metrics_recorder = Some(recorder);
+    }
+
+    /// Builds a simulation whose initial population is exactly the one described
+    /// by `scenario`. Subsequent calls to `reset` restore that population.
+    pub fn from_scenario(scenario: Scenario) -> Self {
+        let mut simulation = Self::new(scenario.config.clone());
+        simulation.scenario = Some(scenario);
+        simulation.apply_scenario();
+        simulation
+    }
+
+    fn apply_scenario(&mut self) {
+        let Some(scenario) = &self.scenario else {
+            return;
+        };
+
+        self.engine.clear();
+        for template in &scenario.initial_agents {
+            for _ in 0..template.count {
+                let x = template
+                    .x
+                    .unwrap_or_else(|| self.engine.rng().gen_range(0.0..self.config.width));
+                let y = template
+                    .y
+                    .unwrap_or_else(|| self.engine.rng().gen_range(0.0..self.config.height));
+                self.engine
+                    .add_agent_with_genes(x, y, template.genes.clone());
+            }
+        }
+        for &(x, y) in &scenario.initial_resources {
+            self.engine.add_resource(x, y);
+        }
+    }
+
+    /// Rebuilds a simulation from a `snapshot`, with the same agents,
+    /// resources and clock.
+    pub fn from_snapshot(snapshot: SimulationSnapshot) -> Result<Self, SimulationError> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SimulationError::SnapshotVersionMismatch {
+                found: snapshot.version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+        let mut simulation = Self::new(snapshot.config);
+        simulation
+            .engine
+            .restore(snapshot.agents, snapshot.resources);
+        if let Some(state) = snapshot.engine {
+            simulation.engine.load_state(state)?;
+        }
+        simulation.step = snapshot.step;
+        Ok(simulation)
+    }
+
+    /// Replaces this simulation with `snapshot`, dropping its scenario,
+    /// events and command log. Leaves it untouched if the snapshot doesn't
+    /// restore.
+    pub fn restore(&mut self, snapshot: SimulationSnapshot) -> Result<(), SimulationError> {
+        let restored = Self::from_snapshot(snapshot)?;
+        #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+        let recorder = self.metrics_recorder.take();
+        *self = restored;
+        #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+        {
+            self.metrics_recorder = recorder;
+        }
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> SimulationSnapshot {
+        SimulationSnapshot {
+            version: SNAPSHOT_VERSION,
+            config: self.config.clone(),
+            agents: self.get_agents(),
+            resources: self.get_resources(),
+            step: self.step,
+            engine: Some(self.engine.save_state()),
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.engine.update();
+        self.step += 1;
+        self.invalidate_stats();
+
+        #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+        if let Some(recorder) = &self.metrics_recorder {
+            let diversity = crate::species::gene_diversity(&self.engine.get_agents());
+            crate::metrics::record_stats(recorder.as_ref(), &self.get_stats(), diversity);
+        }
+    }
+
+    pub fn add_agent(&mut self, x: f64, y: f64) {
+        self.engine.add_agent(x, y);
+        self.invalidate_stats();
+    }
+
+    pub fn add_resource(&mut self, x: f64, y: f64) {
+        self.engine.add_resource(x, y);
+        self.invalidate_stats();
+    }
+
+    /// Randomly removes agents, keeping `survival_fraction` of the population.
+    pub fn cull_agents(&mut self, survival_fraction: f64) {
+        self.engine.cull_agents(survival_fraction);
+        self.invalidate_stats();
+    }
+
+    /// The engine's random stream, for callers adding to the population.
+    pub fn rng(&mut self) -> &mut SimulationRng {
+        self.engine.rng()
+    }
+
+    pub fn reset(&mut self) {
+        self.invalidate_stats();
+        self.engine.set_seed(self.config.seed);
+        if self.scenario.is_some() {
+            self.apply_scenario();
+        } else {
+            self.engine.reset();
+        }
+    }
+
+    /// Statistics for the current state. Computed on the first call after a
+    /// change and reused until the next one, so callers polling several times
+    /// per frame only pay for one pass over the agents.
+    pub fn get_stats(&self) -> SimulationStats {
+        let mut cached = self.cached_stats.lock().unwrap();
+        cached.get_or_insert_with(|| self.engine.get_stats()).clone()
+    }
+
+    /// Drops the cached statistics so the next `get_stats` recomputes them.
+    /// The simulation's own mutating methods already do this.
+    pub fn invalidate_stats(&mut self) {
+        *self.cached_stats.get_mut().unwrap() = None;
+        *self.cached_agents.get_mut().unwrap() = None;
+    }
+
+    /// Adds a region where births stop once `habitat.carrying_capacity`
+    /// agents are inside it.
+    pub fn add_habitat(&mut self, habitat: Habitat) {
+        self.engine.add_habitat(habitat);
+        self.invalidate_stats();
+    }
+
+    pub fn get_habitats(&self) -> Vec<Habitat> {
+        self.engine.get_habitats()
+    }
+
+    pub fn get_connectivity_graph(&self) -> &ConnectivityGraph {
+        self.engine.connectivity_graph()
+    }
+
+    /// Adds a worm-hole that moves agents entering it to its exit.
+    pub fn add_teleporter(&mut self, teleporter: Teleporter) {
+        self.engine.add_teleporter(teleporter);
+    }
+
+    pub fn get_teleporters(&self) -> Vec<Teleporter> {
+        self.engine.get_teleporters()
+    }
+
+    /// Position within the current resource season in [0, 1).
+    pub fn season_phase(&self) -> f64 {
+        self.engine.season_phase()
+    }
+
+    /// 1 in high summer down to 0 in deepest winter; see
+    /// `SimulationConfig::warmth`.
+    pub fn warmth(&self) -> f64 {
+        self.engine.warmth()
+    }
+
+    pub fn season_cycles_completed(&self) -> u32 {
+        self.engine.season_cycles_completed()
+    }
+
+    /// Changes what happens to agents that reach the edge of the world.
+    pub fn set_boundary_behavior(&mut self, boundary: BoundaryBehavior) {
+        self.config.boundary_behavior = boundary;
+        self.engine.set_boundary_behavior(boundary);
+    }
+
+    /// Replaces the biome zones used for resources grown from now on.
+    pub fn set_biome_map(&mut self, biome_map: Option<BiomeMap>) {
+        self.config.biome_map = biome_map;
+        self.engine.apply_config(self.config.clone());
+    }
+
+    /// Updates the fields set in `delta` while the simulation keeps running.
+    /// Fails without changing anything if `max_agents` would drop below the
+    /// current population or the resulting config fails `validate`.
+    pub fn apply_config_delta(&mut self, delta: SimulationConfigDelta) -> Result<(), String> {
+        if let Some(max_agents) = delta.max_agents {
+            let agent_count = self.engine.agent_count();
+            if max_agents < agent_count {
+                return Err(format!(
+                    "max_agents {} is below the current agent count {}",
+                    max_agents, agent_count
+                ));
+            }
+        }
+
+        let mut config = self.config.clone();
+        delta.apply_to(&mut config);
+        config.validate().map_err(|errors| format_config_errors(&errors))?;
+        self.config = config;
+        self.engine.apply_config(self.config.clone());
+        self.invalidate_stats();
+        self.events.push(SimulationEvent::ConfigChanged(delta));
+        Ok(())
+    }
+
+    /// Applies `command` and logs it against the current step. A failed
+    /// `SetConfig` changes nothing and isn't logged.
+    pub fn apply_command(&mut self, command: SimulationCommand) -> Result<(), String> {
+        match &command {
+            SimulationCommand::AddAgent { x, y } => self.add_agent(*x, *y),
+            SimulationCommand::AddResource { x, y } => self.add_resource(*x, *y),
+            SimulationCommand::TriggerFamine { steps } => self.trigger_famine(*steps),
+            SimulationCommand::SetConfig { delta } => self.apply_config_delta(delta.clone())?,
+            SimulationCommand::Reset => self.reset(),
+        }
+        self.command_log.push((self.step, command));
+        Ok(())
+    }
+
+    /// (step, command) for every command applied so far, oldest first.
+    pub fn command_log(&self) -> &[(usize, SimulationCommand)] {
+        &self.command_log
+    }
+
+    /// Updates run since the simulation was built.
+    pub fn current_step(&self) -> usize {
+        self.step
+    }
+
+    /// Returns and clears the events recorded since the last call.
+    pub fn drain_events(&mut self) -> Vec<SimulationEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn agent_count(&self) -> usize {
+        self.engine.agent_count()
+    }
+
+    pub fn resource_count(&self) -> usize {
+        self.engine.resource_count()
+    }
+
+    pub fn death_resource_count(&self) -> usize {
+        self.engine.death_resource_count()
+    }
+
+    pub fn social_learning_events(&self) -> u64 {
+        self.engine.social_learning_events()
+    }
+
+    /// Agents killed by poisonous resources since the world was last cleared.
+    pub fn poison_deaths(&self) -> u32 {
+        self.engine.poison_deaths()
+    }
+
+    pub fn spatial_cell_size(&self) -> Option<f64> {
+        self.engine.spatial_cell_size()
+    }
+
+    pub fn get_memory_stats(&self) -> MemoryStats {
+        self.engine.memory_stats()
+    }
+
+    /// Separate resource patches; see `count_resource_clusters`.
+    pub fn resource_cluster_count(&self) -> usize {
+        count_resource_clusters(&self.get_resources())
+    }
+
+    /// Resource energy eaten per 50-unit cell, decaying by `HEATMAP_DECAY`
+    /// each step so it shows recent feeding.
+    pub fn get_energy_heatmap(&self) -> &[Vec<f64>] {
+        self.engine.energy_heatmap()
+    }
+
+    /// Toxin per 50-unit cell, decaying by `TOXIN_DECAY` each step. `None`
+    /// when the ECS engine is running.
+    pub fn get_toxin_grid(&self) -> Option<&ToxinGrid> {
+        self.engine.toxin_grid()
+    }
+
+    /// `get_toxin_grid` flattened row by row; empty without a toxin grid.
+    pub fn get_toxin_grid_flat(&self) -> Vec<f32> {
+        self.get_toxin_grid().map_or_else(Vec::new, ToxinGrid::to_flat)
+    }
+
+    /// Up to the last `INTERACTION_LOG_CAPACITY` interactions, oldest first.
+    /// Only recorded when `log_interactions` is set.
+    pub fn get_interaction_log(&self) -> &[InteractionLog] {
+        self.engine.interaction_log()
+    }
+
+    /// The latest deaths, oldest first, capped at `max_death_history`.
+    pub fn get_death_history(&self) -> Option<&VecDeque<DeathRecord>> {
+        self.engine.death_history()
+    }
+
+    /// How many of the deaths in `get_death_history` had each reason.
+    pub fn deaths_by_reason(&self) -> HashMap<DeathReason, usize> {
+        count_by_reason(self.get_death_history().into_iter().flatten())
+    }
+
+    /// Stops periodic resource spawning for the next `duration_steps`
+    /// updates. Resources added by hand still appear.
+    pub fn trigger_famine(&mut self, duration_steps: usize) {
+        self.engine.trigger_famine(duration_steps);
+    }
+
+    pub fn famine_active(&self) -> bool {
+        self.engine.famine().is_active()
+    }
+
+    /// (start step, duration) of every famine, manual or automatic, since the
+    /// last reset.
+    pub fn famine_events(&self) -> &[(usize, usize)] {
+        self.engine.famine().events()
+    }
+
+    /// `get_energy_heatmap` flattened row by row, top to bottom.
+    pub fn get_energy_heatmap_flat(&self) -> Vec<f64> {
+        let grid = self.get_energy_heatmap();
+        let rows = grid.first().map_or(0, Vec::len);
+        (0..rows)
+            .flat_map(|y| grid.iter().map(move |column| column[y]))
+            .collect()
+    }
+
+    /// Leaves a deposit holding half of `energy` at (x, y), as a dead agent does.
+    pub fn spawn_death_resource(&mut self, x: f64, y: f64, energy: f64) {
+        self.engine.spawn_death_resource(x, y, energy);
+        self.invalidate_stats();
+    }
+
+    pub fn get_agents(&self) -> Vec<Agent> {
+        self.engine.get_agents()
+    }
+
+    /// Calls `f` with the agents, without the copy `get_agents` makes. The
+    /// legacy engine lends its own agents; the ECS engine's are converted once
+    /// per change and reused until the next. `f` must not call `with_agents`
+    /// itself.
+    pub fn with_agents<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Agent]) -> R,
+    {
+        if let Some(agents) = self.engine.agent_slice() {
+            return f(agents);
+        }
+        let mut cached = self.cached_agents.lock().unwrap();
+        f(cached.get_or_insert_with(|| self.engine.get_agents()))
+    }
+
+    /// The agent with ECS `StableId` `stable_id`, if it is still alive.
+    /// Always `None` on the legacy engine.
+    pub fn find_agent_by_id(&self, stable_id: u64) -> Option<Agent> {
+        self.engine.find_agent(stable_id)
+    }
+
+    /// Agents inside the rectangle with corners (x1, y1) and (x2, y2), edges
+    /// included. The corners may be given in any order.
+    pub fn agents_in_rect(&self, x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Agent> {
+        let (min_x, max_x) = (x1.min(x2), x1.max(x2));
+        let (min_y, max_y) = (y1.min(y2), y1.max(y2));
+        self.get_agents()
+            .into_iter()
+            .filter(|agent| {
+                (min_x..=max_x).contains(&agent.x) && (min_y..=max_y).contains(&agent.y)
+            })
+            .collect()
+    }
+
+    pub fn get_resources(&self) -> Vec<Resource> {
+        self.engine.get_resources()
+    }
+
+    pub fn get_config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Groups the current agents into species by genetic distance.
+    pub fn detect_species(&self, threshold: f64) -> Vec<Vec<usize>> {
+        detect_species(&self.get_agents(), threshold)
+    }
+
+    /// Histogram of `gene_name` across the current agents; see
+    /// `gene_histogram`.
+    pub fn gene_histogram(&self, gene_name: &str, buckets: usize) -> Option<Vec<u32>> {
+        gene_histogram(&self.get_agents(), gene_name, buckets)
+    }
+
+    /// Spread of `gene_name` across the population; see `gene_boxplot`.
+    pub fn gene_percentiles(&self, gene_name: &str) -> Option<GeneBoxplot> {
+        self.with_agents(|agents| gene_boxplot(agents, gene_name))
+    }
+
+    /// Gene × fitness scatter of the current population.
+    pub fn fitness_landscape(&self) -> Vec<FitnessSample> {
+        fitness_landscape(&self.get_agents())
+    }
+
+    pub fn is_rayon_available() -> bool {
+        EcsSimulationEngine::is_rayon_available()
+    }
+
+    pub fn set_rayon_initialized(initialized: bool) {
+        EcsSimulationEngine::set_rayon_initialized(initialized);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archetype::PositionDistribution;
+    use crate::genes::{gene_bounds_for, GeneBounds, GENE_NAMES};
+    use crate::toxin::{TOXIN_DECAY, TOXIN_THRESHOLD};
+
+    #[test]
+    fn test_ecs_agent_conversion_preserves_animations() {
+        let mut engine = EcsSimulationEngine::new(SimulationConfig::default());
+
+        let mut dying = Agent::new(100.0, 200.0, Genes::new(), 3);
+        dying.is_dying = true;
+        dying.death_fade = 0.4;
+        dying.death_reason = Some(DeathReason::KilledByPredator);
+        dying.spawn_fade = 1.0;
+        let mut spawning = Agent::new(300.0, 400.0, Genes::new(), 0);
+        spawning.spawn_fade = 0.25;
+
+        engine.restore(vec![dying, spawning], Vec::new());
+        let mut agents = engine.get_agents();
+        agents.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+        assert!(agents[0].is_dying);
+        assert_eq!(agents[0].death_fade, 0.4);
+        assert_eq!(agents[0].death_reason, Some(DeathReason::KilledByPredator));
+        assert_eq!(agents[0].spawn_fade, 1.0);
+
+        assert!(!agents[1].is_dying);
+        assert_eq!(agents[1].death_reason, None);
+        assert_eq!(agents[1].spawn_fade, 0.25);
+        assert_eq!(agents[1].spawn_position, Some((300.0, 400.0)));
+    }
+
+    #[test]
+    fn test_ecs_stats_agree_with_converted_agents() {
+        let mut engine = EcsSimulationEngine::new(SimulationConfig::default());
+        for _ in 0..200 {
+            engine.update();
+        }
+
+        let stats = engine.get_stats();
+        let agents = engine.get_agents();
+        assert_eq!(stats.agent_count, agents.len());
+        let total_energy: f64 = agents.iter().map(|a| a.energy).sum();
+        assert!((stats.total_energy - total_energy).abs() < 1e-6);
+        assert_eq!(stats.total_kills, agents.iter().map(|a| a.kills).sum::<u32>());
+        assert_eq!(stats.age_distribution, age_distribution(&agents));
+        let species = species_summary(&agents, engine.config.speciation_threshold);
+        assert_eq!(stats.species_count, species.species_count);
+        assert_eq!(stats.largest_species_size, species.largest_species_size);
+        assert_eq!(stats.largest_species_fraction, species.largest_species_fraction);
+        let r_stars: Vec<f64> = agents.iter().map(|a| a.r_star).collect();
+        assert!((stats.resource_competition_index - sample_variance(&r_stars)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_config_delta_changes_spawn_rate_mid_run() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            resource_spawn_rate: 0.0001,
+            use_ecs: false,
+            ..Default::default()
+        });
+        for _ in 0..30 {
+            simulation.update();
+        }
+        assert_eq!(simulation.resource_count(), 0);
+
+        let delta = SimulationConfigDelta {
+            resource_spawn_rate: Some(60.0),
+            ..Default::default()
+        };
+        simulation.apply_config_delta(delta.clone()).unwrap();
+        for _ in 0..10 {
+            simulation.update();
+        }
+
+        assert!(simulation.resource_count() > 0);
+        assert_eq!(simulation.get_config().resource_spawn_rate, 60.0);
+        assert_eq!(simulation.drain_events(), vec![SimulationEvent::ConfigChanged(delta)]);
+        assert!(simulation.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_config_delta_rejects_max_agents_below_population() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 20,
+            use_ecs: false,
+            ..Default::default()
+        });
+        let delta = SimulationConfigDelta {
+            max_agents: Some(5),
+            resource_spawn_rate: Some(1.0),
+            ..Default::default()
+        };
+
+        assert!(simulation.apply_config_delta(delta).is_err());
+        assert_eq!(simulation.get_config().max_agents, 5000);
+        assert_eq!(simulation.get_config().resource_spawn_rate, 0.2);
+        assert!(simulation.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_config_delta_limits_reach_the_ecs_engine() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 20,
+            initial_resources: 20,
+            use_ecs: true,
+            ..Default::default()
+        });
+        let agents = simulation.agent_count();
+        let resources = simulation.resource_count();
+        let delta = SimulationConfigDelta {
+            max_agents: Some(agents),
+            max_resources: Some(resources),
+            resource_spawn_rate: Some(60.0),
+            ..Default::default()
+        };
+        simulation.apply_config_delta(delta).unwrap();
+
+        for _ in 0..300 {
+            simulation.update();
+            assert!(simulation.agent_count() <= agents);
+            assert!(simulation.resource_count() <= resources);
+        }
+
+        let invalid = SimulationConfigDelta {
+            resource_spawn_rate: Some(-1.0),
+            ..Default::default()
+        };
+        assert!(simulation.apply_config_delta(invalid).is_err());
+        assert_eq!(simulation.get_config().resource_spawn_rate, 60.0);
+    }
+
+    #[test]
+    fn test_spatial_grid_cells_shrink_as_population_grows() {
+        let mut engine = LegacySimulationEngine::new(SimulationConfig {
+            initial_agents: 100,
+            initial_resources: 0,
+            max_agents: 6000,
+            adaptive_spatial_grid: true,
+            ..Default::default()
+        });
+        for _ in 0..SPATIAL_ADAPT_INTERVAL {
+            engine.update();
+        }
+        let sparse_size = engine.spatial_cell_size().unwrap();
+
+        let mut rng = thread_rng();
+        for _ in 0..5000 {
+            engine.add_agent(rng.gen_range(0.0..1000.0), rng.gen_range(0.0..800.0));
+        }
+        for _ in 0..SPATIAL_ADAPT_INTERVAL {
+            engine.update();
+        }
+        let dense_size = engine.spatial_cell_size().unwrap();
+
+        assert!(dense_size < sparse_size, "{} -> {}", sparse_size, dense_size);
+        // sqrt(1000 * 800 / 5100) with the tolerance for drift since the rebuild
+        assert!((dense_size - 12.5).abs() < 2.0, "{}", dense_size);
+
+        // The rebuilt index still finds agents
+        let agent = &engine.get_agents()[0];
+        assert!(!engine.get_nearby_agents(agent.x, agent.y, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_agents_meet_the_world_edge() {
+        let after_crossing = |boundary_behavior| {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                width: 1000.0,
+                height: 800.0,
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                boundary_behavior,
+                use_ecs: false,
+                ..Default::default()
+            });
+            let mut agent = Agent::new(999.99, 400.0, Genes::new(), 1);
+            agent.age = 10.0; // Past infant mortality
+            (agent.dx, agent.dy) = (2.0, 0.0);
+            simulation.engine.restore(vec![agent], Vec::new());
+            simulation.update();
+            simulation
+        };
+
+        let wrapped = after_crossing(BoundaryBehavior::Wrap).get_agents();
+        assert!(wrapped[0].x < 1.0);
+
+        let bounced = after_crossing(BoundaryBehavior::Bounce).get_agents();
+        assert!(bounced[0].x <= 1000.0);
+        assert!(bounced[0].dx < 0.0);
+
+        let lethal = after_crossing(BoundaryBehavior::Lethal);
+        assert!(lethal.get_agents().is_empty());
+        assert_eq!(lethal.deaths_by_reason()[&DeathReason::FellOffEdge], 1);
+    }
+
+    #[test]
+    fn test_surrounded_prey_flees_through_the_gap() {
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                use_ecs,
+                seed: Some(5),
+                ..Default::default()
+            });
+            let agent = |x, y, is_predator| {
+                let genes = Genes {
+                    is_predator,
+                    omnivore: 0.0,
+                    pack_mentality: 0.0,
+                    speed: 2.0,
+                    sense_range: 100.0,
+                    stamina: 1.0,
+                    reproduction_threshold: f64::INFINITY,
+                    ..Genes::new()
+                };
+                let mut agent = Agent::new(x, y, genes, 1);
+                agent.age = 10.0; // Past infant mortality
+                agent
+            };
+            // Predators west, north and south leave the way east open
+            let agents = vec![
+                agent(400.0, 400.0, 0.0),
+                agent(370.0, 400.0, 1.0),
+                agent(400.0, 370.0, 1.0),
+                agent(400.0, 430.0, 1.0),
+            ];
+            simulation.engine.restore(agents, Vec::new());
+
+            for _ in 0..60 {
+                simulation.update();
+            }
+
+            let prey = simulation
+                .get_agents()
+                .into_iter()
+                .find(|agent| !agent.is_predator())
+                .unwrap();
+            // A second of sprinting at 2 * (2.5 + 0.5) covers six units, mostly
+            // east; wandering would cover two
+            let (run_x, run_y) = (prey.x - 400.0, prey.y - 400.0);
+            let context = format!("ecs {}: ({}, {})", use_ecs, run_x, run_y);
+            assert_eq!(prey.state, AgentState::Fleeing, "{}", context);
+            assert!(run_x > 4.0, "{}", context);
+            assert!(run_y.abs() < run_x / 2.0, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_dim_prey_misjudge_where_predators_are() {
+        for use_ecs in [false, true] {
+            let error = |intelligence: f64, seed: u64| {
+                let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                    initial_agents: 0,
+                    initial_resources: 0,
+                    resource_spawn_rate: 0.0,
+                    use_ecs,
+                    seed: Some(seed),
+                    ..Default::default()
+                });
+                let agent = |x, is_predator| {
+                    let genes = Genes {
+                        is_predator,
+                        omnivore: 0.0,
+                        pack_mentality: 0.0,
+                        sense_range: 100.0,
+                        intelligence,
+                        reproduction_threshold: f64::INFINITY,
+                        ..Genes::new()
+                    };
+                    let mut agent = Agent::new(x, 400.0, genes, 1);
+                    agent.age = 10.0; // Past infant mortality
+                    agent
+                };
+                let agents = vec![agent(400.0, 0.0), agent(430.0, 1.0)];
+                simulation.engine.restore(agents, Vec::new());
+                simulation.update();
+                let prey = simulation
+                    .get_agents()
+                    .into_iter()
+                    .find(|agent| !agent.is_predator())
+                    .unwrap();
+                assert_eq!(prey.state, AgentState::Fleeing, "ecs {}", use_ecs);
+                prey.perception_error
+            };
+
+            // Thirty units off, a tenth of the wits errs by about four
+            // units, twice the wits by a fifth of one
+            let dim: f64 = (0..10).map(|seed| error(0.1, seed)).sum();
+            let sharp: f64 = (0..10).map(|seed| error(2.0, seed)).sum();
+            assert!(dim > sharp * 5.0, "ecs {}: {} vs {}", use_ecs, dim, sharp);
+        }
+    }
+
+    #[test]
+    fn test_fleeing_prey_tires_and_slows() {
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                use_ecs,
+                seed: Some(5),
+                ..Default::default()
+            });
+            let agent = |x, y, is_predator| {
+                let genes = Genes {
+                    is_predator,
+                    omnivore: 0.0,
+                    pack_mentality: 0.0,
+                    speed: 2.0,
+                    sense_range: 100.0,
+                    stamina: 1.0,
+                    reproduction_threshold: f64::INFINITY,
+                    ..Genes::new()
+                };
+                let mut agent = Agent::new(x, y, genes, 1);
+                agent.age = 10.0; // Past infant mortality
+                agent
+            };
+            let agents = vec![
+                agent(400.0, 400.0, 0.0),
+                agent(370.0, 400.0, 1.0),
+                agent(400.0, 370.0, 1.0),
+                agent(400.0, 430.0, 1.0),
+            ];
+            simulation.engine.restore(agents, Vec::new());
+            let prey = |simulation: &UnifiedSimulation| {
+                simulation
+                    .get_agents()
+                    .into_iter()
+                    .find(|agent| !agent.is_predator())
+                    .unwrap()
+            };
+            let mut stride = |simulation: &mut UnifiedSimulation| {
+                let start = prey(simulation);
+                for _ in 0..10 {
+                    simulation.update();
+                }
+                let end = prey(simulation);
+                (end.x - start.x).hypot(end.y - start.y)
+            };
+
+            let fresh = stride(&mut simulation);
+            // Half a unit of stamina a second runs a full tank dry in two
+            for _ in 0..16 {
+                stride(&mut simulation);
+            }
+            let tired = stride(&mut simulation);
+
+            let prey = prey(&simulation);
+            let context = format!("ecs {}: {} then {}", use_ecs, fresh, tired);
+            assert_eq!(prey.state, AgentState::Fleeing, "{}", context);
+            assert!(prey.current_stamina < 0.2, "{}", context);
+            assert!(tired < fresh * 0.7, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_predators_digest_their_kills() {
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                max_agents: 1,
+                min_agent_count: 0,
+                use_ecs,
+                seed: Some(3),
+                ..Default::default()
+            });
+            let genes = Genes {
+                is_predator: 1.0,
+                metabolism: 1.0,
+                reproduction_threshold: f64::INFINITY,
+                ..Genes::new()
+            };
+            let mut predator = Agent::new(400.0, 400.0, genes, 1);
+            predator.age = 10.0; // Past infant mortality
+            predator.satiation = 1.0;
+            simulation.engine.restore(vec![predator], Vec::new());
+            assert_eq!(simulation.get_stats().average_predator_satiation, 1.0);
+
+            for _ in 0..60 {
+                simulation.update();
+            }
+
+            // A tenth of the belly a second at metabolism 1
+            let satiation = simulation.get_stats().average_predator_satiation;
+            assert!(
+                (satiation - 0.9).abs() < 0.01,
+                "ecs {}: {}",
+                use_ecs,
+                satiation
+            );
+        }
+    }
+
+    #[test]
+    fn test_crowding_and_scarcity_shape_the_phenotype() {
+        for use_ecs in [false, true] {
+            let run = |agent_count: usize, resource_count: usize| {
+                let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                    initial_agents: 0,
+                    initial_resources: 0,
+                    resource_spawn_rate: 0.0,
+                    max_agents: agent_count,
+                    min_agent_count: 0,
+                    use_ecs,
+                    seed: Some(3),
+                    ..Default::default()
+                });
+                let genes = Genes {
+                    is_predator: 0.0,
+                    omnivore: 0.0,
+                    migration: 0.0,
+                    reproduction_threshold: f64::INFINITY,
+                    // The same agent in every run
+                    ..Genes::random(&mut rand::rngs::StdRng::seed_from_u64(3))
+                };
+                let agents = (0..agent_count)
+                    .map(|i| {
+                        let mut agent = Agent::new(400.0 + i as f64, 400.0, genes.clone(), 1);
+                        agent.age = 10.0; // Past infant mortality
+                        agent
+                    })
+                    .collect();
+                // A ring of food, out of reach of feeding but within sight
+                let resources = (0..resource_count)
+                    .map(|i| {
+                        let angle = i as f64 / resource_count as f64 * std::f64::consts::TAU;
+                        let mut resource =
+                            Resource::new(400.0 + 50.0 * angle.cos(), 400.0 + 50.0 * angle.sin());
+                        resource.energy = 30.0;
+                        resource
+                    })
+                    .collect();
+                simulation.engine.restore(agents, resources);
+                let before = simulation.get_stats().total_energy;
+                for _ in 0..60 {
+                    simulation.update();
+                }
+                let stats = simulation.get_stats();
+                (
+                    before - stats.total_energy,
+                    stats.average_reproduction_suppression,
+                )
+            };
+
+            // Scarcity raises metabolism...
+            let (fed_burn, _) = run(1, 12);
+            let (starved_burn, lone_suppression) = run(1, 0);
+            assert!(starved_burn > fed_burn * 1.2, "ecs {}", use_ecs);
+            // ...and crowding suppresses reproduction
+            let (_, crowded_suppression) = run(20, 0);
+            assert_eq!(lone_suppression, 0.0, "ecs {}", use_ecs);
+            assert!(crowded_suppression > 0.5, "ecs {}", use_ecs);
+        }
+    }
+
+    #[test]
+    fn test_old_agents_die_of_natural_causes_or_old_age() {
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                use_ecs,
+                ..Default::default()
+            });
+            let genes = Genes {
+                is_predator: 0.0,
+                omnivore: 0.0,
+                stamina: 1.0,
+                ..Genes::new()
+            };
+            let elders = (0..80)
+                .map(|i| {
+                    let (x, y) = (50.0 + (i % 10) as f64 * 80.0, 50.0 + (i / 10) as f64 * 80.0);
+                    let mut elder = Agent::new(x, y, genes.clone(), 1);
+                    elder.age = 190.0;
+                    elder.energy = elder.max_energy;
+                    elder
+                })
+                .collect();
+            simulation.engine.restore(elders, Vec::new());
+
+            // Ten seconds, by the end of which none can have outlived MAX_AGE
+            for _ in 0..600 {
+                simulation.update();
+            }
+
+            let deaths = simulation.deaths_by_reason();
+            let count = |reason| deaths.get(&reason).copied().unwrap_or(0);
+            let context = format!("ecs {}: {:?}", use_ecs, deaths);
+            assert!(count(DeathReason::NaturalCauses) > 0, "{}", context);
+            assert!(count(DeathReason::OldAge) > 0, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_starvation_deaths_are_recorded() {
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                use_ecs,
+                ..Default::default()
+            });
+            let mut agents = Vec::new();
+            for i in 0..3 {
+                let mut agent = Agent::new(100.0 + i as f64 * 50.0, 100.0, Genes::new(), 1);
+                agent.energy = 0.0001;
+                agents.push(agent);
+            }
+            simulation.engine.restore(agents, Vec::new());
+
+            simulation.update();
+
+            let history = simulation.get_death_history().unwrap();
+            assert_eq!(history.len(), 3, "ecs {}", use_ecs);
+            assert!(history
+                .iter()
+                .all(|record| record.reason == DeathReason::Starvation));
+            assert_eq!(history[0].step, 1);
+            assert_eq!(simulation.deaths_by_reason()[&DeathReason::Starvation], 3);
+
+            // Collecting the deaths leaves nothing behind in a snapshot
+            let snapshot = simulation.snapshot();
+            let mut restored = UnifiedSimulation::new(SimulationConfig::default());
+            restored.restore(snapshot.clone()).unwrap();
+            assert_eq!(
+                serde_json::to_value(restored.snapshot()).unwrap(),
+                serde_json::to_value(snapshot).unwrap()
+            );
+
+            simulation.engine.clear();
+            assert!(simulation.get_death_history().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_famine_starves_the_population() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            resource_spawn_rate: 5.0,
+            use_ecs: false,
+            ..Default::default()
+        });
+        // Energy spread so that some agents outlast the famine's 500 steps
+        let agents: Vec<Agent> = (0..40)
+            .map(|i| {
+                let mut agent = Agent::new(20.0 + i as f64 * 15.0, 300.0, Genes::new(), 1);
+                agent.energy = 0.4 + i as f64 * 0.02;
+                agent
+            })
+            .collect();
+        simulation.engine.restore(agents, Vec::new());
+
+        simulation.trigger_famine(500);
+        assert!(simulation.famine_active());
+        for _ in 0..500 {
+            simulation.update();
+        }
+
+        assert!(!simulation.famine_active());
+        assert_eq!(simulation.resource_count(), 0);
+        assert!(simulation.agent_count() <= 28, "{}", simulation.agent_count());
+        assert_eq!(simulation.famine_events(), &[(0, 500)]);
+    }
+
+    #[test]
+    fn test_cooperators_carry_hungry_kin_through_a_famine() {
+        // Each family is a fed agent on a resource and a starving relative
+        // just out of feeding range
+        let survivors = |cooperation: f64| {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                use_ecs: false,
+                ..Default::default()
+            });
+            let mut agents = Vec::new();
+            let mut resources = Vec::new();
+            for family in 0..6 {
+                let x = 60.0 + family as f64 * 120.0;
+                let genes = Genes {
+                    cooperation,
+                    ..Genes::new()
+                };
+                let mut feeder = Agent::new(x, 300.0, genes.clone(), 1);
+                feeder.energy = 80.0;
+                let mut relative = Agent::new(x + 20.0, 300.0, genes, 1);
+                relative.energy = 3.0;
+                agents.extend([feeder, relative]);
+
+                let mut resource = Resource::new(x, 300.0);
+                resource.energy = resource.max_energy;
+                resource.is_spawning = false;
+                resource.spawn_fade = 1.0;
+                resources.push(resource);
+            }
+            simulation.engine.restore(agents, resources);
+
+            simulation.trigger_famine(3000);
+            for _ in 0..3000 {
+                simulation.update();
+            }
+            (simulation.agent_count(), simulation.get_stats().total_energy_shared)
+        };
+
+        let (selfish, selfish_shared) = survivors(0.1);
+        let (cooperative, cooperative_shared) = survivors(0.9);
+        assert_eq!(selfish_shared, 0.0);
+        assert!(cooperative_shared > 0.0);
+        assert!(cooperative > selfish, "{} vs {}", cooperative, selfish);
+    }
+
+    #[test]
+    fn test_social_learning_is_counted() {
+        let mut engine = LegacySimulationEngine::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            ..Default::default()
+        });
+        let agent = |x: f64, energy: f64, intelligence: f64| {
+            let genes = Genes {
+                intelligence,
+                aggression: 0.2,
+                sense_range: 50.0,
+                ..Genes::new()
+            };
+            let mut agent = Agent::new(x, 100.0, genes, 1);
+            agent.energy = energy;
+            agent.age = SOCIAL_LEARNING_INTERVAL - 0.001;
+            agent
+        };
+        // A poor learner next to a rich neighbour, and a poor agent too dim to learn
+        let agents = vec![
+            agent(100.0, 20.0, 1.0),
+            agent(110.0, 90.0, 1.0),
+            agent(500.0, 20.0, 0.5),
+            agent(510.0, 90.0, 1.0),
+        ];
+        engine.restore(agents, Vec::new());
+
+        engine.update();
+        assert_eq!(engine.social_learning_events(), 1);
+        engine.update();
+        assert_eq!(engine.social_learning_events(), 1);
+    }
+
+    #[test]
+    fn test_agents_in_rect() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            use_ecs: false,
+            ..Default::default()
+        });
+        simulation.add_agent(100.0, 100.0);
+        simulation.add_agent(200.0, 100.0);
+
+        let inside = simulation.agents_in_rect(90.0, 90.0, 110.0, 110.0);
+        assert_eq!(inside.len(), 1);
+        assert_eq!((inside[0].x, inside[0].y), (100.0, 100.0));
+
+        // Dragging up and to the left selects the same box
+        assert_eq!(simulation.agents_in_rect(110.0, 110.0, 90.0, 90.0).len(), 1);
+        assert!(simulation.agents_in_rect(120.0, 90.0, 180.0, 110.0).is_empty());
+    }
+
+    #[test]
+    fn test_gene_histogram_counts_every_agent() {
+        let simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 30,
+            initial_resources: 0,
+            use_ecs: false,
+            ..Default::default()
+        });
+
+        let histogram = simulation.gene_histogram("speed", 10).unwrap();
+        assert_eq!(histogram.len(), 10);
+        assert_eq!(histogram.iter().sum::<u32>() as usize, simulation.agent_count());
+        assert!(simulation.gene_histogram("wingspan", 10).is_none());
+    }
+
+    #[test]
+    fn test_cached_stats_follow_changes() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            use_ecs: false,
+            ..Default::default()
+        });
+        assert_eq!(simulation.get_stats().agent_count, 0);
+
+        simulation.add_agent(100.0, 100.0);
+        assert_eq!(simulation.get_stats().agent_count, 1);
+        simulation.add_resource(200.0, 200.0);
+        assert_eq!(simulation.get_stats().resource_count, 1);
+
+        let before = simulation.get_stats().average_age;
+        simulation.update();
+        assert!(simulation.get_stats().average_age > before);
+    }
+
+    #[test]
+    fn test_borrowed_agents_follow_changes() {
+        for use_ecs in [false, true] {
+            let mut simulation = UnifiedSimulation::new(SimulationConfig {
+                use_ecs,
+                ..Default::default()
+            });
+            let ages = |agents: &[Agent]| agents.iter().map(|a| a.age).collect::<Vec<_>>();
+            assert_eq!(simulation.with_agents(ages), ages(&simulation.get_agents()));
+
+            let count = simulation.with_agents(|agents| agents.len());
+            simulation.add_agent(100.0, 100.0);
+            assert_eq!(simulation.with_agents(|agents| agents.len()), count + 1);
+
+            simulation.update();
+            assert_eq!(simulation.with_agents(ages), ages(&simulation.get_agents()));
+        }
+    }
+
+    #[test]
+    fn test_parallel_agent_totals_match_sequential() {
+        let mut rng = thread_rng();
+        let agents: Vec<Agent> = (0..5000)
+            .map(|i| {
+                let mut agent = Agent::new(0.0, 0.0, crate::genes::Genes::new(), i % 7);
+                agent.energy = rng.gen_range(0.0..agent.max_energy);
+                agent.age = rng.gen_range(0.0..200.0);
+                agent.kills = i % 3;
+                agent.satiation = rng.gen_range(0.0..1.0);
+                agent
+            })
+            .collect();
+
+        let sequential = accumulate_agent_stats(&agents);
+        let parallel = accumulate_agent_stats_parallel(&agents);
+
+        assert_eq!(parallel.count, 5000);
+        assert_eq!(parallel.count, sequential.count);
+        assert_eq!(parallel.max_generation, sequential.max_generation);
+        assert_eq!(parallel.total_kills, sequential.total_kills);
+        assert_eq!(parallel.predator_count, sequential.predator_count);
+        // Only the order of the additions differs
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0);
+        let sums = |t: &AgentAccumulator| {
+            [
+                t.total_energy,
+                t.age,
+                t.speed,
+                t.size,
+                t.aggression,
+                t.sense_range,
+                t.energy_efficiency,
+                t.fitness,
+                t.reproduction_suppression,
+                t.stamina,
+                t.mutation_strength,
+                t.perception_error,
+                t.predator_satiation,
+            ]
+        };
+        for (a, b) in sums(&parallel).into_iter().zip(sums(&sequential)) {
+            assert!(close(a, b), "{} vs {}", a, b);
+        }
+        assert_eq!(accumulate_agent_stats(&[]), AgentAccumulator::default());
+    }
+
+    #[test]
+    fn test_separated_populations_get_different_species() {
+        let mut engine = LegacySimulationEngine::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            ..Default::default()
+        });
+        // Every gene at the same point of its range, so the two groups are
+        // far apart genetically but close together in space
+        let population = |value: f64| -> Vec<Agent> {
+            let mut genes = Genes::new();
+            for (index, name) in GENE_NAMES.iter().enumerate() {
+                let GeneBounds { min, max } = gene_bounds_for(name);
+                *genes.gene_mut(index) = min + value * (max - min);
+            }
+            (0..10)
+                .map(|i| {
+                    let mut agent = Agent::new(100.0 + i as f64, 100.0, genes.clone(), 1);
+                    // Past infant mortality, and not due to breed before
+                    // the first tagging
+                    agent.age = 10.0;
+                    agent.last_reproduction = 11.0;
+                    agent
+                })
+                .collect()
+        };
+        let mut agents = population(0.1);
+        agents.extend(population(0.9));
+        engine.restore(agents, Vec::new());
+
+        for _ in 0..SPECIATION_INTERVAL {
+            engine.update();
+        }
+
+        let ids: Vec<u32> = engine.get_agents().iter().map(|a| a.species_id).collect();
+        assert!(ids.iter().all(|&id| id != 0));
+        assert!(ids[..10].iter().all(|&id| id == ids[0]));
+        assert!(ids[10..].iter().all(|&id| id == ids[10]));
+        assert_ne!(ids[0], ids[10]);
+        assert_eq!(engine.get_stats().species_count, 2);
+
+        // Re-tagging keeps the IDs already handed out; offspring born since
+        // come after the founders
+        for _ in 0..SPECIATION_INTERVAL {
+            engine.update();
+        }
+        let retagged: Vec<u32> = engine.get_agents().iter().map(|a| a.species_id).collect();
+        assert_eq!(retagged[..ids.len()], ids[..]);
+    }
+
+    #[test]
+    fn test_ecs_tags_separated_populations_as_different_species() {
+        let mut simulation = UnifiedSimulation::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            resource_spawn_rate: 0.0,
+            max_agents: 20,
+            min_agent_count: 0,
+            use_ecs: true,
+            ..Default::default()
+        });
+        // As above, but peaceful and barren so the ECS's combat and births
+        // leave both groups intact
+        let population = |value: f64| -> Vec<Agent> {
+            let mut genes = Genes::new();
+            for (index, name) in GENE_NAMES.iter().enumerate() {
+                let GeneBounds { min, max } = gene_bounds_for(name);
+                *genes.gene_mut(index) = min + value * (max - min);
+            }
+            genes.is_predator = 0.0;
+            genes.omnivore = 0.0;
+            genes.aggression = 0.0;
+            genes.reproduction_threshold = f64::INFINITY;
+            (0..10)
+                .map(|i| {
+                    let mut agent = Agent::new(100.0 + i as f64, 100.0, genes.clone(), 1);
+                    agent.age = 10.0; // Past infant mortality
+                    agent
+                })
+                .collect()
+        };
+        let mut agents = population(0.1);
+        agents.extend(population(0.9));
+        simulation.engine.restore(agents, Vec::new());
+        let species_by_group = |simulation: &UnifiedSimulation| {
+            let agents = simulation.get_agents();
+            let (slow, fast): (Vec<_>, Vec<_>) =
+                agents.iter().partition(|agent| agent.genes.speed < 1.5);
+            let ids = |group: Vec<&Agent>| -> Vec<u32> {
+                group.iter().map(|agent| agent.species_id).collect()
+            };
+            (ids(slow), ids(fast))
+        };
+
+        for _ in 0..SPECIATION_INTERVAL {
+            simulation.update();
+        }
+
+        let (slow, fast) = species_by_group(&simulation);
+        assert_eq!((slow.len(), fast.len()), (10, 10));
+        assert!(slow.iter().chain(&fast).all(|&id| id != 0));
+        assert!(slow.iter().all(|&id| id == slow[0]));
+        assert!(fast.iter().all(|&id| id == fast[0]));
+        assert_ne!(slow[0], fast[0]);
+        assert_eq!(simulation.get_stats().species_count, 2);
+
+        // Re-tagging keeps the IDs already handed out
+        for _ in 0..SPECIATION_INTERVAL {
+            simulation.update();
+        }
+        assert_eq!(species_by_group(&simulation), (slow, fast));
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(SimulationConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rejects_initial_agents_over_max() {
+        let config = SimulationConfig {
+            initial_agents: 600,
+            max_agents: 500,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::InitialAgentsExceedMax {
+                initial: 600,
+                max: 500
+            }])
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_small_canvas() {
+        let config = SimulationConfig {
+            width: 50.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::CanvasTooSmall {
+                width: 50.0,
+                height: 800.0
+            }])
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_spawn_rate() {
+        for rate in [-1.0, f64::NAN, f64::INFINITY] {
+            let config = SimulationConfig {
+                resource_spawn_rate: rate,
+                ..Default::default()
+            };
+            let errors = config.validate().unwrap_err();
+            assert!(matches!(errors[..], [ConfigError::InvalidSpawnRate(_)]), "{}", rate);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_stability_threshold_out_of_range() {
+        let config = SimulationConfig {
+            stability_threshold: 1.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::StabilityThresholdOutOfRange(1.5)])
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_width_mutation_distribution() {
+        let distribution = MutationDistribution::Cauchy { scale: 0.0 };
+        let config = SimulationConfig {
+            mutation_distribution: distribution,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::InvalidMutationDistribution(distribution)])
+        );
+    }
+
+    #[test]
+    fn test_season_factor_bottoms_out_at_zero() {
+        let config = SimulationConfig {
+            season_period_seconds: 100.0,
+            season_amplitude: 1.5,
+            ..Default::default()
+        };
+        assert!((config.season_factor(25.0) - 2.5).abs() < 1e-9);
+        assert_eq!(config.season_factor(75.0), 0.0);
+        assert!((0..100).all(|t| config.season_factor(t as f64) >= 0.0));
+    }
+
+    #[test]
+    fn test_bimodal_initial_genes_found_two_populations() {
+        let around = |is_predator: f64| {
+            Box::new(InitialGeneDistribution::Gaussian {
+                mean: Genes {
+                    is_predator,
+                    ..Genes::new()
+                },
+                std_dev: 0.03,
+            })
+        };
+        let initial_gene_distribution = InitialGeneDistribution::Bimodal {
+            config_a: around(0.1),
+            config_b: around(0.9),
+            fraction_a: 0.5,
+        };
+
+        for use_ecs in [false, true] {
+            let simulation = UnifiedSimulation::new(SimulationConfig {
+                use_ecs,
+                initial_agents: 400,
+                initial_resources: 0,
+                initial_gene_distribution: initial_gene_distribution.clone(),
+                ..Default::default()
+            });
+            let agents = simulation.get_agents();
+            let counts = gene_histogram(&agents, "is_predator", 10).unwrap();
+
+            // Two peaks near 0.1 and 0.9 with an empty valley between them
+            let (low, high) = (counts[0] + counts[1], counts[8] + counts[9]);
+            let valley: u32 = counts[3..7].iter().sum();
+            assert_eq!(valley, 0, "ecs {}: {:?}", use_ecs, counts);
+            assert!(low > agents.len() as u32 / 4, "ecs {}: {:?}", use_ecs, counts);
+            assert!(high > agents.len() as u32 / 4, "ecs {}: {:?}", use_ecs, counts);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_initial_gene_distribution() {
+        let config = SimulationConfig {
+            initial_gene_distribution: InitialGeneDistribution::Bimodal {
+                config_a: Box::new(InitialGeneDistribution::Uniform),
+                config_b: Box::new(InitialGeneDistribution::FromJson("[]".to_string())),
+                fraction_a: 0.5,
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::InvalidInitialGeneDistribution(
+                "gene list is empty".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_every_violation() {
+        let config = SimulationConfig {
+            initial_agents: 10,
+            max_agents: 5,
+            stability_threshold: -0.1,
+            ..Default::default()
+        };
+        let errors = config.validate().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ConfigError::InitialAgentsExceedMax { initial: 10, max: 5 },
+                ConfigError::StabilityThresholdOutOfRange(-0.1),
+            ]
+        );
+        let message = format_config_errors(&errors);
+        assert!(message.contains("initial_agents (10)"), "{}", message);
+        assert!(message.contains("stability_threshold (-0.1)"), "{}", message);
+    }
+
+    #[test]
+    fn test_config_builder_validates_on_build() {
+        let config = SimulationConfigBuilder::new()
+            .size(640.0, 480.0)
+            .initial_agents(20)
+            .max_agents(40)
+            .seed(7)
+            .build()
+            .unwrap();
+        assert_eq!((config.width, config.height), (640.0, 480.0));
+        assert_eq!((config.initial_agents, config.max_agents), (20, 40));
+        assert_eq!(config.seed, Some(7));
+
+        let errors = SimulationConfigBuilder::new()
+            .size(10.0, 480.0)
+            .resource_spawn_rate(-1.0)
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors,
+            vec![
+                ConfigError::CanvasTooSmall {
+                    width: 10.0,
+                    height: 480.0
+                },
+                ConfigError::InvalidSpawnRate(-1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_diff_reports_agent_death() {
+        let config = SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            ..Default::default()
+        };
+        let mut engine = LegacySimulationEngine::new(config);
+        let mut starving = Agent::new(100.0, 100.0, Genes::new(), 1);
+        starving.energy = 0.0001;
+        let healthy = Agent::new(400.0, 300.0, Genes::new(), 1);
+        engine.restore(vec![starving, healthy], Vec::new());
+
+        let before = engine.get_stats();
+        engine.update();
+        let after = engine.get_stats();
+
+        let diff = after.diff(&before);
+        assert_eq!(diff.agent_count_delta, -1);
+        assert!(diff.energy_delta < 0.0);
+        assert!(diff.significant_change(0.1));
+        assert!(!after.diff(&after).significant_change(0.0));
+    }
+
+    #[test]
+    fn test_energy_heatmap_follows_consumption() {
+        let config = SimulationConfig {
+            width: 800.0,
+            height: 600.0,
+            initial_agents: 0,
+            initial_resources: 0,
+            resource_spawn_rate: 0.0001,
+            ..Default::default()
+        };
+        let mut engine = LegacySimulationEngine::new(config);
+
+        let mut agents = Vec::new();
+        let mut resources = Vec::new();
+        for x in (25..800).step_by(50) {
+            for y in (25..600).step_by(50) {
+                let (x, y) = (x as f64, y as f64);
+                agents.push(Agent::new(x, y, Genes::new(), 1));
+                if x < 400.0 && y < 300.0 {
+                    // Worth grazing whatever the agent's R*
+                    let mut resource = Resource::new(x, y);
+                    resource.energy = 30.0;
+                    resources.push(resource);
+                }
+            }
+        }
+        engine.restore(agents, resources);
+
+        for _ in 0..1000 {
+            engine.update();
+        }
+
+        let heatmap = engine.energy_heatmap();
+        let quadrant = |left: bool, top: bool| -> f64 {
+            let columns = heatmap.len();
+            let rows = heatmap[0].len();
+            (0..columns)
+                .filter(|&x| (x < columns / 2) == left)
+                .flat_map(|x| {
+                    (0..rows)
+                        .filter(move |&y| (y < rows / 2) == top)
+                        .map(move |y| heatmap[x][y])
+                })
+                .sum()
+        };
+        let fed = quadrant(true, true);
+        assert!(fed > 0.0);
+        for (left, top) in [(false, true), (true, false), (false, false)] {
+            assert!(fed > quadrant(left, top));
+        }
+    }
+
+    #[test]
+    fn test_hungry_agents_pass_over_patches_below_their_r_star() {
+        let mut engine = LegacySimulationEngine::new(SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            resource_spawn_rate: 0.0001,
+            ..Default::default()
+        });
+        let forager = |x: f64, metabolism: f64, size: f64| {
+            let genes = Genes {
+                metabolism,
+                size,
+                ..Genes::new()
+            };
+            let mut agent = Agent::new(x, 300.0, genes, 1);
+            agent.age = 10.0; // Past infant mortality
+            agent
+        };
+        let patch = |x: f64| {
+            let mut resource = Resource::new(x, 300.0);
+            resource.energy = 6.0;
+            resource
+        };
+        let (lean, hungry) = (forager(200.0, 0.5, 1.0), forager(600.0, 2.5, 2.0));
+        assert!(lean.r_star < 6.0 && hungry.r_star > 6.0);
+        engine.restore(vec![lean, hungry], vec![patch(200.0), patch(600.0)]);
+
+        engine.update();
+
+        let resources = engine.get_resources();
+        let energy_at = |x: f64| resources.iter().find(|r| r.x == x).unwrap().energy;
+        assert!(energy_at(200.0) < 6.0);
+        assert!(energy_at(600.0) >= 6.0);
+    }
+
+    #[test]
+    fn test_no_births_into_a_full_habitat() {
+        let births = |capacity: Option<usize>| {
+            let mut engine = LegacySimulationEngine::new(SimulationConfig {
+                initial_agents: 0,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                min_agent_count: 0,
+                ..Default::default()
+            });
+            if let Some(carrying_capacity) = capacity {
+                engine.add_habitat(Habitat {
+                    x: 400.0,
+                    y: 300.0,
+                    radius: 100.0,
+                    carrying_capacity,
+                });
+            }
+            let breeders = (0..4)
+                .map(|i| {
+                    let genes = Genes {
+                        reproduction_threshold: 50.0,
+                        ..Genes::new()
+                    };
+                    let mut agent = Agent::new(390.0 + i as f64 * 5.0, 300.0, genes, 1);
+                    agent.age = 10.0; // Past infant mortality
+                    agent.energy = 100.0;
+                    agent
+                })
+                .collect();
+            engine.restore(breeders, Vec::new());
+            engine.update();
+            engine
+                .get_agents()
+                .iter()
+                .filter(|agent| agent.generation > 1)
+                .count()
+        };
+
+        assert!(births(None) > 0);
+        assert_eq!(births(Some(4)), 0);
+    }
+
+    #[test]
+    fn test_ecs_energy_heatmap_maps_poison_bites() {
+        let config = SimulationConfig {
+            width: 800.0,
+            height: 600.0,
+            initial_agents: 0,
+            initial_resources: 0,
+            resource_spawn_rate: 0.0001,
+            ..Default::default()
+        };
+        let mut engine = EcsSimulationEngine::new(config);
+        let mut poison = Resource::new(120.0, 320.0);
+        poison.make_poisonous();
+        poison.energy = 30.0;
+        poison.spawn_fade = 1.0;
+        poison.is_spawning = false;
+        engine.restore(vec![Agent::new(120.0, 320.0, Genes::new(), 1)], vec![poison]);
+
+        engine.update();
+        let heatmap = engine.energy_heatmap();
+        assert_eq!((heatmap.len(), heatmap[0].len()), (16, 12));
+        let bitten = heatmap[2][6];
+        assert!((bitten - 30.0).abs() < 0.1, "{}", bitten);
+        assert_eq!(heatmap.iter().flatten().filter(|&&cell| cell > 0.0).count(), 1);
+        // Drained along with the bite, so it can't linger in exports
+        let mut drained = engine
+            .ecs_world
+            .world
+            .query::<&crate::ecs::ResourceConsumption>();
+        assert!(drained.iter().next().is_none());
+        drop(drained);
+
+        engine.update();
+        let faded = engine.energy_heatmap()[2][6];
+        assert!((faded - bitten * HEATMAP_DECAY).abs() < 1e-9, "{}", faded);
+
+        engine.reset();
+        assert!(engine.energy_heatmap().iter().flatten().all(|&cell| cell == 0.0));
+    }
+
+    #[test]
+    fn test_toxin_accumulates_at_kills_and_decays() {
+        let (site_x, site_y) = (200.0, 300.0);
+        let config = SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            ..Default::default()
+        };
+        let mut engine = LegacySimulationEngine::new(config);
+        let victims: Vec<Agent> = (0..20)
+            .map(|_| Agent::new(site_x, site_y, Genes::new(), 1))
+            .collect();
+        engine.restore(victims, Vec::new());
+
+        engine.cull_agents(0.0);
+        let toxin = |engine: &LegacySimulationEngine| {
+            engine.toxin_grid().unwrap().at(site_x, site_y)
+        };
+        let after_kills = toxin(&engine);
+        assert!(after_kills > TOXIN_THRESHOLD);
+        assert_eq!(engine.toxin_grid().unwrap().at(600.0, 100.0), 0.0);
+
+        engine.update();
+        assert!((toxin(&engine) - after_kills * TOXIN_DECAY).abs() < 1e-9);
+        for _ in 0..100 {
+            engine.update();
+        }
+        assert!(toxin(&engine) < 0.1 * TOXIN_THRESHOLD);
+    }
+
+    #[test]
+    fn test_mass_kill_leaves_resources_at_kill_site() {
+        let (site_x, site_y) = (200.0, 300.0);
+        let resources_at_site = |engine: &dyn SimulationEngine| {
+            engine
+                .get_resources()
+                .iter()
+                .filter(|r| r.distance_to(site_x, site_y) < 1.0)
+                .count()
+        };
+
+        let config = SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            ..Default::default()
+        };
+        let engines: Vec<Box<dyn SimulationEngine>> = vec![
+            Box::new(LegacySimulationEngine::new(config.clone())),
+            Box::new(EcsSimulationEngine::new(config)),
+        ];
+
+        for mut engine in engines {
+            let victims: Vec<Agent> = (0..20)
+                .map(|_| Agent::new(site_x, site_y, Genes::new(), 1))
+                .collect();
+            engine.restore(victims, Vec::new());
+            assert_eq!(resources_at_site(engine.as_ref()), 0);
+
+            engine.cull_agents(0.0);
+
+            assert_eq!(engine.agent_count(), 0);
+            assert_eq!(resources_at_site(engine.as_ref()), 20);
+            assert_eq!(engine.death_resource_count(), 20);
+            let deposits = engine.get_resources();
+            assert!(deposits.iter().all(|r| r.is_carcass && r.energy > 0.0));
+        }
+    }
+
+    #[test]
+    fn test_agent_at_teleporter_entrance_arrives_at_exit() {
+        let config = SimulationConfig {
+            initial_agents: 0,
+            initial_resources: 0,
+            teleporters: vec![Teleporter::new(100.0, 100.0, 700.0, 500.0, 20.0)],
+            ..Default::default()
+        };
+        let engines: Vec<Box<dyn SimulationEngine>> = vec![
+            Box::new(LegacySimulationEngine::new(config.clone())),
+            Box::new(EcsSimulationEngine::new(config)),
+        ];
+
+        for mut engine in engines {
+            let traveller = Agent::new(100.0, 100.0, Genes::new(), 1);
+            let bystander = Agent::new(400.0, 300.0, Genes::new(), 1);
+            engine.restore(vec![traveller, bystander], Vec::new());
+
+            engine.update();
+
+            let agents = engine.get_agents();
+            let arrived = agents.iter().filter(|a| a.distance_to(700.0, 500.0) < 10.0);
+            assert_eq!(arrived.count(), 1);
+            assert!(agents.iter().any(|a| a.distance_to(400.0, 300.0) < 10.0));
+            assert_eq!(engine.get_teleporters()[0].teleportations, 1);
+
+            engine.clear();
+            assert_eq!(engine.get_teleporters()[0].teleportations, 0);
+        }
+    }
+
+    #[test]
+    fn test_archetypes_spawn_exact_counts() {
+        let archetype = |speed: f64, is_predator: f64, count: usize, position| AgentArchetype {
+            genes: Genes {
+                speed,
+                is_predator,
+                ..Genes::new()
+            },
+            initial_energy: 60.0,
+            position_distribution: position,
+            count,
+        };
+        let config = SimulationConfig {
+            initial_agents: 500,
+            initial_resources: 0,
+            agent_archetypes: vec![
+                archetype(0.5, 0.0, 200, PositionDistribution::Uniform),
+                archetype(
+                    2.5,
+                    1.0,
+                    20,
+                    PositionDistribution::Fixed { x: 300.0, y: 200.0 },
+                ),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(config.initial_agent_count(), 220);
+        let engines: Vec<Box<dyn SimulationEngine>> = vec![
+            Box::new(LegacySimulationEngine::new(config.clone())),
+            Box::new(EcsSimulationEngine::new(config)),
+        ];
+
+        for mut engine in engines {
+            for _ in 0..2 {
+                let agents = engine.get_agents();
+                assert_eq!(agents.len(), 220);
+                let herbivores = agents.iter().filter(|a| a.genes.speed == 0.5).count();
+                let predators: Vec<&Agent> =
+                    agents.iter().filter(|a| a.genes.speed == 2.5).collect();
+                assert_eq!(herbivores, 200);
+                assert_eq!(predators.len(), 20);
+                assert!(predators
+                    .iter()
+                    .all(|a| a.is_predator() && (a.x, a.y) == (300.0, 200.0)));
+                assert!(agents.iter().all(|a| a.energy == 60.0));
+                engine.reset();
+            }
+        }
+    }
+
+    #[test]
+    fn test_winter_slows_regrowth_and_costs_energy() {
+        let config = SimulationConfig {
+            season_period_seconds: 10.0,
+            ..Default::default()
+        };
+        // Summer peaks with spawning a quarter of the way into each season
+        assert!((config.warmth(12.5) - 1.0).abs() < 1e-9);
+        assert!(config.warmth(17.5).abs() < 1e-9);
+        assert_eq!(config.season_cycles_completed(25.0), 2);
+        let no_seasons = SimulationConfig {
+            season_period_seconds: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(no_seasons.warmth(17.5), 1.0);
+        assert_eq!(no_seasons.season_cycles_completed(100.0), 0);
+
+        assert_eq!(seasonal_metabolic_scale(1.0), 1.0);
+        assert!((seasonal_metabolic_scale(0.0) - 1.3).abs() < 1e-9);
+
+        let regrowth = |warmth: f64| {
+            let mut resource = EcsResource {
+                energy: 20.0,
+                max_energy: 60.0,
+                size: 3.0,
+                growth_rate: 0.3,
+                regeneration_rate: 0.05,
+                age: 0.0,
+                target_energy: 60.0,
+                is_spawning: false,
+                spawn_fade: 1.0,
+                is_depleting: false,
+                deplete_fade: 0.0,
+                is_carcass: false,
+                resource_type: ResourceType::Food,
+                energy_delta: 0.0,
+            };
+            resource.update(1.0, seasonal_growth_scale(warmth));
+            resource.energy - 20.0
+        };
+        assert!((regrowth(0.0) / regrowth(1.0) - WINTER_GROWTH_FLOOR).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_interleaved_seeded_simulations_keep_their_own_streams() {
+        for use_ecs in [false, true] {
+            let config = |seed: u64| SimulationConfig {
+                use_ecs,
+                initial_agents: 50,
+                initial_resources: 100,
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let positions = |simulation: &UnifiedSimulation| -> Vec<(f64, f64, f64)> {
+                let agents = simulation.get_agents();
+                agents.iter().map(|a| (a.x, a.y, a.energy)).collect()
+            };
+
+            let mut alone = UnifiedSimulation::new(config(5));
+            for _ in 0..120 {
+                alone.update();
+            }
+
+            let mut first = UnifiedSimulation::new(config(5));
+            let mut other = UnifiedSimulation::new(config(6));
+            for _ in 0..120 {
+                first.update();
+                other.update();
+            }
+
+            assert_eq!(positions(&alone), positions(&first));
+            assert_eq!(alone.get_resources().len(), first.get_resources().len());
+            assert_ne!(positions(&first), positions(&other));
+        }
     }
 }
@@ -0,0 +1,404 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Point index used to find agents near a position.
+pub trait SpatialIndex: Send + Sync {
+    fn insert(&mut self, x: f64, y: f64, id: usize);
+    /// IDs of every point within `radius` of (x, y), in no particular order.
+    fn query_radius(&self, x: f64, y: f64, radius: f64) -> Vec<usize>;
+    fn clear(&mut self);
+}
+
+/// Which `SpatialIndex` an engine builds.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpatialBackend {
+    /// Fixed grid covering the world; points outside it land in edge cells.
+    #[default]
+    Grid,
+    /// Hash map holding only occupied cells, so large queries over a sparse
+    /// world skip the empty ones.
+    Hash,
+    /// Quad tree splitting a region into four once it holds more than
+    /// `DEFAULT_QUADTREE_CAPACITY` points, so clusters get finer cells and
+    /// empty space none.
+    QuadTree,
+}
+
+impl SpatialBackend {
+    pub fn build(self, width: f64, height: f64, cell_size: f64) -> Box<dyn SpatialIndex> {
+        match self {
+            SpatialBackend::Grid => Box::new(SpatialGrid::new(width, height, cell_size)),
+            SpatialBackend::Hash => Box::new(SpatialHash::new(cell_size)),
+            SpatialBackend::QuadTree => {
+                Box::new(QuadTree::new(width, height, DEFAULT_QUADTREE_CAPACITY))
+            }
+        }
+    }
+}
+
+type Entry = (usize, f64, f64);
+
+fn within(entry: &Entry, x: f64, y: f64, radius: f64) -> bool {
+    let (dx, dy) = (entry.1 - x, entry.2 - y);
+    dx * dx + dy * dy <= radius * radius
+}
+
+pub struct SpatialGrid {
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<Entry>>,
+}
+
+impl SpatialGrid {
+    pub fn new(width: f64, height: f64, cell_size: f64) -> Self {
+        let columns = ((width / cell_size).ceil() as usize).max(1);
+        let rows = ((height / cell_size).ceil() as usize).max(1);
+        Self {
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        }
+    }
+
+    fn column(&self, x: f64) -> usize {
+        ((x / self.cell_size).floor().max(0.0) as usize).min(self.columns - 1)
+    }
+
+    fn row(&self, y: f64) -> usize {
+        ((y / self.cell_size).floor().max(0.0) as usize).min(self.rows - 1)
+    }
+}
+
+impl SpatialIndex for SpatialGrid {
+    fn insert(&mut self, x: f64, y: f64, id: usize) {
+        let cell = self.row(y) * self.columns + self.column(x);
+        self.cells[cell].push((id, x, y));
+    }
+
+    fn query_radius(&self, x: f64, y: f64, radius: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        for row in self.row(y - radius)..=self.row(y + radius) {
+            for column in self.column(x - radius)..=self.column(x + radius) {
+                let cell = &self.cells[row * self.columns + column];
+                found.extend(
+                    cell.iter()
+                        .filter(|entry| within(entry, x, y, radius))
+                        .map(|entry| entry.0),
+                );
+            }
+        }
+        found
+    }
+
+    fn clear(&mut self) {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+    }
+}
+
+pub struct SpatialHash {
+    cell_size: f64,
+    cells: HashMap<(i32, i32), Vec<Entry>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn key(&self, x: f64, y: f64) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+impl SpatialIndex for SpatialHash {
+    fn insert(&mut self, x: f64, y: f64, id: usize) {
+        let key = self.key(x, y);
+        self.cells.entry(key).or_default().push((id, x, y));
+    }
+
+    fn query_radius(&self, x: f64, y: f64, radius: f64) -> Vec<usize> {
+        let (min_x, min_y) = self.key(x - radius, y - radius);
+        let (max_x, max_y) = self.key(x + radius, y + radius);
+        let span = (max_x - min_x + 1) as usize * (max_y - min_y + 1) as usize;
+
+        let matching = |entries: &Vec<Entry>| {
+            entries
+                .iter()
+                .filter(|entry| within(entry, x, y, radius))
+                .map(|entry| entry.0)
+                .collect::<Vec<_>>()
+        };
+
+        // Walk whichever is smaller: the cells under the query or the occupied ones
+        if span <= self.cells.len() {
+            (min_x..=max_x)
+                .flat_map(|cx| (min_y..=max_y).map(move |cy| (cx, cy)))
+                .filter_map(|key| self.cells.get(&key))
+                .flat_map(matching)
+                .collect()
+        } else {
+            self.cells
+                .iter()
+                .filter(|((cx, cy), _)| {
+                    (min_x..=max_x).contains(cx) && (min_y..=max_y).contains(cy)
+                })
+                .flat_map(|(_, entries)| matching(entries))
+                .collect()
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cells.clear();
+    }
+}
+
+/// Points a quad tree region holds before it splits.
+pub const DEFAULT_QUADTREE_CAPACITY: usize = 8;
+/// Depth below which regions no longer split, so many points on the same spot
+/// can't recurse forever.
+const QUADTREE_MAX_DEPTH: usize = 16;
+
+/// (min_x, min_y, max_x, max_y).
+type Bounds = (f64, f64, f64, f64);
+
+struct QuadNode<T> {
+    bounds: Bounds,
+    depth: usize,
+    points: Vec<(T, f64, f64)>,
+    /// Index in `QuadTree::nodes` of the first of four children, in the
+    /// order top-left, top-right, bottom-left, bottom-right.
+    children: Option<usize>,
+}
+
+impl<T> QuadNode<T> {
+    fn new(bounds: Bounds, depth: usize) -> Self {
+        Self {
+            bounds,
+            depth,
+            points: Vec::new(),
+            children: None,
+        }
+    }
+
+    /// Which child the point (x, y) of these bounds falls in.
+    fn quadrant(&self, x: f64, y: f64) -> usize {
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+        let right = x >= (min_x + max_x) / 2.0;
+        let bottom = y >= (min_y + max_y) / 2.0;
+        usize::from(right) + 2 * usize::from(bottom)
+    }
+}
+
+/// Quad tree over a `width` x `height` world. Points outside it are filed
+/// under the nearest edge, as in `SpatialGrid`, but matched against queries
+/// by their real position.
+pub struct QuadTree<T> {
+    width: f64,
+    height: f64,
+    capacity: usize,
+    nodes: Vec<QuadNode<T>>,
+}
+
+impl<T: Copy> QuadTree<T> {
+    /// Splits a region once it holds more than `capacity` points.
+    pub fn new(width: f64, height: f64, capacity: usize) -> Self {
+        Self {
+            width,
+            height,
+            capacity: capacity.max(1),
+            nodes: vec![QuadNode::new((0.0, 0.0, width, height), 0)],
+        }
+    }
+
+    fn clamp(&self, x: f64, y: f64) -> (f64, f64) {
+        (x.clamp(0.0, self.width), y.clamp(0.0, self.height))
+    }
+
+    pub fn insert(&mut self, x: f64, y: f64, value: T) {
+        let (cx, cy) = self.clamp(x, y);
+        let mut node = 0;
+        while let Some(first) = self.nodes[node].children {
+            node = first + self.nodes[node].quadrant(cx, cy);
+        }
+        self.nodes[node].points.push((value, x, y));
+        if self.nodes[node].points.len() > self.capacity
+            && self.nodes[node].depth < QUADTREE_MAX_DEPTH
+        {
+            self.split(node);
+        }
+    }
+
+    fn split(&mut self, node: usize) {
+        let (min_x, min_y, max_x, max_y) = self.nodes[node].bounds;
+        let (mid_x, mid_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let depth = self.nodes[node].depth + 1;
+        let first = self.nodes.len();
+        for bounds in [
+            (min_x, min_y, mid_x, mid_y),
+            (mid_x, min_y, max_x, mid_y),
+            (min_x, mid_y, mid_x, max_y),
+            (mid_x, mid_y, max_x, max_y),
+        ] {
+            self.nodes.push(QuadNode::new(bounds, depth));
+        }
+        self.nodes[node].children = Some(first);
+
+        for (value, x, y) in std::mem::take(&mut self.nodes[node].points) {
+            let (cx, cy) = self.clamp(x, y);
+            let child = first + self.nodes[node].quadrant(cx, cy);
+            self.nodes[child].points.push((value, x, y));
+        }
+    }
+
+    /// Values of every point within `radius` of (x, y), in no particular
+    /// order.
+    pub fn query_range(&self, x: f64, y: f64, radius: f64) -> Vec<T> {
+        // Clamping both ends of the query box keeps it overlapping the regions
+        // that out-of-world points were filed under
+        let (low_x, low_y) = self.clamp(x - radius, y - radius);
+        let (high_x, high_y) = self.clamp(x + radius, y + radius);
+        let mut found = Vec::new();
+        self.collect(0, (low_x, low_y, high_x, high_y), (x, y, radius * radius), &mut found);
+        found
+    }
+
+    fn collect(&self, node: usize, query: Bounds, circle: (f64, f64, f64), found: &mut Vec<T>) {
+        let node = &self.nodes[node];
+        let (min_x, min_y, max_x, max_y) = node.bounds;
+        if query.2 < min_x || query.0 > max_x || query.3 < min_y || query.1 > max_y {
+            return;
+        }
+        match node.children {
+            Some(first) => {
+                for child in first..first + 4 {
+                    self.collect(child, query, circle, found);
+                }
+            }
+            None => {
+                let (x, y, radius_squared) = circle;
+                found.extend(
+                    node.points
+                        .iter()
+                        .filter(|(_, px, py)| (px - x).powi(2) + (py - y).powi(2) <= radius_squared)
+                        .map(|&(value, _, _)| value),
+                );
+            }
+        }
+    }
+
+    /// Drops every point and subdivision.
+    pub fn clear(&mut self) {
+        self.nodes.truncate(1);
+        self.nodes[0].points.clear();
+        self.nodes[0].children = None;
+    }
+
+    /// Regions in the tree, split or not.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+impl SpatialIndex for QuadTree<usize> {
+    fn insert(&mut self, x: f64, y: f64, id: usize) {
+        QuadTree::insert(self, x, y, id);
+    }
+
+    fn query_radius(&self, x: f64, y: f64, radius: f64) -> Vec<usize> {
+        self.query_range(x, y, radius)
+    }
+
+    fn clear(&mut self) {
+        QuadTree::clear(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_backends_agree() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let points: Vec<(f64, f64)> = (0..2000)
+            .map(|_| (rng.gen_range(-20.0..1020.0), rng.gen_range(-20.0..820.0)))
+            .collect();
+
+        let mut grid = SpatialBackend::Grid.build(1000.0, 800.0, 50.0);
+        let mut hash = SpatialBackend::Hash.build(1000.0, 800.0, 50.0);
+        let mut tree = SpatialBackend::QuadTree.build(1000.0, 800.0, 50.0);
+        for (id, &(x, y)) in points.iter().enumerate() {
+            grid.insert(x, y, id);
+            hash.insert(x, y, id);
+            tree.insert(x, y, id);
+        }
+
+        for radius in [0.0, 20.0, 50.0, 100.0, 200.0, 2000.0] {
+            for _ in 0..50 {
+                let (x, y) = (rng.gen_range(0.0..1000.0), rng.gen_range(0.0..800.0));
+                let mut expected: Vec<usize> = points
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &(px, py))| (px - x).hypot(py - y) <= radius)
+                    .map(|(id, _)| id)
+                    .collect();
+                let mut from_grid = grid.query_radius(x, y, radius);
+                let mut from_hash = hash.query_radius(x, y, radius);
+                let mut from_tree = tree.query_radius(x, y, radius);
+                expected.sort_unstable();
+                from_grid.sort_unstable();
+                from_hash.sort_unstable();
+                from_tree.sort_unstable();
+                assert_eq!(from_grid, expected, "grid, radius {}", radius);
+                assert_eq!(from_hash, expected, "hash, radius {}", radius);
+                assert_eq!(from_tree, expected, "quad tree, radius {}", radius);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_empties_every_backend() {
+        for backend in [SpatialBackend::Grid, SpatialBackend::Hash, SpatialBackend::QuadTree] {
+            let mut index = backend.build(100.0, 100.0, 10.0);
+            index.insert(5.0, 5.0, 0);
+            assert_eq!(index.query_radius(5.0, 5.0, 1.0), vec![0]);
+            index.clear();
+            assert!(index.query_radius(5.0, 5.0, 100.0).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_quad_tree_splits_only_where_crowded() {
+        let mut tree = QuadTree::new(1000.0, 1000.0, 8);
+        for i in 0..8 {
+            tree.insert(10.0 + i as f64, 10.0, i);
+        }
+        assert_eq!(tree.node_count(), 1);
+        tree.insert(20.0, 10.0, 8);
+        assert!(tree.node_count() > 1);
+
+        // A pile of identical points stops splitting at the depth limit
+        for i in 0..100 {
+            tree.insert(500.0, 500.0, 100 + i);
+        }
+        assert!(tree.node_count() <= 1 + 4 * QUADTREE_MAX_DEPTH * 2);
+        assert_eq!(tree.query_range(500.0, 500.0, 0.0).len(), 100);
+        assert_eq!(tree.query_range(10.0, 10.0, 100.0).len(), 9);
+
+        tree.clear();
+        assert_eq!(tree.node_count(), 1);
+        assert!(tree.query_range(500.0, 500.0, 1000.0).is_empty());
+    }
+}
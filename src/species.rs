@@ -0,0 +1,270 @@
+use crate::agent::Agent;
+use crate::genes::{genetic_distance, Genes, GENE_COUNT};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Only agents this close to each other are compared when clustering.
+pub const SPECIES_PROXIMITY_RADIUS: f64 = 200.0;
+
+/// Genetic distance below which two agents are linked into the same species.
+pub const DEFAULT_SPECIES_THRESHOLD: f64 = 0.3;
+
+/// Single-linkage agglomerative clustering of agents by genetic distance.
+///
+/// Two agents are joined when they are within `SPECIES_PROXIMITY_RADIUS` of each
+/// other and their genetic distance is at most `threshold`; species are the
+/// connected components of that graph. Returns agent indices grouped by species,
+/// largest species first.
+pub fn detect_species(agents: &[Agent], threshold: f64) -> Vec<Vec<usize>> {
+    cluster(agents, |agent| (agent.x, agent.y, &agent.genes), threshold)
+}
+
+/// `detect_species` over anything `locate` can place and give genes to.
+fn cluster<T>(
+    items: &[T],
+    locate: impl Fn(&T) -> (f64, f64, &Genes),
+    threshold: f64,
+) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..items.len()).collect();
+
+    for i in 0..items.len() {
+        let (ax, ay, a_genes) = locate(&items[i]);
+        for (j, item) in items.iter().enumerate().skip(i + 1) {
+            let (bx, by, b_genes) = locate(item);
+            if (ax - bx).hypot(ay - by) > SPECIES_PROXIMITY_RADIUS {
+                continue;
+            }
+            if genetic_distance(a_genes, b_genes) <= threshold {
+                let root_a = find_root(&mut parent, i);
+                let root_b = find_root(&mut parent, j);
+                if root_a != root_b {
+                    parent[root_b] = root_a;
+                }
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut cluster_of_root = vec![usize::MAX; items.len()];
+    for i in 0..items.len() {
+        let root = find_root(&mut parent, i);
+        if cluster_of_root[root] == usize::MAX {
+            cluster_of_root[root] = clusters.len();
+            clusters.push(Vec::new());
+        }
+        clusters[cluster_of_root[root]].push(i);
+    }
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    clusters
+}
+
+/// Sets every agent's `species_id` from `detect_species`, keeping IDs stable
+/// between calls: each species, largest first, takes over the most common
+/// existing ID among its members that no larger species has claimed, and
+/// otherwise gets `next_id`, which is then advanced. IDs start at 1; 0 means
+/// an agent hasn't been tagged yet.
+pub fn assign_species_ids(agents: &mut [Agent], threshold: f64, next_id: &mut u32) {
+    let current: Vec<u32> = agents.iter().map(|agent| agent.species_id).collect();
+    let ids = stable_species_ids(&detect_species(agents, threshold), &current, next_id);
+    for (agent, id) in agents.iter_mut().zip(ids) {
+        agent.species_id = id;
+    }
+}
+
+/// `assign_species_ids` for bare `(x, y, genes)` samples tagged `current`,
+/// returning the new tags in the same order.
+pub fn assign_genome_species_ids(
+    genomes: &[(f64, f64, Genes)],
+    current: &[u32],
+    threshold: f64,
+    next_id: &mut u32,
+) -> Vec<u32> {
+    let clusters = cluster(genomes, |(x, y, genes)| (*x, *y, genes), threshold);
+    stable_species_ids(&clusters, current, next_id)
+}
+
+fn stable_species_ids(clusters: &[Vec<usize>], current: &[u32], next_id: &mut u32) -> Vec<u32> {
+    let mut ids = current.to_vec();
+    let mut claimed = HashSet::new();
+    for members in clusters {
+        let mut votes: HashMap<u32, usize> = HashMap::new();
+        for &i in members {
+            let id = current[i];
+            if id != 0 && !claimed.contains(&id) {
+                *votes.entry(id).or_default() += 1;
+            }
+        }
+        // Ties go to the older (smaller) ID so the choice doesn't depend on hash order
+        let inherited = votes
+            .into_iter()
+            .max_by_key(|&(id, count)| (count, std::cmp::Reverse(id)))
+            .map(|(id, _)| id);
+        let id = inherited.unwrap_or_else(|| {
+            *next_id += 1;
+            *next_id - 1
+        });
+        claimed.insert(id);
+        for &i in members {
+            ids[i] = id;
+        }
+    }
+    ids
+}
+
+/// How many species a population splits into and how big the largest is.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpeciesSummary {
+    pub species_count: usize,
+    pub largest_species_size: usize,
+    pub largest_species_fraction: f64,
+}
+
+impl SpeciesSummary {
+    fn from_clusters(clusters: &[Vec<usize>], population: usize) -> Self {
+        if population == 0 {
+            return Self::default();
+        }
+        let largest = clusters.first().map_or(0, Vec::len);
+        Self {
+            species_count: clusters.len(),
+            largest_species_size: largest,
+            largest_species_fraction: largest as f64 / population as f64,
+        }
+    }
+}
+
+pub fn species_summary(agents: &[Agent], threshold: f64) -> SpeciesSummary {
+    SpeciesSummary::from_clusters(&detect_species(agents, threshold), agents.len())
+}
+
+/// The last `SpeciesSummary` an engine computed and the epoch it belongs to.
+/// Clustering is quadratic in the population, so engines recompute it only
+/// when the epoch moves on or after `invalidate`, not on every read.
+#[derive(Debug, Default)]
+pub struct SpeciesCache {
+    cached: Mutex<Option<(usize, SpeciesSummary)>>,
+}
+
+impl SpeciesCache {
+    /// The summary for `epoch`, from `compute` unless already cached.
+    pub fn get(&self, epoch: usize, compute: impl FnOnce() -> SpeciesSummary) -> SpeciesSummary {
+        let mut cached = self.cached.lock().unwrap();
+        match *cached {
+            Some((cached_epoch, summary)) if cached_epoch == epoch => summary,
+            _ => {
+                let summary = compute();
+                *cached = Some((epoch, summary));
+                summary
+            }
+        }
+    }
+
+    /// Forces the next `get` to recompute, for when the population is
+    /// replaced wholesale.
+    pub fn invalidate(&mut self) {
+        *self.cached.get_mut().unwrap() = None;
+    }
+}
+
+/// `species_summary` for bare `(x, y, genes)` samples, for engines that don't
+/// keep `Agent`s around.
+pub fn genome_species_summary(genomes: &[(f64, f64, Genes)], threshold: f64) -> SpeciesSummary {
+    let clusters = cluster(genomes, |(x, y, genes)| (*x, *y, genes), threshold);
+    SpeciesSummary::from_clusters(&clusters, genomes.len())
+}
+
+/// Mean standard deviation of the normalized genes across the population.
+pub fn gene_diversity(agents: &[Agent]) -> f64 {
+    if agents.len() < 2 {
+        return 0.0;
+    }
+
+    let genomes: Vec<[f64; GENE_COUNT]> = agents.iter().map(|a| a.genes.normalized()).collect();
+    let count = genomes.len() as f64;
+    let total_std_dev: f64 = (0..GENE_COUNT)
+        .map(|gene| {
+            let mean = genomes.iter().map(|g| g[gene]).sum::<f64>() / count;
+            let variance = genomes.iter().map(|g| (g[gene] - mean).powi(2)).sum::<f64>() / count;
+            variance.sqrt()
+        })
+        .sum();
+
+    total_std_dev / GENE_COUNT as f64
+}
+
+pub(crate) fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genes::Genes;
+
+    fn genes_with(value: f64) -> Genes {
+        let mut genes = Genes::new();
+        genes.speed = 0.1 + value * 2.9;
+        genes.sense_range = 5.0 + value * 145.0;
+        genes.size = 0.3 + value * 2.2;
+        genes.energy_efficiency = 0.1 + value * 2.4;
+        genes.reproduction_threshold = 10.0 + value * 190.0;
+        genes.mutation_rate = 0.001 + value * 0.299;
+        genes.aggression = value;
+        genes.color_hue = value * 360.0;
+        genes.is_predator = value;
+        genes.hunting_speed = 0.5 + value * 2.5;
+        genes.attack_power = 0.1 + value * 2.9;
+        genes.defense = 0.1 + value * 2.9;
+        genes.stealth = value;
+        genes.pack_mentality = value;
+        genes.territory_size = 10.0 + value * 290.0;
+        genes.metabolism = 0.1 + value * 2.9;
+        genes.intelligence = 0.1 + value * 2.9;
+        genes.stamina = 0.1 + value * 2.9;
+        genes.omnivore = value;
+        genes.migration = value;
+        genes.cooperation = value;
+        genes
+    }
+
+    #[test]
+    fn test_detect_species_separates_gene_clusters() {
+        let mut agents = Vec::new();
+        for i in 0..5 {
+            agents.push(Agent::new(100.0 + i as f64, 100.0, genes_with(0.1), 1));
+        }
+        for i in 0..3 {
+            agents.push(Agent::new(110.0 + i as f64, 100.0, genes_with(0.9), 1));
+        }
+
+        let clusters = detect_species(&agents, DEFAULT_SPECIES_THRESHOLD);
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0], vec![0, 1, 2, 3, 4]);
+        assert_eq!(clusters[1], vec![5, 6, 7]);
+
+        let summary = species_summary(&agents, DEFAULT_SPECIES_THRESHOLD);
+        assert_eq!(summary.species_count, 2);
+        assert_eq!(summary.largest_species_size, 5);
+        assert!((summary.largest_species_fraction - 5.0 / 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_species_cache_recomputes_per_epoch_or_when_invalidated() {
+        let mut cache = SpeciesCache::default();
+        let summary = |species_count| SpeciesSummary {
+            species_count,
+            ..SpeciesSummary::default()
+        };
+        assert_eq!(cache.get(0, || summary(1)).species_count, 1);
+        assert_eq!(cache.get(0, || summary(2)).species_count, 1);
+        assert_eq!(cache.get(1, || summary(3)).species_count, 3);
+        cache.invalidate();
+        assert_eq!(cache.get(1, || summary(4)).species_count, 4);
+    }
+}
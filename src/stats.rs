@@ -0,0 +1,278 @@
+//! Small statistics helpers used to compare simulation runs.
+
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TTestResult {
+    pub t_statistic: f64,
+    pub degrees_of_freedom: f64,
+    /// Two-tailed p-value.
+    pub p_value: f64,
+}
+
+pub fn mean(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// Unbiased sample variance (n - 1 denominator).
+pub fn sample_variance(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(samples);
+    samples.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+/// The `p`th percentile (0 to 100) of ascending `sorted`, interpolating
+/// linearly between the two nearest ranks. 0 for an empty slice.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let Some(&last) = sorted.last() else {
+        return 0.0;
+    };
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    match sorted.get(below + 1) {
+        Some(&above) => sorted[below] + (above - sorted[below]) * rank.fract(),
+        None => last,
+    }
+}
+
+/// Two-tailed 95% critical values of Student's t for 1 to 29 degrees of freedom.
+const T_CRITICAL_95: [f64; 29] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045,
+];
+/// Normal approximation used from 30 samples up.
+const Z_CRITICAL_95: f64 = 1.96;
+
+/// (low, high) bounds of the 95% confidence interval for the mean of
+/// `samples`, from Student's t below 30 samples and the normal distribution
+/// above. Collapses to the mean with fewer than two samples.
+pub fn confidence_interval_95(samples: &[f64]) -> (f64, f64) {
+    let m = mean(samples);
+    if samples.len() < 2 {
+        return (m, m);
+    }
+    let critical = T_CRITICAL_95
+        .get(samples.len() - 2)
+        .copied()
+        .unwrap_or(Z_CRITICAL_95);
+    let margin = critical * (sample_variance(samples) / samples.len() as f64).sqrt();
+    (m - margin, m + margin)
+}
+
+/// (intercept, slope) of the least-squares line through (xs[i], ys[i]). The
+/// slope is 0 when every x is the same.
+pub fn least_squares_line(xs: &[f64], ys: &[f64]) -> (f64, f64) {
+    let (x_mean, y_mean) = (mean(xs), mean(ys));
+    let covariance: f64 = xs.iter().zip(ys).map(|(x, y)| (x - x_mean) * (y - y_mean)).sum();
+    let x_spread: f64 = xs.iter().map(|x| (x - x_mean).powi(2)).sum();
+    let slope = if x_spread > 0.0 { covariance / x_spread } else { 0.0 };
+    (y_mean - slope * x_mean, slope)
+}
+
+/// Least-squares fit of the discrete logistic model to a population series,
+/// returning (r, K) per sample interval. Per-capita growth
+/// (N[t+1] - N[t]) / N[t] is regressed on N[t]: r is the intercept and
+/// K = -r / slope. Without density dependence (slope >= 0) K is the largest
+/// count. `None` with fewer than two growth intervals from nonzero counts.
+pub fn fit_logistic_growth(counts: &[f64]) -> Option<(f64, f64)> {
+    let (sizes, rates): (Vec<f64>, Vec<f64>) = counts
+        .windows(2)
+        .filter(|pair| pair[0] > 0.0)
+        .map(|pair| (pair[0], (pair[1] - pair[0]) / pair[0]))
+        .unzip();
+    if sizes.len() < 2 {
+        return None;
+    }
+
+    let (r, slope) = least_squares_line(&sizes, &rates);
+    let largest = counts.iter().copied().fold(0.0, f64::max);
+    let k = if slope < 0.0 && r > 0.0 { -r / slope } else { largest };
+    Some((r, k))
+}
+
+/// Welch's unequal-variance t-test. Needs at least two samples per set.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTestResult> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let se_a = sample_variance(a) / n_a;
+    let se_b = sample_variance(b) / n_b;
+    let se = se_a + se_b;
+    let diff = mean(a) - mean(b);
+
+    if se == 0.0 {
+        // Both sets are constant: either identical or trivially different
+        let p_value = if diff == 0.0 { 1.0 } else { 0.0 };
+        return Some(TTestResult {
+            t_statistic: if diff == 0.0 { 0.0 } else { diff.signum() * f64::INFINITY },
+            degrees_of_freedom: n_a + n_b - 2.0,
+            p_value,
+        });
+    }
+
+    let t_statistic = diff / se.sqrt();
+    // Welch–Satterthwaite approximation
+    let degrees_of_freedom =
+        se * se / (se_a * se_a / (n_a - 1.0) + se_b * se_b / (n_b - 1.0));
+
+    Some(TTestResult {
+        t_statistic,
+        degrees_of_freedom,
+        p_value: student_t_two_tailed_p(t_statistic, degrees_of_freedom),
+    })
+}
+
+/// P(|T| >= |t|) for Student's t distribution with `df` degrees of freedom.
+pub fn student_t_two_tailed_p(t: f64, df: f64) -> f64 {
+    let x = df / (df + t * t);
+    regularized_incomplete_beta(x, df / 2.0, 0.5).clamp(0.0, 1.0)
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+pub fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    let front = ln_front.exp();
+
+    // The continued fraction converges quickly only on this side of the mean
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz's method).
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+
+        // Even step
+        let numerator = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + numerator * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        result *= d * c;
+
+        // Odd step
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + numerator * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        result *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+
+    result
+}
+
+/// Natural log of the gamma function (Lanczos approximation, g = 7).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_welch_t_test_matches_reference_values() {
+        // I_0.5(a, a) = 0.5 by symmetry
+        assert!((regularized_incomplete_beta(0.5, 3.0, 3.0) - 0.5).abs() < 1e-10);
+
+        // t = 2.228 is the two-tailed 5% critical value at 10 degrees of freedom
+        assert!((student_t_two_tailed_p(2.228, 10.0) - 0.05).abs() < 1e-3);
+
+        let a = [19.8, 20.4, 19.6, 17.8, 18.5, 18.9, 18.3, 18.9, 19.5, 22.0];
+        let b = [28.2, 26.6, 20.1, 23.3, 25.2, 22.1, 17.7, 27.6, 20.6, 13.7];
+        let result = welch_t_test(&a, &b).unwrap();
+        assert!((result.t_statistic - -2.0740).abs() < 1e-3);
+        assert!((result.degrees_of_freedom - 10.209).abs() < 1e-3);
+        assert!((result.p_value - 0.0643).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_percentile_interpolates_between_ranks() {
+        let sorted = [1.0, 2.0, 4.0, 8.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 100.0), 8.0);
+        assert_eq!(percentile(&sorted, 50.0), 3.0);
+        assert!((percentile(&sorted, 25.0) - 1.75).abs() < 1e-12);
+        assert_eq!(percentile(&[5.0], 95.0), 5.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_t_table_matches_student_t() {
+        for (i, &critical) in T_CRITICAL_95.iter().enumerate() {
+            let p = student_t_two_tailed_p(critical, (i + 1) as f64);
+            assert!((p - 0.05).abs() < 1e-3, "df {}: p = {:.4}", i + 1, p);
+        }
+    }
+}
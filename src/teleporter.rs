@@ -0,0 +1,56 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Furthest, on each axis, an agent lands from a teleporter's exit, so
+/// travellers arriving together don't stack on one point.
+pub const TELEPORT_SCATTER: f64 = 5.0;
+
+/// Worm-hole that moves any agent entering the circle of `radius` around
+/// (from_x, from_y) to just beside (to_x, to_y).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Teleporter {
+    pub from_x: f64,
+    pub from_y: f64,
+    pub to_x: f64,
+    pub to_y: f64,
+    pub radius: f64,
+    /// Agents sent through since the teleporter was placed.
+    #[serde(default)]
+    pub teleportations: usize,
+}
+
+impl Teleporter {
+    pub fn new(from_x: f64, from_y: f64, to_x: f64, to_y: f64, radius: f64) -> Self {
+        Self {
+            from_x,
+            from_y,
+            to_x,
+            to_y,
+            radius,
+            teleportations: 0,
+        }
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let dx = x - self.from_x;
+        let dy = y - self.from_y;
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+}
+
+/// Sends (x, y) through the first of `teleporters` whose entrance it is in,
+/// landing within `TELEPORT_SCATTER` of the exit. Returns whether it moved.
+pub fn teleport(
+    teleporters: &mut [Teleporter],
+    x: &mut f64,
+    y: &mut f64,
+    rng: &mut impl Rng,
+) -> bool {
+    let Some(teleporter) = teleporters.iter_mut().find(|t| t.contains(*x, *y)) else {
+        return false;
+    };
+    *x = teleporter.to_x + rng.gen_range(-TELEPORT_SCATTER..=TELEPORT_SCATTER);
+    *y = teleporter.to_y + rng.gen_range(-TELEPORT_SCATTER..=TELEPORT_SCATTER);
+    teleporter.teleportations += 1;
+    true
+}
@@ -0,0 +1,406 @@
+use crate::agent::Agent;
+use crate::genes::{Genes, InitialGeneDistribution};
+use crate::headless_simulation::{
+    HeadlessSimulationConfig, HeadlessSimulationV2, SimulationDiagnostics,
+};
+use crate::stats::{confidence_interval_95, mean, sample_variance, welch_t_test};
+use serde::Serialize;
+
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+/// Fittest survivors of each `run_single_test` kept in the gene archive.
+const ARCHIVE_PER_RUN: usize = 10;
+
+/// Runs batches of headless simulations for offline tuning and evaluation.
+/// Runs made through `run_single_test` also feed a `GeneArchive` that later
+/// runs can be seeded from, and parameter sweeps collect into a `TestSuite`.
+#[derive(Clone, Default)]
+pub struct TestHarness {
+    archive: GeneArchive,
+    generation: u32,
+    suite: TestSuite,
+}
+
+/// Genes of a surviving agent, kept for seeding later runs.
+#[derive(Clone, Serialize)]
+pub struct GeneArchiveEntry {
+    /// Which `run_single_test` call, counting from 0, the agent survived.
+    pub generation: u32,
+    pub genes: Genes,
+    /// `Genes::get_fitness_score` of `genes`.
+    pub fitness: f64,
+    /// Config of the run the agent survived.
+    pub config: HeadlessSimulationConfig,
+}
+
+/// The best genes of every archived run, in the order they were added.
+#[derive(Clone, Default, Serialize)]
+pub struct GeneArchive {
+    pub entries: Vec<GeneArchiveEntry>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ComparisonResult {
+    pub config_a_mean: f64,
+    pub config_b_mean: f64,
+    pub p_value: f64,
+    pub significant: bool,
+}
+
+/// Outcome of one config in a parameter sweep.
+#[derive(Clone, Serialize)]
+pub struct TestResult {
+    pub config: HeadlessSimulationConfig,
+    pub total_steps: usize,
+    pub final_agent_count: usize,
+    pub stability_score: f64,
+    pub simulation_quality_score: f64,
+    pub extinction_occurred: bool,
+    pub steps_per_second: f64,
+}
+
+impl From<SimulationDiagnostics> for TestResult {
+    fn from(diagnostics: SimulationDiagnostics) -> Self {
+        Self {
+            total_steps: diagnostics.total_steps,
+            final_agent_count: diagnostics.final_stats.agent_count,
+            stability_score: diagnostics.stability_score,
+            simulation_quality_score: diagnostics.simulation_quality_score,
+            extinction_occurred: diagnostics.extinction_occurred,
+            steps_per_second: diagnostics.steps_per_second,
+            config: diagnostics.config,
+        }
+    }
+}
+
+/// Spread of scores over a set of runs, with a 95% confidence interval for
+/// the mean score.
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct TestSuiteStatistics {
+    pub mean_score: f64,
+    pub std_dev_score: f64,
+    pub ci_95_low: f64,
+    pub ci_95_high: f64,
+    pub mean_stability: f64,
+    pub mean_steps_per_second: f64,
+}
+
+impl TestSuiteStatistics {
+    /// Summarises runs given as parallel slices of score, stability and
+    /// speed, one entry per run.
+    pub fn from_runs(scores: &[f64], stabilities: &[f64], steps_per_second: &[f64]) -> Self {
+        let (ci_95_low, ci_95_high) = confidence_interval_95(scores);
+        Self {
+            mean_score: mean(scores),
+            std_dev_score: sample_variance(scores).sqrt(),
+            ci_95_low,
+            ci_95_high,
+            mean_stability: mean(stabilities),
+            mean_steps_per_second: mean(steps_per_second),
+        }
+    }
+}
+
+/// Results of a batch of runs, scored by `simulation_quality_score`.
+#[derive(Clone, Default)]
+pub struct TestSuite {
+    pub results: Vec<TestResult>,
+}
+
+impl TestSuite {
+    pub fn new(results: Vec<TestResult>) -> Self {
+        Self { results }
+    }
+
+    pub fn add_result(&mut self, result: TestResult) {
+        self.results.push(result);
+    }
+
+    pub fn compute_statistics(&self) -> TestSuiteStatistics {
+        let column = |field: fn(&TestResult) -> f64| -> Vec<f64> {
+            self.results.iter().map(field).collect()
+        };
+        TestSuiteStatistics::from_runs(
+            &column(|r| r.simulation_quality_score),
+            &column(|r| r.stability_score),
+            &column(|r| r.steps_per_second),
+        )
+    }
+}
+
+impl TestHarness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `config` once and archives the `ARCHIVE_PER_RUN` fittest agents
+    /// alive at the end.
+    pub fn run_single_test(&mut self, config: HeadlessSimulationConfig) -> TestResult {
+        let mut simulation = HeadlessSimulationV2::new(config.clone());
+        let diagnostics = simulation.run();
+
+        let fitness = |agent: &Agent| agent.genes.get_fitness_score();
+        let mut survivors = simulation.get_agents();
+        survivors.sort_by(|a, b| fitness(b).total_cmp(&fitness(a)));
+        let generation = self.generation;
+        let entries = survivors
+            .into_iter()
+            .take(ARCHIVE_PER_RUN)
+            .map(|agent| GeneArchiveEntry {
+                generation,
+                fitness: fitness(&agent),
+                genes: agent.genes,
+                config: config.clone(),
+            });
+        self.archive.entries.extend(entries);
+        self.generation += 1;
+
+        diagnostics.into()
+    }
+
+    /// Every archived entry, fittest first.
+    pub fn get_archive_best_genes(&self) -> Vec<GeneArchiveEntry> {
+        let mut entries = self.archive.entries.clone();
+        entries.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+        entries
+    }
+
+    pub fn archive(&self) -> &GeneArchive {
+        &self.archive
+    }
+
+    /// Every result of the parameter sweeps run so far, in order.
+    pub fn suite(&self) -> &TestSuite {
+        &self.suite
+    }
+
+    /// Makes `config` start every agent as a copy of one of the `n` fittest
+    /// archived genes. Leaves `config` alone while the archive is empty.
+    pub fn seed_from_archive(&self, config: &mut HeadlessSimulationConfig, n: usize) {
+        let genes: Vec<Genes> = self
+            .get_archive_best_genes()
+            .into_iter()
+            .take(n)
+            .map(|entry| entry.genes)
+            .collect();
+        if genes.is_empty() {
+            return;
+        }
+        let json = serde_json::to_string(&genes).expect("genes serialize to JSON");
+        config.initial_gene_distribution = InitialGeneDistribution::FromJson(json);
+    }
+
+    /// Runs each config once, in order, adding the results to the suite.
+    pub fn run_parameter_sweep(
+        &mut self,
+        configs: Vec<HeadlessSimulationConfig>,
+    ) -> Vec<TestResult> {
+        let results: Vec<TestResult> = configs.into_iter().map(Self::run_config).collect();
+        self.add_results(&results);
+        results
+    }
+
+    /// `run_parameter_sweep` with one rayon task per config. Results come
+    /// back, and join the suite, in the order of `configs` once every run
+    /// has finished.
+    #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+    pub fn run_parameter_sweep_parallel(
+        &mut self,
+        configs: Vec<HeadlessSimulationConfig>,
+    ) -> Vec<TestResult> {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        let results: Vec<TestResult> = configs.into_par_iter().map(Self::run_config).collect();
+        self.add_results(&results);
+        results
+    }
+
+    fn add_results(&mut self, results: &[TestResult]) {
+        for result in results {
+            self.suite.add_result(result.clone());
+        }
+    }
+
+    fn run_config(config: HeadlessSimulationConfig) -> TestResult {
+        HeadlessSimulationV2::new(config).run().into()
+    }
+
+    /// Runs `config` `n` times and summarises the spread of the results.
+    pub fn run_repeated(config: HeadlessSimulationConfig, n: usize) -> TestSuiteStatistics {
+        let results = (0..n).map(|_| Self::run_config(config.clone())).collect();
+        TestSuite::new(results).compute_statistics()
+    }
+
+    /// Runs each config `n_runs` times and compares their stability scores
+    /// with Welch's t-test. A seeded config seeds its runs `seed`,
+    /// `seed + 1`, ..., so the comparison is reproducible.
+    pub fn compare_configs(
+        config_a: HeadlessSimulationConfig,
+        config_b: HeadlessSimulationConfig,
+        n_runs: usize,
+    ) -> ComparisonResult {
+        let samples_a = Self::stability_samples(&config_a, n_runs);
+        let samples_b = Self::stability_samples(&config_b, n_runs);
+
+        // Fewer than two runs per config gives no variance estimate
+        let p_value = welch_t_test(&samples_a, &samples_b)
+            .map(|result| result.p_value)
+            .unwrap_or(1.0);
+
+        ComparisonResult {
+            config_a_mean: mean(&samples_a),
+            config_b_mean: mean(&samples_b),
+            p_value,
+            significant: p_value < SIGNIFICANCE_LEVEL,
+        }
+    }
+
+    fn stability_samples(config: &HeadlessSimulationConfig, n_runs: usize) -> Vec<f64> {
+        (0..n_runs)
+            .map(|run| {
+                let config = HeadlessSimulationConfig {
+                    seed: config.seed.map(|seed| seed.wrapping_add(run as u64)),
+                    ..config.clone()
+                };
+                HeadlessSimulationV2::new(config).run().stability_score
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_simulations_are_send_and_sync() {
+        assert_send_sync::<HeadlessSimulationV2>();
+        assert_send_sync::<crate::simulation_core::UnifiedSimulation>();
+    }
+
+    #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_parallel_sweep_matches_sequential() {
+        // Seeded, so every run of a config ends the same way
+        let configs: Vec<HeadlessSimulationConfig> = (1..=8)
+            .map(|i| HeadlessSimulationConfig {
+                seed: Some(i as u64),
+                target_duration_minutes: 0.02 * i as f64,
+                speed_multiplier: 1.0,
+                use_ecs: false,
+                initial_agents: 10 * i,
+                initial_resources: 0,
+                resource_spawn_rate: 0.0,
+                min_agent_count: 0,
+                ..Default::default()
+            })
+            .collect();
+
+        let mut harness = TestHarness::new();
+        let sequential = harness.run_parameter_sweep(configs.clone());
+        let parallel = harness.run_parameter_sweep_parallel(configs);
+
+        assert_eq!(parallel.len(), 8);
+        assert_eq!(harness.suite().results.len(), 16);
+        for (expected, actual) in sequential.iter().zip(&parallel) {
+            assert_eq!(actual.config.initial_agents, expected.config.initial_agents);
+            assert_eq!(actual.total_steps, expected.total_steps);
+            assert_eq!(actual.final_agent_count, expected.final_agent_count);
+            assert_eq!(actual.stability_score, expected.stability_score);
+        }
+    }
+
+    #[test]
+    fn test_confidence_interval_contains_true_mean() {
+        // Offsets cycle through -5..=5, so every full cycle averages exactly 50
+        let suite = |runs: usize| {
+            let results = (0..runs)
+                .map(|i| TestResult {
+                    config: HeadlessSimulationConfig::default(),
+                    total_steps: 0,
+                    final_agent_count: 0,
+                    stability_score: 0.5,
+                    simulation_quality_score: 50.0 + ((i * 7) % 11) as f64 - 5.0,
+                    extinction_occurred: false,
+                    steps_per_second: 1000.0,
+                })
+                .collect();
+            TestSuite::new(results).compute_statistics()
+        };
+
+        // 11 runs use the t table, 44 the normal approximation
+        let small = suite(11);
+        let large = suite(44);
+        for stats in [&small, &large] {
+            assert!((stats.mean_score - 50.0).abs() < 1e-9);
+            assert!(stats.ci_95_low < 50.0 && 50.0 < stats.ci_95_high);
+            assert_eq!(stats.mean_stability, 0.5);
+            assert_eq!(stats.mean_steps_per_second, 1000.0);
+        }
+        assert!(large.ci_95_high - large.ci_95_low < small.ci_95_high - small.ci_95_low);
+    }
+
+    #[test]
+    fn test_archive_seeded_runs_start_fitter() {
+        let config = HeadlessSimulationConfig {
+            target_duration_minutes: 0.01,
+            speed_multiplier: 1.0,
+            use_ecs: false,
+            initial_agents: 100,
+            min_agent_count: 0,
+            ..Default::default()
+        };
+
+        let mut harness = TestHarness::new();
+        let mut seeded = config.clone();
+        harness.seed_from_archive(&mut seeded, 5);
+        assert!(matches!(seeded.initial_gene_distribution, InitialGeneDistribution::Uniform));
+
+        harness.run_single_test(config.clone());
+        harness.run_single_test(config.clone());
+        let best = harness.get_archive_best_genes();
+        assert_eq!(best.len(), 2 * ARCHIVE_PER_RUN);
+        assert!(best.windows(2).all(|pair| pair[0].fitness >= pair[1].fitness));
+        assert_eq!(best.iter().filter(|entry| entry.generation == 1).count(), ARCHIVE_PER_RUN);
+
+        harness.seed_from_archive(&mut seeded, 5);
+        let initial_fitness = |config: HeadlessSimulationConfig| {
+            let agents = HeadlessSimulationV2::new(config).get_agents();
+            let scores: Vec<f64> = agents.iter().map(|a| a.genes.get_fitness_score()).collect();
+            mean(&scores)
+        };
+        let random = initial_fitness(config);
+        let from_archive = initial_fitness(seeded);
+        assert!(from_archive > random, "{} <= {}", from_archive, random);
+    }
+
+    #[test]
+    fn test_compare_configs_detects_resource_difference() {
+        // Without food the agents-per-resource pressure trips collapse
+        // detection after two seconds, two history samples in, too few for a
+        // stability score; with plenty the run lasts its twelve seconds
+        let rich = HeadlessSimulationConfig {
+            initial_agents: 100,
+            initial_resources: 200,
+            resource_spawn_rate: 0.0,
+            min_agent_count: 0,
+            target_duration_minutes: 0.2,
+            speed_multiplier: 1.0,
+            use_ecs: false,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let poor = HeadlessSimulationConfig {
+            initial_resources: 0,
+            ..rich.clone()
+        };
+
+        let result = TestHarness::compare_configs(rich, poor.clone(), 5);
+        assert!(result.significant, "p = {:.4}", result.p_value);
+
+        // Same seeds, same runs
+        let result = TestHarness::compare_configs(poor.clone(), poor, 3);
+        assert_eq!(result.config_a_mean, result.config_b_mean);
+        assert!(!result.significant);
+    }
+}
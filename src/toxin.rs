@@ -0,0 +1,87 @@
+/// Fraction of each cell's toxin kept from one frame to the next.
+pub const TOXIN_DECAY: f64 = 0.95;
+/// Toxin left in a cell by each kill inside it.
+pub const TOXIN_PER_KILL: f64 = 0.25;
+/// Cells above this level harm agents passing through.
+pub const TOXIN_THRESHOLD: f64 = 0.5;
+/// Energy per second lost in a toxic cell per unit of toxin, before defense.
+pub const TOXIN_DAMAGE_RATE: f64 = 2.0;
+
+/// Toxin left behind by kills, per `cell_size` square, decaying every frame.
+/// Cells are indexed `[x][y]` like the energy heatmap.
+#[derive(Clone, Debug)]
+pub struct ToxinGrid {
+    cell_size: f64,
+    cells: Vec<Vec<f64>>,
+}
+
+impl ToxinGrid {
+    pub fn new(width: f64, height: f64, cell_size: f64) -> Self {
+        let columns = ((width / cell_size).ceil() as usize).max(1);
+        let rows = ((height / cell_size).ceil() as usize).max(1);
+        Self {
+            cell_size,
+            cells: vec![vec![0.0; rows]; columns],
+        }
+    }
+
+    fn cell(&self, x: f64, y: f64) -> (usize, usize) {
+        let column = (x / self.cell_size).floor().max(0.0) as usize;
+        let row = (y / self.cell_size).floor().max(0.0) as usize;
+        (
+            column.min(self.cells.len() - 1),
+            row.min(self.cells[0].len() - 1),
+        )
+    }
+
+    /// Toxin level of the cell containing (x, y); points off the grid use the
+    /// nearest edge cell.
+    pub fn at(&self, x: f64, y: f64) -> f64 {
+        let (column, row) = self.cell(x, y);
+        self.cells[column][row]
+    }
+
+    pub fn add_toxin(&mut self, x: f64, y: f64, amount: f64) {
+        let (column, row) = self.cell(x, y);
+        self.cells[column][row] += amount;
+    }
+
+    pub fn decay(&mut self) {
+        for cell in self.cells.iter_mut().flatten() {
+            *cell *= TOXIN_DECAY;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in self.cells.iter_mut().flatten() {
+            *cell = 0.0;
+        }
+    }
+
+    pub fn cells(&self) -> &[Vec<f64>] {
+        &self.cells
+    }
+
+    /// Levels row by row, top to bottom.
+    pub fn to_flat(&self) -> Vec<f32> {
+        let rows = self.cells[0].len();
+        (0..rows)
+            .flat_map(|row| self.cells.iter().map(move |column| column[row] as f32))
+            .collect()
+    }
+}
+
+/// Energy per second an agent with `defense` loses standing in toxin `level`.
+pub fn toxin_damage(level: f64, defense: f64) -> f64 {
+    if level > TOXIN_THRESHOLD {
+        TOXIN_DAMAGE_RATE * level / defense
+    } else {
+        0.0
+    }
+}
+
+/// Multiplier on a resource's appeal when it sits in toxin `level`. Poorly
+/// defended agents shy away from poisoned ground more than tough ones.
+pub fn toxin_avoidance(level: f64, defense: f64) -> f64 {
+    1.0 / (1.0 + level / defense)
+}
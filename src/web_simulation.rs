@@ -1,9 +1,53 @@
-use crate::simulation_core::{SimulationConfig, UnifiedSimulation};
+use crate::agent::{generation_distribution, Agent, AgentInfo};
+use crate::archetype::AgentArchetype;
+use crate::biome::{BiomeMap, BiomeZone};
+use crate::boundary::BoundaryBehavior;
+use crate::death::DEFAULT_MAX_DEATH_HISTORY;
+use crate::frame_pacing::{run_frame, RateTracker, MAX_STEPS_PER_FRAME};
+use crate::genes::{
+    gene_bounds_for, GeneBounds, InitialGeneDistribution, MutationDistribution, GENE_NAMES,
+};
+use crate::habitat::Habitat;
+use crate::resource::{Resource, ResourceInfo, ResourceType};
+use crate::headless_simulation::{
+    benchmark_simulation, rows_to_csv, BenchmarkResult, CsvRow, BENCHMARK_STEPS,
+};
+use crate::scenario::Scenario;
+use crate::simulation_core::{
+    format_config_errors, SimulationCommand, SimulationConfig, SimulationConfigDelta,
+    SimulationSnapshot, SimulationStats, UnifiedSimulation,
+};
+use crate::spatial::SpatialBackend;
+use crate::teleporter::Teleporter;
+use crate::test_harness::TestSuiteStatistics;
+use crate::species::DEFAULT_SPECIES_THRESHOLD;
 use crate::webgl_renderer::WebGlRenderer;
+use serde::Serialize;
+use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
+const SELECTION_TRACKING_RADIUS: f64 = 5.0;
+/// Frames between refreshes of the gene histogram panel.
+const HISTOGRAM_UPDATE_INTERVAL: u32 = 120;
+/// Bars in the gene histogram panel.
+const HISTOGRAM_BUCKETS: usize = 20;
+/// Steps per run in `run_benchmark_statistics`, short enough to repeat.
+const QUICK_BENCHMARK_STEPS: usize = 1_000;
+/// Bytes in one page of wasm linear memory.
+const WASM_PAGE_BYTES: u32 = 65_536;
+/// Simulation steps between rows of `export_csv`, one simulated second.
+const CSV_HISTORY_INTERVAL: usize = 60;
+
+#[derive(Serialize)]
+struct WebSimulationStats {
+    #[serde(flatten)]
+    simulation: SimulationStats,
+    fps: f64,
+    simulation_tps: f64,
+}
+
 #[wasm_bindgen]
 pub struct WebSimulation {
     simulation: UnifiedSimulation,
@@ -11,8 +55,28 @@ pub struct WebSimulation {
     webgl_renderer: Option<WebGlRenderer>,
     is_running: bool,
     use_webgl: bool,
-    _last_frame_time: f64,
     frame_count: u32,
+    fps_window: RateTracker,
+    tick_window: RateTracker,
+    selected_agent_id: Option<u64>,
+    selected_agent_position: Option<(f64, f64)>,
+    /// Corners (start_x, start_y, end_x, end_y) of the lasso being dragged.
+    lasso: Option<(f64, f64, f64, f64)>,
+    /// Last known positions of the agents the lasso picked, tracked like
+    /// `selected_agent_position`.
+    lasso_selection: Vec<(f64, f64)>,
+    show_connections: bool,
+    show_mutations: bool,
+    show_trophic_levels: bool,
+    previous_stats: SimulationStats,
+    /// Simulation steps per animation frame, 1 to `MAX_STEPS_PER_FRAME`.
+    max_steps_per_frame: usize,
+    /// Gene shown in the histogram panel, if any.
+    histogram_gene: Option<String>,
+    /// Gene shown in the box-and-whisker panel, if any.
+    boxplot_gene: Option<String>,
+    /// Rows for `export_csv`, one every `CSV_HISTORY_INTERVAL` steps.
+    csv_history: Vec<CsvRow>,
 }
 
 #[wasm_bindgen]
@@ -24,7 +88,7 @@ impl WebSimulation {
         let document = window.document().unwrap();
         let canvas = document
             .get_element_by_id(canvas_id)
-            .unwrap()
+            .ok_or_else(|| JsValue::from_str(&format!("No element with id '{}'", canvas_id)))?
             .dyn_into::<HtmlCanvasElement>()?;
 
         // Set canvas size based on available space
@@ -53,8 +117,30 @@ impl WebSimulation {
             min_agent_count: 10,
             max_agent_count: 3000,
             use_ecs: true,
+            boundary_behavior: BoundaryBehavior::Wrap,
+            season_period_seconds: 60.0,
+            season_amplitude: 0.5,
+            log_interactions: false,
+            spatial_backend: SpatialBackend::Grid,
+            adaptive_spatial_grid: false,
+            speciation_threshold: DEFAULT_SPECIES_THRESHOLD,
+            max_death_history: DEFAULT_MAX_DEATH_HISTORY,
+            resource_dynamics: false,
+            mutation_distribution: MutationDistribution::default(),
+            initial_gene_distribution: InitialGeneDistribution::Uniform,
+            teleporters: Vec::new(),
+            agent_archetypes: Vec::new(),
+            use_allometric_scaling: false,
+            rescue_on_near_extinction: false,
+            rescue_agent_count: 50,
+            seed: None,
+            biome_map: None,
         };
 
+        config
+            .validate()
+            .map_err(|errors| js_sys::Error::new(&format_config_errors(&errors)))?;
+
         // Create simulation
         let simulation = UnifiedSimulation::new(config);
 
@@ -62,16 +148,16 @@ impl WebSimulation {
         let webgl_renderer = match WebGlRenderer::new(canvas.clone()) {
             Ok(renderer) => Some(renderer),
             Err(e) => {
-                web_sys::console::log_1(&format!("WebGL initialization failed: {:?}", e).into());
+                tracing::warn!("WebGL initialization failed: {:?}", e);
                 None
             }
         };
         let use_webgl = webgl_renderer.is_some();
 
         if use_webgl {
-            web_sys::console::log_1(&"WebGL initialized successfully!".into());
+            tracing::info!("WebGL initialized successfully!");
         } else {
-            web_sys::console::log_1(&"Using Canvas 2D rendering (WebGL not available)".into());
+            tracing::info!("Using Canvas 2D rendering (WebGL not available)");
         }
 
         Ok(WebSimulation {
@@ -80,8 +166,21 @@ impl WebSimulation {
             webgl_renderer,
             is_running: false,
             use_webgl,
-            _last_frame_time: 0.0,
             frame_count: 0,
+            fps_window: RateTracker::new(),
+            tick_window: RateTracker::new(),
+            selected_agent_id: None,
+            selected_agent_position: None,
+            lasso: None,
+            lasso_selection: Vec::new(),
+            show_connections: false,
+            show_mutations: false,
+            show_trophic_levels: false,
+            previous_stats: SimulationStats::default(),
+            max_steps_per_frame: 1,
+            histogram_gene: None,
+            boxplot_gene: None,
+            csv_history: Vec::new(),
         })
     }
 
@@ -97,15 +196,57 @@ impl WebSimulation {
     }
 
     pub fn step(&mut self) {
-        self.simulation.update();
+        self.tick();
         self.render();
     }
 
+    /// Runs `steps_per_frame` simulation steps per animation frame, clamped
+    /// to 1-`MAX_STEPS_PER_FRAME`.
+    pub fn set_speed(&mut self, steps_per_frame: usize) {
+        self.max_steps_per_frame = steps_per_frame.clamp(1, MAX_STEPS_PER_FRAME);
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SimulationStats")]
     pub fn get_stats(&self) -> JsValue {
-        let stats = self.simulation.get_stats();
+        let stats = WebSimulationStats {
+            simulation: self.simulation.get_stats(),
+            fps: self.get_fps(),
+            simulation_tps: self.get_simulation_tps(),
+        };
         serde_wasm_bindgen::to_value(&stats).unwrap()
     }
 
+    pub fn get_fps(&self) -> f64 {
+        self.fps_window.rate()
+    }
+
+    pub fn get_simulation_tps(&self) -> f64 {
+        self.tick_window.rate()
+    }
+
+    pub fn get_species_count(&self) -> usize {
+        self.simulation.get_stats().species_count
+    }
+
+    /// `{reason: count}` over the recorded death history.
+    pub fn get_deaths_by_reason(&self) -> JsValue {
+        let counts: HashMap<String, usize> = self
+            .simulation
+            .deaths_by_reason()
+            .into_iter()
+            .map(|(reason, count)| (format!("{:?}", reason), count))
+            .collect();
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        counts.serialize(&serializer).unwrap()
+    }
+
+    /// Array of `{speed, size, aggression, energy_efficiency, fitness}` points
+    /// for plotting the fitness landscape.
+    #[wasm_bindgen(unchecked_return_type = "FitnessSample[]")]
+    pub fn get_fitness_landscape(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.simulation.fitness_landscape()).unwrap()
+    }
+
     pub fn get_rendering_mode(&self) -> String {
         if self.use_webgl {
             "WebGL".to_string()
@@ -128,16 +269,40 @@ impl WebSimulation {
             Ok(renderer) => {
                 self.webgl_renderer = Some(renderer);
                 self.use_webgl = true;
-                web_sys::console::log_1(&"WebGL forced successfully!".into());
+                tracing::info!("WebGL forced successfully!");
                 true
             }
             Err(e) => {
-                web_sys::console::log_1(&format!("Failed to force WebGL: {:?}", e).into());
+                tracing::warn!("Failed to force WebGL: {:?}", e);
                 false
             }
         }
     }
 
+    pub fn get_render_stats(&self) -> JsValue {
+        let stats = self.webgl_renderer.as_ref().map(WebGlRenderer::render_stats);
+        serde_wasm_bindgen::to_value(&stats).unwrap()
+    }
+
+    /// The engine's `MemoryStats` with the wasm linear memory size filled in.
+    pub fn get_memory_stats(&self) -> JsValue {
+        let mut stats = self.simulation.get_memory_stats();
+        let buffer: js_sys::ArrayBuffer = wasm_bindgen::memory()
+            .unchecked_into::<js_sys::WebAssembly::Memory>()
+            .buffer()
+            .unchecked_into();
+        stats.wasm_memory_pages = buffer.byte_length() / WASM_PAGE_BYTES;
+        serde_wasm_bindgen::to_value(&stats).unwrap()
+    }
+
+    /// Average GPU time per WebGL frame in nanoseconds; 0 without WebGL or
+    /// GPU timer queries.
+    pub fn get_gpu_timing(&self) -> f64 {
+        self.webgl_renderer
+            .as_ref()
+            .map_or(0.0, WebGlRenderer::get_gpu_time_ns)
+    }
+
     pub fn add_agent(&mut self, x: f64, y: f64) {
         self.simulation.add_agent(x, y);
     }
@@ -148,6 +313,337 @@ impl WebSimulation {
 
     pub fn reset(&mut self) {
         self.simulation.reset();
+        self.clear_selection();
+    }
+
+    /// Selects the agent nearest to (x, y) within `radius`, returning its ID.
+    pub fn find_agent_at(&mut self, x: f64, y: f64, radius: f64) -> Option<u64> {
+        let agents = self.simulation.get_agents();
+        match nearest_agent(&agents, x, y, radius) {
+            Some(agent) => {
+                self.selected_agent_id = Some(agent.id());
+                self.selected_agent_position = Some((agent.x, agent.y));
+            }
+            None => self.clear_selection(),
+        }
+        self.selected_agent_id
+    }
+
+    /// `AgentInfo` of the agent nearest to (x, y) within `radius`, or `null`.
+    /// Unlike `find_agent_at`, leaves the selection alone.
+    pub fn get_agent_at(&self, x: f64, y: f64, radius: f64) -> JsValue {
+        let agents = self.simulation.get_agents();
+        match nearest_agent(&agents, x, y, radius) {
+            Some(agent) => serde_wasm_bindgen::to_value(&AgentInfo::from_agent(agent)).unwrap(),
+            None => JsValue::null(),
+        }
+    }
+
+    /// `ResourceInfo` of the resource nearest to (x, y) within `radius`, or
+    /// `null`.
+    pub fn get_resource_at(&self, x: f64, y: f64, radius: f64) -> JsValue {
+        let resources = self.simulation.get_resources();
+        match nearest_resource(&resources, x, y, radius) {
+            Some(resource) => {
+                serde_wasm_bindgen::to_value(&ResourceInfo::from_resource(resource)).unwrap()
+            }
+            None => JsValue::null(),
+        }
+    }
+
+    pub fn get_selected_agent_id(&self) -> Option<u64> {
+        self.selected_agent_id
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selected_agent_id = None;
+        self.selected_agent_position = None;
+        self.lasso_selection.clear();
+    }
+
+    pub fn start_lasso(&mut self, x: f64, y: f64) {
+        self.lasso = Some((x, y, x, y));
+    }
+
+    pub fn update_lasso(&mut self, x: f64, y: f64) {
+        if let Some((start_x, start_y, _, _)) = self.lasso {
+            self.lasso = Some((start_x, start_y, x, y));
+        }
+    }
+
+    /// Selects every agent inside the lasso and returns their IDs.
+    pub fn end_lasso(&mut self) -> JsValue {
+        let Some((start_x, start_y, end_x, end_y)) = self.lasso.take() else {
+            return serde_wasm_bindgen::to_value(&Vec::<u64>::new()).unwrap();
+        };
+        let agents = self.simulation.agents_in_rect(start_x, start_y, end_x, end_y);
+        self.lasso_selection = agents.iter().map(|agent| (agent.x, agent.y)).collect();
+        let ids: Vec<u64> = agents.iter().map(Agent::id).collect();
+        serde_wasm_bindgen::to_value(&ids).unwrap()
+    }
+
+    pub fn get_selected_agents(&self) -> JsValue {
+        let agents = self.simulation.get_agents();
+        let selected: Vec<&Agent> = self
+            .lasso_selection
+            .iter()
+            .filter_map(|&(x, y)| nearest_agent(&agents, x, y, SELECTION_TRACKING_RADIUS))
+            .collect();
+        serde_wasm_bindgen::to_value(&selected).unwrap()
+    }
+
+    pub fn find_agent_by_id(&self, stable_id: u64) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.simulation.find_agent_by_id(stable_id)).unwrap()
+    }
+
+    pub fn add_habitat(&mut self, x: f64, y: f64, radius: f64, capacity: usize) {
+        self.simulation.add_habitat(Habitat {
+            x,
+            y,
+            radius,
+            carrying_capacity: capacity,
+        });
+    }
+
+    pub fn add_teleporter(&mut self, from_x: f64, from_y: f64, to_x: f64, to_y: f64, radius: f64) {
+        self.simulation
+            .add_teleporter(Teleporter::new(from_x, from_y, to_x, to_y, radius));
+    }
+
+    pub fn get_season_phase(&self) -> f64 {
+        self.simulation.season_phase()
+    }
+
+    pub fn trigger_famine(&mut self, steps: usize) {
+        self.simulation.trigger_famine(steps);
+    }
+
+    pub fn is_famine_active(&self) -> bool {
+        self.simulation.famine_active()
+    }
+
+    /// Benchmarks a copy of the current simulation for `BENCHMARK_STEPS`
+    /// steps, leaving this one untouched. Blocks until it finishes.
+    pub fn run_benchmark(&self) -> JsValue {
+        let mut copy = UnifiedSimulation::from_snapshot(self.simulation.snapshot()).unwrap();
+        let result = benchmark_simulation(&mut copy, BENCHMARK_STEPS, || now_ms() / 1000.0);
+        serde_wasm_bindgen::to_value(&result).unwrap()
+    }
+
+    /// Benchmarks `n` copies of the current simulation for
+    /// `QUICK_BENCHMARK_STEPS` steps each and summarises them. Scores are
+    /// `final_score`s; stability is the fraction of the starting agents left.
+    pub fn run_benchmark_statistics(&self, n: u32) -> JsValue {
+        let snapshot = self.simulation.snapshot();
+        let results: Vec<BenchmarkResult> = (0..n)
+            .map(|_| {
+                let mut copy = UnifiedSimulation::from_snapshot(snapshot.clone()).unwrap();
+                benchmark_simulation(&mut copy, QUICK_BENCHMARK_STEPS, || now_ms() / 1000.0)
+            })
+            .collect();
+        let column = |field: fn(&BenchmarkResult) -> f64| -> Vec<f64> {
+            results.iter().map(field).collect()
+        };
+        let statistics = TestSuiteStatistics::from_runs(
+            &column(|r| r.final_score),
+            &column(|r| {
+                if r.steps_per_second > 0.0 {
+                    r.final_score / r.steps_per_second
+                } else {
+                    0.0
+                }
+            }),
+            &column(|r| r.steps_per_second),
+        );
+        serde_wasm_bindgen::to_value(&statistics).unwrap()
+    }
+
+    pub fn get_interaction_log_json(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.simulation.get_interaction_log()).unwrap()
+    }
+
+    pub fn get_energy_heatmap_flat(&self) -> Vec<f64> {
+        self.simulation.get_energy_heatmap_flat()
+    }
+
+    pub fn get_toxin_grid(&self) -> Vec<f32> {
+        self.simulation.get_toxin_grid_flat()
+    }
+
+    pub fn get_generation_distribution(&self) -> Vec<u32> {
+        generation_distribution(&self.simulation.get_agents())
+            .into_iter()
+            .map(|count| count as u32)
+            .collect()
+    }
+
+    pub fn get_stats_diff(&mut self) -> JsValue {
+        let stats = self.simulation.get_stats();
+        let diff = stats.diff(&self.previous_stats);
+        self.previous_stats = stats;
+        serde_wasm_bindgen::to_value(&diff).unwrap()
+    }
+
+    /// Accepts "wrap", "bounce" or "lethal"; returns false for anything else.
+    pub fn set_boundary_behavior(&mut self, mode: &str) -> bool {
+        match mode.parse::<BoundaryBehavior>() {
+            Ok(boundary) => {
+                self.simulation.set_boundary_behavior(boundary);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Takes a JSON array of `BiomeZone`s for resources grown from now on; an
+    /// empty array removes the biome map. Returns false, changing nothing, if
+    /// the array doesn't parse.
+    pub fn set_biome_zones(&mut self, zones: JsValue) -> bool {
+        match serde_wasm_bindgen::from_value::<Vec<BiomeZone>>(zones) {
+            Ok(zones) => {
+                let biome_map = (!zones.is_empty()).then(|| BiomeMap::new(zones));
+                self.simulation.set_biome_map(biome_map);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The whole simulation as a plain object that `import_snapshot` takes
+    /// back, e.g. to save a run and resume it later.
+    pub fn export_snapshot(&self) -> JsValue {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.simulation.snapshot().serialize(&serializer).unwrap()
+    }
+
+    /// Replaces the simulation with an `export_snapshot` result. Returns
+    /// false, changing nothing, if it doesn't parse or is from another
+    /// snapshot version.
+    pub fn import_snapshot(&mut self, data: JsValue) -> bool {
+        match serde_wasm_bindgen::from_value::<SimulationSnapshot>(data) {
+            Ok(snapshot) => self.simulation.restore(snapshot).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Stats sampled every `CSV_HISTORY_INTERVAL` steps since the simulation
+    /// last started over, in the same CSV as
+    /// `SimulationDiagnostics::to_csv_string`.
+    pub fn export_csv(&self) -> String {
+        rows_to_csv(self.csv_history.iter().copied())
+    }
+
+    /// Shows or hides pack connection lines, returning the new state.
+    pub fn toggle_connections(&mut self) -> bool {
+        self.show_connections = !self.show_connections;
+        self.show_connections
+    }
+
+    /// Shows or hides the highlight on freshly mutated agents, returning the
+    /// new state.
+    pub fn toggle_mutation_display(&mut self) -> bool {
+        self.show_mutations = !self.show_mutations;
+        self.show_mutations
+    }
+
+    /// Switches agent colouring to trophic levels and back, returning the new
+    /// state.
+    pub fn toggle_trophic_display(&mut self) -> bool {
+        self.show_trophic_levels = !self.show_trophic_levels;
+        self.show_trophic_levels
+    }
+
+    /// Agents per equal slice of `gene_name`'s range, lowest first. Empty for
+    /// unknown gene names.
+    pub fn get_gene_histogram(&self, gene_name: &str, buckets: usize) -> Vec<u32> {
+        self.simulation
+            .gene_histogram(gene_name, buckets)
+            .unwrap_or_default()
+    }
+
+    /// Shows `gene_name`'s histogram in the corner of the canvas, refreshed
+    /// every `HISTOGRAM_UPDATE_INTERVAL` frames. Returns false, hiding the
+    /// panel, if it isn't a gene name.
+    pub fn set_histogram_gene(&mut self, gene_name: &str) -> bool {
+        let known = GENE_NAMES.contains(&gene_name);
+        self.histogram_gene = known.then(|| gene_name.to_string());
+        let counts = self.histogram_counts();
+        if let Some(renderer) = self.webgl_renderer.as_mut() {
+            renderer.set_histogram(&counts);
+        }
+        known
+    }
+
+    /// `GeneBoxplot` of `gene_name` across the population; null for unknown
+    /// gene names.
+    pub fn get_gene_boxplot(&self, gene_name: &str) -> JsValue {
+        match self.simulation.gene_percentiles(gene_name) {
+            Some(boxplot) => serde_wasm_bindgen::to_value(&boxplot).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Shows `gene_name`'s box-and-whisker plot in the corner of the canvas,
+    /// or hides it if it's already shown or isn't a gene name. Refreshed
+    /// alongside the histogram. Returns whether the plot is now shown.
+    pub fn toggle_boxplot(&mut self, gene_name: &str) -> bool {
+        let showing = self.boxplot_gene.as_deref() == Some(gene_name);
+        let show = !showing && GENE_NAMES.contains(&gene_name);
+        self.boxplot_gene = show.then(|| gene_name.to_string());
+        let fractions = self.boxplot_fractions();
+        if let Some(renderer) = self.webgl_renderer.as_mut() {
+            renderer.set_boxplot(fractions);
+        }
+        show
+    }
+
+    /// Applies a JSON `SimulationConfigDelta` without resetting; returns false
+    /// if it doesn't parse or is rejected.
+    pub fn update_config(&mut self, json: &str) -> bool {
+        match serde_json::from_str::<SimulationConfigDelta>(json) {
+            Ok(delta) => self.simulation.apply_config_delta(delta).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Applies a JSON `SimulationCommand` and adds it to the command log;
+    /// returns false if it doesn't parse or is rejected.
+    pub fn apply_command_json(&mut self, json: &str) -> bool {
+        match serde_json::from_str::<SimulationCommand>(json) {
+            Ok(command) => self.simulation.apply_command(command).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn load_scenario(&mut self, json: &str) -> Result<(), JsValue> {
+        let scenario = Scenario::from_json(json).map_err(|e| JsValue::from_str(&e))?;
+        self.simulation = UnifiedSimulation::from_scenario(scenario);
+        Ok(())
+    }
+
+    /// Restarts the simulation with a JSON list of `AgentArchetype`s as its
+    /// founders. Returns false, leaving the simulation alone, if the list
+    /// doesn't parse or needs more than `max_agents` agents.
+    pub fn load_archetypes(&mut self, json: &str) -> bool {
+        let Ok(archetypes) = serde_json::from_str::<Vec<AgentArchetype>>(json) else {
+            return false;
+        };
+        let config = SimulationConfig {
+            agent_archetypes: archetypes,
+            ..self.simulation.get_config().clone()
+        };
+        if config.validate().is_err() {
+            return false;
+        }
+        self.simulation = UnifiedSimulation::new(config);
+        true
+    }
+
+    pub fn load_builtin_scenario(&mut self, name: &str) -> Result<(), JsValue> {
+        let scenario = Scenario::builtin(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown scenario: {}", name)))?;
+        self.simulation = UnifiedSimulation::from_scenario(scenario);
+        Ok(())
     }
 
     pub fn animate(&mut self) {
@@ -155,7 +651,11 @@ impl WebSimulation {
             return;
         }
 
-        self.simulation.update();
+        let steps = run_frame(&mut self.simulation, self.max_steps_per_frame);
+        let now = now_ms();
+        for _ in 0..steps {
+            self.tick_window.record(now);
+        }
         self.render();
 
         // Request next frame
@@ -169,7 +669,29 @@ impl WebSimulation {
         );
     }
 
+    fn tick(&mut self) {
+        self.simulation.update();
+        self.tick_window.record(now_ms());
+    }
+
+    /// Samples the stats if a `CSV_HISTORY_INTERVAL` boundary has been
+    /// passed since the last row; a frame can run several steps. Starts over
+    /// once the step count goes back, after a reset, scenario or import.
+    fn record_csv_history(&mut self) {
+        let step = self.simulation.current_step();
+        if self.csv_history.last().is_some_and(|row| row.step > step) {
+            self.csv_history.clear();
+        }
+        let last = self.csv_history.last().map_or(0, |row| row.step);
+        if step / CSV_HISTORY_INTERVAL > last / CSV_HISTORY_INTERVAL {
+            let stats = self.simulation.get_stats();
+            self.csv_history.push(CsvRow::from_stats(step, &stats));
+        }
+    }
+
     fn render(&mut self) {
+        self.record_csv_history();
+        self.fps_window.record(now_ms());
         if self.use_webgl {
             self.render_webgl();
         } else {
@@ -177,14 +699,74 @@ impl WebSimulation {
         }
     }
 
+    /// Current counts for the histogram panel; empty while it's hidden.
+    fn histogram_counts(&self) -> Vec<u32> {
+        self.histogram_gene
+            .as_deref()
+            .map(|gene| self.get_gene_histogram(gene, HISTOGRAM_BUCKETS))
+            .unwrap_or_default()
+    }
+
+    /// The boxplot panel's percentiles as fractions of the gene's range;
+    /// `None` while it's hidden.
+    fn boxplot_fractions(&self) -> Option<[f32; 5]> {
+        let gene = self.boxplot_gene.as_deref()?;
+        let boxplot = self.simulation.gene_percentiles(gene)?;
+        let GeneBounds { min, max } = gene_bounds_for(gene);
+        let fraction = |value: f64| ((value - min) / (max - min)) as f32;
+        Some([boxplot.p5, boxplot.p25, boxplot.median, boxplot.p75, boxplot.p95].map(fraction))
+    }
+
     fn render_webgl(&mut self) {
+        let refresh_panels = self.frame_count.is_multiple_of(HISTOGRAM_UPDATE_INTERVAL);
+        let histogram = refresh_panels.then(|| self.histogram_counts());
+        let boxplot = refresh_panels.then(|| self.boxplot_fractions());
         if let Some(ref mut renderer) = self.webgl_renderer {
-            // Get agents and resources from unified simulation
-            let agents = self.simulation.get_agents();
             let resources = self.simulation.get_resources();
 
-            renderer.update_agents(&agents);
+            // Borrow the agents rather than copying them every frame
+            self.simulation.with_agents(|agents| {
+                // Agent IDs are derived from position, so follow the selected
+                // agent to wherever it moved since the last frame.
+                if let Some((x, y)) = self.selected_agent_position {
+                    match nearest_agent(agents, x, y, SELECTION_TRACKING_RADIUS) {
+                        Some(agent) => {
+                            self.selected_agent_id = Some(agent.id());
+                            self.selected_agent_position = Some((agent.x, agent.y));
+                        }
+                        None => {
+                            self.selected_agent_id = None;
+                            self.selected_agent_position = None;
+                        }
+                    }
+                }
+                self.lasso_selection = self
+                    .lasso_selection
+                    .iter()
+                    .filter_map(|&(x, y)| nearest_agent(agents, x, y, SELECTION_TRACKING_RADIUS))
+                    .map(|agent| (agent.x, agent.y))
+                    .collect();
+                renderer.set_selected_agent(self.selected_agent_id);
+                renderer.set_lasso(self.lasso);
+                renderer.set_connections_visible(self.show_connections);
+                renderer.set_show_mutations(self.show_mutations);
+                renderer.set_show_trophic_levels(self.show_trophic_levels);
+                if let Some(counts) = &histogram {
+                    renderer.set_histogram(counts);
+                }
+                if let Some(fractions) = boxplot {
+                    renderer.set_boxplot(fractions);
+                }
+
+                renderer.update_agents(agents);
+            });
             renderer.update_resources(&resources);
+            renderer.update_habitats(&self.simulation.get_habitats());
+            renderer.update_teleporters(&self.simulation.get_teleporters());
+            renderer.set_warmth(self.simulation.warmth());
+            renderer.update_energy_heatmap(self.simulation.get_energy_heatmap());
+            let toxin = self.simulation.get_toxin_grid();
+            renderer.update_toxin_overlay(toxin.map_or(&[], |grid| grid.cells()));
             renderer.render();
 
             // Debug: Log rendering info only occasionally
@@ -194,7 +776,7 @@ impl WebSimulation {
                         // Removed verbose logging
             }
         } else {
-            web_sys::console::log_1(&"WebGL renderer is None!".into());
+            tracing::warn!("WebGL renderer is None!");
         }
     }
 
@@ -228,7 +810,13 @@ impl WebSimulation {
         // Render resources
         let resources = self.simulation.get_resources();
         for resource in &resources {
-            ctx.set_fill_style(&format!("hsl({}, 70%, 60%)", resource.energy * 120.0).into());
+            let lightness = if resource.is_carcass { 30 } else { 60 };
+            let style = if resource.resource_type == ResourceType::Poison {
+                "hsl(300, 100%, 55%)".to_string()
+            } else {
+                format!("hsl({}, 70%, {}%)", resource.energy * 120.0, lightness)
+            };
+            ctx.set_fill_style(&style.into());
             ctx.begin_path();
             ctx.arc(
                 resource.x,
@@ -271,5 +859,44 @@ impl WebSimulation {
             );
             ctx.stroke();
         }
+
+        // Dashed selection lasso on top, as the WebGL renderer draws it
+        if let Some((start_x, start_y, end_x, end_y)) = self.lasso {
+            ctx.set_stroke_style(&"rgba(255, 255, 255, 0.9)".into());
+            ctx.set_line_width(1.0);
+            ctx.set_line_dash(&js_sys::Array::of2(&6.0.into(), &6.0.into())).unwrap();
+            ctx.stroke_rect(
+                start_x.min(end_x),
+                start_y.min(end_y),
+                (end_x - start_x).abs(),
+                (end_y - start_y).abs(),
+            );
+            ctx.set_line_dash(&js_sys::Array::new()).unwrap();
+        }
     }
 }
+
+fn nearest_agent(agents: &[Agent], x: f64, y: f64, radius: f64) -> Option<&Agent> {
+    agents
+        .iter()
+        .map(|agent| (agent, agent.distance_to(x, y)))
+        .filter(|(_, distance)| *distance <= radius)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(agent, _)| agent)
+}
+
+fn nearest_resource(resources: &[Resource], x: f64, y: f64, radius: f64) -> Option<&Resource> {
+    resources
+        .iter()
+        .map(|resource| (resource, resource.distance_to(x, y)))
+        .filter(|(_, distance)| *distance <= radius)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(resource, _)| resource)
+}
+
+fn now_ms() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map(|performance| performance.now())
+        .unwrap_or(0.0)
+}
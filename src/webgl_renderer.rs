@@ -1,36 +1,184 @@
-use crate::agent::{Agent, DeathReason};
-use crate::resource::Resource;
+use crate::agent::{Agent, AgentState, DeathReason};
+use crate::habitat::Habitat;
+use crate::render_layout::{
+    agent_viewport_bounds, atlas_index, atlas_pixels, boxplot_panel, boxplot_segments,
+    histogram_bar_rects, histogram_panel, render_subset, smooth_gpu_time, AGENT_COLOR_OFFSET,
+    AGENT_ENERGY_OFFSET, AGENT_MUTATED_OFFSET, AGENT_POSITION_OFFSET, AGENT_SATIATION_OFFSET,
+    AGENT_SELECTED_OFFSET, AGENT_SIZE_OFFSET, AGENT_SPAWN_TIME_OFFSET, AGENT_STATE_OFFSET,
+    AGENT_STRIDE, ATLAS_CELL_SIZE, ATLAS_WIDTH, CONNECTION_ALPHA_OFFSET, CONNECTION_COLOR_OFFSET,
+    CONNECTION_POSITION_OFFSET, CONNECTION_STRIDE, DEFAULT_MAX_RENDER_AGENTS,
+    HABITAT_CENTER_OFFSET, HABITAT_POSITION_OFFSET, HABITAT_RADIUS_OFFSET, HABITAT_STRIDE,
+    HABITAT_VERTICES, TELEPORTER_CENTER_OFFSET, TELEPORTER_POSITION_OFFSET,
+    TELEPORTER_RADIUS_OFFSET, TELEPORTER_SPIN_OFFSET, TELEPORTER_STRIDE, TELEPORTER_VERTICES,
+};
+#[cfg(feature = "webgl2")]
+use crate::render_layout::{instance_data, BILLBOARD_QUAD, INSTANCE_AGE_OFFSET, INSTANCE_STRIDE};
+use crate::resource::{Resource, ResourceType};
+use crate::teleporter::Teleporter;
+use crate::toxin::TOXIN_THRESHOLD;
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 use web_sys::{
-    HtmlCanvasElement, WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlShader,
-    WebGlUniformLocation,
+    ExtDisjointTimerQuery, HtmlCanvasElement, WebGlBuffer, WebGlProgram, WebGlQuery,
+    WebGlRenderingContext, WebGlShader, WebGlTexture, WebGlUniformLocation,
 };
+#[cfg(feature = "webgl2")]
+use web_sys::{WebGl2RenderingContext, WebGlVertexArrayObject};
+
+// Pack connection lines between nearby pack members
+const MAX_CONNECTION_SEGMENTS: usize = 5000;
+const PACK_CONNECTION_DISTANCE: f32 = 80.0;
+const PACK_CONNECTION_ALPHA: f32 = 0.2;
+
+// Selection lasso outline: four corner positions drawn as a dashed line loop
+const LASSO_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+const LASSO_DASH_LENGTH: f32 = 6.0;
+
+// Gene box-and-whisker panel, drawn as solid lines with the lasso program
+const BOXPLOT_COLOR: [f32; 4] = [1.0, 0.85, 0.4, 0.9];
+// Longer than any canvas, so the lasso shader's dashes never break a line
+const SOLID_DASH_LENGTH: f32 = 1.0e6;
+
+// Gene histogram panel; each bar is the unit quad strip scaled to its
+// rectangle by a uniform
+const HISTOGRAM_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.45];
+const HISTOGRAM_BAR_COLOR: [f32; 4] = [0.45, 0.8, 1.0, 0.9];
+
+// Streak drawn behind each migrating agent
+const MIGRATION_TRAIL_PARTICLES: usize = 12;
+const MIGRATION_TRAIL_LENGTH: f32 = 40.0;
+
+// Brightness of deposits left by dead agents relative to grown resources
+const CARCASS_SHADE: f64 = 0.45;
+
+// Poisonous resources stand out in bright magenta whatever their energy
+const POISON_COLOR: (f64, f64, f64) = (1.0, 0.1, 0.9);
+
+// Colours for tagged species, picked by `species_id % 12`
+const SPECIES_PALETTE: [[f32; 3]; 12] = [
+    [0.90, 0.10, 0.29],
+    [0.24, 0.71, 0.29],
+    [1.00, 0.88, 0.10],
+    [0.26, 0.39, 0.85],
+    [0.96, 0.51, 0.19],
+    [0.57, 0.12, 0.71],
+    [0.27, 0.94, 0.94],
+    [0.94, 0.20, 0.90],
+    [0.74, 0.96, 0.05],
+    [0.98, 0.75, 0.83],
+    [0.00, 0.50, 0.50],
+    [0.67, 0.43, 0.16],
+];
+
+// Colours for trophic levels 1 to 5: blue, orange, red, then crimson for 4 and up
+const TROPHIC_PALETTE: [[f32; 3]; 5] = [
+    [0.20, 0.45, 0.95],
+    [1.00, 0.55, 0.10],
+    [0.95, 0.10, 0.10],
+    [0.60, 0.05, 0.20],
+    [0.60, 0.05, 0.20],
+];
+
+// Energy heatmap cells reuse the connection vertex layout, six vertices per cell
+const HEATMAP_COLOR: [f32; 3] = [1.0, 0.55, 0.1];
+const HEATMAP_MAX_ALPHA: f32 = 0.35;
+// Toxin overlay uses the same layout; cells reach full tint at twice the harm threshold
+const TOXIN_COLOR: [f32; 3] = [0.55, 0.9, 0.2];
+const TOXIN_MAX_ALPHA: f32 = 0.4;
+const TOXIN_FULL_LEVEL: f64 = 2.0 * TOXIN_THRESHOLD;
 
 pub struct WebGlRenderer {
     gl: WebGlRenderingContext,
     agent_program: WebGlProgram,
     resource_program: WebGlProgram,
     trail_program: WebGlProgram,
+    connections_program: WebGlProgram,
+    habitat_program: WebGlProgram,
+    teleporter_program: WebGlProgram,
+    lasso_program: WebGlProgram,
+    histogram_program: WebGlProgram,
     agent_buffer: WebGlBuffer,
     resource_buffer: WebGlBuffer,
     trail_buffer: WebGlBuffer,
+    connections_buffer: WebGlBuffer,
+    habitat_buffer: WebGlBuffer,
+    teleporter_buffer: WebGlBuffer,
+    heatmap_buffer: WebGlBuffer,
+    toxin_buffer: WebGlBuffer,
+    lasso_buffer: WebGlBuffer,
+    lasso_visible: bool,
+    boxplot_buffer: WebGlBuffer,
+    /// Line vertices in `boxplot_buffer`; 0 hides the panel.
+    boxplot_vertex_count: i32,
+    histogram_quad_buffer: WebGlBuffer,
+    /// Bar heights as fractions of the tallest bar; empty hides the panel.
+    histogram_bars: Vec<f32>,
     agent_count: u32,
     resource_count: u32,
     trail_count: u32,
+    connection_segment_count: u32,
+    connections_visible: bool,
+    show_mutations: bool,
+    show_trophic_levels: bool,
+    /// `UnifiedSimulation::warmth`, cooling the background in winter.
+    warmth: f64,
+    habitat_count: u32,
+    teleporter_count: u32,
+    heatmap_vertex_count: u32,
+    toxin_vertex_count: u32,
     canvas_size_location: Option<WebGlUniformLocation>,
     time_location: Option<WebGlUniformLocation>,
+    viewport_bounds_location: Option<WebGlUniformLocation>,
+    atlas_location: Option<WebGlUniformLocation>,
+    show_mutations_location: Option<WebGlUniformLocation>,
+    atlas_texture: WebGlTexture,
     agent_positions: Vec<(f32, f32)>,
+    migrating_agents: Vec<(f32, f32, f32, f32)>, // Position and heading of migrating agents
     resource_positions: Vec<(f32, f32)>,
     resource_growth_states: Vec<f32>, // Track growth state for each resource
     time: f32,
     canvas_width: u32,
     canvas_height: u32,
+    selected_agent_id: Option<u64>,
+    max_render_agents: usize,
+    /// How much harder agents far from the centre are thinned once the
+    /// population passes `max_render_agents`; see `render_subset`.
+    render_lod_bias: f64,
+    rendered_agent_count: u32,
+    culled_agent_count: u32,
+    /// `EXT_disjoint_timer_query`, which WebGL2 contexts don't offer.
+    timer_extension: Option<ExtDisjointTimerQuery>,
+    gpu_timer: Option<WebGlQuery>,
+    /// Whether `gpu_timer` holds a finished frame's time not yet read back.
+    pending_query: bool,
+    avg_gpu_time_ns: f64,
+    #[cfg(feature = "webgl2")]
+    instanced_agents: Option<InstancedAgentRenderer>,
+}
+
+/// Agents drawn and left out by the last `update_agents`.
+#[derive(Clone, Debug, Serialize)]
+pub struct RenderStats {
+    pub rendered_agent_count: u32,
+    pub culled_agent_count: u32,
+    pub max_render_agents: usize,
 }
 
 impl WebGlRenderer {
     pub fn new(canvas: HtmlCanvasElement) -> Result<Self, JsValue> {
         web_sys::console::log_1(&"Attempting to create WebGL context...".into());
 
+        // A WebGL2 context serves every WebGL1 call below, and lets
+        // `upgrade_to_webgl2` pick it up for instanced agent rendering.
+        #[cfg(feature = "webgl2")]
+        let gl = match canvas.get_context("webgl2")? {
+            Some(context) => context.unchecked_into::<WebGlRenderingContext>(),
+            None => canvas
+                .get_context("webgl")?
+                .unwrap()
+                .dyn_into::<WebGlRenderingContext>()?,
+        };
+        #[cfg(not(feature = "webgl2"))]
         let gl = canvas
             .get_context("webgl")?
             .unwrap()
@@ -61,6 +209,16 @@ impl WebGlRenderer {
         let resource_program = Self::create_resource_shader_program(&gl)?;
         web_sys::console::log_1(&"Creating trail shader program...".into());
         let trail_program = Self::create_trail_shader_program(&gl)?;
+        web_sys::console::log_1(&"Creating connections shader program...".into());
+        let connections_program = Self::create_connections_shader_program(&gl)?;
+        web_sys::console::log_1(&"Creating habitat shader program...".into());
+        let habitat_program = Self::create_habitat_shader_program(&gl)?;
+        web_sys::console::log_1(&"Creating teleporter shader program...".into());
+        let teleporter_program = Self::create_teleporter_shader_program(&gl)?;
+        web_sys::console::log_1(&"Creating lasso shader program...".into());
+        let lasso_program = Self::create_lasso_shader_program(&gl)?;
+        web_sys::console::log_1(&"Creating histogram shader program...".into());
+        let histogram_program = Self::create_histogram_shader_program(&gl)?;
         web_sys::console::log_1(&"All shader programs created successfully!".into());
 
         // Create buffers
@@ -69,31 +227,166 @@ impl WebGlRenderer {
             .create_buffer()
             .ok_or("Failed to create resource buffer")?;
         let trail_buffer = gl.create_buffer().ok_or("Failed to create trail buffer")?;
+        let connections_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create connections buffer")?;
+        let habitat_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create habitat buffer")?;
+        let teleporter_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create teleporter buffer")?;
+        let heatmap_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create heatmap buffer")?;
+        let toxin_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create toxin buffer")?;
+        let lasso_buffer = gl.create_buffer().ok_or("Failed to create lasso buffer")?;
+        let boxplot_buffer = gl.create_buffer().ok_or("Failed to create boxplot buffer")?;
+        let histogram_quad_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create histogram buffer")?;
+        let unit_quad: Vec<u8> = [0.0f32, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&histogram_quad_buffer));
+        gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &unit_quad,
+            WebGlRenderingContext::STATIC_DRAW,
+        );
 
         // Get uniform locations
         let canvas_size_location = gl.get_uniform_location(&agent_program, "u_canvas_size");
         let time_location = gl.get_uniform_location(&agent_program, "u_time");
-
-        Ok(WebGlRenderer {
+        let viewport_bounds_location =
+            gl.get_uniform_location(&agent_program, "u_viewport_bounds");
+        let atlas_location = gl.get_uniform_location(&agent_program, "u_atlas");
+        let show_mutations_location =
+            gl.get_uniform_location(&agent_program, "u_show_mutations");
+        let atlas_texture = Self::build_atlas_texture(&gl)?;
+
+        let timer_extension = gl
+            .get_extension("EXT_disjoint_timer_query")
+            .ok()
+            .flatten()
+            .map(|extension| extension.unchecked_into::<ExtDisjointTimerQuery>());
+        let gpu_timer = timer_extension
+            .as_ref()
+            .and_then(ExtDisjointTimerQuery::create_query_ext);
+
+        #[allow(unused_mut)]
+        let mut renderer = WebGlRenderer {
             gl,
             agent_program,
             resource_program,
             trail_program,
+            connections_program,
+            habitat_program,
+            teleporter_program,
+            lasso_program,
+            histogram_program,
             agent_buffer,
             resource_buffer,
             trail_buffer,
+            connections_buffer,
+            habitat_buffer,
+            teleporter_buffer,
+            heatmap_buffer,
+            toxin_buffer,
+            lasso_buffer,
+            lasso_visible: false,
+            boxplot_buffer,
+            boxplot_vertex_count: 0,
+            histogram_quad_buffer,
+            histogram_bars: Vec::new(),
             agent_count: 0,
             resource_count: 0,
             trail_count: 0,
+            connection_segment_count: 0,
+            connections_visible: false,
+            show_mutations: false,
+            show_trophic_levels: false,
+            warmth: 1.0,
+            habitat_count: 0,
+            teleporter_count: 0,
+            heatmap_vertex_count: 0,
+            toxin_vertex_count: 0,
             canvas_size_location,
             time_location,
+            viewport_bounds_location,
+            atlas_location,
+            show_mutations_location,
+            atlas_texture,
             agent_positions: Vec::new(),
+            migrating_agents: Vec::new(),
             resource_positions: Vec::new(),
             resource_growth_states: Vec::new(),
             time: 0.0,
             canvas_width: width as u32,
             canvas_height: height as u32,
-        })
+            selected_agent_id: None,
+            max_render_agents: DEFAULT_MAX_RENDER_AGENTS,
+            render_lod_bias: 0.0,
+            rendered_agent_count: 0,
+            culled_agent_count: 0,
+            timer_extension,
+            gpu_timer,
+            pending_query: false,
+            avg_gpu_time_ns: 0.0,
+            #[cfg(feature = "webgl2")]
+            instanced_agents: None,
+        };
+
+        #[cfg(feature = "webgl2")]
+        if let Err(e) = renderer.upgrade_to_webgl2(canvas) {
+            web_sys::console::log_1(&format!("Staying on WebGL1 agents: {:?}", e).into());
+        }
+
+        Ok(renderer)
+    }
+
+    /// Switches agent rendering to instanced billboards if `canvas` has a
+    /// WebGL2 context. On failure the WebGL1 point-sprite path stays active.
+    #[cfg(feature = "webgl2")]
+    pub fn upgrade_to_webgl2(&mut self, canvas: HtmlCanvasElement) -> Result<(), JsValue> {
+        let gl = canvas
+            .get_context("webgl2")?
+            .ok_or("WebGL2 is not available on this canvas")?
+            .dyn_into::<WebGl2RenderingContext>()?;
+
+        self.instanced_agents = Some(InstancedAgentRenderer::new(gl)?);
+        web_sys::console::log_1(&"Using WebGL2 instanced agent rendering".into());
+        Ok(())
+    }
+
+    /// Uploads the `atlas_pixels` icon row used by the agent fragment shader.
+    fn build_atlas_texture(gl: &WebGlRenderingContext) -> Result<WebGlTexture, JsValue> {
+        let texture = gl.create_texture().ok_or("Failed to create atlas texture")?;
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGlRenderingContext::TEXTURE_2D,
+            0,
+            WebGlRenderingContext::RGBA as i32,
+            ATLAS_WIDTH as i32,
+            ATLAS_CELL_SIZE as i32,
+            0,
+            WebGlRenderingContext::RGBA,
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            Some(&atlas_pixels()),
+        )?;
+        for (parameter, value) in [
+            (WebGlRenderingContext::TEXTURE_MIN_FILTER, WebGlRenderingContext::NEAREST),
+            (WebGlRenderingContext::TEXTURE_MAG_FILTER, WebGlRenderingContext::NEAREST),
+            (WebGlRenderingContext::TEXTURE_WRAP_S, WebGlRenderingContext::CLAMP_TO_EDGE),
+            (WebGlRenderingContext::TEXTURE_WRAP_T, WebGlRenderingContext::CLAMP_TO_EDGE),
+        ] {
+            gl.tex_parameteri(WebGlRenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, None);
+        Ok(texture)
     }
 
     fn create_agent_shader_program(gl: &WebGlRenderingContext) -> Result<WebGlProgram, JsValue> {
@@ -105,14 +398,43 @@ attribute vec2 a_position;
 attribute vec3 a_color;
 attribute float a_size;
 attribute float a_energy;
+attribute float a_selected;
+attribute float a_state;
+attribute float a_mutated;
+attribute float a_spawn_time;
+attribute float a_satiation;
 uniform vec2 u_canvas_size;
 uniform float u_time;
+uniform vec4 u_viewport_bounds;
 varying vec3 v_color;
 varying float v_energy;
 varying vec2 v_position;
 varying float v_size;
+varying float v_selected;
+varying float v_state;
+varying float v_mutated;
+varying float v_spawn_time;
+varying float v_satiation;
 
 void main() {
+    v_color = a_color;
+    v_energy = a_energy;
+    v_position = a_position;
+    v_size = a_size;
+    v_selected = a_selected;
+    v_state = a_state;
+    v_mutated = a_mutated;
+    v_spawn_time = a_spawn_time;
+    v_satiation = a_satiation;
+
+    // Cull agents outside the visible region (min_x, min_y, max_x, max_y)
+    if (a_position.x < u_viewport_bounds.x || a_position.y < u_viewport_bounds.y ||
+        a_position.x > u_viewport_bounds.z || a_position.y > u_viewport_bounds.w) {
+        gl_PointSize = 0.0;
+        gl_Position = vec4(2.0, 2.0, 0.0, 1.0);
+        return;
+    }
+
     // Transform from pixel coordinates to normalized device coordinates
     vec2 ndc = (a_position / u_canvas_size) * 2.0 - 1.0;
     ndc.y = -ndc.y; // Flip Y axis
@@ -128,11 +450,6 @@ void main() {
         float pulse = sin(u_time * 3.0 + a_position.x * 0.1) * 0.2 + 1.0;
         float energy_scale = 0.5 + a_energy * 0.01;
         gl_PointSize = base_size * pulse * energy_scale;
-    
-    v_color = a_color;
-    v_energy = a_energy;
-    v_position = a_position;
-    v_size = a_size;
 }"#,
         )?;
 
@@ -144,14 +461,31 @@ varying vec3 v_color;
 varying float v_energy;
 varying vec2 v_position;
 varying float v_size;
+varying float v_selected;
+varying float v_state;
+varying float v_mutated;
+varying float v_spawn_time;
+varying float v_satiation;
 uniform float u_time;
+uniform sampler2D u_atlas;
+uniform float u_show_mutations;
 
 void main() {
     vec2 center = gl_PointCoord - 0.5;
     float dist = length(center);
     
-    // Create a soft circular particle with enhanced edges
-    float alpha = 1.0 - smoothstep(0.0, 0.5, dist);
+    // Selection ring around the inspected agent
+    if (v_selected > 0.5 && abs(dist - 0.4) < 0.05) {
+        gl_FragColor = vec4(1.0, 1.0, 1.0, 1.0);
+        return;
+    }
+    
+    // The agent's state picks its icon from the atlas row
+    vec2 atlas_uv = vec2((floor(v_state + 0.5) + gl_PointCoord.x) / 8.0, gl_PointCoord.y);
+    float alpha = texture2D(u_atlas, atlas_uv).a;
+    if (alpha <= 0.0) {
+        discard;
+    }
     
     // Add inner core with bright center
     float core = 1.0 - smoothstep(0.0, 0.2, dist);
@@ -198,6 +532,16 @@ void main() {
     float rotation = sin(angle * 4.0 + u_time * 2.0) * 0.05 + 0.95;
     final_color *= rotation;
     
+    // Pulsing white highlight on agents born with a fresh mutation, fading out
+    float since_spawn = u_time - v_spawn_time;
+    if (u_show_mutations > 0.5 && v_mutated > 0.0 && since_spawn < 5.0) {
+        float highlight = (sin(u_time * 8.0) * 0.5 + 0.5) * (1.0 - since_spawn / 5.0);
+        final_color = mix(final_color, vec3(1.0), highlight * 0.7);
+    }
+    
+    // Well-fed predators are resting, so draw them faded
+    alpha *= 1.0 - smoothstep(0.8, 1.0, v_satiation) * 0.5;
+    
     gl_FragColor = vec4(final_color, alpha);
 }"#,
         )?;
@@ -397,6 +741,282 @@ void main() {
         }
     }
 
+    fn create_connections_shader_program(
+        gl: &WebGlRenderingContext,
+    ) -> Result<WebGlProgram, JsValue> {
+        let vertex_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::VERTEX_SHADER,
+            r#"precision highp float;
+attribute vec2 a_position;
+attribute vec3 a_color;
+attribute float a_alpha;
+uniform vec2 u_canvas_size;
+varying vec3 v_color;
+varying float v_alpha;
+
+void main() {
+    vec2 ndc = (a_position / u_canvas_size) * 2.0 - 1.0;
+    ndc.y = -ndc.y;
+    gl_Position = vec4(ndc, 0.0, 1.0);
+
+    v_color = a_color;
+    v_alpha = a_alpha;
+}"#,
+        )?;
+
+        let fragment_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::FRAGMENT_SHADER,
+            r#"precision highp float;
+varying vec3 v_color;
+varying float v_alpha;
+
+void main() {
+    gl_FragColor = vec4(v_color, v_alpha);
+}"#,
+        )?;
+
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        gl.link_program(&program);
+
+        let link_status = gl.get_program_parameter(&program, WebGlRenderingContext::LINK_STATUS);
+        if link_status.as_bool().unwrap_or(false) {
+            Ok(program)
+        } else {
+            let error = gl.get_program_info_log(&program).unwrap_or_default();
+            Err(format!("Failed to link connections shader program: {}", error).into())
+        }
+    }
+
+    fn create_lasso_shader_program(gl: &WebGlRenderingContext) -> Result<WebGlProgram, JsValue> {
+        let vertex_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::VERTEX_SHADER,
+            r#"precision highp float;
+attribute vec2 a_position;
+uniform vec2 u_canvas_size;
+
+void main() {
+    vec2 ndc = (a_position / u_canvas_size) * 2.0 - 1.0;
+    ndc.y = -ndc.y;
+    gl_Position = vec4(ndc, 0.0, 1.0);
+}"#,
+        )?;
+
+        // Dashes run diagonally in screen space, so every edge of the
+        // rectangle gets the same pattern
+        let fragment_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::FRAGMENT_SHADER,
+            r#"precision highp float;
+uniform vec4 u_color;
+uniform float u_dash_length;
+
+void main() {
+    if (mod(floor((gl_FragCoord.x + gl_FragCoord.y) / u_dash_length), 2.0) > 0.5) {
+        discard;
+    }
+    gl_FragColor = u_color;
+}"#,
+        )?;
+
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        gl.link_program(&program);
+
+        let link_status = gl.get_program_parameter(&program, WebGlRenderingContext::LINK_STATUS);
+        if link_status.as_bool().unwrap_or(false) {
+            Ok(program)
+        } else {
+            let error = gl.get_program_info_log(&program).unwrap_or_default();
+            Err(format!("Failed to link lasso shader program: {}", error).into())
+        }
+    }
+
+    fn create_histogram_shader_program(
+        gl: &WebGlRenderingContext,
+    ) -> Result<WebGlProgram, JsValue> {
+        let vertex_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::VERTEX_SHADER,
+            r#"precision highp float;
+attribute vec2 a_corner;
+uniform vec4 u_rect;
+uniform vec2 u_canvas_size;
+
+void main() {
+    vec2 position = u_rect.xy + a_corner * u_rect.zw;
+    vec2 ndc = (position / u_canvas_size) * 2.0 - 1.0;
+    ndc.y = -ndc.y;
+    gl_Position = vec4(ndc, 0.0, 1.0);
+}"#,
+        )?;
+
+        let fragment_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::FRAGMENT_SHADER,
+            r#"precision highp float;
+uniform vec4 u_color;
+
+void main() {
+    gl_FragColor = u_color;
+}"#,
+        )?;
+
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        gl.link_program(&program);
+
+        let link_status = gl.get_program_parameter(&program, WebGlRenderingContext::LINK_STATUS);
+        if link_status.as_bool().unwrap_or(false) {
+            Ok(program)
+        } else {
+            let error = gl.get_program_info_log(&program).unwrap_or_default();
+            Err(format!("Failed to link histogram shader program: {}", error).into())
+        }
+    }
+
+    fn create_habitat_shader_program(gl: &WebGlRenderingContext) -> Result<WebGlProgram, JsValue> {
+        let vertex_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::VERTEX_SHADER,
+            r#"precision highp float;
+attribute vec2 a_position;
+attribute vec2 a_center;
+attribute float a_radius;
+uniform vec2 u_canvas_size;
+varying vec2 v_offset;
+varying float v_radius;
+
+void main() {
+    vec2 ndc = (a_position / u_canvas_size) * 2.0 - 1.0;
+    ndc.y = -ndc.y;
+    gl_Position = vec4(ndc, 0.0, 1.0);
+
+    v_offset = a_position - a_center;
+    v_radius = a_radius;
+}"#,
+        )?;
+
+        let fragment_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::FRAGMENT_SHADER,
+            r#"precision highp float;
+varying vec2 v_offset;
+varying float v_radius;
+
+void main() {
+    float dist = length(v_offset);
+    if (dist > v_radius) {
+        discard;
+    }
+
+    vec3 color = vec3(0.4, 0.9, 0.6);
+    float alpha = 0.08;
+
+    // Dashed 2px border: alternate 12px dashes along the circumference
+    if (dist > v_radius - 2.0) {
+        float arc = (atan(v_offset.y, v_offset.x) + 3.14159265) * v_radius;
+        if (mod(arc, 24.0) < 12.0) {
+            alpha = 0.6;
+        }
+    }
+
+    gl_FragColor = vec4(color, alpha);
+}"#,
+        )?;
+
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        gl.link_program(&program);
+
+        let link_status = gl.get_program_parameter(&program, WebGlRenderingContext::LINK_STATUS);
+        if link_status.as_bool().unwrap_or(false) {
+            Ok(program)
+        } else {
+            let error = gl.get_program_info_log(&program).unwrap_or_default();
+            Err(format!("Failed to link habitat shader program: {}", error).into())
+        }
+    }
+
+    fn create_teleporter_shader_program(
+        gl: &WebGlRenderingContext,
+    ) -> Result<WebGlProgram, JsValue> {
+        let vertex_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::VERTEX_SHADER,
+            r#"precision highp float;
+attribute vec2 a_position;
+attribute vec2 a_center;
+attribute float a_radius;
+attribute float a_spin;
+uniform vec2 u_canvas_size;
+varying vec2 v_offset;
+varying float v_radius;
+varying float v_spin;
+
+void main() {
+    vec2 ndc = (a_position / u_canvas_size) * 2.0 - 1.0;
+    ndc.y = -ndc.y;
+    gl_Position = vec4(ndc, 0.0, 1.0);
+
+    v_offset = a_position - a_center;
+    v_radius = a_radius;
+    v_spin = a_spin;
+}"#,
+        )?;
+
+        let fragment_shader = Self::create_shader(
+            gl,
+            WebGlRenderingContext::FRAGMENT_SHADER,
+            r#"precision highp float;
+varying vec2 v_offset;
+varying float v_radius;
+varying float v_spin;
+uniform float u_time;
+
+void main() {
+    float dist = length(v_offset) / v_radius;
+    if (dist > 1.0) {
+        discard;
+    }
+
+    // Three rings whose bright arcs rotate, clockwise at the entrance and
+    // anticlockwise at the exit
+    float angle = atan(v_offset.y, v_offset.x) + v_spin * u_time * 3.0;
+    float rings = abs(fract(dist * 3.0) - 0.5);
+    float ring = smoothstep(0.15, 0.0, rings);
+    float arcs = 0.5 + 0.5 * sin(angle * 3.0 + dist * 6.0);
+
+    vec3 entrance = vec3(0.7, 0.3, 1.0);
+    vec3 exit = vec3(0.3, 0.8, 1.0);
+    vec3 color = v_spin > 0.0 ? entrance : exit;
+    float alpha = ring * arcs * 0.8 + (1.0 - dist) * 0.15;
+
+    gl_FragColor = vec4(color, alpha);
+}"#,
+        )?;
+
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        gl.link_program(&program);
+
+        let link_status = gl.get_program_parameter(&program, WebGlRenderingContext::LINK_STATUS);
+        if link_status.as_bool().unwrap_or(false) {
+            Ok(program)
+        } else {
+            let error = gl.get_program_info_log(&program).unwrap_or_default();
+            Err(format!("Failed to link teleporter shader program: {}", error).into())
+        }
+    }
+
     fn create_shader(
         gl: &WebGlRenderingContext,
         shader_type: u32,
@@ -424,6 +1044,25 @@ void main() {
     }
 
     pub fn update_agents(&mut self, agents: &[Agent]) {
+        // Past the limit only the most energetic agents are drawn
+        let subset: Vec<Agent>;
+        let total = agents.len();
+        let agents = if total > self.max_render_agents {
+            let indices = render_subset(
+                agents,
+                self.max_render_agents,
+                self.render_lod_bias,
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+            subset = indices.into_iter().map(|i| agents[i].clone()).collect();
+            &subset[..]
+        } else {
+            agents
+        };
+        self.rendered_agent_count = agents.len() as u32;
+        self.culled_agent_count = (total - agents.len()) as u32;
+
         // Clear previous data
         self.agent_positions.clear();
 
@@ -439,6 +1078,14 @@ void main() {
         let mut agent_data = Vec::new();
         self.agent_positions.clear();
 
+        self.migrating_agents.clear();
+        for agent in agents {
+            if let Some((dir_x, dir_y)) = agent.migrating_direction {
+                self.migrating_agents
+                    .push((agent.x as f32, agent.y as f32, dir_x as f32, dir_y as f32));
+            }
+        }
+
         // Add a test particle if no agents exist
         if agents.is_empty() {
             web_sys::console::log_1(&"No agents found, adding test particle".into());
@@ -462,100 +1109,35 @@ void main() {
             // Energy attribute
             agent_data.extend_from_slice(&(100.0 as f32).to_le_bytes());
 
-            self.agent_count = 1;
-        } else {
-            for agent in agents {
-                // Position (vec2)
-                agent_data.extend_from_slice(&(agent.x as f32).to_le_bytes());
-                agent_data.extend_from_slice(&(agent.y as f32).to_le_bytes());
-                self.agent_positions.push((agent.x as f32, agent.y as f32));
-
-                // Enhanced color based on genes and energy with predator distinction and death states
-                let is_predator = agent.genes.is_predator > 0.5;
-
-                // Handle death colors
-                let (hue, saturation, lightness) = if agent.is_dying {
-                    match agent.death_reason {
-                        Some(DeathReason::Starvation) => (0.0, 0.8, 0.3), // Dark red for starvation
-                        Some(DeathReason::OldAge) => (30.0, 0.6, 0.4),    // Orange for old age
-                        Some(DeathReason::KilledByPredator) => (0.0, 1.0, 0.2), // Bright red for predation
-                        Some(DeathReason::Combat) => (15.0, 0.9, 0.3), // Red-orange for combat
-                        Some(DeathReason::NaturalCauses) => (60.0, 0.5, 0.4), // Yellow for natural causes
-                        None => (0.0, 0.7, 0.3),                              // Default dark red
-                    }
-                } else {
-                    let base_hue = if is_predator {
-                        // Predators: Red to orange range (0-60 degrees)
-                        (agent.genes.attack_power * 60.0 + agent.genes.aggression * 30.0) % 60.0
-                    } else {
-                        // Prey: Blue to green range (180-240 degrees)
-                        (agent.genes.speed * 60.0 + agent.genes.sense_range * 0.5 + 180.0) % 60.0
-                            + 180.0
-                    };
-
-                    let base_saturation = if is_predator {
-                        0.95 + agent.genes.attack_power * 0.05 // Predators more saturated
-                    } else {
-                        0.9 + agent.genes.size * 0.1 // Prey normal saturation
-                    };
-
-                    let base_lightness = if is_predator {
-                        0.6 + agent.energy * 0.003 + agent.genes.attack_power * 0.1
-                    // Predators brighter
-                    } else {
-                        0.5 + agent.energy * 0.004 // Prey normal brightness
-                    };
-
-                    (base_hue, base_saturation, base_lightness)
-                };
+            // Selected attribute
+            agent_data.extend_from_slice(&0.0f32.to_le_bytes());
 
-                // Convert HSL to RGB with enhanced vibrancy
-                let (r, g, b) = Self::hsl_to_rgb(hue as f32, saturation as f32, lightness as f32);
+            // Atlas index attribute
+            agent_data.extend_from_slice(&0.0f32.to_le_bytes());
 
-                // Add extra vibrancy and energy-based color enhancement
-                let vibrancy = if is_predator { 1.5 } else { 1.3 }; // Predators more vibrant
-                let energy_boost = (agent.energy * 0.002) as f32;
-                let predator_boost = if is_predator {
-                    agent.genes.attack_power as f32 * 0.1
-                } else {
-                    0.0
-                };
+            // Mutated gene mask and spawn time attributes
+            agent_data.extend_from_slice(&0.0f32.to_le_bytes());
+            agent_data.extend_from_slice(&0.0f32.to_le_bytes());
 
-                agent_data.extend_from_slice(
-                    &(((r * vibrancy + energy_boost + predator_boost).min(1.0)) as f32)
-                        .to_le_bytes(),
-                );
-                agent_data.extend_from_slice(
-                    &(((g * vibrancy + energy_boost * 0.7 + predator_boost * 0.5).min(1.0)) as f32)
-                        .to_le_bytes(),
-                );
-                agent_data.extend_from_slice(
-                    &(((b * vibrancy + energy_boost * 0.3 + predator_boost * 0.2).min(1.0)) as f32)
-                        .to_le_bytes(),
-                );
-
-                // Size attribute with fade effects
-                let size_factor = if agent.is_dying {
-                    1.0 - agent.death_fade as f32 // Shrink when dying
-                } else {
-                    agent.spawn_fade as f32 // Grow when spawning
-                };
-                let adjusted_size = agent.genes.size as f32 * size_factor;
-                agent_data.extend_from_slice(&adjusted_size.to_le_bytes());
+            self.agent_count = 1;
+        } else {
+            #[cfg(feature = "webgl2")]
+            if self.instanced_agents.is_some() {
+                self.update_agent_instances(agents);
+                self.update_connections(agents);
+                return;
+            }
 
-                // Energy attribute with fade effects
-                let energy_factor = if agent.is_dying {
-                    1.0 - agent.death_fade as f32 // Fade energy when dying
-                } else {
-                    agent.spawn_fade as f32 // Fade in energy when spawning
-                };
-                let adjusted_energy = agent.energy as f32 * energy_factor;
-                agent_data.extend_from_slice(&adjusted_energy.to_le_bytes());
+            for agent in agents {
+                self.agent_positions.push((agent.x as f32, agent.y as f32));
+                for value in self.agent_vertex(agent) {
+                    agent_data.extend_from_slice(&value.to_le_bytes());
+                }
             }
         }
 
         // Safety check: ensure data size matches expected count
-        let expected_bytes_per_agent = 28; // 2 pos + 3 color + 1 size + 1 energy = 7 floats * 4 bytes
+        let expected_bytes_per_agent = AGENT_STRIDE as usize;
         let expected_total_bytes = self.agent_count as usize * expected_bytes_per_agent;
 
         if agent_data.len() != expected_total_bytes {
@@ -580,6 +1162,483 @@ void main() {
             &agent_data,
             WebGlRenderingContext::DYNAMIC_DRAW,
         );
+
+        self.update_connections(agents);
+    }
+
+    /// Links each pack-minded predator to every agent within pack hunting
+    /// distance, using `agent_positions` filled by `update_agents`.
+    fn update_connections(&mut self, agents: &[Agent]) {
+        self.connection_segment_count = 0;
+        if !self.connections_visible {
+            return;
+        }
+
+        let mut connection_data = Vec::new();
+        let mut segment_count = 0;
+
+        'agents: for (i, agent) in agents.iter().enumerate() {
+            if !agent.is_predator() || agent.genes.pack_mentality <= 0.6 {
+                continue;
+            }
+            let Some(&(x1, y1)) = self.agent_positions.get(i) else {
+                break;
+            };
+            let vertex = self.agent_vertex(agent);
+            let color = [vertex[2], vertex[3], vertex[4]];
+
+            for (j, &(x2, y2)) in self.agent_positions.iter().enumerate() {
+                if j == i {
+                    continue;
+                }
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                if dx * dx + dy * dy > PACK_CONNECTION_DISTANCE * PACK_CONNECTION_DISTANCE {
+                    continue;
+                }
+                if segment_count >= MAX_CONNECTION_SEGMENTS {
+                    break 'agents;
+                }
+
+                for (x, y) in [(x1, y1), (x2, y2)] {
+                    for value in [x, y, color[0], color[1], color[2], PACK_CONNECTION_ALPHA] {
+                        connection_data.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                segment_count += 1;
+            }
+        }
+
+        self.connection_segment_count = segment_count as u32;
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.connections_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &connection_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    pub fn update_habitats(&mut self, habitats: &[Habitat]) {
+        let mut habitat_data = Vec::with_capacity(habitats.len() * HABITAT_VERTICES * HABITAT_STRIDE as usize);
+        for habitat in habitats {
+            let (cx, cy, r) = (habitat.x as f32, habitat.y as f32, habitat.radius as f32);
+            let corners = [
+                (cx - r, cy - r),
+                (cx + r, cy - r),
+                (cx + r, cy + r),
+                (cx - r, cy - r),
+                (cx + r, cy + r),
+                (cx - r, cy + r),
+            ];
+            for (x, y) in corners {
+                for value in [x, y, cx, cy, r] {
+                    habitat_data.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+        self.habitat_count = habitats.len() as u32;
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.habitat_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &habitat_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    pub fn update_teleporters(&mut self, teleporters: &[Teleporter]) {
+        let mut teleporter_data = Vec::with_capacity(
+            teleporters.len() * TELEPORTER_VERTICES * TELEPORTER_STRIDE as usize,
+        );
+        for teleporter in teleporters {
+            let r = teleporter.radius as f32;
+            let ends = [
+                (teleporter.from_x as f32, teleporter.from_y as f32, 1.0f32),
+                (teleporter.to_x as f32, teleporter.to_y as f32, -1.0f32),
+            ];
+            for (cx, cy, spin) in ends {
+                let corners = [
+                    (cx - r, cy - r),
+                    (cx + r, cy - r),
+                    (cx + r, cy + r),
+                    (cx - r, cy - r),
+                    (cx + r, cy + r),
+                    (cx - r, cy + r),
+                ];
+                for (x, y) in corners {
+                    for value in [x, y, cx, cy, r, spin] {
+                        teleporter_data.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+        }
+        self.teleporter_count = teleporters.len() as u32;
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.teleporter_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &teleporter_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    /// Outlines the selection rectangle with corners (x1, y1) and (x2, y2);
+    /// `None` hides it.
+    pub fn set_lasso(&mut self, lasso: Option<(f64, f64, f64, f64)>) {
+        self.lasso_visible = lasso.is_some();
+        let Some((x1, y1, x2, y2)) = lasso else {
+            return;
+        };
+        let (x1, y1, x2, y2) = (x1 as f32, y1 as f32, x2 as f32, y2 as f32);
+        let corners = [x1, y1, x2, y1, x2, y2, x1, y2];
+        let lasso_data: Vec<u8> = corners.iter().flat_map(|value| value.to_le_bytes()).collect();
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.lasso_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &lasso_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    /// Shows a box-and-whisker plot in the top-left corner from the p5, p25,
+    /// median, p75 and p95 of a gene, each as a fraction of the gene's range.
+    /// `None` hides it.
+    pub fn set_boxplot(&mut self, fractions: Option<[f32; 5]>) {
+        let Some(fractions) = fractions else {
+            self.boxplot_vertex_count = 0;
+            return;
+        };
+        let segments = boxplot_segments(fractions, boxplot_panel());
+        self.boxplot_vertex_count = (segments.len() * 2) as i32;
+        let boxplot_data: Vec<u8> = segments
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.boxplot_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &boxplot_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    /// Shows `counts` as a bar chart in the top-right corner, scaled so the
+    /// tallest bar fills the panel. An empty slice hides it.
+    pub fn set_histogram(&mut self, counts: &[u32]) {
+        let tallest = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        self.histogram_bars = counts.iter().map(|&count| count as f32 / tallest).collect();
+    }
+
+    /// Tints each cell of `grid` (indexed `[x][y]`, spanning the canvas) by
+    /// its share of the hottest cell. An empty grid hides the heatmap.
+    pub fn update_energy_heatmap(&mut self, grid: &[Vec<f64>]) {
+        let hottest = grid.iter().flatten().copied().fold(0.0, f64::max);
+        let (heatmap_data, vertex_count) = if hottest > 0.0 {
+            self.grid_overlay_vertices(grid, HEATMAP_COLOR, HEATMAP_MAX_ALPHA, hottest)
+        } else {
+            (Vec::new(), 0)
+        };
+        self.heatmap_vertex_count = vertex_count as u32;
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.heatmap_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &heatmap_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    /// Tints toxic cells of `grid` (indexed `[x][y]`) by their level. An
+    /// empty grid hides the overlay.
+    pub fn update_toxin_overlay(&mut self, grid: &[Vec<f64>]) {
+        let (toxin_data, vertex_count) =
+            self.grid_overlay_vertices(grid, TOXIN_COLOR, TOXIN_MAX_ALPHA, TOXIN_FULL_LEVEL);
+        self.toxin_vertex_count = vertex_count as u32;
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.toxin_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &toxin_data,
+            WebGlRenderingContext::DYNAMIC_DRAW,
+        );
+    }
+
+    /// Two triangles per positive cell, in the connection vertex layout, with
+    /// alpha rising to `max_alpha` at `full_value`.
+    fn grid_overlay_vertices(
+        &self,
+        grid: &[Vec<f64>],
+        color: [f32; 3],
+        max_alpha: f32,
+        full_value: f64,
+    ) -> (Vec<u8>, usize) {
+        let columns = grid.len();
+        let rows = grid.first().map_or(0, Vec::len);
+        let mut data = Vec::new();
+        let mut vertex_count = 0;
+        if rows == 0 {
+            return (data, vertex_count);
+        }
+
+        let cell_width = self.canvas_width as f32 / columns as f32;
+        let cell_height = self.canvas_height as f32 / rows as f32;
+        for (x, column) in grid.iter().enumerate() {
+            for (y, &value) in column.iter().enumerate() {
+                if value <= 0.0 {
+                    continue;
+                }
+                let alpha = max_alpha * (value / full_value).min(1.0) as f32;
+                let (left, top) = (x as f32 * cell_width, y as f32 * cell_height);
+                let (right, bottom) = (left + cell_width, top + cell_height);
+                let corners = [
+                    (left, top),
+                    (right, top),
+                    (right, bottom),
+                    (left, top),
+                    (right, bottom),
+                    (left, bottom),
+                ];
+                for (vx, vy) in corners {
+                    let [r, g, b] = color;
+                    for value in [vx, vy, r, g, b, alpha] {
+                        data.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                vertex_count += corners.len();
+            }
+        }
+        (data, vertex_count)
+    }
+
+    /// Caps the agents uploaded per frame at `max_agents`.
+    pub fn set_max_render_agents(&mut self, max_agents: usize) {
+        self.max_render_agents = max_agents;
+    }
+
+    /// 0 keeps the most energetic agents wherever they are; higher values
+    /// spend more of the `max_render_agents` budget near the centre.
+    pub fn set_render_lod_bias(&mut self, bias: f64) {
+        self.render_lod_bias = bias.max(0.0);
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            rendered_agent_count: self.rendered_agent_count,
+            culled_agent_count: self.culled_agent_count,
+            max_render_agents: self.max_render_agents,
+        }
+    }
+
+    /// `u_canvas_size` as last set on the agent program, read back from the
+    /// GL context; `None` before the first agent pass.
+    pub fn agent_canvas_size(&self) -> Option<(f32, f32)> {
+        let location = self.canvas_size_location.as_ref()?;
+        let value = self.gl.get_uniform(&self.agent_program, location);
+        let size = value.dyn_into::<js_sys::Float32Array>().ok()?.to_vec();
+        match size[..] {
+            [width, height] if width > 0.0 && height > 0.0 => Some((width, height)),
+            _ => None,
+        }
+    }
+
+    /// Rolling average GPU time of a `render` call, in nanoseconds; 0 when
+    /// the context has no timer queries or none has finished yet.
+    pub fn get_gpu_time_ns(&self) -> f64 {
+        self.avg_gpu_time_ns
+    }
+
+    /// Folds the previous frame's query into `avg_gpu_time_ns` once the GPU
+    /// has finished it. Returns whether the timer is free for this frame.
+    fn collect_gpu_time(&mut self) -> bool {
+        let (Some(extension), Some(query)) = (&self.timer_extension, &self.gpu_timer) else {
+            return false;
+        };
+        if !self.pending_query {
+            return true;
+        }
+        let available = extension
+            .get_query_object_ext(query, ExtDisjointTimerQuery::QUERY_RESULT_AVAILABLE_EXT)
+            .as_bool()
+            .unwrap_or(false);
+        if !available {
+            return false;
+        }
+        self.pending_query = false;
+
+        // A disjoint event (power change, context switch) spoils the timing
+        let disjoint = self
+            .gl
+            .get_parameter(ExtDisjointTimerQuery::GPU_DISJOINT_EXT)
+            .ok()
+            .and_then(|value| value.as_bool())
+            .unwrap_or(true);
+        if !disjoint {
+            if let Some(elapsed_ns) = extension
+                .get_query_object_ext(query, ExtDisjointTimerQuery::QUERY_RESULT_EXT)
+                .as_f64()
+            {
+                self.avg_gpu_time_ns = smooth_gpu_time(self.avg_gpu_time_ns, elapsed_ns);
+            }
+        }
+        true
+    }
+
+    pub fn set_connections_visible(&mut self, visible: bool) {
+        self.connections_visible = visible;
+    }
+
+    /// Highlights agents born with a fresh mutation for their first 5 seconds.
+    pub fn set_show_mutations(&mut self, show: bool) {
+        self.show_mutations = show;
+    }
+
+    pub fn set_warmth(&mut self, warmth: f64) {
+        self.warmth = warmth.clamp(0.0, 1.0);
+    }
+
+    /// Colours agents by trophic level instead of genes or species.
+    pub fn set_show_trophic_levels(&mut self, show: bool) {
+        self.show_trophic_levels = show;
+    }
+
+    /// Per-agent attributes in `AGENT_STRIDE` layout: position, color, size,
+    /// energy, selection flag, atlas index, mutated gene mask, the renderer
+    /// time the agent was born at and its satiation.
+    fn agent_vertex(&self, agent: &Agent) -> [f32; 12] {
+        // Living agents shade from blue (foragers) to red (hunters) along
+        // the predator gene; the icon still marks predators on the hunt
+        let predation = agent.genes.is_predator.clamp(0.0, 1.0);
+        let is_predator = agent.genes.is_predator > 0.5;
+
+        // Handle death colors
+        let (hue, saturation, lightness) = if agent.is_dying {
+            match agent.death_reason {
+                Some(DeathReason::Starvation) => (0.0, 0.8, 0.3), // Dark red for starvation
+                Some(DeathReason::OldAge) => (30.0, 0.6, 0.4),    // Orange for old age
+                Some(DeathReason::KilledByPredator) => (0.0, 1.0, 0.2), // Bright red for predation
+                Some(DeathReason::Combat) => (15.0, 0.9, 0.3), // Red-orange for combat
+                Some(DeathReason::NaturalCauses) => (60.0, 0.5, 0.4), // Yellow for natural causes
+                Some(DeathReason::Poisoned) => (300.0, 0.8, 0.35),    // Magenta for poison
+                Some(DeathReason::FellOffEdge) => (200.0, 0.5, 0.4),  // Steel blue for the edge
+                None => (0.0, 0.7, 0.3),                              // Default dark red
+            }
+        } else {
+            // Blue (240 degrees) through violet to red (360 degrees)
+            let base_hue = (240.0 + 120.0 * predation) % 360.0;
+
+            // Hunters more saturated and brighter
+            let forager_saturation = 0.9 + agent.genes.size * 0.1;
+            let hunter_saturation = 0.95 + agent.genes.attack_power * 0.05;
+            let base_saturation =
+                forager_saturation + (hunter_saturation - forager_saturation) * predation;
+
+            let forager_lightness = 0.5 + agent.energy * 0.004;
+            let hunter_lightness = 0.6 + agent.energy * 0.003 + agent.genes.attack_power * 0.1;
+            let base_lightness =
+                forager_lightness + (hunter_lightness - forager_lightness) * predation;
+
+            (base_hue, base_saturation, base_lightness)
+        };
+
+        // Convert HSL to RGB with enhanced vibrancy
+        let (r, g, b) = Self::hsl_to_rgb(hue as f32, saturation as f32, lightness as f32);
+
+        // Add extra vibrancy and energy-based color enhancement
+        let vibrancy = 1.3 + 0.2 * predation as f32; // Hunters more vibrant
+        let energy_boost = (agent.energy * 0.002) as f32;
+        let predator_boost = (agent.genes.attack_power * predation) as f32 * 0.1;
+
+        let red = (r * vibrancy + energy_boost + predator_boost).min(1.0);
+        let green = (g * vibrancy + energy_boost * 0.7 + predator_boost * 0.5).min(1.0);
+        let blue = (b * vibrancy + energy_boost * 0.3 + predator_boost * 0.2).min(1.0);
+
+        // Tagged species share a palette colour; dying agents keep their death colour
+        let [red, green, blue] = if self.show_trophic_levels && !agent.is_dying {
+            let level = agent.trophic_level.clamp(1, TROPHIC_PALETTE.len() as u8);
+            TROPHIC_PALETTE[level as usize - 1]
+        } else if agent.species_id != 0 && !agent.is_dying {
+            SPECIES_PALETTE[agent.species_id as usize % SPECIES_PALETTE.len()]
+        } else {
+            [red, green, blue]
+        };
+
+        // Size attribute with fade effects
+        let size_factor = if agent.is_dying {
+            1.0 - agent.death_fade as f32 // Shrink when dying
+        } else {
+            agent.spawn_fade as f32 // Grow when spawning
+        };
+        let adjusted_size = agent.genes.size as f32 * size_factor;
+
+        // Energy attribute with fade effects
+        let energy_factor = if agent.is_dying {
+            1.0 - agent.death_fade as f32 // Fade energy when dying
+        } else {
+            agent.spawn_fade as f32 // Fade in energy when spawning
+        };
+        let adjusted_energy = agent.energy as f32 * energy_factor;
+
+        // Selected attribute
+        let selected = if self.selected_agent_id == Some(agent.id()) {
+            1.0f32
+        } else {
+            0.0f32
+        };
+        [
+            agent.x as f32,
+            agent.y as f32,
+            red,
+            green,
+            blue,
+            adjusted_size,
+            adjusted_energy,
+            selected,
+            atlas_index(&agent.state, is_predator) as f32,
+            // Masks for the 21 genes are exact in an f32
+            agent.mutated_genes as f32,
+            self.time - agent.age as f32,
+            agent.satiation as f32,
+        ]
+    }
+
+    #[cfg(feature = "webgl2")]
+    fn update_agent_instances(&mut self, agents: &[Agent]) {
+        self.agent_positions
+            .extend(agents.iter().map(|agent| (agent.x as f32, agent.y as f32)));
+        let instance_data = instance_data(agents, |agent| self.agent_vertex(agent));
+
+        if let Some(instanced) = self.instanced_agents.as_mut() {
+            instanced.update(&instance_data);
+        }
+    }
+
+    /// Highlights the agent with this `Agent::id` on subsequent `update_agents` calls.
+    pub fn set_selected_agent(&mut self, id: Option<u64>) {
+        self.selected_agent_id = id;
     }
 
     pub fn update_resources(&mut self, resources: &[Resource]) {
@@ -635,9 +1694,16 @@ void main() {
 
                 // Enhanced color based on energy - vibrant green to yellow to orange
                 let energy_ratio = (resource.energy / 100.0).min(1.0);
-                let r = energy_ratio * 1.2;
-                let g = (1.0 - energy_ratio * 0.3) * 1.1;
-                let b = energy_ratio * 0.3;
+                let shade = if resource.is_carcass { CARCASS_SHADE } else { 1.0 };
+                let (r, g, b) = if resource.resource_type == ResourceType::Poison {
+                    POISON_COLOR
+                } else {
+                    (
+                        energy_ratio * 1.2 * shade,
+                        (1.0 - energy_ratio * 0.3) * 1.1 * shade,
+                        energy_ratio * 0.3 * shade,
+                    )
+                };
 
                 resource_data.extend_from_slice(&(r.min(1.0) as f32).to_le_bytes());
                 resource_data.extend_from_slice(&(g.min(1.0) as f32).to_le_bytes());
@@ -694,18 +1760,48 @@ void main() {
         // Create particle trails
         self.update_trails();
 
+        let timing = self.collect_gpu_time();
+        if timing {
+            if let (Some(extension), Some(query)) = (&self.timer_extension, &self.gpu_timer) {
+                extension.begin_query_ext(ExtDisjointTimerQuery::TIME_ELAPSED_EXT, query);
+            }
+        }
+
         // Clear the canvas with a beautiful gradient background
         self.render_background();
 
+        // Habitats sit underneath everything else, then the teleporters
+        self.render_habitats();
+        self.render_teleporters();
+
+        // Recent feeding activity tints the ground, and kill sites poison it
+        self.render_grid_overlay(&self.heatmap_buffer, self.heatmap_vertex_count);
+        self.render_grid_overlay(&self.toxin_buffer, self.toxin_vertex_count);
+
         // Render particle trails first (background)
         self.render_trails();
 
+        // Pack connections sit between trails and resources
+        self.render_connections();
+
         // Render resources
         self.render_resources();
 
         // Render agents (foreground)
         self.render_agents();
 
+        // The selection lasso and gene panels go on top of everything
+        self.render_lasso();
+        self.render_histogram();
+        self.render_boxplot();
+
+        if timing {
+            if let Some(extension) = &self.timer_extension {
+                extension.end_query_ext(ExtDisjointTimerQuery::TIME_ELAPSED_EXT);
+                self.pending_query = true;
+            }
+        }
+
         // Debug: Check for WebGL errors (only log once per second)
         let error = self.gl.get_error();
         if error != 0 && (self.time * 60.0) as i32 % 60 == 0 {
@@ -718,9 +1814,11 @@ void main() {
     fn render_background(&self) {
         // Create a beautiful animated gradient background
         let time_factor = (self.time * 0.1).sin() * 0.02;
-        let r = 0.1 + time_factor; // Brighter background
-        let g = 0.15 + time_factor * 0.5;
-        let b = 0.25 + time_factor * 0.3;
+        // Warm green-tinged summers fading to cold blue-grey winters
+        let chill = (1.0 - self.warmth) as f32;
+        let r = 0.1 + time_factor + 0.03 * (1.0 - chill); // Brighter background
+        let g = 0.15 + time_factor * 0.5 + 0.03 * (1.0 - chill);
+        let b = 0.25 + time_factor * 0.3 + 0.08 * chill;
 
         self.gl.clear_color(r, g, b, 1.0);
         self.gl.clear(
@@ -767,6 +1865,26 @@ void main() {
             }
         }
 
+        // Migrating agents leave a long straight streak behind them
+        for (x, y, dir_x, dir_y) in &self.migrating_agents {
+            for j in 0..MIGRATION_TRAIL_PARTICLES {
+                let life = j as f32 / MIGRATION_TRAIL_PARTICLES as f32;
+                let distance = life * MIGRATION_TRAIL_LENGTH;
+
+                trail_data.extend_from_slice(&(x - dir_x * distance).to_le_bytes());
+                trail_data.extend_from_slice(&(y - dir_y * distance).to_le_bytes());
+
+                let fade = 1.0 - life * 0.8;
+                trail_data.extend_from_slice(&(0.6 * fade).to_le_bytes());
+                trail_data.extend_from_slice(&(0.9 * fade).to_le_bytes());
+                trail_data.extend_from_slice(&fade.to_le_bytes());
+
+                trail_data.extend_from_slice(&life.to_le_bytes());
+
+                trail_count += 1;
+            }
+        }
+
         self.trail_count = trail_count;
 
         self.gl.bind_buffer(
@@ -956,7 +2074,349 @@ void main() {
             .draw_arrays(WebGlRenderingContext::POINTS, 0, self.resource_count as i32);
     }
 
+    fn render_habitats(&self) {
+        if self.habitat_count == 0 {
+            return;
+        }
+
+        self.gl.use_program(Some(&self.habitat_program));
+
+        let canvas_size_location = self
+            .gl
+            .get_uniform_location(&self.habitat_program, "u_canvas_size");
+        if let Some(ref location) = canvas_size_location {
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.habitat_buffer),
+        );
+
+        let attributes = [
+            ("a_position", 2, HABITAT_POSITION_OFFSET),
+            ("a_center", 2, HABITAT_CENTER_OFFSET),
+            ("a_radius", 1, HABITAT_RADIUS_OFFSET),
+        ];
+        for (name, size, offset) in attributes {
+            let location = self.gl.get_attrib_location(&self.habitat_program, name);
+            if location < 0 {
+                continue;
+            }
+            self.gl.enable_vertex_attrib_array(location as u32);
+            self.gl.vertex_attrib_pointer_with_i32(
+                location as u32,
+                size,
+                WebGlRenderingContext::FLOAT,
+                false,
+                HABITAT_STRIDE,
+                offset,
+            );
+        }
+
+        self.gl.draw_arrays(
+            WebGlRenderingContext::TRIANGLES,
+            0,
+            (self.habitat_count as usize * HABITAT_VERTICES) as i32,
+        );
+    }
+
+    fn render_teleporters(&self) {
+        if self.teleporter_count == 0 {
+            return;
+        }
+
+        self.gl.use_program(Some(&self.teleporter_program));
+
+        let canvas_size_location = self
+            .gl
+            .get_uniform_location(&self.teleporter_program, "u_canvas_size");
+        if let Some(ref location) = canvas_size_location {
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+        let time_location = self
+            .gl
+            .get_uniform_location(&self.teleporter_program, "u_time");
+        if let Some(ref location) = time_location {
+            self.gl.uniform1f(Some(location), self.time);
+        }
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.teleporter_buffer),
+        );
+
+        let attributes = [
+            ("a_position", 2, TELEPORTER_POSITION_OFFSET),
+            ("a_center", 2, TELEPORTER_CENTER_OFFSET),
+            ("a_radius", 1, TELEPORTER_RADIUS_OFFSET),
+            ("a_spin", 1, TELEPORTER_SPIN_OFFSET),
+        ];
+        for (name, size, offset) in attributes {
+            let location = self.gl.get_attrib_location(&self.teleporter_program, name);
+            if location < 0 {
+                continue;
+            }
+            self.gl.enable_vertex_attrib_array(location as u32);
+            self.gl.vertex_attrib_pointer_with_i32(
+                location as u32,
+                size,
+                WebGlRenderingContext::FLOAT,
+                false,
+                TELEPORTER_STRIDE,
+                offset,
+            );
+        }
+
+        self.gl.draw_arrays(
+            WebGlRenderingContext::TRIANGLES,
+            0,
+            (self.teleporter_count as usize * TELEPORTER_VERTICES) as i32,
+        );
+    }
+
+    fn render_grid_overlay(&self, buffer: &WebGlBuffer, vertex_count: u32) {
+        if vertex_count == 0 {
+            return;
+        }
+
+        self.gl.use_program(Some(&self.connections_program));
+
+        let canvas_size_location = self
+            .gl
+            .get_uniform_location(&self.connections_program, "u_canvas_size");
+        if let Some(ref location) = canvas_size_location {
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(buffer),
+        );
+
+        let attributes = [
+            ("a_position", 2, CONNECTION_POSITION_OFFSET),
+            ("a_color", 3, CONNECTION_COLOR_OFFSET),
+            ("a_alpha", 1, CONNECTION_ALPHA_OFFSET),
+        ];
+        for (name, size, offset) in attributes {
+            let location = self.gl.get_attrib_location(&self.connections_program, name);
+            if location < 0 {
+                continue;
+            }
+            self.gl.enable_vertex_attrib_array(location as u32);
+            self.gl.vertex_attrib_pointer_with_i32(
+                location as u32,
+                size,
+                WebGlRenderingContext::FLOAT,
+                false,
+                CONNECTION_STRIDE,
+                offset,
+            );
+        }
+
+        self.gl.draw_arrays(
+            WebGlRenderingContext::TRIANGLES,
+            0,
+            vertex_count as i32,
+        );
+    }
+
+    fn render_lasso(&self) {
+        if !self.lasso_visible {
+            return;
+        }
+        self.draw_outline(
+            &self.lasso_buffer,
+            WebGlRenderingContext::LINE_LOOP,
+            4,
+            LASSO_COLOR,
+            LASSO_DASH_LENGTH,
+        );
+    }
+
+    fn render_boxplot(&self) {
+        if self.boxplot_vertex_count == 0 {
+            return;
+        }
+        self.draw_outline(
+            &self.boxplot_buffer,
+            WebGlRenderingContext::LINES,
+            self.boxplot_vertex_count,
+            BOXPLOT_COLOR,
+            SOLID_DASH_LENGTH,
+        );
+    }
+
+    /// Draws `vertex_count` canvas-pixel positions from `buffer` as `mode`
+    /// lines with the lasso program.
+    fn draw_outline(
+        &self,
+        buffer: &WebGlBuffer,
+        mode: u32,
+        vertex_count: i32,
+        [r, g, b, a]: [f32; 4],
+        dash_length: f32,
+    ) {
+        self.gl.use_program(Some(&self.lasso_program));
+
+        let uniform = |name: &str| self.gl.get_uniform_location(&self.lasso_program, name);
+        if let Some(ref location) = uniform("u_canvas_size") {
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+        if let Some(ref location) = uniform("u_color") {
+            self.gl.uniform4f(Some(location), r, g, b, a);
+        }
+        if let Some(ref location) = uniform("u_dash_length") {
+            self.gl.uniform1f(Some(location), dash_length);
+        }
+
+        self.gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(buffer));
+        let position_location = self.gl.get_attrib_location(&self.lasso_program, "a_position");
+        if position_location < 0 {
+            return;
+        }
+        self.gl.enable_vertex_attrib_array(position_location as u32);
+        self.gl.vertex_attrib_pointer_with_i32(
+            position_location as u32,
+            2,
+            WebGlRenderingContext::FLOAT,
+            false,
+            0,
+            0,
+        );
+
+        self.gl.draw_arrays(mode, 0, vertex_count);
+    }
+
+    fn render_histogram(&self) {
+        if self.histogram_bars.is_empty() {
+            return;
+        }
+
+        self.gl.use_program(Some(&self.histogram_program));
+        let uniform = |name: &str| self.gl.get_uniform_location(&self.histogram_program, name);
+        let rect_location = uniform("u_rect");
+        let color_location = uniform("u_color");
+        if let Some(ref location) = uniform("u_canvas_size") {
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.histogram_quad_buffer),
+        );
+        let corner_location = self.gl.get_attrib_location(&self.histogram_program, "a_corner");
+        if corner_location < 0 {
+            return;
+        }
+        self.gl.enable_vertex_attrib_array(corner_location as u32);
+        self.gl.vertex_attrib_pointer_with_i32(
+            corner_location as u32,
+            2,
+            WebGlRenderingContext::FLOAT,
+            false,
+            0,
+            0,
+        );
+
+        let panel = histogram_panel(self.canvas_width as f32);
+        let quads = std::iter::once((panel, HISTOGRAM_BACKGROUND_COLOR)).chain(
+            histogram_bar_rects(&self.histogram_bars, panel)
+                .into_iter()
+                .map(|rect| (rect, HISTOGRAM_BAR_COLOR)),
+        );
+        for ([x, y, width, height], [r, g, b, a]) in quads {
+            self.gl.uniform4f(rect_location.as_ref(), x, y, width, height);
+            self.gl.uniform4f(color_location.as_ref(), r, g, b, a);
+            self.gl.draw_arrays(WebGlRenderingContext::TRIANGLE_STRIP, 0, 4);
+        }
+    }
+
+    fn render_connections(&self) {
+        if !self.connections_visible || self.connection_segment_count == 0 {
+            return;
+        }
+
+        self.gl.use_program(Some(&self.connections_program));
+
+        let canvas_size_location = self
+            .gl
+            .get_uniform_location(&self.connections_program, "u_canvas_size");
+        if let Some(ref location) = canvas_size_location {
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+
+        self.gl.bind_buffer(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            Some(&self.connections_buffer),
+        );
+
+        let attributes = [
+            ("a_position", 2, CONNECTION_POSITION_OFFSET),
+            ("a_color", 3, CONNECTION_COLOR_OFFSET),
+            ("a_alpha", 1, CONNECTION_ALPHA_OFFSET),
+        ];
+        for (name, size, offset) in attributes {
+            let location = self.gl.get_attrib_location(&self.connections_program, name);
+            if location < 0 {
+                continue;
+            }
+            self.gl.enable_vertex_attrib_array(location as u32);
+            self.gl.vertex_attrib_pointer_with_i32(
+                location as u32,
+                size,
+                WebGlRenderingContext::FLOAT,
+                false,
+                CONNECTION_STRIDE,
+                offset,
+            );
+        }
+
+        self.gl.draw_arrays(
+            WebGlRenderingContext::LINES,
+            0,
+            (self.connection_segment_count * 2) as i32,
+        );
+    }
+
     fn render_agents(&self) {
+        #[cfg(feature = "webgl2")]
+        if let Some(instanced) = &self.instanced_agents {
+            instanced.render(
+                self.canvas_width,
+                self.canvas_height,
+                self.time,
+                &self.atlas_texture,
+                self.show_mutations,
+            );
+            return;
+        }
+
         if self.agent_count == 0 {
             return;
         }
@@ -974,7 +2434,16 @@ void main() {
 
         // Set uniforms
         if let Some(ref location) = self.canvas_size_location {
-            self.gl.uniform2f(Some(location), 800.0, 600.0);
+            self.gl.uniform2f(
+                Some(location),
+                self.canvas_width as f32,
+                self.canvas_height as f32,
+            );
+        }
+        if let Some(ref location) = self.viewport_bounds_location {
+            let [min_x, min_y, max_x, max_y] =
+                agent_viewport_bounds(self.canvas_width as f32, self.canvas_height as f32);
+            self.gl.uniform4f(Some(location), min_x, min_y, max_x, max_y);
         }
         if let Some(ref location) = self.time_location {
             self.gl.uniform1f(Some(location), self.time);
@@ -995,8 +2464,8 @@ void main() {
             2,
             WebGlRenderingContext::FLOAT,
             false,
-            28, // 2 floats for position + 3 floats for color + 1 float for size + 1 float for energy
-            0,
+            AGENT_STRIDE,
+            AGENT_POSITION_OFFSET,
         );
 
         // Color attribute (vec3)
@@ -1007,8 +2476,8 @@ void main() {
             3,
             WebGlRenderingContext::FLOAT,
             false,
-            28,
-            8,
+            AGENT_STRIDE,
+            AGENT_COLOR_OFFSET,
         );
 
         // Size attribute (float)
@@ -1019,8 +2488,8 @@ void main() {
             1,
             WebGlRenderingContext::FLOAT,
             false,
-            28,
-            20,
+            AGENT_STRIDE,
+            AGENT_SIZE_OFFSET,
         );
 
         // Energy attribute (float)
@@ -1031,10 +2500,65 @@ void main() {
             1,
             WebGlRenderingContext::FLOAT,
             false,
-            28,
-            24,
+            AGENT_STRIDE,
+            AGENT_ENERGY_OFFSET,
+        );
+
+        // Selected attribute (float)
+        let selected_location =
+            self.gl
+                .get_attrib_location(&self.agent_program, "a_selected") as u32;
+        self.gl.enable_vertex_attrib_array(selected_location);
+        self.gl.vertex_attrib_pointer_with_i32(
+            selected_location,
+            1,
+            WebGlRenderingContext::FLOAT,
+            false,
+            AGENT_STRIDE,
+            AGENT_SELECTED_OFFSET,
+        );
+
+        // Atlas index attribute (float)
+        let state_location = self.gl.get_attrib_location(&self.agent_program, "a_state") as u32;
+        self.gl.enable_vertex_attrib_array(state_location);
+        self.gl.vertex_attrib_pointer_with_i32(
+            state_location,
+            1,
+            WebGlRenderingContext::FLOAT,
+            false,
+            AGENT_STRIDE,
+            AGENT_STATE_OFFSET,
         );
 
+        // Mutated gene mask and spawn time attributes (float)
+        for (name, offset) in [
+            ("a_mutated", AGENT_MUTATED_OFFSET),
+            ("a_spawn_time", AGENT_SPAWN_TIME_OFFSET),
+            ("a_satiation", AGENT_SATIATION_OFFSET),
+        ] {
+            let location = self.gl.get_attrib_location(&self.agent_program, name) as u32;
+            self.gl.enable_vertex_attrib_array(location);
+            self.gl.vertex_attrib_pointer_with_i32(
+                location,
+                1,
+                WebGlRenderingContext::FLOAT,
+                false,
+                AGENT_STRIDE,
+                offset,
+            );
+        }
+        if let Some(ref location) = self.show_mutations_location {
+            self.gl
+                .uniform1f(Some(location), if self.show_mutations { 1.0 } else { 0.0 });
+        }
+
+        self.gl.active_texture(WebGlRenderingContext::TEXTURE0);
+        self.gl
+            .bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&self.atlas_texture));
+        if let Some(ref location) = self.atlas_location {
+            self.gl.uniform1i(Some(location), 0);
+        }
+
         // Draw points
         self.gl
             .draw_arrays(WebGlRenderingContext::POINTS, 0, self.agent_count as i32);
@@ -1087,3 +2611,302 @@ void main() {
         (r + m, g + m, b + m)
     }
 }
+
+/// WebGL2 agent renderer: one instanced draw call of billboard quads, so agent
+/// size isn't limited by the driver's maximum `gl_PointSize`.
+#[cfg(feature = "webgl2")]
+struct InstancedAgentRenderer {
+    gl: WebGl2RenderingContext,
+    program: WebGlProgram,
+    vertex_array: WebGlVertexArrayObject,
+    instance_buffer: WebGlBuffer,
+    canvas_size_location: Option<WebGlUniformLocation>,
+    time_location: Option<WebGlUniformLocation>,
+    atlas_location: Option<WebGlUniformLocation>,
+    show_mutations_location: Option<WebGlUniformLocation>,
+    instance_count: i32,
+}
+
+#[cfg(feature = "webgl2")]
+impl InstancedAgentRenderer {
+    fn new(gl: WebGl2RenderingContext) -> Result<Self, JsValue> {
+        let program = Self::create_program(&gl)?;
+
+        let vertex_array = gl
+            .create_vertex_array()
+            .ok_or("Failed to create vertex array")?;
+        gl.bind_vertex_array(Some(&vertex_array));
+
+        // Base quad, shared by every instance
+        let quad_buffer = gl.create_buffer().ok_or("Failed to create quad buffer")?;
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&quad_buffer));
+        let quad_data: Vec<u8> = BILLBOARD_QUAD
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        gl.buffer_data_with_u8_array(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &quad_data,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+        let corner_location = gl.get_attrib_location(&program, "a_corner") as u32;
+        gl.enable_vertex_attrib_array(corner_location);
+        gl.vertex_attrib_pointer_with_i32(
+            corner_location,
+            2,
+            WebGl2RenderingContext::FLOAT,
+            false,
+            0,
+            0,
+        );
+
+        // Per-instance agent attributes
+        let instance_buffer = gl
+            .create_buffer()
+            .ok_or("Failed to create instance buffer")?;
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&instance_buffer));
+        let attributes = [
+            ("a_position", 2, AGENT_POSITION_OFFSET),
+            ("a_color", 3, AGENT_COLOR_OFFSET),
+            ("a_size", 1, AGENT_SIZE_OFFSET),
+            ("a_energy", 1, AGENT_ENERGY_OFFSET),
+            ("a_selected", 1, AGENT_SELECTED_OFFSET),
+            ("a_state", 1, AGENT_STATE_OFFSET),
+            ("a_mutated", 1, AGENT_MUTATED_OFFSET),
+            ("a_spawn_time", 1, AGENT_SPAWN_TIME_OFFSET),
+            ("a_satiation", 1, AGENT_SATIATION_OFFSET),
+            ("a_age", 1, INSTANCE_AGE_OFFSET),
+        ];
+        for (name, components, offset) in attributes {
+            let location = gl.get_attrib_location(&program, name);
+            if location < 0 {
+                // Optimized out by the shader compiler
+                continue;
+            }
+            let location = location as u32;
+            gl.enable_vertex_attrib_array(location);
+            gl.vertex_attrib_pointer_with_i32(
+                location,
+                components,
+                WebGl2RenderingContext::FLOAT,
+                false,
+                INSTANCE_STRIDE,
+                offset,
+            );
+            gl.vertex_attrib_divisor(location, 1);
+        }
+
+        gl.bind_vertex_array(None);
+
+        let canvas_size_location = gl.get_uniform_location(&program, "u_canvas_size");
+        let time_location = gl.get_uniform_location(&program, "u_time");
+        let atlas_location = gl.get_uniform_location(&program, "u_atlas");
+        let show_mutations_location = gl.get_uniform_location(&program, "u_show_mutations");
+
+        Ok(Self {
+            gl,
+            program,
+            vertex_array,
+            instance_buffer,
+            canvas_size_location,
+            time_location,
+            atlas_location,
+            show_mutations_location,
+            instance_count: 0,
+        })
+    }
+
+    fn create_program(gl: &WebGl2RenderingContext) -> Result<WebGlProgram, JsValue> {
+        // Shader compilation only uses calls shared with WebGL1
+        let gl1 = gl.unchecked_ref::<WebGlRenderingContext>();
+
+        let vertex_shader = WebGlRenderer::create_shader(
+            gl1,
+            WebGlRenderingContext::VERTEX_SHADER,
+            r#"#version 300 es
+precision highp float;
+in vec2 a_corner;
+in vec2 a_position;
+in vec3 a_color;
+in float a_size;
+in float a_energy;
+in float a_selected;
+in float a_state;
+in float a_mutated;
+in float a_spawn_time;
+in float a_satiation;
+in float a_age;
+uniform vec2 u_canvas_size;
+uniform float u_time;
+out vec2 v_local;
+out vec3 v_color;
+out float v_energy;
+out float v_selected;
+out float v_state;
+out float v_mutated;
+out float v_spawn_time;
+out float v_satiation;
+out float v_age;
+out vec2 v_position;
+
+void main() {
+    // Same footprint as the WebGL1 point sprite, without the point size cap
+    float pulse = sin(u_time * 3.0 + a_position.x * 0.1) * 0.2 + 1.0;
+    float energy_scale = 0.5 + a_energy * 0.01;
+    float diameter = a_size * 30.0 * pulse * energy_scale;
+
+    vec2 pixel = a_position + a_corner * diameter * 0.5;
+    vec2 ndc = (pixel / u_canvas_size) * 2.0 - 1.0;
+    ndc.y = -ndc.y; // Flip Y axis
+    gl_Position = vec4(ndc, 0.0, 1.0);
+
+    v_local = a_corner * 0.5; // Matches gl_PointCoord - 0.5
+    v_color = a_color;
+    v_energy = a_energy;
+    v_selected = a_selected;
+    v_state = a_state;
+    v_mutated = a_mutated;
+    v_spawn_time = a_spawn_time;
+    v_satiation = a_satiation;
+    v_age = a_age;
+    v_position = a_position;
+}"#,
+        )?;
+
+        let fragment_shader = WebGlRenderer::create_shader(
+            gl1,
+            WebGlRenderingContext::FRAGMENT_SHADER,
+            r#"#version 300 es
+precision highp float;
+in vec2 v_local;
+in vec3 v_color;
+in float v_energy;
+in float v_selected;
+in float v_state;
+in float v_mutated;
+in float v_spawn_time;
+in float v_satiation;
+in float v_age;
+in vec2 v_position;
+uniform float u_time;
+uniform sampler2D u_atlas;
+uniform float u_show_mutations;
+out vec4 frag_color;
+
+void main() {
+    float dist = length(v_local);
+
+    // Selection ring around the inspected agent
+    if (v_selected > 0.5 && abs(dist - 0.4) < 0.05) {
+        frag_color = vec4(1.0, 1.0, 1.0, 1.0);
+        return;
+    }
+
+    // The agent's state picks its icon from the atlas row, as in WebGL1
+    vec2 point_coord = v_local + 0.5;
+    vec2 atlas_uv = vec2((floor(v_state + 0.5) + point_coord.x) / 8.0, point_coord.y);
+    float alpha = texture(u_atlas, atlas_uv).a;
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    // Bright core
+    float core = 1.0 - smoothstep(0.0, 0.2, dist);
+    float inner_glow = 1.0 - smoothstep(0.0, 0.4, dist);
+    vec3 final_color = mix(v_color, mix(v_color, vec3(1.0), 0.6), core * 0.8);
+    final_color = mix(final_color, mix(v_color, vec3(1.0), 0.4), inner_glow * 0.6);
+
+    // Energy glow and pulse
+    final_color += vec3(v_energy * 0.002);
+    final_color *= sin(u_time * 3.0 + v_position.x * 0.1) * 0.15 + 0.85;
+
+    // Older agents fade toward grey
+    float age_fade = clamp(v_age / 200.0, 0.0, 1.0) * 0.5;
+    float luminance = dot(final_color, vec3(0.299, 0.587, 0.114));
+    final_color = mix(final_color, vec3(luminance), age_fade);
+
+    // Pulsing white highlight on agents born with a fresh mutation, fading out
+    float since_spawn = u_time - v_spawn_time;
+    if (u_show_mutations > 0.5 && v_mutated > 0.0 && since_spawn < 5.0) {
+        float highlight = (sin(u_time * 8.0) * 0.5 + 0.5) * (1.0 - since_spawn / 5.0);
+        final_color = mix(final_color, vec3(1.0), highlight * 0.7);
+    }
+
+    // Well-fed predators are resting, so draw them faded
+    alpha *= 1.0 - smoothstep(0.8, 1.0, v_satiation) * 0.5;
+
+    frag_color = vec4(final_color, alpha);
+}"#,
+        )?;
+
+        let program = gl.create_program().ok_or("Failed to create program")?;
+        gl.attach_shader(&program, &vertex_shader);
+        gl.attach_shader(&program, &fragment_shader);
+        gl.link_program(&program);
+
+        let link_status = gl.get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS);
+        if link_status.as_bool().unwrap_or(false) {
+            Ok(program)
+        } else {
+            let error = gl.get_program_info_log(&program).unwrap_or_default();
+            Err(format!("Failed to link instanced agent program: {}", error).into())
+        }
+    }
+
+    fn update(&mut self, instance_data: &[u8]) {
+        self.gl.bind_buffer(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            Some(&self.instance_buffer),
+        );
+        self.gl.buffer_data_with_u8_array(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            instance_data,
+            WebGl2RenderingContext::DYNAMIC_DRAW,
+        );
+        self.instance_count = instance_data.len() as i32 / INSTANCE_STRIDE;
+    }
+
+    /// Draws the agents with their icons from `atlas`, the texture
+    /// `WebGlRenderer::build_atlas_texture` made on the same context, and
+    /// highlights fresh mutations when `show_mutations` is set.
+    fn render(
+        &self,
+        canvas_width: u32,
+        canvas_height: u32,
+        time: f32,
+        atlas: &WebGlTexture,
+        show_mutations: bool,
+    ) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        self.gl.use_program(Some(&self.program));
+        if let Some(ref location) = self.canvas_size_location {
+            self.gl
+                .uniform2f(Some(location), canvas_width as f32, canvas_height as f32);
+        }
+        if let Some(ref location) = self.time_location {
+            self.gl.uniform1f(Some(location), time);
+        }
+        self.gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        self.gl
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(atlas));
+        if let Some(ref location) = self.atlas_location {
+            self.gl.uniform1i(Some(location), 0);
+        }
+        if let Some(ref location) = self.show_mutations_location {
+            self.gl
+                .uniform1f(Some(location), if show_mutations { 1.0 } else { 0.0 });
+        }
+
+        self.gl.bind_vertex_array(Some(&self.vertex_array));
+        self.gl.draw_arrays_instanced(
+            WebGl2RenderingContext::TRIANGLES,
+            0,
+            (BILLBOARD_QUAD.len() / 2) as i32,
+            self.instance_count,
+        );
+        self.gl.bind_vertex_array(None);
+    }
+}
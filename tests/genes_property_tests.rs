@@ -0,0 +1,134 @@
+use battleo::genes::{Genes, MutationDistribution};
+use proptest::prelude::*;
+use rand::thread_rng;
+
+/// Every gene with the range `Genes::inherit_from` clamps it to.
+const GENE_RANGES: [(&str, f64, f64); 21] = [
+    ("speed", 0.1, 3.0),
+    ("sense_range", 5.0, 150.0),
+    ("size", 0.3, 2.5),
+    ("energy_efficiency", 0.1, 2.5),
+    ("reproduction_threshold", 10.0, 200.0),
+    ("mutation_rate", 0.001, 0.3),
+    ("aggression", 0.0, 1.0),
+    ("color_hue", 0.0, 360.0),
+    ("is_predator", 0.0, 1.0),
+    ("hunting_speed", 0.5, 3.0),
+    ("attack_power", 0.1, 3.0),
+    ("defense", 0.1, 3.0),
+    ("stealth", 0.0, 1.0),
+    ("pack_mentality", 0.0, 1.0),
+    ("territory_size", 10.0, 300.0),
+    ("metabolism", 0.1, 3.0),
+    ("intelligence", 0.1, 3.0),
+    ("stamina", 0.1, 3.0),
+    ("omnivore", 0.0, 1.0),
+    ("migration", 0.0, 1.0),
+    ("cooperation", 0.0, 1.0),
+];
+
+/// Mutation noise is N(0, 0.05); ten standard deviations never happens in practice.
+const MUTATION_TOLERANCE: f64 = 0.5;
+
+/// Extra defense or attack the arms race may add on top of inheritance.
+const ARMS_RACE_TOLERANCE: f64 = 0.05;
+
+fn gene_mut<'a>(genes: &'a mut Genes, name: &str) -> &'a mut f64 {
+    match name {
+        "speed" => &mut genes.speed,
+        "sense_range" => &mut genes.sense_range,
+        "size" => &mut genes.size,
+        "energy_efficiency" => &mut genes.energy_efficiency,
+        "reproduction_threshold" => &mut genes.reproduction_threshold,
+        "mutation_rate" => &mut genes.mutation_rate,
+        "aggression" => &mut genes.aggression,
+        "color_hue" => &mut genes.color_hue,
+        "is_predator" => &mut genes.is_predator,
+        "hunting_speed" => &mut genes.hunting_speed,
+        "attack_power" => &mut genes.attack_power,
+        "defense" => &mut genes.defense,
+        "stealth" => &mut genes.stealth,
+        "pack_mentality" => &mut genes.pack_mentality,
+        "territory_size" => &mut genes.territory_size,
+        "metabolism" => &mut genes.metabolism,
+        "intelligence" => &mut genes.intelligence,
+        "stamina" => &mut genes.stamina,
+        "omnivore" => &mut genes.omnivore,
+        "migration" => &mut genes.migration,
+        "cooperation" => &mut genes.cooperation,
+        _ => panic!("unknown gene {}", name),
+    }
+}
+
+fn gene(genes: &Genes, name: &str) -> f64 {
+    *gene_mut(&mut genes.clone(), name)
+}
+
+/// Parents whose genes sit at `fraction` (0..1) of the way through each range.
+fn parent_at(fractions: &[f64]) -> Genes {
+    let mut genes = Genes::new();
+    for (&(name, min, max), fraction) in GENE_RANGES.iter().zip(fractions) {
+        *gene_mut(&mut genes, name) = min + fraction * (max - min);
+    }
+    genes
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10_000))]
+
+    #[test]
+    fn gene_clamping_holds(gene1 in 0.0f64..5.0, gene2 in 0.0f64..5.0, mutation in 0.0f64..0.5) {
+        let mut rng = thread_rng();
+        let genes = Genes::new();
+
+        let gaussian = MutationDistribution::default();
+        let raw =
+            genes.blend_and_mutate_gene("color_hue", gene1, gene2, mutation, gaussian, &mut rng);
+        prop_assert!(raw >= gene1.min(gene2) - MUTATION_TOLERANCE);
+        prop_assert!(raw <= gene1.max(gene2) + MUTATION_TOLERANCE);
+
+        // Every gene of the offspring is clamped into its own documented range
+        let mut a = genes.clone();
+        let mut b = Genes::new();
+        for &(name, _, _) in &GENE_RANGES {
+            *gene_mut(&mut a, name) = gene1;
+            *gene_mut(&mut b, name) = gene2;
+        }
+        let offspring = a.inherit_from(&b, mutation, gaussian, 0.0, 0.0, &mut thread_rng());
+        for &(name, min, max) in &GENE_RANGES {
+            let value = gene(&offspring, name);
+            prop_assert!(
+                (min..=max).contains(&value),
+                "{} = {} outside [{}, {}]", name, value, min, max
+            );
+        }
+    }
+
+    #[test]
+    fn inherit_from_stays_within_parent_ranges(
+        fractions_a in prop::collection::vec(0.0f64..=1.0, GENE_RANGES.len()),
+        fractions_b in prop::collection::vec(0.0f64..=1.0, GENE_RANGES.len()),
+        mutation in 0.0f64..0.5,
+        avg_attack in 0.0f64..3.0,
+        avg_defense in 0.0f64..3.0,
+    ) {
+        let a = parent_at(&fractions_a);
+        let b = parent_at(&fractions_b);
+        let gaussian = MutationDistribution::default();
+        let offspring =
+            a.inherit_from(&b, mutation, gaussian, avg_attack, avg_defense, &mut thread_rng());
+
+        for &(name, min, max) in &GENE_RANGES {
+            let (parent_a, parent_b) = (gene(&a, name), gene(&b, name));
+            let tolerance = match name {
+                "attack_power" | "defense" => MUTATION_TOLERANCE + ARMS_RACE_TOLERANCE,
+                _ => MUTATION_TOLERANCE,
+            };
+            let value = gene(&offspring, name);
+
+            prop_assert!(value <= max && value >= min, "{} = {} outside [{}, {}]", name, value, min, max);
+            prop_assert!(value >= parent_a.min(parent_b) - tolerance, "{} = {}", name, value);
+            prop_assert!(value <= parent_a.max(parent_b) + tolerance, "{} = {}", name, value);
+        }
+    }
+}
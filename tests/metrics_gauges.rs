@@ -0,0 +1,32 @@
+#![cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+
+use battleo::simulation_core::{SimulationConfig, UnifiedSimulation};
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use std::sync::Arc;
+
+#[test]
+fn agent_gauge_is_published_after_updates() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let config = SimulationConfig {
+        initial_agents: 50,
+        initial_resources: 100,
+        ..Default::default()
+    };
+    let mut simulation = UnifiedSimulation::new(config).with_metrics(Arc::new(recorder));
+
+    for _ in 0..1000 {
+        simulation.update();
+    }
+
+    let agents = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .find(|(key, _, _, _)| key.key().name() == "battleo.agents")
+        .map(|(_, _, _, value)| value);
+    match agents {
+        Some(DebugValue::Gauge(value)) => assert!(value.into_inner() > 0.0),
+        other => panic!("expected a battleo.agents gauge, got {:?}", other),
+    }
+}
@@ -0,0 +1,25 @@
+// Build the addon first with `npm run build:node`.
+// eslint-disable-next-line @typescript-eslint/no-var-requires
+const { HeadlessSimulation } = require('..');
+
+describe('HeadlessSimulation (napi)', () => {
+  it('runs for 0.1 minutes and returns diagnostics', () => {
+    const sim = new HeadlessSimulation({
+      targetDurationMinutes: 0.1,
+      initialAgents: 20,
+      initialResources: 50,
+    });
+
+    const diagnostics = sim.run();
+
+    expect(diagnostics.total_steps).toBeGreaterThan(0);
+    expect(diagnostics.final_stats.agent_count).toBeGreaterThanOrEqual(0);
+    expect(sim.getDiagnostics().total_steps).toBe(diagnostics.total_steps);
+  });
+
+  it('rejects an unknown boundary behavior', () => {
+    expect(() => new HeadlessSimulation({ boundaryBehavior: 'sticky' })).toThrow(
+      /Unknown boundary behavior/,
+    );
+  });
+});
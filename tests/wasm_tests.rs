@@ -0,0 +1,99 @@
+#![cfg(target_arch = "wasm32")]
+
+use battleo::agent::Agent;
+use battleo::genes::Genes;
+use battleo::webgl_renderer::WebGlRenderer;
+use battleo::{BattleSimulation, ParallelProcessor};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// Appends a canvas with `id` to the page so `BattleSimulation::new` can
+/// find it.
+fn add_canvas(id: &str) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    canvas.set_id(id);
+    document.body().unwrap().append_child(&canvas).unwrap();
+}
+
+fn stats_json(simulation: &BattleSimulation) -> serde_json::Value {
+    let json: String = js_sys::JSON::stringify(&simulation.get_stats()).unwrap().into();
+    serde_json::from_str(&json).unwrap()
+}
+
+fn agent_count(simulation: &BattleSimulation) -> u64 {
+    stats_json(simulation)["agent_count"].as_u64().unwrap()
+}
+
+#[wasm_bindgen_test]
+fn parallel_processor_has_workers() {
+    assert!(ParallelProcessor::new().get_worker_count() > 0);
+}
+
+#[wasm_bindgen_test]
+fn missing_canvas_is_an_error() {
+    assert!(BattleSimulation::new("nonexistent").is_err());
+}
+
+#[wasm_bindgen_test]
+fn stats_are_json_with_an_agent_count() {
+    add_canvas("stats-canvas");
+    let simulation = BattleSimulation::new("stats-canvas").unwrap();
+    let stats = stats_json(&simulation);
+    assert!(stats["agent_count"].is_u64(), "{}", stats);
+}
+
+#[wasm_bindgen_test]
+fn reset_restores_the_initial_population() {
+    add_canvas("reset-canvas");
+    let mut simulation = BattleSimulation::new("reset-canvas").unwrap();
+    let initial = agent_count(&simulation);
+
+    simulation.add_agent(100.0, 100.0);
+    simulation.add_agent(200.0, 200.0);
+    simulation.reset();
+    assert_eq!(agent_count(&simulation), initial);
+}
+
+#[wasm_bindgen_test]
+fn gpu_timing_is_never_negative() {
+    add_canvas("gpu-timing-canvas");
+    let simulation = BattleSimulation::new("gpu-timing-canvas").unwrap();
+    let timing = simulation.get_gpu_timing();
+    assert!(timing >= 0.0, "{}", timing);
+}
+
+#[wasm_bindgen_test]
+fn agent_canvas_size_follows_the_canvas() {
+    let canvas = web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    canvas.set_width(1200);
+    canvas.set_height(900);
+
+    let mut renderer = WebGlRenderer::new(canvas).unwrap();
+    renderer.update_agents(&[Agent::new(600.0, 450.0, Genes::new(), 1)]);
+    renderer.render();
+    assert_eq!(renderer.agent_canvas_size(), Some((1200.0, 900.0)));
+}
+
+#[wasm_bindgen_test]
+fn add_agent_adds_one_agent() {
+    add_canvas("add-agent-canvas");
+    let mut simulation = BattleSimulation::new("add-agent-canvas").unwrap();
+    let before = agent_count(&simulation);
+
+    simulation.add_agent(100.0, 100.0);
+    assert_eq!(agent_count(&simulation), before + 1);
+}